@@ -0,0 +1,20 @@
+//! The programs shared by the benchmarks.
+
+// Functions end with an explicit `return`, as they do throughout the interpreter
+#![allow(clippy::needless_return)]
+
+use pyru::examples::examples;
+
+/// Returns a large program made by repeating the examples until it is at least the given number
+/// of bytes long.
+pub fn program(length: usize) -> String {
+    let sources: Vec<&str> = examples().iter().map(|example| example.source).collect();
+    let mut program = String::new();
+    while program.len() < length {
+        for source in &sources {
+            program.push_str(source);
+            program.push('\n');
+        }
+    }
+    return program;
+}
@@ -0,0 +1,29 @@
+//! Benchmarks the lexer on a large program.
+//!
+//! Run with `cargo bench --bench lexer`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use pyru::lexer::Lexer;
+
+mod common;
+
+/// The length of the program lexed, in bytes.
+const PROGRAM_LENGTH: usize = 64 * 1024;
+
+fn lex(c: &mut Criterion) {
+    let source = common::program(PROGRAM_LENGTH);
+
+    let mut group = c.benchmark_group("lexer");
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.bench_function("lex", |b| {
+        b.iter_batched(
+            || source.clone(),
+            |source| Lexer::new(source, 2).run().unwrap(),
+            BatchSize::SmallInput,
+        );
+    });
+    group.finish();
+}
+
+criterion_group!(benches, lex);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+//! Benchmarks the parser on the tokens of a large program.
+//!
+//! Run with `cargo bench --bench parser`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use pyru::{lexer::Lexer, parser::Parser};
+
+mod common;
+
+/// The length of the program parsed, in bytes.
+const PROGRAM_LENGTH: usize = 64 * 1024;
+
+fn parse(c: &mut Criterion) {
+    let source = common::program(PROGRAM_LENGTH);
+    let tokens = Lexer::new(source, 2).run().unwrap();
+
+    let mut group = c.benchmark_group("parser");
+    group.throughput(Throughput::Elements(tokens.len() as u64));
+    group.bench_function("parse", |b| {
+        b.iter_batched(
+            || tokens.clone(),
+            |tokens| Parser::new(tokens).parse().unwrap(),
+            BatchSize::SmallInput,
+        );
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,55 @@
+//! Embeds pyru in a Rust program, registering a native function for scripts to call, running
+//! two scripts that share their globals and calling a function defined by one of them.
+//!
+//! Run it with `cargo run --example embed`.
+
+#![allow(clippy::needless_return)]
+
+use std::time::Duration;
+
+use pyru::{
+    engine::Engine,
+    error::EvaluatorError,
+    evaluator::Evaluator,
+    value::{LiteralType, Value},
+};
+
+/// A native function that returns the square root of a number.
+fn sqrt(_: &mut Evaluator, args: Vec<Value>) -> Result<Value, EvaluatorError> {
+    return match &args[0] {
+        Value::Literal(LiteralType::Num(n)) => Ok(Value::Literal(LiteralType::Num(n.sqrt()))),
+        _ => Err(EvaluatorError::ExpectedNumber),
+    };
+}
+
+fn main() {
+    let mut engine = Engine::builder()
+        .with_timeout(Duration::from_secs(1))
+        .with_native("sqrt", 1, sqrt)
+        .build();
+
+    let setup = r#"
+def hypotenuse(a, b):
+  return sqrt(a * a + b * b);
+"#;
+    engine.run(setup).expect("the setup script should run");
+
+    // The output of a script is returned to the host rather than printed
+    match engine.run("print(hypotenuse(3, 4));") {
+        Ok(output) => println!("The script printed {output:?}"),
+        Err(e) => eprintln!("{e}"),
+    }
+
+    // Functions defined by a script can also be called directly
+    let mut hypotenuse = engine.get_function("hypotenuse").expect("hypotenuse is defined");
+    let args = [Value::Literal(LiteralType::Num(5.0)), Value::Literal(LiteralType::Num(12.0))];
+    match hypotenuse.call(&args) {
+        Ok(value) => println!("hypotenuse(5, 12) = {value}"),
+        Err(e) => eprintln!("{e}"),
+    }
+
+    // Scripts that run for too long are stopped
+    if let Err(e) = engine.run("while true:\n  pass;\n") {
+        eprintln!("{e}");
+    }
+}
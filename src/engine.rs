@@ -0,0 +1,327 @@
+//! The `engine` module provides `Engine`, a facade over the phases of the interpreter for
+//! programs that embed pyru as a scripting language.
+//!
+//! ## Overview
+//!
+//! An `Engine` is configured with an `EngineBuilder`, which can register native functions
+//! written in Rust, restrict the built in native functions and limit how long each script may
+//! run for. Scripts run by the same engine share their globals, so a host can run a script that
+//! defines some functions and then run others that use them, or call those functions directly
+//! from Rust through `Engine::get_function`.
+//!
+//! Any Rust value that implements Serde's `Serialize` can be passed into the scripts as a global
+//! with `Engine::set_global`, and any global can be read back into a type that implements
+//! `Deserialize` with `Engine::get_global`.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use pyru::{engine::Engine, value::{LiteralType, Value}};
+//!
+//! let mut engine = Engine::builder()
+//!     .with_timeout(Duration::from_secs(1))
+//!     .with_native("double", 1, |_, args| match &args[0] {
+//!         Value::Literal(LiteralType::Num(n)) => Ok(Value::Literal(LiteralType::Num(n * 2.0))),
+//!         _ => Ok(Value::Literal(LiteralType::Null)),
+//!     })
+//!     .build();
+//!
+//! assert_eq!(engine.run("print(double(21));").unwrap(), vec!["42".to_string()]);
+//!
+//! engine.run("def add(a, b):\n  return a + b;\n").unwrap();
+//! let sum = engine
+//!     .get_function("add")
+//!     .unwrap()
+//!     .call(&[Value::Literal(LiteralType::Num(1.0)), Value::Literal(LiteralType::Num(2.0))]);
+//! assert_eq!(sum.unwrap(), Value::Literal(LiteralType::Num(3.0)));
+//!
+//! engine.set_global("scores", &vec![3, 4, 5]).unwrap();
+//! engine.run("let total = scores[0] + scores[1] + scores[2];").unwrap();
+//! assert_eq!(engine.get_global::<u32>("total").unwrap(), 12);
+//! ```
+
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    callable::NativeFunc,
+    error::{EngineError, EvaluatorError},
+    evaluator::{max_depth_for, Evaluator, NativePolicy, OutputSink, DEFAULT_STACK_SIZE, MAX_MEMORY},
+    lexer::Lexer,
+    marshal::{from_value, to_value},
+    number::NumberFormat,
+    parser::Parser,
+    semanticanalyser::{ScopingMode, SemanticAnalyser},
+    value::Value,
+};
+
+/// The signature of a native function registered by the host.
+pub type NativeFn = fn(&mut Evaluator, Vec<Value>) -> Result<Value, EvaluatorError>;
+
+/// Configures and builds an `Engine`.
+///
+/// ## Fields
+/// - `natives`: The built in native functions that scripts may call.
+/// - `host_natives`: The native functions registered by the host.
+/// - `timeout`: How long each script may run for, if it is limited.
+/// - `max_depth`: The number of nested calls each script can make, if it is limited to fewer
+///   than fit on the stack.
+/// - `stack_size`: How many bytes of stack the thread that runs the scripts has.
+/// - `max_steps`: The number of statements each script may execute, if it is limited.
+/// - `max_memory`: Roughly how many bytes the variables of the scripts may take up.
+/// - `checked_division`: Whether dividing by zero is an error.
+/// - `number_format`: How numbers are written when scripts print them.
+/// - `sink`: Whether the lines printed by scripts are also written to stdout.
+/// - `tabsize`: The number of spaces a tab is worth when reading indentation.
+#[derive(Clone, Debug)]
+pub struct EngineBuilder {
+    natives: NativePolicy,
+    host_natives: Vec<NativeFunc>,
+    timeout: Option<Duration>,
+    max_depth: Option<usize>,
+    stack_size: usize,
+    max_steps: Option<usize>,
+    max_memory: usize,
+    checked_division: bool,
+    number_format: NumberFormat,
+    sink: OutputSink,
+    tabsize: u8,
+}
+
+impl Default for EngineBuilder {
+    fn default() -> Self {
+        return Self {
+            natives: NativePolicy::All,
+            host_natives: Vec::new(),
+            timeout: None,
+            max_depth: None,
+            stack_size: DEFAULT_STACK_SIZE,
+            max_steps: None,
+            max_memory: MAX_MEMORY,
+            checked_division: false,
+            number_format: NumberFormat::default(),
+            sink: OutputSink::Quiet,
+            tabsize: 2,
+        };
+    }
+}
+
+impl EngineBuilder {
+    /// Limits how long each script may run for, after which it fails with
+    /// `EngineError::LimitExceeded`, holding the output it printed before it was stopped.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        return self;
+    }
+
+    /// Limits how many nested calls each script can make, after which it fails with
+    /// `EngineError::LimitExceeded`. By default this is as many as fit on the stack, and a limit
+    /// larger than that is lowered to it.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        return self;
+    }
+
+    /// Sets how many bytes of stack the thread that runs the scripts has, which decides how
+    /// many nested calls they can make before they are stopped rather than overflowing the
+    /// stack, which would abort the process. Scripts run on the thread that calls the engine,
+    /// so this is the size of that thread's stack. By default this is `DEFAULT_STACK_SIZE`,
+    /// which is no more than any thread Rust starts has, so a host that runs scripts on a
+    /// thread with a larger stack can allow deeper recursion by passing its size.
+    pub fn with_stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = stack_size;
+        return self;
+    }
+
+    /// Limits how many statements each script may execute, after which it fails with
+    /// `EngineError::LimitExceeded`. Unlike a timeout, the same script is always stopped at the
+    /// same point, however busy the host is.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        return self;
+    }
+
+    /// Limits roughly how many bytes the variables of the scripts may take up, after which the
+    /// script that went over fails with `EngineError::LimitExceeded`. By default this is
+    /// `MAX_MEMORY`. The globals that scripts share count towards the limit of each script.
+    pub fn with_max_memory(mut self, max_memory: usize) -> Self {
+        self.max_memory = max_memory;
+        return self;
+    }
+
+    /// Makes dividing a number by zero raise an error that scripts can catch, rather than giving
+    /// infinity or NaN.
+    pub fn with_checked_division(mut self, checked_division: bool) -> Self {
+        self.checked_division = checked_division;
+        return self;
+    }
+
+    /// Sets how numbers are written when scripts print them or convert them to strings, such
+    /// as how many significant digits they are rounded to and how large or small they must be
+    /// to be written in scientific notation.
+    pub fn with_number_format(mut self, number_format: NumberFormat) -> Self {
+        self.number_format = number_format;
+        return self;
+    }
+
+    /// Registers a native function that scripts can call by the given name.
+    pub fn with_native(mut self, name: &str, arity: usize, fun: NativeFn) -> Self {
+        self.host_natives.push(NativeFunc::new(name.to_string(), arity, fun));
+        return self;
+    }
+
+    /// Sets which of the built in native functions scripts may call.
+    pub fn with_natives(mut self, natives: NativePolicy) -> Self {
+        self.natives = natives;
+        return self;
+    }
+
+    /// Sets whether the lines printed by scripts are also written to stdout. By default they are
+    /// only returned to the host.
+    pub fn with_sink(mut self, sink: OutputSink) -> Self {
+        self.sink = sink;
+        return self;
+    }
+
+    /// Sets the number of spaces a tab is worth when reading indentation.
+    pub fn with_tabsize(mut self, tabsize: u8) -> Self {
+        self.tabsize = tabsize;
+        return self;
+    }
+
+    /// Builds the engine.
+    pub fn build(self) -> Engine {
+        let names: Vec<String> =
+            self.host_natives.iter().map(|native| native.name.clone()).collect();
+        let max_depth = max_depth_for(self.stack_size);
+
+        let mut evaluator = Evaluator::with_natives(self.natives)
+            .with_timeout(self.timeout)
+            .with_max_depth(self.max_depth.map_or(max_depth, |limit| limit.min(max_depth)))
+            .with_max_steps(self.max_steps)
+            .with_max_memory(self.max_memory)
+            .with_checked_division(self.checked_division)
+            .with_number_format(self.number_format)
+            .with_sink(self.sink);
+        for native in self.host_natives {
+            evaluator = evaluator.with_native(native);
+        }
+
+        let analyser = SemanticAnalyser::new(Vec::new())
+            .with_mode(ScopingMode::Repl)
+            .with_globals(names);
+
+        return Engine { evaluator, analyser, tabsize: self.tabsize };
+    }
+}
+
+/// Runs pyru scripts on behalf of a host program.
+///
+/// ## Fields
+/// - `evaluator`: The evaluator that runs every script, holding the globals they define.
+/// - `analyser`: The semantic analyser, which remembers the globals declared by each script.
+/// - `tabsize`: The number of spaces a tab is worth when reading indentation.
+pub struct Engine {
+    evaluator: Evaluator,
+    analyser: SemanticAnalyser,
+    tabsize: u8,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        return Self::builder().build();
+    }
+}
+
+impl Engine {
+    /// Creates an engine with the default configuration.
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Returns a builder for configuring an engine.
+    pub fn builder() -> EngineBuilder {
+        return EngineBuilder::default();
+    }
+
+    /// Runs a script and returns the lines it printed.
+    pub fn run(&mut self, source: &str) -> Result<Vec<String>, EngineError> {
+        let tokens = Lexer::new(source.to_string(), self.tabsize).run()?;
+        let ast = Parser::new(tokens).parse()?;
+        self.analyser.run_next(ast.clone())?;
+        self.evaluator.resolve(&ast);
+        return self.evaluator.interpret(ast).map_err(|e| stopped(&mut self.evaluator, e));
+    }
+
+    /// Converts a Rust value into a value and defines it as a global, so that the scripts run
+    /// afterwards can use it.
+    pub fn set_global<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) -> Result<(), EngineError> {
+        let value = to_value(value)?;
+        self.evaluator.define_global(name, value);
+        self.analyser.declare_global(name.to_string());
+        return Ok(());
+    }
+
+    /// Converts the value of a global, defined by the host or by one of the scripts run so far,
+    /// into a Rust value.
+    pub fn get_global<T: DeserializeOwned>(&self, name: &str) -> Result<T, EngineError> {
+        return match self.evaluator.global(name) {
+            Some(value) => Ok(from_value(&value)?),
+            None => Err(EngineError::GlobalNotFound { name: name.to_string() }),
+        };
+    }
+
+    /// Returns roughly how many bytes the globals and any other variables still alive take up,
+    /// which is what counts towards the memory limit.
+    pub fn memory_usage(&self) -> usize {
+        return self.evaluator.memory_usage();
+    }
+
+    /// Returns a function defined by one of the scripts run so far, so that the host can call
+    /// it.
+    pub fn get_function(&mut self, name: &str) -> Result<PyruFunction<'_>, EngineError> {
+        return match self.evaluator.global(name) {
+            Some(function @ Value::Function(_)) => {
+                Ok(PyruFunction { evaluator: &mut self.evaluator, function })
+            },
+            _ => Err(EngineError::FunctionNotFound { name: name.to_string() }),
+        };
+    }
+}
+
+/// A function defined by a script, which the host can call with `PyruFunction::call`.
+///
+/// ## Fields
+/// - `evaluator`: The evaluator of the engine the function was defined in.
+/// - `function`: The function itself.
+pub struct PyruFunction<'e> {
+    evaluator: &'e mut Evaluator,
+    function: Value,
+}
+
+impl PyruFunction<'_> {
+    /// Calls the function with the given arguments and returns the value it returns. Any limit
+    /// on how long scripts may run for also applies to the call.
+    pub fn call(&mut self, args: &[Value]) -> Result<Value, EngineError> {
+        return self.evaluator
+            .call_function(self.function.clone(), args.to_vec())
+            .map_err(|e| stopped(self.evaluator, e));
+    }
+}
+
+/// Converts an error from the evaluator into an engine error, keeping the output printed before
+/// it and the line of the statement the script was stopped at if a limit was exceeded.
+fn stopped(evaluator: &mut Evaluator, error: EvaluatorError) -> EngineError {
+    return match error.limit() {
+        Some(limit) => EngineError::LimitExceeded {
+            limit,
+            line: evaluator.stopped_at(),
+            output: evaluator.take_output(),
+            error,
+        },
+        None => EngineError::Evaluator(error),
+    };
+}
@@ -9,6 +9,8 @@
 //! - `ParserError`: Errors that occur during the parsing phase.
 //! - `SemanticAnalyserError`: Errors that occur during the semantic analysis phase.
 //! - `EvaluatorError`: Errors that occur during the evaluation phase.
+//! - `MarshalError`: Errors that occur when converting between values and Rust values.
+//! - `EngineError`: Any of the above, as returned by the `Engine` facade.
 //!
 //! Each error variant provides detailed information about the nature of the error, including
 //! the line number, position, and additional context where applicable. This helps in debugging
@@ -17,7 +19,7 @@
 //! ## Example
 //!
 //! ```rust
-//! use crate::error::LexerError;
+//! use pyru::error::LexerError;
 //!
 //! let error = LexerError::UnexpectedCharacter {
 //!     c: 'x',
@@ -35,9 +37,11 @@
 //! structured and consistent manner. They implement the `thiserror::Error` trait, allowing
 //! them to be easily formatted and displayed.
 
+use std::time::Duration;
+
 use thiserror::Error;
 
-use crate::{expr::Expr, stmt::Stmt};
+use crate::{evaluator::Limit, expr::Expr, stmt::Stmt, token::Token, value::Value};
 
 /// Represents errors that occur during the lexical analysis phase.
 #[derive(Error, Debug)]
@@ -46,10 +50,18 @@ pub enum LexerError {
     #[error("Unterminated string on line {line}")]
     UnterminatedString { line: usize, start: usize, end: usize },
 
+    /// Occurs when a backslash in a string is followed by a character that cannot be escaped.
+    #[error("Invalid escape sequence '\\{c}' on line {line}")]
+    InvalidEscape { c: char, line: usize, start: usize, end: usize },
+
     /// Occurs when an unrecognized character is encountered.
     #[error("Unexpected character '{c}' on line {line}")]
     UnexpectedCharacter { c: char, line: usize, start: usize, end: usize },
 
+    /// Occurs when an underscore in a number is not between two digits, e.g. `1__000` or `10_`.
+    #[error("Invalid underscore in the number '{lexeme}' on line {line}, underscores must separate digits")]
+    InvalidNumericSeparator { lexeme: String, line: usize, start: usize, end: usize },
+
     /// Occurs when the lexer expects another character but there are no more.
     #[error("No more characters left on line {line}")]
     NoCharactersLeft { line: usize, start: usize, end: usize },
@@ -59,8 +71,15 @@ pub enum LexerError {
     CannotPeekAtTheEnd { line: usize, start: usize, end: usize },
 
     /// Occurs when incorrect indentation is detected.
-    #[error("Incorrect indentation on line {line}")]
-    IncorrectIndentation { line: usize },
+    #[error(
+        "Incorrect indentation on line {line}, which is indented {spaces} spaces but indentation \
+        must be a multiple of {tabsize}"
+    )]
+    IncorrectIndentation { line: usize, spaces: u8, tabsize: u8 },
+
+    /// Occurs when the source code is longer than the lexer accepts.
+    #[error("The source code is {length} bytes long, but the limit is {limit} bytes")]
+    SourceTooLarge { length: usize, limit: usize },
 }
 
 /// Represents errors that occur during the parsing phase.
@@ -90,6 +109,39 @@ pub enum ParserError {
     #[error("Expect ';' after return value '{value}' on line {line}")]
     ExpectedSemicolonAfterReturnValue { value: String, line: usize },
 
+    /// Occurs when a type annotation does not name a type.
+    #[error("Expected the name of a type but found '{found}' on line {line}")]
+    ExpectedTypeName { found: String, line: usize },
+
+    /// Occurs when a semicolon is missing after a `break` keyword.
+    #[error("Expect ';' after 'break' on line {line}")]
+    ExpectedSemicolonAfterBreak { line: usize },
+
+    /// Occurs when the `import` keyword is not followed by the name of a module.
+    #[error("Expect a module name after 'import' on line {line}")]
+    ExpectedModuleNameAfterImport { line: usize },
+
+    /// Occurs when a semicolon is missing after an `import` statement.
+    #[error("Expect ';' after the module being imported on line {line}")]
+    ExpectedSemicolonAfterImport { line: usize },
+
+    /// Occurs when a semicolon is missing after a `pass` keyword.
+    #[error("Expect ';' after 'pass' on line {line}")]
+    ExpectedSemicolonAfterPass { line: usize },
+
+    /// Occurs when the `del` keyword is not followed by a variable name or an item of a list or
+    /// dictionary held in a variable.
+    #[error("Expect a variable name or an item after 'del' on line {line}")]
+    ExpectedVariableNameAfterDel { line: usize },
+
+    /// Occurs when a semicolon is missing after a `del` statement.
+    #[error("Expect ';' after the variable being deleted on line {line}")]
+    ExpectedSemicolonAfterDel { line: usize },
+
+    /// Occurs when a semicolon is missing after a `continue` keyword.
+    #[error("Expect ';' after 'continue' on line {line}")]
+    ExpectedSemicolonAfterContinue { line: usize },
+
     /// Occurs when a left parenthesis is missing after a `while` keyword.
     #[error("Expect '(' after 'while' on line {line}")]
     ExpectedLParenAfterWhile { line: usize },
@@ -112,7 +164,7 @@ pub enum ParserError {
 
     /// Occurs when more than 255 arguments are passed to a function.
     #[error("More than 255 arguments have been passed to {callee}")]
-    TooManyArguments { callee: Expr },
+    TooManyArguments { callee: Box<Expr> },
 
     /// Occurs when a right parenthesis is missing after function arguments.
     #[error("Expect ')' after arguments on line {line}")]
@@ -142,6 +194,34 @@ pub enum ParserError {
     #[error("Expect '(' after function name on line {line}")]
     ExpectedLParenAfterFunctionName { line: usize },
 
+    /// Occurs when a class name is expected but not found.
+    #[error("Expect class name on line {line}")]
+    ExpectedClassName { line: usize },
+
+    /// Occurs when an enum name is expected but not found.
+    #[error("Expect enum name on line {line}")]
+    ExpectedEnumName { line: usize },
+
+    /// Occurs when the name of a variant is missing from an enum declaration.
+    #[error("Expect the name of a variant in the enum on line {line}")]
+    ExpectedVariantName { line: usize },
+
+    /// Occurs when a semicolon is missing after the variants of an enum.
+    #[error("Expect ';' after the variants of the enum on line {line}")]
+    ExpectedSemicolonAfterEnum { line: usize },
+
+    /// Occurs when a method name is expected but not found.
+    #[error("Expect method name on line {line}")]
+    ExpectedMethodName { line: usize },
+
+    /// Occurs when a left parenthesis is missing after a method name.
+    #[error("Expect '(' after method name on line {line}")]
+    ExpectedLParenAfterMethodName { line: usize },
+
+    /// Occurs when the body of a class contains something other than a method.
+    #[error("Expected a method declaration in the class body on line {line}")]
+    ExpectedMethodDeclaration { line: usize },
+
     /// Occurs when more than 255 parameters are passed to a function.
     #[error("More than 255 parameters have been passed to the '{name}' on line {line}")]
     TooManyParameters { name: String, line: usize },
@@ -150,10 +230,30 @@ pub enum ParserError {
     #[error("Expect a parameter name on line {line}")]
     ExpectedParameterName { line: usize },
 
+    /// Occurs when a right bracket is missing after an index or a slice.
+    #[error("Expected ']' after the index on line {line}")]
+    ExpectedRBrackAfterIndex { line: usize },
+
     /// Occurs when a right bracket is missing after list values.
     #[error("Expected ']' after the values of a list on line {line}")]
     ExpectedRBrackAfterValues { line: usize },
 
+    /// Occurs when a right parenthesis is missing after tuple values.
+    #[error("Expected ')' after the values of a tuple on line {line}")]
+    ExpectedRParenAfterValues { line: usize },
+
+    /// Occurs when the names in a destructuring declaration are not followed by a value.
+    #[error("Expected '=' after the names being destructured on line {line}")]
+    ExpectedDestructureInitializer { line: usize },
+
+    /// Occurs when a colon is missing between a key and its value in a dictionary.
+    #[error("Expected ':' after the key of a dictionary entry on line {line}")]
+    ExpectedColonAfterKey { line: usize },
+
+    /// Occurs when a right brace is missing after dictionary entries.
+    #[error("Expected '}}' after the entries of a dictionary on line {line}")]
+    ExpectedRBraceAfterEntries { line: usize },
+
     /// Occurs when a method is called on a non-identifier.
     #[error("Can only call methods on identifiers, not '{value}' on line {line}")]
     CanOnlyCallIdentifiers { value: String, line: usize },
@@ -174,18 +274,52 @@ pub enum ParserError {
     #[error("Expected ':' at the end of line {line}")]
     ExpectedColon { line: usize },
 
-    /// Occurs when a loop body is missing.
-    #[error("Expected a body in the {type_} loop on line {line}")]
-    ExpectedBody { type_: String, line: usize },
+    /// Occurs when the body of a block is not indented one level further than the line that
+    /// opened it.
+    #[error(
+        "Expected an indented {type_} body on line {line}, which is indented {found} levels but \
+        the block expects {expected}"
+    )]
+    ExpectedBody { type_: String, line: usize, found: usize, expected: usize },
+
+    /// Occurs when a line is indented further than the block it is in, without a statement
+    /// opening a new block before it.
+    #[error("Line {line} is indented {found} levels but the block expects {expected}")]
+    UnexpectedIndent { line: usize, found: usize, expected: usize },
 
     /// Occurs when a dedent is expected but not found.
     #[error("Expected a dedent on line {line}")]
     ExpectedDedent { line: usize },
 
+    /// Occurs when a semicolon is missing after a `raise` statement.
+    #[error("Expected ';' after the raised value on line {line}")]
+    ExpectedSemicolonAfterRaise { line: usize },
+
+    /// Occurs when the body of a `match` statement contains something other than a `case` arm.
+    #[error("Expected a 'case' arm in the match body on line {line}")]
+    ExpectedCase { line: usize },
+
+    /// Occurs when a `case` arm does not have a literal, range or wildcard pattern.
+    #[error("Expected a literal, range or '_' pattern after 'case' on line {line}")]
+    ExpectedPattern { line: usize },
+
+    /// Occurs when a `case` arm follows a wildcard arm, which matches every value.
+    #[error("The case on line {line} can never match, as it comes after a '_' case")]
+    UnreachableCase { line: usize },
+
+    /// Occurs when a `try` statement has neither an `except` nor a `finally` branch.
+    #[error("Expected an 'except' or 'finally' branch after the try body on line {line}")]
+    ExpectedExceptOrFinally { line: usize },
+
     /// Occurs when a colon is missing after a `while` loop condition.
     #[error("Expected ':' after the while loop condition on line {line}")]
     ExpectedColonAfterWhileCondition { line: usize },
 
+    /// Occurs when expressions or blocks are nested inside of each other more than
+    /// `MAX_NESTING` levels deep.
+    #[error("Expressions and blocks cannot be nested more than {limit} levels deep, but they are on line {line}")]
+    TooDeeplyNested { limit: usize, line: usize },
+
     /// Represents an unknown parser error.
     #[error("Unknown parser error")]
     Unknown,
@@ -196,11 +330,11 @@ pub enum ParserError {
 pub enum SemanticAnalyserError {
     /// Occurs when a statement does not match the expected statement.
     #[error("The statement provided ({stmt}), was different to the statement expected ({expected})")]
-    DifferentStatement { stmt: Stmt, expected: String },
+    DifferentStatement { stmt: Box<Stmt>, expected: String },
 
     /// Occurs when an expression does not match the expected expression.
     #[error("The expression provided ({expr}), was different to the expression expected ({expected})")]
-    DifferentExpression { expr: Expr, expected: String },
+    DifferentExpression { expr: Box<Expr>, expected: String },
 
     /// Occurs when a variable is already declared in the current scope.
     #[error("Already a variable named '{name}' in this scope")]
@@ -213,6 +347,48 @@ pub enum SemanticAnalyserError {
     /// Occurs when a `return` statement is used outside of a function.
     #[error("Cannot return outside of a function")]
     CannotReturnOutsideFunction,
+
+    /// Occurs when a `break` statement is used outside of a loop.
+    #[error("Cannot break outside of a loop")]
+    CannotBreakOutsideLoop,
+
+    /// Occurs when a `del` statement names a variable that is not declared in the current scope.
+    #[error("Cannot delete '{name}' as it is not declared in this scope")]
+    CannotDeleteUndeclaredVariable { name: String },
+
+    /// Occurs when a `continue` statement is used outside of a loop.
+    #[error("Cannot continue outside of a loop")]
+    CannotContinueOutsideLoop,
+
+    /// Occurs when an `import` statement names a module that is not in the standard library.
+    #[error("There is no module named '{name}' to import on line {line}")]
+    UnknownModule { name: String, line: usize },
+
+    /// Occurs when an enum declares two variants with the same name.
+    #[error("The enum '{enum_name}' declares the variant '{name}' more than once on line {line}")]
+    DuplicateEnumVariant { enum_name: String, name: String, line: usize },
+
+    /// Occurs when a method does not have a parameter for the instance it is called on.
+    #[error("The method '{name}' must take 'self' as its first parameter")]
+    MethodWithoutSelf { name: String },
+
+    /// Occurs when a `break` or `continue` statement names a label that is not the label of an
+    /// enclosing loop.
+    #[error("There is no enclosing loop labelled '{name}'")]
+    UndefinedLabel { name: String },
+
+    /// Occurs when an annotation names a type that does not exist.
+    #[error("Unknown type '{name}' on line {line}")]
+    UnknownType { name: String, line: usize },
+
+    /// Occurs when a value that can be seen to have one type is given to a variable, parameter
+    /// or function return annotated with another.
+    #[error("'{name}' is annotated as '{expected}' but was given a value of type '{found}' on line {line}")]
+    TypeMismatch { name: String, expected: String, found: String, line: usize },
+
+    /// Occurs when a loop is given the same label as a loop it is nested in.
+    #[error("The label '{name}' is already used by an enclosing loop")]
+    LabelAlreadyInUse { name: String },
 }
 
 /// Represents errors that occur during the evaluation phase.
@@ -220,15 +396,15 @@ pub enum SemanticAnalyserError {
 pub enum EvaluatorError {
     /// Occurs when a statement does not match the expected statement.
     #[error("The statement provided ({stmt}), was different to the statement expected ({expected})")]
-    DifferentStatement { stmt: Stmt, expected: String },
+    DifferentStatement { stmt: Box<Stmt>, expected: String },
 
     /// Occurs when an expression does not match the expected expression.
     #[error("The expression provided ({expr}), was different to the expected ({expected})")]
-    DifferentExpression { expr: Expr, expected: String },
+    DifferentExpression { expr: Box<Expr>, expected: String },
 
-    /// Occurs when a literal value is expected but not found.
-    #[error("Expected a literal value")]
-    ExpectedLiteralValue,
+    /// Occurs when an `import` statement names a module that is not in the standard library.
+    #[error("There is no module named '{name}' to import")]
+    UnknownModule { name: String },
 
     /// Occurs when a list is expected in a membership expression but not found.
     #[error("Expected a list in the membership expression")]
@@ -303,11 +479,295 @@ pub enum EvaluatorError {
     #[error("The item could not be found in the list")]
     ItemNotFound,
 
+    /// Occurs when a number is divided by zero while division is checked.
+    #[error("Cannot divide by zero in {left} {operator} {right} on line {line}")]
+    DivisionByZero { left: String, operator: String, right: String, line: usize },
+
+    /// Occurs when a list would be added to itself, or to a list or collection inside of it.
+    #[error("A list cannot hold itself")]
+    ListCannotHoldItself,
+
+    /// Occurs when a value that is not a tuple or list is destructured.
+    #[error("Only tuples and lists can be destructured")]
+    CannotDestructureValue,
+
+    /// Occurs when the number of names being declared differs from the number of items.
+    #[error("Expected {names} values to destructure but got {items}")]
+    DestructureLengthMismatch { names: usize, items: usize },
+
+    /// Occurs when a value that is not a literal is used as a dictionary key.
+    #[error("Dictionary keys must be strings, numbers, booleans or null")]
+    InvalidDictKey,
+
+    /// Occurs when a value that is not a literal is added to a set.
+    #[error("Set items must be strings, numbers, booleans or null")]
+    InvalidSetItem,
+
+    /// Occurs when a set is expected but a different value is found.
+    #[error("Expected a set")]
+    ExpectedSet,
+
+    /// Occurs when a set is created from a value that is not a collection.
+    #[error("A set can only be created from a list, tuple or set")]
+    CannotCreateSet,
+
+    /// Occurs when an invalid method is called on a set.
+    #[error("That method does not exist on a set")]
+    InvalidSetMethod,
+
+    /// Occurs when a native function that works on a list is passed a value that is not a list.
+    #[error("The {name} function must be passed a list")]
+    ExpectedListArgument { name: String },
+
+    /// Occurs when a native function that works on a list of numbers is passed a list holding
+    /// something else.
+    #[error("The {name} function must be passed a list of numbers, but it contains a {type_name}")]
+    ExpectedNumberList { name: String, type_name: String },
+
+    /// Occurs when a native function that needs at least one item is passed an empty list.
+    #[error("The {name} function cannot be passed an empty list")]
+    EmptyListArgument { name: String },
+
+    /// Occurs when a native function that works on a function is passed a value that is not one.
+    #[error("The {name} function must be passed a function")]
+    ExpectedFunctionArgument { name: String },
+
+    /// Occurs when the length of a value that is not a string or collection is asked for.
+    #[error("A value of type {type_name} has no length")]
+    CannotGetLength { type_name: String },
+
+    /// Occurs when `fetch` does not get a successful response from a URL.
+    #[error("Could not fetch {url}: {reason}")]
+    FetchFailed { url: String, reason: String },
+
+    /// Occurs when the template passed to `format` has a placeholder without a value or an
+    /// unmatched brace.
+    #[error("Invalid format template: {reason}")]
+    InvalidFormat { reason: String },
+
+    /// Occurs when CSV text has a quoted field that is never closed.
+    #[error("The CSV has a quoted field starting on line {line} that is never closed")]
+    InvalidCsv { line: usize },
+
+    /// Occurs when `csvStringify` is passed something other than a list of rows.
+    #[error("The csvStringify function must be passed a list of rows, each a list or tuple")]
+    ExpectedCsvRows,
+
+    /// Occurs when the operating system cannot provide random bytes.
+    #[error("Random numbers could not be generated")]
+    RandomnessUnavailable,
+
+    /// Occurs when the square root of a negative number is taken.
+    #[error("Cannot take the square root of a negative number")]
+    NegativeSquareRoot,
+
+    /// Occurs when a string that does not hold a number is converted to a number.
+    #[error("'{text}' is not a valid number")]
+    InvalidNumber { text: String },
+
+    /// Occurs when a value that is not a string or number is converted to a number.
+    #[error("A value of type {type_name} cannot be converted to a number")]
+    CannotConvertToNumber { type_name: String },
+
+    /// Occurs when a deque is created from a value that is not a list or tuple.
+    #[error("A deque can only be created from a list, tuple or deque")]
+    CannotCreateDeque,
+
+    /// Occurs when an invalid method is called on a deque.
+    #[error("That method does not exist on a deque")]
+    InvalidDequeMethod,
+
+    /// Occurs when an invalid method is called on a string.
+    #[error("That method does not exist on a string")]
+    InvalidStringMethod,
+
+    /// Occurs when a string method is passed an argument that is not a string.
+    #[error("Expected the argument to be a string")]
+    ExpectedStringArgument,
+
+    /// Occurs when a key cannot be found in a dictionary.
+    #[error("The key '{key}' could not be found in the dictionary")]
+    KeyNotFound { key: String },
+
+    /// Occurs when a slice is taken of a dictionary.
+    #[error("A dictionary cannot be sliced")]
+    CannotSliceDict,
+
+    /// Occurs when an invalid method is called on a dictionary.
+    #[error("That method does not exist on a dictionary")]
+    InvalidDictMethod,
+
+    /// Occurs when a value that does not support index assignment is assigned to by index.
+    #[error("The value does not support index assignment")]
+    CannotAssignToIndex,
+
+    /// Occurs when an item is deleted from a value that is not a list or dictionary.
+    #[error("The value does not support deleting items")]
+    CannotDeleteItem,
+
+    /// Occurs when a slice of a list is assigned a value that is not a list or tuple.
+    #[error("A slice can only be assigned the items of a list or tuple")]
+    CannotAssignToSlice,
+
+    /// Occurs when a program creates a string or collection larger than the interpreter allows.
+    #[error("The value created on line {line} is too large")]
+    ValueTooLarge { line: usize },
+
+    /// Occurs when a list is repeated by a number that is not a whole number.
+    #[error("A list can only be repeated a whole number of times")]
+    CannotRepeatList,
+
     /// Occurs when two values cannot be compared.
     #[error("The two values could not be compared")]
     CannotCompareValues,
 
-    /// Occurs when a value passed to the hash function is not a string.
-    #[error("The value passed in to the hash function must be a string")]
-    CannotHashValue,
+    /// Occurs when a value passed to the hash function cannot be printed, such as a function.
+    #[error("A value of type {type_name} cannot be hashed")]
+    CannotHashValue { type_name: String },
+
+    /// Occurs when the hash function is asked for an algorithm it does not offer.
+    #[error("'{name}' is not a hash algorithm, expected sha256, sha512, sha1 or md5")]
+    UnknownHashAlgorithm { name: String },
+
+    /// Occurs when a `break` statement is executed outside of a loop.
+    #[error("Cannot break outside of a loop")]
+    BreakOutsideLoop,
+
+    /// Occurs when a `continue` statement is executed outside of a loop.
+    #[error("Cannot continue outside of a loop")]
+    ContinueOutsideLoop,
+
+    /// Occurs when a program prints more lines than the evaluator allows.
+    #[error("The program printed more than its limit of {limit} lines")]
+    OutputLimitExceeded { limit: usize },
+
+    /// Occurs when a program runs for longer than the evaluator's time limit.
+    #[error("The program ran for longer than its time limit of {limit:?}")]
+    TimeLimitExceeded { limit: Duration },
+
+    /// Occurs when a program is stopped by cancelling the token it was run with.
+    #[error("The program was cancelled before it finished")]
+    Cancelled,
+
+    /// Occurs when a program makes more nested calls than the evaluator's recursion limit.
+    #[error("The program made more than its limit of {limit} nested calls")]
+    RecursionLimitExceeded { limit: usize },
+
+    /// Occurs when the variables of a program take up more memory than the evaluator's memory
+    /// limit.
+    #[error("The program used more than its memory limit of {limit} bytes")]
+    MemoryLimitExceeded { limit: usize },
+
+    /// Occurs when a program executes more statements than the evaluator's step limit.
+    #[error("The program executed more than its limit of {limit} statements")]
+    StepLimitExceeded { limit: usize },
+
+    /// Occurs when a program raises a value with a `raise` statement.
+    #[error("{value} (raised on line {line})")]
+    Raised { value: Box<Value>, line: usize },
+
+    /// Occurs when a `for` loop is given a value that is not a list, deque or string to loop over.
+    #[error("A {type_name} cannot be looped over, only a list, deque or string can be")]
+    CannotIterate { type_name: String },
+
+    /// Occurs when a value other than a list or tuple is spread into the arguments of a call.
+    #[error("Only a list or tuple can be spread into arguments, but a {type_name} was given")]
+    CannotSpread { type_name: String },
+
+    /// Occurs when a field or method that an instance does not have is used.
+    #[error("The instance has no field or method named '{name}'")]
+    UndefinedProperty { name: String },
+
+    /// Occurs when a variant that an enum does not have is used.
+    #[error("The enum '{enum_name}' has no variant named '{name}'")]
+    UndefinedVariant { enum_name: String, name: String },
+
+    /// Occurs when a method that does not take `self` is called on an instance.
+    #[error("The method '{name}' must take 'self' as its first parameter")]
+    ExpectedSelfParameter { name: String },
+
+    /// Occurs when a property of a value that is not an instance or an enum is used.
+    #[error("Only instances and enums have properties, but '{name}' is a {type_name}")]
+    OnlyInstancesHaveProperties { name: String, type_name: String },
+}
+
+impl EvaluatorError {
+    /// Creates the error for a variable that is not defined.
+    pub fn undefined_variable(name: &Token) -> Self {
+        return EvaluatorError::UndefinedVariable {
+            name: name.lexeme.to_string(),
+            start: name.start,
+            end: name.end,
+            line: name.line,
+        };
+    }
+
+    /// Returns whether the error can be caught by a `try` statement. Exceeding a limit or being
+    /// cancelled cannot be caught, so that a program cannot ignore either.
+    pub fn is_catchable(&self) -> bool {
+        return self.limit().is_none() && !matches!(self, EvaluatorError::Cancelled);
+    }
+
+    /// Returns the limit that the error reports being exceeded, if it is one.
+    pub fn limit(&self) -> Option<Limit> {
+        return match self {
+            EvaluatorError::TimeLimitExceeded { .. } => Some(Limit::Time),
+            EvaluatorError::ValueTooLarge { .. } => Some(Limit::Memory),
+            EvaluatorError::OutputLimitExceeded { .. } => Some(Limit::Output),
+            EvaluatorError::RecursionLimitExceeded { .. } => Some(Limit::Recursion),
+            EvaluatorError::StepLimitExceeded { .. } => Some(Limit::Steps),
+            EvaluatorError::MemoryLimitExceeded { .. } => Some(Limit::Memory),
+            _ => None,
+        };
+    }
+}
+
+/// Represents errors that occur when converting between the values of the language and Rust
+/// values.
+#[derive(Error, Debug)]
+pub enum MarshalError {
+    /// Occurs when a Rust value cannot be represented as a value of the language.
+    #[error("Could not convert the Rust value into a value: {message}")]
+    IntoValue { message: String },
+
+    /// Occurs when a value cannot be converted into the Rust type that was asked for.
+    #[error("Could not convert the value into the Rust type: {message}")]
+    FromValue { message: String },
+}
+
+/// Represents an error from any phase of running a program through an `Engine`.
+#[derive(Error, Debug)]
+pub enum EngineError {
+    /// Occurs when the source code cannot be lexed.
+    #[error(transparent)]
+    Lexer(#[from] LexerError),
+
+    /// Occurs when the source code cannot be parsed.
+    #[error(transparent)]
+    Parser(#[from] ParserError),
+
+    /// Occurs when the program fails semantic analysis.
+    #[error(transparent)]
+    SemanticAnalyser(#[from] SemanticAnalyserError),
+
+    /// Occurs when the program fails while it is being evaluated.
+    #[error(transparent)]
+    Evaluator(#[from] EvaluatorError),
+
+    /// Occurs when the program is stopped by one of its limits, keeping the output it printed
+    /// before it was stopped and the line of the statement it was stopped at.
+    #[error("{error}")]
+    LimitExceeded { limit: Limit, line: Option<usize>, output: Vec<String>, error: EvaluatorError },
+
+    /// Occurs when a value cannot be passed between the host and a script.
+    #[error(transparent)]
+    Marshal(#[from] MarshalError),
+
+    /// Occurs when the host asks for a function that no script has defined.
+    #[error("No function named '{name}' has been defined")]
+    FunctionNotFound { name: String },
+
+    /// Occurs when the host asks for a global that has not been defined.
+    #[error("No global named '{name}' has been defined")]
+    GlobalNotFound { name: String },
 }
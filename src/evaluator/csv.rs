@@ -0,0 +1,87 @@
+//! The `csv` module reads and writes comma separated values, for the `csvParse` and
+//! `csvStringify` native functions.
+//!
+//! ## Overview
+//!
+//! The format follows RFC 4180: rows end with a newline (`\n` or `\r\n`), fields are separated by
+//! commas, and a field holding a comma, quote or newline is wrapped in quotes, with each quote
+//! inside it doubled. A newline after the last row is optional, and every field is read as a
+//! string.
+
+/// Parses CSV text into its rows of fields, or returns the line on which a quoted field starts
+/// that is never closed.
+pub fn parse(text: &str) -> Result<Vec<Vec<String>>, usize> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    let mut chars = text.chars().peekable();
+
+    // Whether anything has been read since the end of the last row
+    let mut started = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() => {
+                let opened = line;
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            field.push(c);
+                        }
+                        None => return Err(opened),
+                    }
+                }
+                started = true;
+            }
+            ',' => {
+                row.push(std::mem::take(&mut field));
+                started = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+                line += 1;
+                started = false;
+            }
+            c => {
+                field.push(c);
+                started = true;
+            }
+        }
+    }
+
+    if started {
+        row.push(field);
+        rows.push(row);
+    }
+    return Ok(rows);
+}
+
+/// Writes rows of fields as CSV text, with a newline after each row.
+pub fn stringify(rows: &[Vec<String>]) -> String {
+    let mut text = String::new();
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|field| quote(field)).collect();
+        text.push_str(&fields.join(","));
+        text.push('\n');
+    }
+    return text;
+}
+
+/// Wraps a field in quotes if it holds a character that would otherwise end it.
+fn quote(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", field.replace('"', "\"\""));
+    }
+    return field.to_string();
+}
@@ -0,0 +1,252 @@
+//! The `cycles` module frees the environments that are only kept alive by reference cycles.
+//!
+//! ## Overview
+//!
+//! A function holds the environment it was declared in, and that environment holds the
+//! function, so an environment in which a function or class was declared is never dropped by
+//! reference counting alone, even once nothing else can reach it. When the evaluator leaves the
+//! environment of a function call or a loop, `release` looks for such a cycle by trial deletion.
+//!
+//! Starting from the environment, it walks everything reachable through the variables of
+//! environments, the closures of functions, the methods of classes, the fields of instances and
+//! the items of lists, counting how many of the references to each of them it found. Anything with more references
+//! than were found is referred to from outside of what was walked, such as by a variable of an
+//! enclosing environment or a value the evaluator is still holding, so it is alive, as is
+//! everything it refers to. Every other environment that was walked can no longer be reached, so
+//! its variables are cleared, which breaks the cycles running through it.
+//!
+//! An environment is only walked if something other than the evaluator refers to it, so
+//! leaving a call that declared no functions costs nothing. Cycles made only of instances, such
+//! as an instance that holds itself in a field, are not looked for.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::{
+    callable::Func,
+    evaluator::Env,
+    value::Value,
+};
+
+/// Something that is shared by reference counting and can refer to an environment.
+///
+/// ## Variants
+/// - `Environment`: The variables of an environment.
+/// - `Methods`: The methods of a class, which every copy of the class and its instances share.
+/// - `Fields`: The fields of an instance, which every copy of the instance shares.
+/// - `Items`: The items of a list, which every copy of the list shares.
+enum Node {
+    Environment(Env),
+    Methods(Rc<HashMap<String, Func>>),
+    Fields(Rc<RefCell<HashMap<String, Value>>>),
+    Items(Rc<RefCell<Vec<Value>>>),
+}
+
+impl Node {
+    /// Returns the address that identifies the node.
+    fn id(&self) -> usize {
+        return match self {
+            Node::Environment(environment) => Rc::as_ptr(environment) as *const () as usize,
+            Node::Methods(methods) => Rc::as_ptr(methods) as *const () as usize,
+            Node::Fields(fields) => Rc::as_ptr(fields) as *const () as usize,
+            Node::Items(items) => Rc::as_ptr(items) as *const () as usize,
+        };
+    }
+
+    /// Returns the number of references to the node.
+    fn strong_count(&self) -> usize {
+        return match self {
+            Node::Environment(environment) => Rc::strong_count(environment),
+            Node::Methods(methods) => Rc::strong_count(methods),
+            Node::Fields(fields) => Rc::strong_count(fields),
+            Node::Items(items) => Rc::strong_count(items),
+        };
+    }
+}
+
+/// What the walk found out about a node.
+///
+/// ## Fields
+/// - `references`: The number of references to the node, if it was walked.
+/// - `found`: The number of references to the node found while walking.
+/// - `children`: The nodes that the node refers to.
+#[derive(Default)]
+struct Visit {
+    references: Option<usize>,
+    found: usize,
+    children: Vec<usize>,
+}
+
+/// Walks the nodes reachable from an environment, counting the references to each of them.
+///
+/// ## Fields
+/// - `visits`: What has been found out about each node, by its address.
+/// - `pending`: The nodes that have been found but not yet walked.
+/// - `environments`: The environments that have been walked.
+#[derive(Default)]
+struct Walk {
+    visits: HashMap<usize, Visit>,
+    pending: Vec<(usize, Node)>,
+    environments: Vec<(usize, Env)>,
+}
+
+impl Walk {
+    /// Records a reference from one node to another, queueing the other to be walked if it has
+    /// not been found before. The node passed in is a reference the walk holds of its own, so it
+    /// is not counted among the references to the node.
+    fn refer(&mut self, from: usize, to: Node) {
+        let id = to.id();
+        self.visits.entry(from).or_default().children.push(id);
+
+        let visit = self.visits.entry(id).or_default();
+        visit.found += 1;
+        if visit.references.is_none() {
+            visit.references = Some(to.strong_count() - 1);
+            self.pending.push((id, to));
+        }
+    }
+
+    /// Records the references held by a value that belongs to the given node.
+    fn value(&mut self, from: usize, value: &Value) {
+        match value {
+            Value::Function(function) => {
+                self.refer(from, Node::Environment(Rc::clone(function.closure())));
+            },
+            Value::Class(class) => self.refer(from, Node::Methods(Rc::clone(class.method_table()))),
+            Value::Instance(instance) => {
+                self.refer(from, Node::Fields(Rc::clone(instance.field_table())));
+                self.refer(from, Node::Methods(Rc::clone(instance.class.method_table())));
+            },
+            Value::List(list) => self.refer(from, Node::Items(Rc::clone(list.storage()))),
+            Value::Tuple(items) => {
+                for item in items {
+                    self.value(from, item);
+                }
+            },
+            Value::Deque(deque) => {
+                for item in deque.iter() {
+                    self.value(from, item);
+                }
+            },
+            Value::Dict(dict) => {
+                for item in dict.entries.values() {
+                    self.value(from, item);
+                }
+            },
+            Value::Enum(_)
+            | Value::Literal(_)
+            | Value::NativeFunction(_)
+            | Value::Set(_)
+            | Value::Variant(_) => {},
+        }
+    }
+
+    /// Records the references held by a node. Returns `false` if the node is being changed, in
+    /// which case nothing can be known about it.
+    fn node(&mut self, id: usize, node: Node) -> bool {
+        match node {
+            Node::Environment(environment) => {
+                let Ok(borrowed) = environment.try_borrow() else {
+                    return false;
+                };
+                for value in borrowed.values() {
+                    self.value(id, value);
+                }
+
+                // The enclosing environment is referred to, but is not walked, as it is alive
+                // for as long as anything it encloses is
+                if let Some(enclosing) = borrowed.enclosing() {
+                    let enclosing = Rc::as_ptr(enclosing) as *const () as usize;
+                    self.visits.entry(id).or_default().children.push(enclosing);
+                    self.visits.entry(enclosing).or_default().found += 1;
+                }
+                drop(borrowed);
+                self.environments.push((id, environment));
+            },
+            Node::Methods(methods) => {
+                for method in methods.values() {
+                    self.refer(id, Node::Environment(Rc::clone(method.closure())));
+                }
+            },
+            Node::Fields(fields) => {
+                let Ok(borrowed) = fields.try_borrow() else {
+                    return false;
+                };
+                for value in borrowed.values() {
+                    self.value(id, value);
+                }
+            },
+            Node::Items(items) => {
+                let Ok(borrowed) = items.try_borrow() else {
+                    return false;
+                };
+                for value in borrowed.iter() {
+                    self.value(id, value);
+                }
+            },
+        }
+        return true;
+    }
+
+    /// Returns the nodes that are referred to from outside of the walk, along with everything
+    /// they refer to.
+    fn alive(&self) -> HashSet<usize> {
+        let mut alive = HashSet::new();
+        let mut stack: Vec<usize> = self.visits
+            .iter()
+            .filter(|(_, visit)| visit.references.is_some_and(|references| references > visit.found))
+            .map(|(&id, _)| id)
+            .collect();
+
+        while let Some(id) = stack.pop() {
+            if !alive.insert(id) {
+                continue;
+            }
+            if let Some(visit) = self.visits.get(&id) {
+                stack.extend(visit.children.iter().copied());
+            }
+        }
+        return alive;
+    }
+}
+
+/// Releases an environment that the evaluator is leaving. If it can no longer be reached, the
+/// variables of it and of every other environment that can only be reached through it are
+/// cleared, freeing the cycles between them and the functions declared in them.
+pub fn release(environment: Env) {
+    // Nothing but the evaluator refers to the environment, so it is freed as normal
+    if Rc::strong_count(&environment) == 1 {
+        return;
+    }
+
+    let mut walk = Walk::default();
+    let root = Node::Environment(environment);
+    let id = root.id();
+
+    // The reference passed in is not one that the program can reach
+    walk.visits.entry(id).or_default().references = Some(root.strong_count() - 1);
+    walk.pending.push((id, root));
+
+    while let Some((id, node)) = walk.pending.pop() {
+        if !walk.node(id, node) {
+            return;
+        }
+    }
+
+    let alive = walk.alive();
+    if alive.contains(&id) {
+        return;
+    }
+
+    for (id, environment) in std::mem::take(&mut walk.environments) {
+        if !alive.contains(&id) {
+            // The variables are dropped after the environment is no longer borrowed, as
+            // dropping them can drop other environments
+            let variables = environment.borrow_mut().clear();
+            drop(variables);
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use std::{
-    cell::RefCell,
+    cell::Cell,
     collections::HashMap,
     fmt::{self, Debug},
+    mem,
     rc::Rc,
 };
 
@@ -14,54 +15,221 @@ use crate::{
 
 /// The `Environment` struct represents a scope in which variables are defined and stored.
 /// It supports nested scopes by maintaining a reference to an enclosing environment.
+///
+/// Variables are kept in the order they were declared, so that a variable the resolver has given
+/// a slot can be read by its index rather than by looking up its name. The names the resolver
+/// found declared in a scope are declared in its environment before any of them are defined,
+/// and a name keeps its index for as long as the environment exists, so a slot always refers to
+/// the same variable.
 /// 
 /// ## Fields
-/// - `values`: A `HashMap` that stores variable names and their corresponding values.
+/// - `variables`: The variables, in the order they were declared. A variable that has been
+///   declared but not defined yet, or that has been removed, has no value.
+/// - `indexes`: A `HashMap` from the name of each variable to its index in `variables`.
 /// - `enclosing`: An optional reference to an enclosing environment, allowing for nested scopes.
+/// - `size`: Roughly how many bytes the environment and the values of its variables take up.
+/// - `usage`: The total size of every environment that is still alive, shared by an environment
+///   with those it encloses.
 #[derive(Debug)]
 pub struct Environment {
-    values: HashMap<String, Rc<RefCell<Value>>>,
-    enclosing: Option<Env> // Composition
+    variables: Vec<Variable>,
+    indexes: HashMap<String, usize>,
+    enclosing: Option<Env>, // Composition
+    size: usize,
+    usage: Rc<Cell<usize>>,
+}
+
+/// A variable defined in an environment.
+///
+/// ## Fields
+/// - `name`: The name of the variable.
+/// - `value`: The value of the variable, if it is defined.
+/// - `size`: Roughly how many bytes the value took up when it was assigned, along with any
+///   changes made to it in place since.
+#[derive(Debug)]
+struct Variable {
+    name: String,
+    value: Option<Value>,
+    size: usize,
 }
 
 impl Environment {
-    /// Creates a new `Environment` instance.
+    /// Creates a new `Environment` instance. An environment without an enclosing one starts
+    /// counting the memory used by the environments inside of it.
     pub fn new(enclosing: Option<Env>) -> Self {
+        let usage = match &enclosing {
+            Some(enclosing) => Rc::clone(&enclosing.borrow().usage),
+            None => Rc::new(Cell::new(0)),
+        };
+        let size = mem::size_of::<Environment>();
+        usage.set(usage.get() + size);
+
         return Self {
-            values: HashMap::new(),
+            variables: Vec::new(),
+            indexes: HashMap::new(),
             enclosing,
+            size,
+            usage,
         }
     }
 
-    /// Defines a new variable in the current environment.
+    /// Creates a new empty `Environment` with no enclosing environment, which counts the
+    /// memory it uses along with this one.
+    pub fn inner(&self) -> Self {
+        let size = mem::size_of::<Environment>();
+        self.usage.set(self.usage.get() + size);
+
+        return Self {
+            variables: Vec::new(),
+            indexes: HashMap::new(),
+            enclosing: None,
+            size,
+            usage: Rc::clone(&self.usage),
+        };
+    }
+
+    /// Sets the environment enclosing this one.
+    pub fn set_enclosing(&mut self, enclosing: Env) {
+        self.enclosing = Some(enclosing);
+    }
+
+    /// Returns roughly how many bytes this environment, every environment enclosing it and
+    /// every environment they enclose take up, along with the values of their variables.
+    pub fn usage(&self) -> usize {
+        return self.usage.get();
+    }
+
+    /// Replaces the value of the variable at the index, keeping the size up to date.
+    fn replace(&mut self, index: usize, value: Option<Value>) -> Option<Value> {
+        let added = value.as_ref().map_or(0, Value::approximate_size);
+        let variable = &mut self.variables[index];
+        let removed = mem::replace(&mut variable.size, added);
+        let old = mem::replace(&mut variable.value, value);
+        self.resize(removed, added);
+        return old;
+    }
+
+    /// Changes the size of the environment by the given number of bytes removed and added.
+    fn resize(&mut self, removed: usize, added: usize) {
+        self.size = self.size - removed + added;
+        self.usage.set(self.usage.get() - removed + added);
+    }
+
+    /// Declares a variable in the current environment without defining it, returning its
+    /// index. A name that is already declared keeps its index.
+    pub fn declare(&mut self, name: &str) -> usize {
+        if let Some(&index) = self.indexes.get(name) {
+            return index;
+        }
+
+        let index = self.variables.len();
+        self.resize(0, name.len());
+        self.indexes.insert(name.to_string(), index);
+        self.variables.push(Variable { name: name.to_string(), value: None, size: 0 });
+        return index;
+    }
+
+    /// Defines a variable in the current environment. Defining a variable that is already
+    /// declared replaces its value and keeps its index.
     pub fn define(&mut self, name: String, value: Value) {
-        self.values.insert(name, Rc::new(RefCell::new(value)));
+        match self.indexes.get(&name) {
+            Some(&index) => {
+                self.replace(index, Some(value));
+            },
+            None => {
+                let size = value.approximate_size();
+                self.resize(0, name.len() + size);
+                self.indexes.insert(name.clone(), self.variables.len());
+                self.variables.push(Variable { name, value: Some(value), size });
+            }
+        }
+    }
+
+    /// Returns the index of a variable defined in this environment, excluding any enclosing
+    /// environments.
+    fn index_of(&self, name: &str) -> Option<usize> {
+        return self.indexes
+            .get(name)
+            .copied()
+            .filter(|&index| self.variables[index].value.is_some());
+    }
+
+    /// Returns the values of the variables defined in this environment, excluding any
+    /// enclosing environments.
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        return self.variables.iter().filter_map(|variable| variable.value.as_ref());
+    }
+
+    /// Returns the environment enclosing this one, if there is one.
+    pub fn enclosing(&self) -> Option<&Env> {
+        return self.enclosing.as_ref();
+    }
+
+    /// Removes every variable from the environment, returning their values so that they can be
+    /// dropped once the environment is no longer borrowed.
+    pub fn clear(&mut self) -> Vec<Value> {
+        self.indexes.clear();
+        let overhead = mem::size_of::<Environment>();
+        self.resize(self.size - overhead, 0);
+        return mem::take(&mut self.variables).into_iter().filter_map(|variable| variable.value).collect();
+    }
+
+    /// Returns the names and values of the variables defined in this environment, excluding
+    /// any enclosing environments.
+    pub fn snapshot(&self) -> HashMap<String, Value> {
+        return self.variables
+            .iter()
+            .filter_map(|variable| Some((variable.name.clone(), variable.value.clone()?)))
+            .collect();
+    }
+
+    /// Removes a variable from the current environment, returning its value. The name stays
+    /// declared, keeping its index. Variables in enclosing environments cannot be removed.
+    pub fn remove(&mut self, name: &Token) -> Result<Value, EvaluatorError> {
+        return match self.index_of(&name.lexeme).and_then(|index| self.replace(index, None)) {
+            Some(removed) => Ok(removed),
+            None => Err(EvaluatorError::undefined_variable(name)),
+        };
+    }
+
+    /// Returns whether a variable with the given name is defined in this environment, excluding
+    /// any enclosing environments.
+    pub fn contains(&self, name: &str) -> bool {
+        return self.index_of(name).is_some();
     }
 
     /// Retrieves the value of a variable from the current or enclosing environments.
     pub fn get(&self, name: &Token) -> Result<Value, EvaluatorError> {
-        return match self.values.get(&name.lexeme) {
-            Some(v) => Ok(v.borrow().clone()),
+        let value = self.indexes
+            .get(name.lexeme.as_str())
+            .and_then(|&index| self.variables[index].value.as_ref());
+
+        return match value {
+            Some(value) => Ok(value.clone()),
             None => {
                 if let Some(enclosing) = &self.enclosing {
                     return enclosing.borrow().get(name);
                 } else {
-                    return Err(EvaluatorError::UndefinedVariable {
-                        name: name.lexeme.clone(),
-                        start: name.start,
-                        end: name.end,
-                        line: name.line,
-                    });
+                    return Err(EvaluatorError::undefined_variable(name));
                 }
             }
         }
     }
 
+    /// Retrieves the value of the variable with the given index in the environment the given
+    /// number of environments out from this one, if it is defined.
+    pub fn get_at(&self, depth: usize, index: usize) -> Option<Value> {
+        if depth > 0 {
+            return self.enclosing.as_ref()?.borrow().get_at(depth - 1, index);
+        }
+
+        return self.variables.get(index)?.value.clone();
+    }
+
     /// Assigns a new value to an existing variable in the current or enclosing environments.
     pub fn assign(&mut self, name: &Token, value: Value) -> Result<Value, EvaluatorError> {
-        if self.values.contains_key(&name.lexeme) {
-            self.values
-                .insert(name.lexeme.clone(), Rc::new(RefCell::new(value.clone())));
+        if let Some(index) = self.index_of(&name.lexeme) {
+            self.replace(index, Some(value.clone()));
             return Ok(value);
         }
 
@@ -69,17 +237,53 @@ impl Environment {
             return enclosing.borrow_mut().assign(name, value);
         }
 
-        return Err(EvaluatorError::UndefinedVariable {
-            name: name.lexeme.clone(),
-            start: name.start,
-            end: name.end,
-            line: name.line
-        });
+        return Err(EvaluatorError::undefined_variable(name));
+    }
+
+    /// Changes the size of a variable in the current or enclosing environments by the given
+    /// number of bytes removed and added, after its value was changed in place.
+    pub fn resize_variable(&mut self, name: &Token, removed: usize, added: usize) {
+        if let Some(index) = self.index_of(&name.lexeme) {
+            // The value may be shared with other variables and changed through them, so no more
+            // can be removed than the variable was counted as taking up
+            let variable = &mut self.variables[index];
+            let removed = removed.min(variable.size);
+            variable.size = variable.size - removed + added;
+            self.resize(removed, added);
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().resize_variable(name, removed, added);
+        }
+    }
+
+    /// Assigns a new value to the variable with the given index in the environment the given
+    /// number of environments out from this one, returning whether it is defined and so was
+    /// assigned to.
+    pub fn assign_at(&mut self, depth: usize, index: usize, value: Value) -> bool {
+        if depth > 0 {
+            return match &self.enclosing {
+                Some(enclosing) => enclosing.borrow_mut().assign_at(depth - 1, index, value),
+                None => false,
+            };
+        }
+
+        return match self.variables.get(index) {
+            Some(Variable { value: Some(_), .. }) => {
+                self.replace(index, Some(value));
+                true
+            },
+            _ => false,
+        };
+    }
+}
+
+impl Drop for Environment {
+    fn drop(&mut self) {
+        self.usage.set(self.usage.get() - self.size);
     }
 }
 
 impl fmt::Display for Environment {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        return write!(f, "Environment(values: {:#?}, enclosing: {})", self.values, self.enclosing.is_some());
+        return write!(f, "Environment(values: {:#?}, enclosing: {})", self.snapshot(), self.enclosing.is_some());
     }
 }
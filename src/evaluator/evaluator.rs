@@ -1,42 +1,362 @@
 use std::{
     cell::RefCell,
+    collections::{HashMap, VecDeque},
+    fmt,
+    io::{self, BufRead, Write},
     rc::Rc,
-    time::{SystemTime, UNIX_EPOCH},
+    sync::{atomic::{AtomicBool, Ordering}, Arc},
+    time::{Duration, Instant},
 };
-use sha2::{Sha256, Digest};
+
+use serde::{Deserialize, Serialize};
 
 use crate::{
     alteration,
     arithmetic,
     callable::{Callable, Func, NativeFunc},
+    class::{Class, Instance, INITIALIZER},
     comparison,
+    cycles,
+    dict::Dict,
+    enumeration::Enum,
     environment::Environment,
     error::EvaluatorError,
     expr::{self, Expr},
     list::List,
+    natives::{builtins, CONSTANTS, HOST_ONLY},
+    number::NumberFormat,
+    resolver::{Resolver, Slot},
+    scopes::{Scope, Scopes},
+    set::Set,
     stmt::{self, Stmt},
-    token::TokenType,
+    string,
+    token::{Token, TokenType},
     value::{LiteralType, Value},
 };
 
+/// The maximum number of bytes in a string created by a program.
+pub const MAX_STRING_LENGTH: usize = 1 << 20;
+
+/// The maximum number of items in a list, dictionary, set or tuple created by a program.
+pub const MAX_COLLECTION_LENGTH: usize = 1 << 20;
+
+/// The maximum number of lines a program can print in a single call to `Evaluator::interpret`.
+pub const MAX_OUTPUT_LINES: usize = 10_000;
+
+/// The number of nested function calls a program can make by default before it is stopped.
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+/// Roughly the most bytes of stack that each nested function call takes up, allowing for the
+/// statements and expressions nested inside of its body.
+pub const CALL_STACK_SIZE: usize = 16 * 1024;
+
+/// The size of the stack that a thread is assumed to have when it is not known, which is the
+/// size of the threads that Rust starts by default.
+pub const DEFAULT_STACK_SIZE: usize = 2 * 1024 * 1024;
+
+/// Returns the number of nested function calls that fit on a stack of the given size, up to
+/// `MAX_CALL_DEPTH`.
+pub fn max_depth_for(stack_size: usize) -> usize {
+    return (stack_size / CALL_STACK_SIZE).min(MAX_CALL_DEPTH);
+}
+
+/// The number of statements a program run by the server can execute before it is stopped.
+pub const MAX_STEPS: usize = 10_000_000;
+
+/// Roughly how many bytes the variables of a program can take up by default before it is
+/// stopped.
+pub const MAX_MEMORY: usize = 256 * 1024 * 1024;
+
 pub type ExprResult = Result<Value, EvaluatorError>;
-pub type StmtResult = Result<(), Result<Value, EvaluatorError>>;
 pub type Env = Rc<RefCell<Environment>>;
 
+/// The outcome of executing a statement, which tells the statements around it whether to carry
+/// on with the next statement or unwind.
+///
+/// ## Variants
+/// - `Normal`: The statement finished, so the next one runs.
+/// - `Return`: A `return` statement was executed, unwinding to the enclosing function, which
+///   returns the value.
+/// - `Break`: A `break` statement was executed, unwinding to the enclosing loop, or to the
+///   enclosing loop with the label if there is one, which stops.
+/// - `Continue`: A `continue` statement was executed, unwinding in the same way to the loop,
+///   which moves on to its next iteration.
+/// - `Error`: An error occurred, unwinding until a `try` statement catches it.
+#[derive(Debug)]
+pub enum Exec {
+    Normal,
+    Return(Value),
+    Break(Option<String>),
+    Continue(Option<String>),
+    Error(EvaluatorError),
+}
+
+impl Exec {
+    /// Returns whether the statement finished, so that the next statement should run.
+    pub fn is_normal(&self) -> bool {
+        return matches!(self, Exec::Normal);
+    }
+
+    /// Returns the value returned by the body of a function or a program, if it returned one. A
+    /// `break` or `continue` that was not inside a loop is an error.
+    pub fn into_result(self) -> Result<Option<Value>, EvaluatorError> {
+        return match self {
+            Exec::Normal => Ok(None),
+            Exec::Return(value) => Ok(Some(value)),
+            Exec::Break(_) => Err(EvaluatorError::BreakOutsideLoop),
+            Exec::Continue(_) => Err(EvaluatorError::ContinueOutsideLoop),
+            Exec::Error(e) => Err(e),
+        };
+    }
+}
+
+/// A global variable that was defined by the most recent call to `Evaluator::interpret`.
+///
+/// ## Fields
+/// - `name`: The name of the variable.
+/// - `type_name`: The name of the type of its value, e.g. `number` or `function`.
+/// - `value`: The value as it would be printed, only recorded for literal values.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct GlobalDefinition {
+    pub name: String,
+    pub type_name: String,
+    pub value: Option<String>,
+}
+
+impl fmt::Display for GlobalDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match &self.value {
+            Some(value) => write!(f, "{}: {} = {value}", self.name, self.type_name),
+            None => write!(f, "{}: {}", self.name, self.type_name),
+        };
+    }
+}
+
+/// Which of the native functions a program is allowed to call.
+///
+/// ## Variants
+/// - `All`: Every native function is available.
+/// - `Allow`: Only the named native functions are available.
+/// - `Deny`: Every native function except the named ones is available.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum NativePolicy {
+    #[default]
+    All,
+    Allow(Vec<String>),
+    Deny(Vec<String>),
+}
+
+impl NativePolicy {
+    /// Returns whether the native function with the given name is available.
+    pub fn permits(&self, name: &str) -> bool {
+        return match self {
+            NativePolicy::All => true,
+            NativePolicy::Allow(names) => names.iter().any(|n| n == name),
+            NativePolicy::Deny(names) => !names.iter().any(|n| n == name),
+        };
+    }
+
+    /// Returns the policy with the native functions in `HOST_ONLY` denied as well, for programs
+    /// run by the server.
+    pub fn sandboxed(self) -> Self {
+        return match self {
+            NativePolicy::All => {
+                NativePolicy::Deny(HOST_ONLY.iter().map(|name| name.to_string()).collect())
+            }
+            NativePolicy::Allow(names) => NativePolicy::Allow(
+                names.into_iter().filter(|name| !HOST_ONLY.contains(&name.as_str())).collect(),
+            ),
+            NativePolicy::Deny(mut names) => {
+                names.extend(HOST_ONLY.iter().map(|name| name.to_string()));
+                NativePolicy::Deny(names)
+            }
+        };
+    }
+}
+
+/// Where the lines printed by a program are sent, besides being collected and returned by
+/// `Evaluator::interpret`.
+///
+/// ## Variants
+/// - `Echo`: Each line is also written to stdout as it is printed, as a command line interpreter
+///   would.
+/// - `Quiet`: Lines are only collected, as they are when serving requests, so that programs do
+///   not fill up the logs.
+/// - `Stream`: Each line is also passed to the function as it is printed, which is how a run
+///   sends its output to whoever is receiving its events before the program has finished.
+#[derive(Clone, Default)]
+pub enum OutputSink {
+    #[default]
+    Echo,
+    Quiet,
+    Stream(Arc<dyn Fn(&str) + Send + Sync>),
+}
+
+impl OutputSink {
+    /// Creates a sink that passes each printed line to the given function.
+    pub fn stream(f: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        return OutputSink::Stream(Arc::new(f));
+    }
+
+    /// Sends a line printed by the program to the sink.
+    pub fn write(&self, line: &str) {
+        match self {
+            OutputSink::Echo => println!("{line}"),
+            OutputSink::Quiet => {},
+            OutputSink::Stream(f) => f(line),
+        }
+    }
+}
+
+impl fmt::Debug for OutputSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            OutputSink::Echo => write!(f, "Echo"),
+            OutputSink::Quiet => write!(f, "Quiet"),
+            OutputSink::Stream(_) => write!(f, "Stream(..)"),
+        };
+    }
+}
+
+impl PartialEq for OutputSink {
+    /// Two sinks that stream lines are equal if they pass them to the same function.
+    fn eq(&self, other: &Self) -> bool {
+        return match (self, other) {
+            (OutputSink::Echo, OutputSink::Echo) | (OutputSink::Quiet, OutputSink::Quiet) => true,
+            (OutputSink::Stream(a), OutputSink::Stream(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        };
+    }
+}
+
+/// Where the lines read by the `input` native function come from.
+///
+/// ## Variants
+/// - `Stdin`: Each line is read from stdin when it is asked for, after writing the prompt to
+///   stdout, as a command line interpreter would.
+/// - `Buffer`: Lines are taken in order from those given in advance, as they are when serving
+///   requests, and the prompt is not written anywhere.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum InputSource {
+    #[default]
+    Stdin,
+    Buffer(VecDeque<String>),
+}
+
+impl InputSource {
+    /// Creates a buffer holding each line of the given text.
+    pub fn buffer(text: &str) -> Self {
+        return InputSource::Buffer(text.lines().map(str::to_string).collect());
+    }
+}
+
+/// A flag shared between a running program and whoever started it, which stops the program the
+/// next time it checks its time limit once the flag is set. Clones of a token share the flag, so
+/// one token can stop every program it was given to, such as when the server shuts down.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Creates a token that has not been cancelled.
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Stops every program that was given this token or a clone of it.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns whether the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        return self.0.load(Ordering::Relaxed);
+    }
+}
+
+/// A limit on the resources a program can use, which stops the program when it is exceeded.
+///
+/// ## Variants
+/// - `Time`: The program ran for longer than the evaluator's time limit.
+/// - `Memory`: The program created a string or collection larger than `MAX_STRING_LENGTH` or
+///   `MAX_COLLECTION_LENGTH`, or used more memory than the evaluator's memory limit.
+/// - `Output`: The program printed more than `MAX_OUTPUT_LINES` lines.
+/// - `Recursion`: The program made more nested calls than the evaluator's recursion limit.
+/// - `Steps`: The program executed more statements than the evaluator's step limit.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Limit {
+    Time,
+    Memory,
+    Output,
+    Recursion,
+    Steps,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Limit::Time => write!(f, "time"),
+            Limit::Memory => write!(f, "memory"),
+            Limit::Output => write!(f, "output"),
+            Limit::Recursion => write!(f, "recursion"),
+            Limit::Steps => write!(f, "step"),
+        };
+    }
+}
+
 /// The `Evaluator` struct is responsible for evaluating the AST and executing the program.
-/// It maintains the current environment and provides methods for evaluating expressions and
-/// executing statements.
+/// It maintains the stack of scopes it is running in and provides methods for evaluating
+/// expressions and executing statements.
 ///
 /// # Attributes
-/// - `environment`: The current environment in which the evaluator is operating. This is an `Rc<RefCell<Environment>>`
-///   that allows for shared ownership and interior mutability.
+/// - `scopes`: The stack of scopes in which the evaluator is operating, with the innermost on top.
 /// - `globals`: The global environment that contains global variables and functions. This is also an `Rc<RefCell<Environment>>`.
 /// - `output`: A vector of strings used to store output.
+/// - `sink`: Where printed lines are also sent, such as stdout.
+/// - `input`: Where the lines read by `input` come from.
+/// - `defined`: The globals defined by the most recent call to `interpret`.
+/// - `natives`: The native functions that are defined in the global environment.
+/// - `host_natives`: The native functions registered by the program embedding the evaluator.
+/// - `timeout`: How long each call to `interpret` may run for, if it is limited.
+/// - `deadline`: The time at which the current call to `interpret` must stop.
+/// - `cancel`: The token that stops the program when it is cancelled.
+/// - `stopped_at`: The line of the statement at which the current call to `interpret` was stopped
+///   by a limit, if it was.
+/// - `depth`: The number of calls to functions defined by the program that are running.
+/// - `max_depth`: The number of nested calls a program can make before it is stopped.
+/// - `steps`: The number of statements executed by the current call to `interpret`.
+/// - `max_steps`: The number of statements each call to `interpret` may execute, if it is
+///   limited.
+/// - `max_memory`: Roughly how many bytes the environments of a program and the values of their
+///   variables may take up.
+/// - `checked_division`: Whether dividing by zero is an error, rather than giving infinity or
+///   NaN.
+/// - `number_format`: How numbers are written when they are printed or converted to strings.
 pub struct Evaluator {
-    pub environment: Env,
-    #[allow(dead_code)]
+    pub scopes: Scopes,
     pub globals: Env,
     output: Vec<String>,
+    sink: OutputSink,
+    input: InputSource,
+    defined: Vec<GlobalDefinition>,
+    natives: NativePolicy,
+    host_natives: Vec<NativeFunc>,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    cancel: CancelToken,
+    stopped_at: Option<usize>,
+    depth: usize,
+    max_depth: usize,
+    steps: usize,
+    max_steps: Option<usize>,
+    max_memory: usize,
+    checked_division: bool,
+    number_format: NumberFormat,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        return Self::new();
+    }
 }
 
 impl Evaluator {
@@ -45,48 +365,589 @@ impl Evaluator {
     /// # Returns
     /// A new `Evaluator` instance.
     pub fn new() -> Self {
+        return Self::with_natives(NativePolicy::All);
+    }
+
+    /// Creates a new `Evaluator` instance whose global environment only contains the native
+    /// functions permitted by the given policy.
+    pub fn with_natives(natives: NativePolicy) -> Self {
+        let globals = Self::create_globals(&natives);
+
+        return Self {
+            scopes: Scopes::new(Rc::clone(&globals)),
+            globals,
+            output: Vec::new(),
+            sink: OutputSink::default(),
+            input: InputSource::default(),
+            defined: Vec::new(),
+            natives,
+            host_natives: Vec::new(),
+            timeout: None,
+            deadline: None,
+            cancel: CancelToken::new(),
+            stopped_at: None,
+            depth: 0,
+            max_depth: MAX_CALL_DEPTH,
+            steps: 0,
+            max_steps: None,
+            max_memory: MAX_MEMORY,
+            checked_division: false,
+            number_format: NumberFormat::default(),
+        };
+    }
+
+    /// Defines a native function provided by the program embedding the evaluator. It is kept
+    /// when the evaluator is reset.
+    pub fn with_native(mut self, native: NativeFunc) -> Self {
+        let value = Value::NativeFunction(native.clone());
+        self.globals.borrow_mut().define(native.name.clone(), value);
+        self.host_natives.push(native);
+        return self;
+    }
+
+    /// Sets where printed lines are also sent, such as stdout.
+    pub fn with_sink(mut self, sink: OutputSink) -> Self {
+        self.sink = sink;
+        return self;
+    }
+
+    /// Sets where the lines read by `input` come from.
+    pub fn with_input(mut self, input: InputSource) -> Self {
+        self.input = input;
+        return self;
+    }
+
+    /// Limits how long each call to `interpret` may run for.
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        return self;
+    }
+
+    /// Sets the token that stops the program when it is cancelled, even if it has no time limit.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        return self;
+    }
+
+    /// Limits how many nested calls a program can make, which is `MAX_CALL_DEPTH` by default.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        return self;
+    }
+
+    /// Limits how many statements each call to `interpret` may execute.
+    pub fn with_max_steps(mut self, max_steps: Option<usize>) -> Self {
+        self.max_steps = max_steps;
+        return self;
+    }
+
+    /// Limits roughly how many bytes the variables of a program may take up, which is
+    /// `MAX_MEMORY` by default.
+    pub fn with_max_memory(mut self, max_memory: usize) -> Self {
+        self.max_memory = max_memory;
+        return self;
+    }
+
+    /// Sets whether dividing a number by zero raises a `DivisionByZero` error, which programs
+    /// can catch, rather than giving infinity or NaN as it does by default.
+    pub fn with_checked_division(mut self, checked_division: bool) -> Self {
+        self.checked_division = checked_division;
+        return self;
+    }
+
+    /// Sets how numbers are written when they are printed or converted to strings, including
+    /// the numbers inside of lists and other collections.
+    pub fn with_number_format(mut self, number_format: NumberFormat) -> Self {
+        self.number_format = number_format;
+        return self;
+    }
+
+    /// Creates a global environment containing the built in constants and the built in native
+    /// functions permitted by the policy.
+    fn create_globals(natives: &NativePolicy) -> Env {
         let globals = Rc::new(RefCell::new(Environment::new(None)));
 
-        let clock = NativeFunc::new("clock".to_string(), 0, |_, _| {
-            Ok(Value::Literal(LiteralType::Num(
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs_f64(),
-            )))
-        });
+        for (name, value) in CONSTANTS {
+            globals.borrow_mut().define(name.to_string(), Value::Literal(LiteralType::Num(value)));
+        }
 
-        let hash = NativeFunc::new("hash".to_string(), 1, |_, args| {
-            if let Value::Literal(LiteralType::Str(s)) = &args[0] {
-                let mut hasher = Sha256::new();
-                hasher.update(s);
-                return Ok(Value::Literal(LiteralType::Str(format!("{:x}", hasher.finalize()))));
+        for builtin in builtins() {
+            if natives.permits(builtin.name) {
+                let native = builtin.native().clone();
+                globals.borrow_mut().define(native.name.clone(), Value::NativeFunction(native));
             }
-            return Err(EvaluatorError::CannotHashValue);
-        });
+        }
 
-        globals.borrow_mut().define("clock".to_string(), Value::NativeFunction(clock));
-        globals.borrow_mut().define("hash".to_string(), Value::NativeFunction(hash));
+        return globals;
+    }
 
-        return Self {
-            environment: Rc::clone(&globals),
-            globals,
-            output: Vec::new()
-        };
+    /// Resolves the variables of the given statements against the globals of the evaluator,
+    /// which must be done before they are interpreted by it.
+    pub fn resolve(&self, statements: &[Stmt]) {
+        Resolver::new(Rc::clone(&self.globals)).resolve(statements);
     }
 
     /// Interprets and executes the given statements.
+    ///
+    /// Globals defined by previous calls are kept, so the evaluator can be fed a program in
+    /// several parts, but only the output produced by these statements is returned. If an error
+    /// occurs, the output produced before it can be retrieved with `take_output`. The globals
+    /// that the statements defined can be retrieved with `defined_globals`.
     pub fn interpret(&mut self, statements: Vec<Stmt>) -> Result<Vec<String>, EvaluatorError> {
+        self.output.clear();
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        self.stopped_at = None;
+        self.depth = 0;
+        self.steps = 0;
+        self.scopes.reset(Rc::clone(&self.globals));
+        let before = self.globals.borrow().snapshot();
+
+        let mut result = Ok(());
         for stmt in statements {
-            match self.execute(&stmt) {
-                Ok(()) => {}
-                Err(r) => match r {
-                    Ok(_) => {}
-                    Err(e) => return Err(e),
+            if let Err(e) = self.execute(&stmt).into_result() {
+                self.scopes.reset(Rc::clone(&self.globals));
+                result = Err(e);
+                break;
+            }
+        }
+
+        self.defined = self.globals
+            .borrow()
+            .snapshot()
+            .into_iter()
+            .filter(|(name, _)| !before.contains_key(name))
+            .map(|(name, value)| GlobalDefinition {
+                name,
+                type_name: value.type_name().to_string(),
+                value: match &value {
+                    Value::Literal(literal) => Some(self.stringify(literal)),
+                    _ => None,
+                },
+            })
+            .collect();
+        self.defined.sort_by(|a, b| a.name.cmp(&b.name));
+
+        result?;
+        return Ok(self.take_output());
+    }
+
+    /// Returns the globals, sorted by name, that were defined by the most recent call to
+    /// `interpret`.
+    pub fn defined_globals(&self) -> &[GlobalDefinition] {
+        return &self.defined;
+    }
+
+    /// Returns the output produced so far, leaving the evaluator's output empty.
+    pub fn take_output(&mut self) -> Vec<String> {
+        return std::mem::take(&mut self.output);
+    }
+
+    /// Returns the line of the statement at which the most recent call to `interpret` or
+    /// `call_function` was stopped by a limit. This is the innermost statement whose line is
+    /// known, and is `None` if no limit was exceeded.
+    pub fn stopped_at(&self) -> Option<usize> {
+        return self.stopped_at;
+    }
+
+    /// Returns roughly how many bytes the environments that are still alive take up, along with
+    /// the values of their variables.
+    pub fn memory_usage(&self) -> usize {
+        return self.globals.borrow().usage();
+    }
+
+    /// Returns the output produced so far without clearing it.
+    pub fn output(&self) -> &[String] {
+        return &self.output;
+    }
+
+    /// Reads the next line of input without its line ending, or returns `None` once there is no
+    /// more input to read.
+    pub fn read_line(&mut self, prompt: &str) -> Option<String> {
+        return match &mut self.input {
+            InputSource::Stdin => {
+                print!("{prompt}");
+                let _ = io::stdout().flush();
+
+                let mut line = String::new();
+                match io::stdin().lock().read_line(&mut line) {
+                    Ok(0) | Err(_) => None,
+                    Ok(_) => Some(line.trim_end_matches(['\n', '\r']).to_string()),
+                }
+            }
+            InputSource::Buffer(lines) => lines.pop_front(),
+        };
+    }
+
+    /// Returns the value of a global variable, if it is defined.
+    pub fn global(&self, name: &str) -> Option<Value> {
+        let token = Token::new(TokenType::Identifier, name, "", 0, 0, 0);
+        return self.globals.borrow().get(&token).ok();
+    }
+
+    /// Defines a global variable, which the programs interpreted afterwards can use.
+    pub fn define_global(&mut self, name: &str, value: Value) {
+        self.globals.borrow_mut().define(name.to_string(), value);
+    }
+
+    /// Calls a function defined by a program that was interpreted earlier, returning the value
+    /// it returns. As with `interpret`, the time and step limits apply to the call and only the
+    /// output it produces is kept.
+    pub fn call_function(&mut self, callee: Value, args: Vec<Value>) -> ExprResult {
+        self.output.clear();
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        self.stopped_at = None;
+        self.depth = 0;
+        self.steps = 0;
+        self.scopes.reset(Rc::clone(&self.globals));
+
+        let result = self.call(callee, args);
+
+        self.scopes.reset(Rc::clone(&self.globals));
+        return result;
+    }
+
+    /// Discards all globals and output, returning the evaluator to the state it was created in.
+    pub fn reset(&mut self) {
+        self.globals = Self::create_globals(&self.natives);
+        for native in &self.host_natives {
+            let value = Value::NativeFunction(native.clone());
+            self.globals.borrow_mut().define(native.name.clone(), value);
+        }
+        self.scopes.reset(Rc::clone(&self.globals));
+        self.output.clear();
+        self.defined.clear();
+    }
+
+    /// Applies a binary operator to the values of its operands.
+    fn binary(&mut self, left: &Value, operator: &Token, right: &Value) -> ExprResult {
+        match operator.token_type {
+            TokenType::Greater => {
+                comparison!( > ; left ; right);
+                return Err(EvaluatorError::CannotCompareValues);
+            }
+            TokenType::GreaterEqual => {
+                comparison!( >= ; left ; right);
+                return Err(EvaluatorError::CannotCompareValues);
+            }
+            TokenType::Less => {
+                comparison!( < ; left ; right);
+                return Err(EvaluatorError::CannotCompareValues);
+            }
+            TokenType::LessEqual => {
+                comparison!( <= ; left ; right);
+                return Err(EvaluatorError::CannotCompareValues);
+            }
+            TokenType::BangEqual => {
+                if !self.is_equal(left, right) {
+                    return Ok(Value::Literal(LiteralType::True));
+                }
+                return Ok(Value::Literal(LiteralType::False));
+            }
+            TokenType::EqualEqual => {
+                if self.is_equal(left, right) {
+                    return Ok(Value::Literal(LiteralType::True));
+                }
+                return Ok(Value::Literal(LiteralType::False));
+            }
+            TokenType::Plus => {
+                if let (Value::List(l1), Value::List(l2)) = (left, right) {
+                    // The length is checked before concatenating, so nothing too large is allocated
+                    if l1.len() + l2.len() > MAX_COLLECTION_LENGTH {
+                        return Err(EvaluatorError::ValueTooLarge { line: operator.line });
+                    }
+                    self.check_memory(left.approximate_size() + right.approximate_size())?;
+                    return Ok(Value::List(l1.concat(l2)));
+                }
+                arithmetic!( + ; left ; right ; operator.line );
+                return Err(EvaluatorError::ExpectedNumber);
+            }
+            TokenType::Minus => {
+                arithmetic!( - ; left ; right ; operator.line );
+                return Err(EvaluatorError::ExpectedNumber);
+            }
+            TokenType::FSlash => {
+                self.check_division(left, operator, right)?;
+                arithmetic!( / ; left ; right ; operator.line );
+                return Err(EvaluatorError::ExpectedNumber);
+            }
+            TokenType::FSlashFSlash => {
+                if let (
+                    Value::Literal(LiteralType::Num(ln)),
+                    Value::Literal(LiteralType::Num(rn)),
+                ) = (left, right) {
+                    self.check_division(left, operator, right)?;
+                    return Ok(Value::Literal(LiteralType::Num((ln / rn).floor())));
+                }
+                return Err(EvaluatorError::ExpectedNumber);
+            }
+            TokenType::Asterisk => {
+                if let (Value::List(list), Value::Literal(LiteralType::Num(times)))
+                    | (Value::Literal(LiteralType::Num(times)), Value::List(list)) = (left, right) {
+                    if times.fract() != 0.0 {
+                        return Err(EvaluatorError::CannotRepeatList);
+                    }
+                    // A list repeated a negative number of times is empty, as it is in Python
+                    let times = times.max(0.0);
+                    if list.len() as f64 * times > MAX_COLLECTION_LENGTH as f64 {
+                        return Err(EvaluatorError::ValueTooLarge { line: operator.line });
+                    }
+                    // Each item is copied, so a list of lists is checked before it is repeated
+                    let items: usize = list.values().iter().map(Value::approximate_size).sum();
+                    self.check_memory(items.saturating_mul(times as usize))?;
+                    return Ok(Value::List(list.repeat(times as usize)));
+                }
+                arithmetic!( * ; left ; right ; operator.line );
+                return Err(EvaluatorError::ExpectedNumber);
+            }
+            _ => return Err(EvaluatorError::ExpectedValidBinaryOperator),
+        }
+    }
+
+    /// Returns an error if division is checked and a number is being divided by zero.
+    fn check_division(&self, left: &Value, operator: &Token, right: &Value) -> Result<(), EvaluatorError> {
+        if let (Value::Literal(LiteralType::Num(ln)), Value::Literal(LiteralType::Num(rn))) = (left, right) {
+            if self.checked_division && *rn == 0.0 {
+                return Err(EvaluatorError::DivisionByZero {
+                    left: self.stringify(&LiteralType::Num(*ln)),
+                    operator: operator.lexeme.to_string(),
+                    right: self.stringify(&LiteralType::Num(*rn)),
+                    line: operator.line,
+                });
+            }
+        }
+        return Ok(());
+    }
+
+    /// Evaluates the arguments of a call in order, expanding each spread argument into the items
+    /// of the list or tuple it evaluates to.
+    fn evaluate_arguments(&mut self, arguments: &[Expr]) -> Result<Vec<Value>, EvaluatorError> {
+        let mut args: Vec<Value> = Vec::new();
+        for argument in arguments {
+            let Expr::Spread { value, .. } = argument else {
+                args.push(self.evaluate(argument)?);
+                continue;
+            };
+            match self.evaluate(value)? {
+                Value::List(list) => args.extend(list.into_values()),
+                Value::Tuple(items) => args.extend(items),
+                other => return Err(EvaluatorError::CannotSpread {
+                    type_name: other.type_name().to_string(),
+                }),
+            }
+        }
+        return Ok(args);
+    }
+
+    /// Calls a function with the given arguments, checking that the right number were passed.
+    /// Calling a class creates an instance of it. Native functions use it to call the functions
+    /// they are passed.
+    pub(crate) fn call(&mut self, callee: Value, args: Vec<Value>) -> ExprResult {
+        self.check_deadline()?;
+
+        match callee {
+            Value::Function(f) => {
+                if args.len() != f.arity {
+                    return Err(EvaluatorError::ArgsDifferFromArity {
+                        args: args.len(),
+                        arity: f.arity,
+                    });
+                }
+                if self.depth >= self.max_depth {
+                    return Err(EvaluatorError::RecursionLimitExceeded { limit: self.max_depth });
+                }
+
+                self.depth += 1;
+                let result = f.call(self, args);
+                self.depth -= 1;
+                return result;
+            }
+            Value::NativeFunction(nf) => {
+                if !nf.accepts(args.len()) {
+                    return Err(EvaluatorError::ArgsDifferFromArity {
+                        args: args.len(),
+                        arity: nf.arity,
+                    });
+                }
+                return nf.call(self, args);
+            }
+            Value::Class(class) => {
+                let instance = Instance::new(class.clone());
+                match class.find_method(INITIALIZER) {
+                    Some(init) => {
+                        self.call_method(&instance, init, args)?;
+                    },
+                    None => if !args.is_empty() {
+                        return Err(EvaluatorError::ArgsDifferFromArity {
+                            args: args.len(),
+                            arity: class.arity(),
+                        });
+                    },
+                }
+                return Ok(Value::Instance(instance));
+            }
+            _ => return Err(EvaluatorError::ExpectedFunctionOrClass),
+        }
+    }
+
+    /// Calls a method of a class on an instance, binding `self` to the instance.
+    fn call_method(&mut self, instance: &Instance, method: Func, args: Vec<Value>) -> ExprResult {
+        let bound = method.bind(Value::Instance(instance.clone()))?;
+        return self.call(Value::Function(bound), args);
+    }
+
+    /// Calls a method of the list, dictionary, set, deque or string held by a variable. A list
+    /// is changed in place, as every copy of it shares its items, so only the size counted for
+    /// the variable is updated. The methods that change a dictionary, set or deque assign the
+    /// changed collection back to the variable.
+    ///
+    /// This is kept apart from `visit_listmethodcall_expr` because it needs a large stack frame,
+    /// which would otherwise be taken up by every call of a method on an instance.
+    fn call_collection_method(
+        &mut self,
+        object: &Token,
+        slot: Option<Slot>,
+        name: &Token,
+        list: Value,
+        args: Vec<Value>,
+    ) -> ExprResult {
+        let mut result_value: Option<Value> = None;
+
+        if let Value::List(list) = list {
+            // The list is changed in place, so only the size of what is added to or removed from
+            // it is counted towards the memory used by the variable holding it
+            let (result, removed, added) = match name.lexeme.as_str() {
+                "push" => {
+                    let added = args.first().map_or(0, Value::approximate_size);
+                    list.push(args)?;
+                    (Value::Literal(LiteralType::Null), 0, added)
+                },
+                "pop" => match list.pop() {
+                    Some(popped) => {
+                        let removed = popped.approximate_size();
+                        (popped, removed, 0)
+                    },
+                    None => (Value::Literal(LiteralType::Null), 0, 0),
+                },
+                "remove" => {
+                    let removed = list.remove(args)?;
+                    let size = removed.approximate_size();
+                    (removed, size, 0)
+                },
+                "insertAt" => {
+                    let added = args.get(1).map_or(0, Value::approximate_size);
+                    list.insert_at(args)?;
+                    (Value::Literal(LiteralType::Null), 0, added)
+                },
+                "index" => return Ok(Value::Literal(LiteralType::Num(list.index(args)? as f64))),
+                "len" => return Ok(Value::Literal(LiteralType::Num(list.len() as f64))),
+                "join" => {
+                    let joined = Value::Literal(LiteralType::Str(list.join(args)?));
+                    self.check_size(&joined, name.line)?;
+                    return Ok(joined);
+                },
+                "sort" => {
+                    list.tim_sort()?;
+                    (Value::List(list.clone()), 0, 0)
                 },
+                _ => return Err(EvaluatorError::InvalidListMethod)
             };
+            self.scopes.resize_variable(object, removed, added);
+            self.check_size(&Value::List(list), name.line)?;
+            return Ok(result);
+        } else if let Value::Dict(mut dict) = list {
+            let new_dict = match name.lexeme.as_str() {
+                "keys" => return Ok(Value::List(List::new(dict.keys()))),
+                "values" => return Ok(Value::List(List::new(dict.values()))),
+                "has" => {
+                    if dict.has(args)? {
+                        return Ok(Value::Literal(LiteralType::True));
+                    }
+                    return Ok(Value::Literal(LiteralType::False));
+                },
+                "len" => return Ok(Value::Literal(LiteralType::Num(dict.len() as f64))),
+                "remove" => {
+                    let temp = dict.remove(args)?;
+                    result_value = Some(temp.0);
+                    temp.1
+                },
+                _ => return Err(EvaluatorError::InvalidDictMethod)
+            };
+            self.assign_variable(object, slot, Value::Dict(new_dict.clone()))?;
+            if let Some(v) = result_value {
+                return Ok(v);
+            }
+        } else if let Value::Set(mut set) = list {
+            let new_set = match name.lexeme.as_str() {
+                "add" => set.add(args)?,
+                "remove" => set.remove(args)?,
+                "contains" => {
+                    if args.len() != 1 {
+                        return Err(EvaluatorError::ArgsDifferFromArity { args: args.len(), arity: 1 });
+                    }
+                    if set.contains(&args[0]) {
+                        return Ok(Value::Literal(LiteralType::True));
+                    }
+                    return Ok(Value::Literal(LiteralType::False));
+                },
+                "union" => {
+                    let union = Value::Set(set.union(args)?);
+                    self.check_size(&union, name.line)?;
+                    return Ok(union);
+                },
+                "intersection" => return Ok(Value::Set(set.intersection(args)?)),
+                "len" => return Ok(Value::Literal(LiteralType::Num(set.len() as f64))),
+                _ => return Err(EvaluatorError::InvalidSetMethod)
+            };
+            let new_set = Value::Set(new_set.clone());
+            self.check_size(&new_set, name.line)?;
+            self.assign_variable(object, slot, new_set)?;
+        } else if let Value::Deque(mut deque) = list {
+            let new_deque = match name.lexeme.as_str() {
+                "push" => deque.push(args)?,
+                "pushFront" => deque.push_front(args)?,
+                "pop" => {
+                    let temp = deque.pop();
+                    result_value = temp.0;
+                    temp.1
+                },
+                "popFront" => {
+                    let temp = deque.pop_front();
+                    result_value = temp.0;
+                    temp.1
+                },
+                "peek" => return Ok(deque.peek().cloned().unwrap_or(Value::Literal(LiteralType::Null))),
+                "peekFront" => return Ok(deque.peek_front().cloned().unwrap_or(Value::Literal(LiteralType::Null))),
+                "len" => return Ok(Value::Literal(LiteralType::Num(deque.len() as f64))),
+                _ => return Err(EvaluatorError::InvalidDequeMethod)
+            };
+            let new_deque = Value::Deque(new_deque.clone());
+            self.check_size(&new_deque, name.line)?;
+            self.assign_variable(object, slot, new_deque)?;
+            if let Some(v) = result_value {
+                return Ok(v);
+            }
+        } else if let Value::Literal(LiteralType::Str(text)) = list {
+            let result = string::call_method(&text, name.lexeme.as_str(), args)?;
+            self.check_size(&result, name.line)?;
+            return Ok(result);
+        }
+
+        return Ok(Value::Literal(LiteralType::Null));
+    }
+
+    /// Returns an error if the time limit of the current call to `interpret` has passed, or if
+    /// the program has been cancelled. This is checked on every loop iteration and function
+    /// call, which every long running program makes.
+    fn check_deadline(&self) -> Result<(), EvaluatorError> {
+        if self.cancel.is_cancelled() {
+            return Err(EvaluatorError::Cancelled);
+        }
+        if let (Some(deadline), Some(limit)) = (self.deadline, self.timeout) {
+            if Instant::now() >= deadline {
+                return Err(EvaluatorError::TimeLimitExceeded { limit });
+            }
         }
-        return Ok(self.output.clone());
+        return Ok(());
     }
 
     /// Evaluates an expression.
@@ -98,83 +959,465 @@ impl Evaluator {
     }
 
     /// Executes a statement.
-    fn execute(&mut self, stmt: &Stmt) -> StmtResult {
-        return stmt.accept_stmt(self);
+    fn execute(&mut self, stmt: &Stmt) -> Exec {
+        self.steps += 1;
+        let mut result = match self.max_steps {
+            Some(limit) if self.steps > limit => {
+                Exec::Error(EvaluatorError::StepLimitExceeded { limit })
+            }
+            _ => stmt.accept_stmt(self),
+        };
+
+        // The memory a statement left its variables holding is checked once it has finished
+        if result.is_normal() {
+            if let Err(e) = self.check_memory(0) {
+                result = Exec::Error(e);
+            }
+        }
+
+        // The error passes through the innermost statement first, so it records the line
+        if let Exec::Error(e) = &result {
+            if self.stopped_at.is_none() && e.limit().is_some() {
+                self.stopped_at = stmt.line();
+            }
+        }
+        return result;
     }
 
-    /// Executes a block of statements within a new environment.
-    pub fn execute_block(&mut self, statements: Vec<Stmt>, environment: Env) -> StmtResult {
-        let previous = Rc::clone(&self.environment);
+    /// Executes one iteration of the body of a loop, returning whether the loop should carry on.
+    /// A `continue` anywhere in the body, however deeply nested in `if`, `match` or `try`
+    /// statements, ends the iteration and carries on, while a `break` stops the loop. A `break`
+    /// or `continue` with the label of an outer loop is passed on to the caller along with any
+    /// return or error, so that it unwinds through this loop to the loop it names.
+    fn execute_loop_body(&mut self, label: &Option<Token>, body: &[Stmt]) -> Result<bool, Exec> {
+        let targets = |target: &Option<String>| match target {
+            Some(target) => label.as_ref().is_some_and(|label| label.lexeme == target.as_str()),
+            None => true,
+        };
 
-        self.environment = Rc::clone(&environment);
+        for stmt in body {
+            match self.execute(stmt) {
+                Exec::Normal => {},
+                Exec::Break(target) if targets(&target) => return Ok(false),
+                Exec::Continue(target) if targets(&target) => return Ok(true),
+                exec => return Err(exec),
+            };
+        }
+        return Ok(true);
+    }
 
-        for statement in statements {
-            match self.execute(&statement) {
-                Ok(_) => {}
-                Err(r) => match r {
-                    Ok(v) => {
-                        self.environment = previous;
-                        return Err(Ok(v));
-                    }
-                    Err(e) => return Err(Err(e)),
+    /// Runs a `for` loop whose initializer has been executed, until its condition is false or
+    /// it is broken out of.
+    ///
+    /// Each iteration runs in a scope of its own holding a copy of the loop variable, so a
+    /// function declared in the body keeps the value the variable had in its iteration. The
+    /// value is carried into the scope of the next iteration, where the step changes it. Once
+    /// the loop ends, the loop variable outside of it is left holding its last value.
+    fn run_for_loop(
+        &mut self,
+        label: &Option<Token>,
+        name: &Token,
+        locals: &[String],
+        condition: &Expr,
+        step: &Expr,
+        body: &[Stmt],
+    ) -> Exec {
+        let mut value = match self.scopes.get(name) {
+            Ok(v) => v,
+            Err(e) => return Exec::Error(e),
+        };
+        let mut first = true;
+
+        let result = loop {
+            self.scopes.push(locals);
+            self.scopes.define(name.lexeme.to_string(), value);
+            let iteration = self.run_for_iteration(label, condition, (!first).then_some(step), body);
+            let carried = self.scopes.get(name);
+            self.pop_scope();
+
+            value = match carried {
+                Ok(v) => v,
+                Err(e) => return Exec::Error(e),
+            };
+            match iteration {
+                Ok(true) => first = false,
+                Ok(false) => break Exec::Normal,
+                Err(exec) => break exec,
+            }
+        };
+
+        if let Err(e) = self.scopes.assign(name, value) {
+            return Exec::Error(e);
+        }
+        return result;
+    }
+
+    /// Runs one iteration of a `for` loop in the current scope, returning whether the loop
+    /// should carry on. The step runs first, for every iteration but the first, so it runs
+    /// after every iteration that is not broken out of, including those ended early by
+    /// `continue`.
+    fn run_for_iteration(
+        &mut self,
+        label: &Option<Token>,
+        condition: &Expr,
+        step: Option<&Expr>,
+        body: &[Stmt],
+    ) -> Result<bool, Exec> {
+        if let Some(step) = step {
+            self.evaluate(step).map_err(Exec::Error)?;
+        }
+
+        let condition_evaluation = self.evaluate(condition).map_err(Exec::Error)?;
+        if !condition_evaluation.is_truthy() {
+            return Ok(false);
+        }
+
+        self.check_deadline().map_err(Exec::Error)?;
+        return self.execute_loop_body(label, body);
+    }
+
+    /// Executes statements in the current environment, stopping at the first that does not
+    /// finish normally.
+    fn execute_all(&mut self, statements: &[Stmt]) -> Exec {
+        for stmt in statements {
+            let exec = self.execute(stmt);
+            if !exec.is_normal() {
+                return exec;
+            }
+        }
+        return Exec::Normal;
+    }
+
+    /// Assigns a value to a variable, an item of a list or dictionary, or a field of an instance,
+    /// as one of the targets of an assignment to several targets.
+    fn assign_target(&mut self, target: &Expr, value: Value) -> Result<(), EvaluatorError> {
+        match target {
+            Expr::Var { name, slot } => {
+                self.assign_variable(name, slot.get(), value)?;
+                return Ok(());
+            },
+            Expr::Splice { list, is_splice: false, start: Some(index), end: None } => {
+                let index = self.evaluate(index)?;
+                return self.assign_index(list, &index, value);
+            },
+            Expr::Property { object, name, slot } => {
+                return self.assign_property(object, slot.get(), name, value);
+            },
+            _ => return Err(EvaluatorError::DifferentExpression {
+                expr: Box::new(target.clone()),
+                expected: "assignment target".to_string(),
+            }),
+        }
+    }
+
+    /// Assigns a value to a key of the dictionary, or an index of the list, held in a place.
+    fn assign_index(&mut self, object: &Expr, index: &Value, value: Value) -> Result<(), EvaluatorError> {
+        return self.update_place(object, |target| {
+            return match target {
+                Value::Dict(mut dict) => Ok(Value::Dict(dict.insert(index, value)?.clone())),
+                Value::List(list) => {
+                    list.set(index, value)?;
+                    Ok(Value::List(list))
                 },
+                _ => Err(EvaluatorError::CannotAssignToIndex),
+            };
+        });
+    }
+
+    /// Removes the item at the index of the list or dictionary held in a place.
+    fn delete_item(&mut self, object: &Expr, index: &Value) -> Result<(), EvaluatorError> {
+        return self.update_place(object, |target| {
+            return match target {
+                Value::Dict(mut dict) => Ok(Value::Dict(dict.remove(vec![index.clone()])?.1.clone())),
+                Value::List(list) => {
+                    list.delete(index)?;
+                    Ok(Value::List(list))
+                },
+                _ => Err(EvaluatorError::CannotDeleteItem),
+            };
+        });
+    }
+
+    /// Replaces the items of a slice of the list held in a place with the items of a list or
+    /// tuple. As when slicing, both ends of the slice are included, and leaving out the start or
+    /// the end extends the slice to the start or the end of the list.
+    fn assign_slice(
+        &mut self,
+        object: &Expr,
+        start: Option<Value>,
+        end: Option<Value>,
+        value: Value,
+    ) -> Result<(), EvaluatorError> {
+        return self.update_place(object, |target| {
+            let Value::List(list) = target else {
+                return Err(EvaluatorError::CannotAssignToIndex);
+            };
+
+            // The end is included in the slice, so it must be an item of the list
+            let start = match start {
+                Some(start) => list.checked_start(&start)?,
+                None => 0,
+            };
+            let end = match end {
+                Some(end) => list.checked_index(&end)? + 1,
+                None => list.len(),
+            };
+            if start > end {
+                return Err(EvaluatorError::IndexOutOfRange);
             }
+
+            let items = match value {
+                Value::List(items) => items.into_values(),
+                Value::Tuple(items) => items,
+                _ => return Err(EvaluatorError::CannotAssignToSlice),
+            };
+            list.splice(start, end, items)?;
+            return Ok(Value::List(list));
+        });
+    }
+
+    /// Replaces the value held in a place, which is a variable or an item of a list or dictionary
+    /// held in a place, e.g. `grid[i][j]`, with the result of the update. Each index is only
+    /// evaluated once, and every collection on the way to the place is stored back in turn.
+    fn update_place(
+        &mut self,
+        place: &Expr,
+        update: impl FnOnce(Value) -> Result<Value, EvaluatorError>,
+    ) -> Result<(), EvaluatorError> {
+        let mut indexes = Vec::new();
+        let mut root = place;
+        while let Expr::Splice { list, is_splice: false, start: Some(index), end: None } = root {
+            indexes.push(self.evaluate(index)?);
+            root = list;
+        }
+        let Expr::Var { name, slot } = root else {
+            return Err(EvaluatorError::CannotAssignToIndex);
+        };
+        indexes.reverse();
+
+        // The collections from the variable down to the place, each an item of the one before
+        let mut path = vec![self.look_up(name, slot.get())?];
+        for index in &indexes {
+            let item = match path.last() {
+                Some(Value::List(list)) => list.get(index)?,
+                Some(Value::Dict(dict)) => dict.get(index)?,
+                _ => return Err(EvaluatorError::CannotAssignToIndex),
+            };
+            path.push(item);
+        }
+
+        let mut value = update(path.pop().unwrap_or(Value::Literal(LiteralType::Null)))?;
+        for index in indexes.iter().rev() {
+            value = match path.pop() {
+                Some(Value::List(list)) => {
+                    list.set(index, value)?;
+                    Value::List(list)
+                },
+                Some(Value::Dict(mut dict)) => Value::Dict(dict.insert(index, value)?.clone()),
+                _ => return Err(EvaluatorError::CannotAssignToIndex),
+            };
         }
-        self.environment = previous;
+
+        self.check_size(&value, name.line)?;
+        self.assign_variable(name, slot.get(), value)?;
         return Ok(());
     }
 
-    /// Checks if a value is truthy.
-    fn is_truthy(&mut self, object: &Value) -> Result<bool, EvaluatorError> {
-        match object {
-            Value::Literal(literal) => {
-                return Ok(!matches!(literal, LiteralType::Null | LiteralType::False))
-            }
-            _ => return Err(EvaluatorError::ExpectedLiteralValue),
+    /// Assigns a value to a field of the instance held by a variable.
+    fn assign_property(
+        &mut self,
+        object: &Token,
+        slot: Option<Slot>,
+        name: &Token,
+        value: Value,
+    ) -> Result<(), EvaluatorError> {
+        let target = self.look_up(object, slot)?;
+
+        if let Value::Instance(instance) = target {
+            instance.set(&name.lexeme, value);
+            return Ok(());
+        }
+
+        return Err(EvaluatorError::OnlyInstancesHaveProperties {
+            name: object.lexeme.to_string(),
+            type_name: target.type_name().to_string(),
+        });
+    }
+
+    /// Returns the value of a variable. A variable with a slot is read straight from it, and is
+    /// undefined if the variable in the slot has not been defined yet or has been deleted, or if
+    /// the program was never resolved.
+    fn look_up(&self, name: &Token, slot: Option<Slot>) -> ExprResult {
+        let value = match slot {
+            Some(Slot::Local { depth, index }) => self.scopes.get_at(depth, index),
+            Some(Slot::Global { index }) => self.globals.borrow().get_at(0, index),
+            None => None,
+        };
+
+        return value.ok_or_else(|| EvaluatorError::undefined_variable(name));
+    }
+
+    /// Assigns a value to a variable, finding it in the same way as `look_up`.
+    fn assign_variable(&mut self, name: &Token, slot: Option<Slot>, value: Value) -> ExprResult {
+        let assigned = match slot {
+            Some(Slot::Local { depth, index }) => self.scopes.assign_at(depth, index, value.clone()),
+            Some(Slot::Global { index }) => self.globals.borrow_mut().assign_at(0, index, value.clone()),
+            None => false,
+        };
+
+        if !assigned {
+            return Err(EvaluatorError::undefined_variable(name));
+        }
+        return Ok(value);
+    }
+
+    /// Executes the body of a function in a scope of its own, enclosed by the closure of the
+    /// function, in which the locals of the function are declared and each parameter is defined
+    /// as the argument passed for it.
+    pub fn execute_call(
+        &mut self,
+        closure: &Env,
+        params: &[Token],
+        locals: &[String],
+        arguments: Vec<Value>,
+        body: &[Stmt],
+    ) -> Exec {
+        self.scopes.push_shared(Rc::clone(closure));
+        self.scopes.push(locals);
+        for (param, argument) in params.iter().zip(arguments) {
+            self.scopes.define(param.lexeme.to_string(), argument);
+        }
+
+        let exec = self.execute_all(body);
+        self.pop_scope();
+        self.scopes.pop();
+        return exec;
+    }
+
+    /// Pops the innermost scope. If a function declared in it was still referring to it, it is
+    /// released, freeing it if nothing else can reach it.
+    fn pop_scope(&mut self) {
+        if let Some(Scope::Shared(environment)) = self.scopes.pop() {
+            cycles::release(environment);
+        }
+    }
+
+    /// Returns an error if the memory used by the variables of the program, along with the given
+    /// number of bytes that are about to be used, is more than the memory limit.
+    fn check_memory(&self, allocating: usize) -> Result<(), EvaluatorError> {
+        let usage = self.globals.borrow().usage();
+        if usage.saturating_add(allocating) > self.max_memory {
+            return Err(EvaluatorError::MemoryLimitExceeded { limit: self.max_memory });
+        }
+        return Ok(());
+    }
+
+    /// Checks that a value created on the given line is within the size limits, so that a
+    /// program cannot repeatedly double a value until it runs out of memory.
+    fn check_size(&self, value: &Value, line: usize) -> Result<(), EvaluatorError> {
+        let too_large = match value {
+            Value::Literal(LiteralType::Str(s)) => s.len() > MAX_STRING_LENGTH,
+            Value::List(list) => list.len() > MAX_COLLECTION_LENGTH,
+            Value::Dict(dict) => dict.len() > MAX_COLLECTION_LENGTH,
+            Value::Set(set) => set.len() > MAX_COLLECTION_LENGTH,
+            Value::Deque(deque) => deque.len() > MAX_COLLECTION_LENGTH,
+            Value::Tuple(items) => items.len() > MAX_COLLECTION_LENGTH,
+            _ => false,
+        };
+
+        if too_large {
+            return Err(EvaluatorError::ValueTooLarge { line });
         }
+        return Ok(());
     }
 
-    /// Checks if two values are equal.
-    fn is_equal(&mut self, a: &Value, b: &Value) -> bool {
-        return *a == *b;
+    /// Checks if two values are equal, which is what `==`, `!=` and `in` compare by.
+    ///
+    /// Literals are equal if they have the same type and value, so `1` is not equal to `"1"` or
+    /// `true`, and NaN is not equal to anything, itself included. Lists, tuples and deques are
+    /// equal if they hold equal items in the same order, and dictionaries if they map the same
+    /// keys to equal values, in any order, while sets are equal if they hold the same items.
+    /// Functions, classes, instances and enums are only equal to themselves, however alike
+    /// they are, and native functions are equal if they have the same name. Values of
+    /// different types, such as a list and a tuple, are never equal.
+    pub fn is_equal(&self, a: &Value, b: &Value) -> bool {
+        return match (a, b) {
+            (Value::Literal(a), Value::Literal(b)) => a == b,
+            (Value::List(a), Value::List(b)) => self.all_equal(&a.values(), &b.values()),
+            (Value::Tuple(a), Value::Tuple(b)) => self.all_equal(a, b),
+            (Value::Deque(a), Value::Deque(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| self.is_equal(a, b))
+            },
+            (Value::Dict(a), Value::Dict(b)) => {
+                a.len() == b.len()
+                    && a.entries.iter().all(|(key, value)| {
+                        b.entries.get(key).is_some_and(|other| self.is_equal(value, other))
+                    })
+            },
+            (Value::Set(a), Value::Set(b)) => a == b,
+            (Value::Function(a), Value::Function(b)) => a == b,
+            (Value::NativeFunction(a), Value::NativeFunction(b)) => a.name == b.name,
+            (Value::Class(a), Value::Class(b)) => a == b,
+            (Value::Instance(a), Value::Instance(b)) => a == b,
+            (Value::Enum(a), Value::Enum(b)) => a == b,
+            (Value::Variant(a), Value::Variant(b)) => a == b,
+            _ => false,
+        };
+    }
+
+    /// Checks if two sequences hold equal items in the same order.
+    fn all_equal(&self, a: &[Value], b: &[Value]) -> bool {
+        return a.len() == b.len() && a.iter().zip(b).all(|(a, b)| self.is_equal(a, b));
     }
 
     /// Converts a literal value to its string representation.
     fn stringify(&self, object: &LiteralType) -> String {
         return match object {
-            LiteralType::Num(n) => {
-                let mut text = n.to_string();
-                if text.ends_with(".0") {
-                    text.truncate(text.len() - 2);
-                }
-                text
-            }
+            LiteralType::Num(n) => self.number_format.format(*n),
             LiteralType::Str(s) => s.clone(),
             LiteralType::True => "true".to_string(),
             LiteralType::False => "false".to_string(),
             LiteralType::Null => "null".to_string(),
         }
     }
+
+    /// Converts a value to the string that printing it shows, failing for values that cannot be
+    /// printed, such as functions.
+    pub fn to_text(&self, value: &Value) -> Result<String, EvaluatorError> {
+        return match value {
+            Value::Literal(literal) => Ok(self.stringify(literal)),
+            Value::List(_)
+            | Value::Dict(_)
+            | Value::Set(_)
+            | Value::Deque(_)
+            | Value::Tuple(_)
+            | Value::Class(_)
+            | Value::Enum(_)
+            | Value::Instance(_)
+            | Value::Variant(_) => Ok(value.display(&self.number_format).to_string()),
+            _ => Err(EvaluatorError::ExpectedToPrintLiteralValue),
+        };
+    }
 }
 
 impl expr::ExprVisitor<ExprResult> for Evaluator {
     fn visit_alteration_expr(&mut self, expr: &Expr) -> ExprResult {
         match expr {
-            Expr::Alteration { name, alteration_type } => {
-                let curr_value = self.environment.borrow().get(name)?;
+            Expr::Alteration { name, alteration_type, slot } => {
+                let curr_value = self.look_up(name, slot.get())?;
 
                 match alteration_type {
                     TokenType::Incr => {
-                        alteration!( self ;  + ; name ; curr_value);
+                        alteration!( self ;  + ; name ; slot.get() ; curr_value);
                     }
                     TokenType::Decr => {
-                        alteration!( self ; - ; name ; curr_value);
+                        alteration!( self ; - ; name ; slot.get() ; curr_value);
                     }
                     _ => return Err(EvaluatorError::ExpectedAlterationToken),
                 }
             }
             _ => return Err(EvaluatorError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "alteration".to_string(),
             }),
         }
@@ -182,15 +1425,13 @@ impl expr::ExprVisitor<ExprResult> for Evaluator {
 
     fn visit_assign_expr(&mut self, expr: &Expr) -> ExprResult {
         match expr {
-            Expr::Assign { name, value } => {
+            Expr::Assign { name, value, slot } => {
                 let value = self.evaluate(value)?;
 
-                return self.environment
-                    .borrow_mut()
-                    .assign(name, value);
+                return self.assign_variable(name, slot.get(), value);
             }
             _ => return Err(EvaluatorError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "assign".to_string(),
             }),
         }
@@ -199,59 +1440,13 @@ impl expr::ExprVisitor<ExprResult> for Evaluator {
     fn visit_binary_expr(&mut self, expr: &Expr) -> ExprResult {
         match expr {
             Expr::Binary { left, operator, right } => {
-                let left = self.evaluate(&left)?;
-                let right = self.evaluate(&right)?;
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
 
-                match operator.token_type {
-                    TokenType::Greater => {
-                        comparison!( > ; left ; right);
-                        return Err(EvaluatorError::ExpectedNumber);
-                    }
-                    TokenType::GreaterEqual => {
-                        comparison!( >= ; left ; right);
-                        return Err(EvaluatorError::ExpectedNumber);
-                    }
-                    TokenType::Less => {
-                        comparison!( < ; left ; right);
-                        return Err(EvaluatorError::ExpectedNumber);
-                    }
-                    TokenType::LessEqual => {
-                        comparison!( <= ; left ; right);
-                        return Err(EvaluatorError::ExpectedNumber);
-                    }
-                    TokenType::BangEqual => {
-                        if !self.is_equal(&left, &right) {
-                            return Ok(Value::Literal(LiteralType::True));
-                        }
-                        return Ok(Value::Literal(LiteralType::False));
-                    }
-                    TokenType::EqualEqual => {
-                        if self.is_equal(&left, &right) {
-                            return Ok(Value::Literal(LiteralType::True));
-                        }
-                        return Ok(Value::Literal(LiteralType::False));
-                    }
-                    TokenType::Plus => {
-                        arithmetic!( + ; left ; right );
-                        return Err(EvaluatorError::ExpectedNumber);
-                    }
-                    TokenType::Minus => {
-                        arithmetic!( - ; left ; right);
-                        return Err(EvaluatorError::ExpectedNumber);
-                    }
-                    TokenType::FSlash => {
-                        arithmetic!( / ; left ; right);
-                        return Err(EvaluatorError::ExpectedNumber);
-                    }
-                    TokenType::Asterisk => {
-                        arithmetic!( * ; left ; right);
-                        return Err(EvaluatorError::ExpectedNumber);
-                    }
-                    _ => return Err(EvaluatorError::ExpectedValidBinaryOperator),
-                }
+                return self.binary(&left, operator, &right);
             }
             _ => return Err(EvaluatorError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "binary".to_string(),
             }),
         }
@@ -261,39 +1456,53 @@ impl expr::ExprVisitor<ExprResult> for Evaluator {
         match expr {
             Expr::Call { callee, arguments } => {
                 let callee = self.evaluate(callee)?;
+                let args = self.evaluate_arguments(arguments)?;
 
-                let mut args: Vec<Value> = Vec::new();
-
-                for argument in arguments {
-                    let arg = self.evaluate(argument)?;
-                    args.push(arg);
-                }
+                return self.call(callee, args);
+            }
+            _ => return Err(EvaluatorError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "call".to_string(),
+            }),
+        }
+    }
 
-                match callee {
-                    Value::Function(f) => {
-                        if args.len() != f.arity {
-                            return Err(EvaluatorError::ArgsDifferFromArity {
-                                args: args.len(),
-                                arity: f.arity,
-                            });
-                        }
-                        return f.call(self, args);
-                    }
-                    Value::NativeFunction(nf) => {
-                        if args.len() != nf.arity {
-                            return Err(EvaluatorError::ArgsDifferFromArity {
-                                args: args.len(),
-                                arity: nf.arity,
-                            });
-                        }
-                        return nf.call(self, args);
+    fn visit_comparison_expr(&mut self, expr: &Expr) -> ExprResult {
+        match expr {
+            Expr::Comparison { left, comparisons } => {
+                let mut left = self.evaluate(left)?;
+
+                // Each operand is evaluated once, and the chain stops at the first false link
+                for (operator, right) in comparisons {
+                    let right = self.evaluate(right)?;
+                    let result = self.binary(&left, operator, &right)?;
+                    if !result.is_truthy() {
+                        return Ok(Value::Literal(LiteralType::False));
                     }
-                    _ => return Err(EvaluatorError::ExpectedFunctionOrClass),
+                    left = right;
                 }
+
+                return Ok(Value::Literal(LiteralType::True));
             }
             _ => return Err(EvaluatorError::DifferentExpression {
-                expr: expr.clone(),
-                expected: "call".to_string(),
+                expr: Box::new(expr.clone()),
+                expected: "comparison".to_string(),
+            }),
+        }
+    }
+
+    fn visit_dict_expr(&mut self, expr: &Expr) -> ExprResult {
+        match expr {
+            Expr::Dict { items } => {
+                let mut entries: Vec<(Value, Value)> = Vec::new();
+                for (key, value) in items {
+                    entries.push((self.evaluate(key)?, self.evaluate(value)?));
+                }
+                return Ok(Value::Dict(Dict::new(entries)?));
+            },
+            _ => return Err(EvaluatorError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "dict".to_string(),
             }),
         }
     }
@@ -302,12 +1511,27 @@ impl expr::ExprVisitor<ExprResult> for Evaluator {
         match expr {
             Expr::Grouping { expression } => return self.evaluate(expression),
             _ => return Err(EvaluatorError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "group".to_string(),
             }),
         }
     }
 
+    fn visit_indexassign_expr(&mut self, expr: &Expr) -> ExprResult {
+        match expr {
+            Expr::IndexAssign { object, index, value } => {
+                let index = self.evaluate(index)?;
+                let value = self.evaluate(value)?;
+                self.assign_index(object, &index, value.clone())?;
+                return Ok(value);
+            },
+            _ => return Err(EvaluatorError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "indexassign".to_string(),
+            }),
+        }
+    }
+
     fn visit_list_expr(&mut self, expr: &Expr) -> ExprResult {
         match expr {
             Expr::List { items } => {
@@ -315,136 +1539,214 @@ impl expr::ExprVisitor<ExprResult> for Evaluator {
                 for item in items {
                     list.push(self.evaluate(item)?);
                 }
-                Ok(Value::List(List::new(list)))
+                Ok(Value::List(List::new(list)))
+            },
+            _ => return Err(EvaluatorError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "list".to_string(),
+            }),
+        }
+    }
+
+    fn visit_listmethodcall_expr(&mut self, expr: &Expr) -> ExprResult {
+        match expr {
+            Expr::ListMethodCall { object, call, slot } => {
+                if let Expr::Call { callee, arguments } = &**call {
+                    if let Expr::Var { name, .. } = &**callee {
+                        let args = self.evaluate_arguments(arguments)?;
+
+                        let value = self.look_up(object, slot.get())?;
+                        if let Value::Instance(instance) = value {
+                            // A field holding a function is called as it is, without the instance
+                            if let Some(field) = instance.get(&name.lexeme) {
+                                return self.call(field, args);
+                            }
+                            let method = instance.class.find_method(&name.lexeme).ok_or(
+                                EvaluatorError::UndefinedProperty { name: name.lexeme.to_string() }
+                            )?;
+                            return self.call_method(&instance, method, args);
+                        }
+                        return self.call_collection_method(object, slot.get(), name, value, args);
+                    }
+                }
+
+                return Ok(Value::Literal(LiteralType::Null));
+            },
+            _ => return Err(EvaluatorError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "listmethodcall".to_string(),
+            }),
+        }
+    }
+
+    fn visit_literal_expr(&mut self, expr: &Expr) -> ExprResult {
+        match expr {
+            Expr::Literal { value } => return Ok(Value::Literal(value.clone())),
+            _ => return Err(EvaluatorError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "literal".to_string(),
+            }),
+        }
+    }
+
+    fn visit_logical_expr(&mut self, expr: &Expr) -> ExprResult {
+        match expr {
+            Expr::Logical { left, operator, right } => {
+                let left = self.evaluate(left)?;
+
+                if operator.token_type == TokenType::Or {
+                    if left.is_truthy() {
+                        return Ok(left);
+                    }
+                } else if !left.is_truthy() {
+                    return Ok(left);
+                }
+
+                return self.evaluate(right);
+            }
+            _ => return Err(EvaluatorError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "logical".to_string(),
+            }),
+        }
+    }
+
+    fn visit_membership_expr(&mut self, expr: &Expr) -> ExprResult {
+        match expr {
+            Expr::Membership { left, not, right } => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+
+                let right = match right {
+                    Value::Tuple(items) => Value::List(List::new(items)),
+                    _ => right,
+                };
+
+                if let Value::List(list) = right {
+                    let found = list.values().iter().any(|item| self.is_equal(item, &left));
+                    if found != *not {
+                        return Ok(Value::Literal(LiteralType::True));
+                    } else {
+                        return Ok(Value::Literal(LiteralType::False));
+                    }
+                }
+
+                if let Value::Deque(deque) = &right {
+                    let found = deque.iter().any(|item| self.is_equal(item, &left));
+                    if found != *not {
+                        return Ok(Value::Literal(LiteralType::True));
+                    } else {
+                        return Ok(Value::Literal(LiteralType::False));
+                    }
+                }
+
+                if let Value::Set(set) = &right {
+                    if set.contains(&left) != *not {
+                        return Ok(Value::Literal(LiteralType::True));
+                    } else {
+                        return Ok(Value::Literal(LiteralType::False));
+                    }
+                }
+
+                if let Value::Dict(dict) = right {
+                    if dict.contains(&left) != *not {
+                        return Ok(Value::Literal(LiteralType::True));
+                    } else {
+                        return Ok(Value::Literal(LiteralType::False));
+                    }
+                }
+
+                return Err(EvaluatorError::ExpectedList);
             },
             _ => return Err(EvaluatorError::DifferentExpression {
-                expr: expr.clone(),
-                expected: "list".to_string(),
+                expr: Box::new(expr.clone()),
+                expected: "membership".to_string(),
             }),
         }
     }
 
-    fn visit_listmethodcall_expr(&mut self, expr: &Expr) -> ExprResult {
+    fn visit_property_expr(&mut self, expr: &Expr) -> ExprResult {
         match expr {
-            Expr::ListMethodCall { object, call } => {
-                if let Expr::Call { callee, arguments } = &**call {
-                    if let Expr::Var { name } = &**callee {
-                        let mut args: Vec<Value> = Vec::new();
-
-                        for argument in arguments {
-                            let arg = self.evaluate(argument)?;
-                            args.push(arg);
-                        }
-
-                        let list = self.environment.borrow().get(object)?;
-                        let mut result_value: Option<Value> = None;
-                        let new_list;
-
-                        if let Value::List(mut list) = list {
-                            new_list = match name.lexeme.as_str() {
-                                "push" => list.push(args)?,
-                                "pop" => {
-                                    let temp = list.pop();
-                                    if temp.0.is_some() {
-                                        result_value = temp.0;
-                                    }
-                                    temp.1
-                                },
-                                "remove" => {
-                                    let temp = list.remove(args)?;
-                                    result_value = Some(temp.0);
-                                    temp.1
-                                },
-                                "insertAt" => list.insert_at(args)?,
-                                "index" => return Ok(Value::Literal(LiteralType::Num(list.index(args)? as f64))),
-                                "len" => return Ok(Value::Literal(LiteralType::Num(list.len() as f64))),
-                                "sort" => {
-                                    let sorted_list = list.tim_sort()?;
-                                    result_value = Some(Value::List(sorted_list.clone()));
-                                    Ok(sorted_list)?
-                                },
-                                _ => return Err(EvaluatorError::InvalidListMethod)
-                            };
-                            self.environment.borrow_mut().assign(object, Value::List(new_list.clone()))?;
-                            if let Some(v) = result_value {
-                                return Ok(v);
-                            }
-                        }
+            Expr::Property { object, name, slot } => {
+                let value = self.look_up(object, slot.get())?;
+                if let Value::Instance(instance) = value {
+                    if let Some(field) = instance.get(&name.lexeme) {
+                        return Ok(field);
                     }
+                    // A method that is read rather than called is bound to the instance
+                    return match instance.class.find_method(&name.lexeme) {
+                        Some(method) => {
+                            Ok(Value::Function(method.bind(Value::Instance(instance.clone()))?))
+                        },
+                        None => Err(EvaluatorError::UndefinedProperty {
+                            name: name.lexeme.to_string(),
+                        }),
+                    };
+                }
+                if let Value::Enum(enumeration) = value {
+                    return match enumeration.variant(&name.lexeme) {
+                        Some(variant) => Ok(Value::Variant(variant)),
+                        None => Err(EvaluatorError::UndefinedVariant {
+                            enum_name: enumeration.name.to_string(),
+                            name: name.lexeme.to_string(),
+                        }),
+                    };
                 }
 
-                return Ok(Value::Literal(LiteralType::Null));
+                return Err(EvaluatorError::OnlyInstancesHaveProperties {
+                    name: object.lexeme.to_string(),
+                    type_name: value.type_name().to_string(),
+                });
             },
             _ => return Err(EvaluatorError::DifferentExpression {
-                expr: expr.clone(),
-                expected: "listmethodcall".to_string(),
+                expr: Box::new(expr.clone()),
+                expected: "property".to_string(),
             }),
         }
     }
 
-    fn visit_literal_expr(&mut self, expr: &Expr) -> ExprResult {
+    fn visit_propertyassign_expr(&mut self, expr: &Expr) -> ExprResult {
         match expr {
-            Expr::Literal { value } => return Ok(Value::Literal(value.clone())),
+            Expr::PropertyAssign { object, name, value, slot } => {
+                let value = self.evaluate(value)?;
+                self.assign_property(object, slot.get(), name, value.clone())?;
+                return Ok(value);
+            },
             _ => return Err(EvaluatorError::DifferentExpression {
-                expr: expr.clone(),
-                expected: "literal".to_string(),
+                expr: Box::new(expr.clone()),
+                expected: "propertyassign".to_string(),
             }),
         }
     }
 
-    fn visit_logical_expr(&mut self, expr: &Expr) -> ExprResult {
+    fn visit_set_expr(&mut self, expr: &Expr) -> ExprResult {
         match expr {
-            Expr::Logical { left, operator, right } => {
-                let left = self.evaluate(left)?;
-
-                if operator.token_type == TokenType::Or {
-                    match self.is_truthy(&left) {
-                        Ok(v) => {
-                            if v {
-                                return Ok(left);
-                            }
-                        }
-                        Err(e) => return Err(e),
-                    }
-                } else {
-                    match self.is_truthy(&left) {
-                        Ok(v) => {
-                            if !v {
-                                return Ok(left);
-                            }
-                        }
-                        Err(e) => return Err(e),
-                    }
+            Expr::Set { items } => {
+                let mut set: Vec<Value> = Vec::new();
+                for item in items {
+                    set.push(self.evaluate(item)?);
                 }
-
-                return self.evaluate(right);
-            }
+                return Ok(Value::Set(Set::new(set)?));
+            },
             _ => return Err(EvaluatorError::DifferentExpression {
-                expr: expr.clone(),
-                expected: "logical".to_string(),
+                expr: Box::new(expr.clone()),
+                expected: "set".to_string(),
             }),
         }
     }
 
-    fn visit_membership_expr(&mut self, expr: &Expr) -> ExprResult {
+    fn visit_sliceassign_expr(&mut self, expr: &Expr) -> ExprResult {
         match expr {
-            Expr::Membership { left, not, right } => {
-                let left = self.evaluate(left)?;
-                let right = self.evaluate(right)?;
-
-                if let Value::List(list) = right {
-                    if (list.values.contains(&left) && !not) || (!list.values.contains(&left) && *not) {
-                        return Ok(Value::Literal(LiteralType::True));
-                    } else {
-                        return Ok(Value::Literal(LiteralType::False));
-                    }
-                }
-
-                return Err(EvaluatorError::ExpectedList);
+            Expr::SliceAssign { object, start, end, value } => {
+                let start = start.as_ref().map(|start| self.evaluate(start)).transpose()?;
+                let end = end.as_ref().map(|end| self.evaluate(end)).transpose()?;
+                let value = self.evaluate(value)?;
+                self.assign_slice(object, start, end, value.clone())?;
+                return Ok(value);
             },
             _ => return Err(EvaluatorError::DifferentExpression {
-                expr: expr.clone(),
-                expected: "membership".to_string(),
+                expr: Box::new(expr.clone()),
+                expected: "sliceassign".to_string(),
             }),
         }
     }
@@ -452,6 +1754,15 @@ impl expr::ExprVisitor<ExprResult> for Evaluator {
     fn visit_splice_expr(&mut self, expr: &Expr) -> ExprResult {
         match expr {
             Expr::Splice { list, is_splice, start, end } => {
+                let value = self.evaluate(list)?;
+
+                if let Value::Dict(dict) = value {
+                    return match start {
+                        Some(key) if !*is_splice => dict.get(&self.evaluate(key)?),
+                        _ => Err(EvaluatorError::CannotSliceDict),
+                    };
+                }
+
                 let mut start_idx_expr: Option<Value> = None;
                 let mut end_idx_expr: Option<Value> = None;
 
@@ -485,51 +1796,85 @@ impl expr::ExprVisitor<ExprResult> for Evaluator {
                     return Err(EvaluatorError::ExpectedIndexToBeANum)
                 }
 
-                let value = self.environment.borrow().get(list)?;
-
-                if let Value::List(list) = value {
-                    if let Some(end_idx) = end_idx {
-                        if end_idx >= list.values.len() {
-                            return Err(EvaluatorError::IndexOutOfRange);
-                        }
-                        if start_idx_expr.is_none() {
-                            return Ok(Value::List(List::new(list.values[0..end_idx + 1].to_vec())));
-                        }
-
-                        return Ok(Value::List(List::new(list.values[start_idx..end_idx + 1].to_vec())));
+                // Slicing a tuple gives a tuple, and slicing a list gives a list
+                let (values, is_tuple) = match value {
+                    Value::List(list) => (list.into_values(), false),
+                    Value::Tuple(items) => (items, true),
+                    _ => return Err(EvaluatorError::ValueWasNotAList),
+                };
+                let wrap = |values: Vec<Value>| {
+                    if is_tuple {
+                        return Value::Tuple(values);
                     }
-                    if start_idx >= list.values.len() {
+                    return Value::List(List::new(values));
+                };
+
+                if let Some(end_idx) = end_idx {
+                    if end_idx >= values.len() {
                         return Err(EvaluatorError::IndexOutOfRange);
                     }
-                    if *is_splice {
-                        return Ok(Value::List(List::new(list.values[start_idx..list.values.len()].to_vec())));
+                    if start_idx_expr.is_none() {
+                        return Ok(wrap(values[0..end_idx + 1].to_vec()));
                     }
-                    return Ok(list.values[start_idx].clone());
-                }
 
-                return Err(EvaluatorError::ValueWasNotAList);
+                    return Ok(wrap(values[start_idx..end_idx + 1].to_vec()));
+                }
+                if start_idx >= values.len() {
+                    return Err(EvaluatorError::IndexOutOfRange);
+                }
+                if *is_splice {
+                    return Ok(wrap(values[start_idx..values.len()].to_vec()));
+                }
+                return Ok(values[start_idx].clone());
             },
             _ => return Err(EvaluatorError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "splice".to_string(),
             }),
         }
     }
 
+    fn visit_spread_expr(&mut self, expr: &Expr) -> ExprResult {
+        match expr {
+            // Spread arguments are expanded by the call they are passed to
+            Expr::Spread { value, .. } => {
+                let value = self.evaluate(value)?;
+                return Err(EvaluatorError::CannotSpread { type_name: value.type_name().to_string() });
+            },
+            _ => return Err(EvaluatorError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "spread".to_string(),
+            }),
+        }
+    }
+
+    fn visit_tuple_expr(&mut self, expr: &Expr) -> ExprResult {
+        match expr {
+            Expr::Tuple { items } => {
+                let mut tuple: Vec<Value> = Vec::new();
+                for item in items {
+                    tuple.push(self.evaluate(item)?);
+                }
+                return Ok(Value::Tuple(tuple));
+            },
+            _ => return Err(EvaluatorError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "tuple".to_string(),
+            }),
+        }
+    }
+
     fn visit_unary_expr(&mut self, expr: &Expr) -> ExprResult {
         match expr {
             Expr::Unary { operator, right } => {
                 let right = self.evaluate(right)?;
 
                 match operator.token_type {
-                    TokenType::Bang => match self.is_truthy(&right) {
-                        Ok(v) => {
-                            if v {
-                                return Ok(Value::Literal(LiteralType::False));
-                            }
-                            return Ok(Value::Literal(LiteralType::True));
+                    TokenType::Bang => {
+                        if right.is_truthy() {
+                            return Ok(Value::Literal(LiteralType::False));
                         }
-                        Err(e) => return Err(e),
+                        return Ok(Value::Literal(LiteralType::True));
                     },
                     TokenType::Minus => {
                         if let Value::Literal(LiteralType::Num(n)) = right {
@@ -541,7 +1886,7 @@ impl expr::ExprVisitor<ExprResult> for Evaluator {
                 }
             }
             _ => return Err(EvaluatorError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "unary".to_string(),
             }),
         }
@@ -549,253 +1894,513 @@ impl expr::ExprVisitor<ExprResult> for Evaluator {
 
     fn visit_var_expr(&mut self, expr: &Expr) -> ExprResult {
         match expr {
-            Expr::Var { name } => {
-                return self.environment.borrow().get(name);
-            },
+            Expr::Var { name, slot } => return self.look_up(name, slot.get()),
             _ => return Err(EvaluatorError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "variable".to_string(),
             }),
         }
     }
 }
 
-impl stmt::StmtVisitor<StmtResult> for Evaluator {
-    fn visit_expression_stmt(&mut self, stmt: &Stmt) -> StmtResult {
+impl stmt::StmtVisitor<Exec> for Evaluator {
+    fn visit_break_stmt(&mut self, stmt: &Stmt) -> Exec {
+        match stmt {
+            Stmt::Break { label, .. } => {
+                return Exec::Break(label.as_ref().map(|label| label.lexeme.to_string()));
+            },
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "break".to_string(),
+            }),
+        }
+    }
+
+    fn visit_class_stmt(&mut self, stmt: &Stmt) -> Exec {
+        match stmt {
+            Stmt::Class { name, methods } => {
+                let closure = self.scopes.share();
+                let mut functions = HashMap::new();
+                for method in methods {
+                    if let Stmt::Function { name, .. } = method {
+                        let function = match Func::new(method.clone(), Rc::clone(&closure)) {
+                            Ok(v) => v,
+                            Err(e) => return Exec::Error(e),
+                        };
+                        functions.insert(name.lexeme.to_string(), function);
+                    }
+                }
+
+                let class = Class::new(name.lexeme.to_string(), functions);
+                self.scopes.define(name.lexeme.to_string(), Value::Class(class));
+
+                return Exec::Normal;
+            }
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "class".to_string(),
+            }),
+        }
+    }
+
+    fn visit_enum_stmt(&mut self, stmt: &Stmt) -> Exec {
+        match stmt {
+            Stmt::Enum { name, variants } => {
+                let variants = variants.iter().map(|variant| variant.lexeme.to_string()).collect();
+                let enumeration = Enum::new(name.lexeme.to_string(), variants);
+                self.scopes.define(name.lexeme.to_string(), Value::Enum(enumeration));
+
+                return Exec::Normal;
+            }
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "enum".to_string(),
+            }),
+        }
+    }
+
+    fn visit_continue_stmt(&mut self, stmt: &Stmt) -> Exec {
+        match stmt {
+            Stmt::Continue { label, .. } => {
+                return Exec::Continue(label.as_ref().map(|label| label.lexeme.to_string()));
+            },
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "continue".to_string(),
+            }),
+        }
+    }
+
+    fn visit_del_stmt(&mut self, stmt: &Stmt) -> Exec {
+        match stmt {
+            Stmt::Del { target } => {
+                let result = match target {
+                    Expr::Splice { list, start: Some(index), .. } => {
+                        self.evaluate(index).and_then(|index| self.delete_item(list, &index))
+                    },
+                    Expr::Var { name, .. } => self.scopes.remove(name).map(|_| ()),
+                    _ => Err(EvaluatorError::CannotDeleteItem),
+                };
+                if let Err(e) = result {
+                    return Exec::Error(e);
+                }
+
+                return Exec::Normal;
+            }
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "del".to_string(),
+            }),
+        }
+    }
+
+    fn visit_destructure_stmt(&mut self, stmt: &Stmt) -> Exec {
+        match stmt {
+            Stmt::Destructure { targets, initializer, declare } => {
+                let items = match self.evaluate(initializer) {
+                    Ok(Value::Tuple(items)) => items,
+                    Ok(Value::List(list)) => list.into_values(),
+                    Ok(_) => return Exec::Error(EvaluatorError::CannotDestructureValue),
+                    Err(e) => return Exec::Error(e),
+                };
+
+                if items.len() != targets.len() {
+                    return Exec::Error(EvaluatorError::DestructureLengthMismatch {
+                        names: targets.len(),
+                        items: items.len(),
+                    });
+                }
+
+                // Every value is evaluated before any of the targets are assigned to, so the
+                // targets can swap values without a temporary variable
+                for (target, value) in targets.iter().zip(items) {
+                    let result = match target {
+                        Expr::Var { name, .. } if *declare => {
+                            self.scopes.define(name.lexeme.to_string(), value);
+                            Ok(())
+                        },
+                        _ => self.assign_target(target, value),
+                    };
+                    if let Err(e) = result {
+                        return Exec::Error(e);
+                    }
+                }
+
+                return Exec::Normal;
+            }
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "destructure".to_string(),
+            }),
+        }
+    }
+
+    fn visit_expression_stmt(&mut self, stmt: &Stmt) -> Exec {
         match stmt {
             Stmt::Expression { expression } => {
                 return match self.evaluate(expression) {
-                    Ok(_) => Ok(()),
-                    Err(e) => Err(Err(e)),
+                    Ok(_) => Exec::Normal,
+                    Err(e) => Exec::Error(e),
                 }
             }
-            _ => return Err(Err(EvaluatorError::DifferentStatement {
-                stmt: stmt.clone(),
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
                 expected: "expression".to_string(),
-            })),
+            }),
         }
     }
 
-    fn visit_for_stmt(&mut self, stmt: &Stmt) -> StmtResult {
+    fn visit_for_stmt(&mut self, stmt: &Stmt) -> Exec {
         match stmt {
-            Stmt::For { initializer, condition, step, body } => {
-                match self.execute(initializer) {
-                    Ok(_) => {},
-                    Err(r) => return Err(Ok(r)?),
+            Stmt::For { label, initializer, condition, step, body, locals } => {
+                let Stmt::Var { name, .. } = &**initializer else {
+                    return Exec::Error(EvaluatorError::DifferentStatement {
+                        stmt: initializer.clone(),
+                        expected: "variable".to_string(),
+                    });
                 };
 
-                let mut condition_evaluation = match self.evaluate(condition) {
+                let exec = self.execute(initializer);
+                if !exec.is_normal() {
+                    return exec;
+                }
+
+                let locals = locals.get().map_or(&[][..], |locals| locals);
+                return self.run_for_loop(label, name, locals, condition, step, body);
+            },
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "for".to_string(),
+            })
+        }
+    }
+
+    fn visit_foreach_stmt(&mut self, stmt: &Stmt) -> Exec {
+        match stmt {
+            Stmt::ForEach { label, name, iterable, body, locals } => {
+                let iterable = match self.evaluate(iterable) {
                     Ok(v) => v,
-                    Err(e) => return Err(Err(e)),
+                    Err(e) => return Exec::Error(e),
                 };
-                let mut condition_result = match self.is_truthy(&condition_evaluation) {
-                    Ok(v) => v,
-                    Err(e) => return Err(Err(e)),
+
+                // The elements are taken before the loop starts, so changing the list inside the
+                // loop does not change what is looped over
+                let elements: Vec<Value> = match iterable {
+                    Value::List(list) => list.into_values(),
+                    Value::Deque(deque) => deque.values(),
+                    Value::Literal(LiteralType::Str(s)) => s
+                        .chars()
+                        .map(|c| Value::Literal(LiteralType::Str(c.to_string())))
+                        .collect(),
+                    _ => return Exec::Error(EvaluatorError::CannotIterate {
+                        type_name: iterable.type_name().to_string(),
+                    }),
                 };
 
-                self.environment = Rc::new(RefCell::new(Environment::new(Some(Rc::clone(&self.environment)))));
-                
-                while condition_result {
-                    for stmt in body {
-                        match self.execute(stmt) {
-                            Ok(_) => {}
-                            Err(r) => return Err(Ok(r)?)
-                        };
+                let mut result = Exec::Normal;
+                for element in elements {
+                    if let Err(e) = self.check_deadline() {
+                        result = Exec::Error(e);
+                        break;
                     }
 
-                    match self.evaluate(step) {
-                        Ok(_) => {},
-                        Err(e) => return Err(Err(e)),
-                    };
-                    
-                    condition_evaluation = match self.evaluate(condition) {
-                        Ok(v) => v,
-                        Err(e) => return Err(Err(e)),
-                    };
-                    condition_result = match self.is_truthy(&condition_evaluation) {
-                        Ok(v) => v,
-                        Err(e) => return Err(Err(e)),
-                    };
+                    // Each iteration has a scope of its own holding the element
+                    self.scopes.push(locals.get().map_or(&[], |locals| locals));
+                    self.scopes.define(name.lexeme.to_string(), element);
+                    let iteration = self.execute_loop_body(label, body);
+                    self.pop_scope();
+
+                    match iteration {
+                        Ok(true) => {},
+                        Ok(false) => break,
+                        Err(exec) => {
+                            result = exec;
+                            break;
+                        },
+                    }
                 }
 
-                return Ok(());
-            },
-            _ => return Err(Err(EvaluatorError::DifferentStatement {
-                stmt: stmt.clone(),
-                expected: "for".to_string(),
-            }))
+                return result;
+            }
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "for each".to_string(),
+            }),
         }
     }
 
-    fn visit_function_stmt(&mut self, stmt: &Stmt) -> StmtResult {
+    fn visit_function_stmt(&mut self, stmt: &Stmt) -> Exec {
         match stmt {
             Stmt::Function { name, .. } => {
-                let function = match Func::new(stmt.clone(), self.environment.clone()) {
+                let function = match Func::new(stmt.clone(), self.scopes.share()) {
                     Ok(v) => v,
-                    Err(e) => return Err(Err(e)),
+                    Err(e) => return Exec::Error(e),
                 };
-                self.environment
-                    .borrow_mut()
-                    .define(name.lexeme.clone(), Value::Function(function));
+                self.scopes.define(name.lexeme.to_string(), Value::Function(function));
 
-                return Ok(());
+                return Exec::Normal;
             }
-            _ => return Err(Err(EvaluatorError::DifferentStatement {
-                stmt: stmt.clone(),
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
                 expected: "function".to_string(),
-            })),
+            }),
         }
     }
 
-    fn visit_if_stmt(&mut self, stmt: &Stmt) -> StmtResult {
+    fn visit_if_stmt(&mut self, stmt: &Stmt) -> Exec {
         match stmt {
             Stmt::If { condition, then_branch, else_branch } => {
                 let condition_evaluation = match self.evaluate(condition) {
                     Ok(v) => v,
-                    Err(e) => return Err(Err(e)),
+                    Err(e) => return Exec::Error(e),
                 };
 
-                let condition_evaluation_result = match self.is_truthy(&condition_evaluation) {
-                    Ok(v) => v,
-                    Err(e) => return Err(Err(e)),
-                };
+                let condition_evaluation_result = condition_evaluation.is_truthy();
 
                 if condition_evaluation_result {
-                    for stmt in then_branch {
-                        match self.execute(stmt) {
-                            Ok(_) => {}
-                            Err(r) => match r {
-                                Ok(v) => return Err(Ok(v)),
-                                Err(e) => return Err(Err(e)),
-                            },
-                        };
-                    }
-                } else if else_branch.is_some() {
-                    match self.execute(&else_branch.as_ref().unwrap()) {
-                        Ok(_) => {},
-                        Err(r) => return Err(Ok(r)?)
-                    };
+                    return self.execute_all(then_branch);
+                } else if let Some(else_branch) = else_branch {
+                    return self.execute_all(else_branch);
                 }
 
-                return Ok(());
+                return Exec::Normal;
             }
-            _ => return Err(Err(EvaluatorError::DifferentStatement {
-                stmt: stmt.clone(),
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
                 expected: "if".to_string(),
-            })),
+            }),
         }
     }
     
-    fn visit_print_stmt(&mut self, stmt: &Stmt) -> StmtResult {
+    fn visit_import_stmt(&mut self, stmt: &Stmt) -> Exec {
+        match stmt {
+            Stmt::Import { name, module } => {
+                let Some(module) = module.get() else {
+                    return Exec::Error(EvaluatorError::UnknownModule { name: name.lexeme.to_string() });
+                };
+
+                return self.execute_all(module);
+            }
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "import".to_string(),
+            }),
+        }
+    }
+
+    fn visit_loop_stmt(&mut self, stmt: &Stmt) -> Exec {
+        match stmt {
+            Stmt::Loop { label, body, .. } => {
+                loop {
+                    if let Err(e) = self.check_deadline() {
+                        return Exec::Error(e);
+                    }
+
+                    match self.execute_loop_body(label, body) {
+                        Ok(true) => {},
+                        Ok(false) => return Exec::Normal,
+                        Err(exec) => return exec,
+                    }
+                }
+            }
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "loop".to_string(),
+            }),
+        }
+    }
+
+    fn visit_match_stmt(&mut self, stmt: &Stmt) -> Exec {
         match stmt {
-            Stmt::Print { expression } => {
-                let value = match self.evaluate(expression) {
+            Stmt::Match { subject, arms, .. } => {
+                let value = match self.evaluate(subject) {
                     Ok(v) => v,
-                    Err(e) => return Err(Err(e)),
+                    Err(e) => return Exec::Error(e),
                 };
-                match value {
-                    Value::Literal(literal) => {
-                        println!("{}", self.stringify(&literal));
-                        self.output.push(self.stringify(&literal));
-                        return Ok(());
-                    },
-                    Value::List(list) => {
-                        println!("{list}");
-                        self.output.push(format!("{list}"));
-                        return Ok(());
-                    },
-                    _ => return Err(Err(EvaluatorError::ExpectedToPrintLiteralValue)),
+
+                // Only the body of the first arm that matches is run
+                if let Some((_, body)) = arms.iter().find(|(pattern, _)| pattern.matches(&value)) {
+                    return self.execute_all(body);
+                }
+
+                return Exec::Normal;
+            }
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "match".to_string(),
+            }),
+        }
+    }
+
+    fn visit_pass_stmt(&mut self, stmt: &Stmt) -> Exec {
+        match stmt {
+            Stmt::Pass { .. } => return Exec::Normal,
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "pass".to_string(),
+            }),
+        }
+    }
+
+    fn visit_print_stmt(&mut self, stmt: &Stmt) -> Exec {
+        match stmt {
+            Stmt::Print { expressions } => {
+                let mut texts = Vec::new();
+                for expression in expressions {
+                    let value = match self.evaluate(expression) {
+                        Ok(v) => v,
+                        Err(e) => return Exec::Error(e),
+                    };
+                    match self.to_text(&value) {
+                        Ok(text) => texts.push(text),
+                        Err(e) => return Exec::Error(e),
+                    };
+                }
+                let line = texts.join(" ");
+
+                if self.output.len() >= MAX_OUTPUT_LINES {
+                    return Exec::Error(EvaluatorError::OutputLimitExceeded { limit: MAX_OUTPUT_LINES });
                 }
+                self.sink.write(&line);
+                self.output.push(line);
+                return Exec::Normal;
             }
-            _ => return Err(Err(EvaluatorError::DifferentStatement {
-                stmt: stmt.clone(),
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
                 expected: "print".to_string(),
-            })),
+            }),
+        }
+    }
+
+    fn visit_raise_stmt(&mut self, stmt: &Stmt) -> Exec {
+        match stmt {
+            Stmt::Raise { keyword, value } => {
+                let value = match self.evaluate(value) {
+                    Ok(v) => v,
+                    Err(e) => return Exec::Error(e),
+                };
+                return Exec::Error(EvaluatorError::Raised { value: Box::new(value), line: keyword.line });
+            }
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "raise".to_string(),
+            }),
         }
     }
 
-    fn visit_return_stmt(&mut self, stmt: &Stmt) -> StmtResult {
+    fn visit_return_stmt(&mut self, stmt: &Stmt) -> Exec {
         match stmt {
             Stmt::Return { keyword: _, value } => {
                 let mut return_value = Value::Literal(LiteralType::Null);
                 if value.is_some() {
                     return_value = match self.evaluate(value.as_ref().unwrap()) {
                         Ok(v) => v,
-                        Err(e) => return Err(Err(e)),
+                        Err(e) => return Exec::Error(e),
                     };
                 }
-                return Err(Ok(return_value));
+                return Exec::Return(return_value);
             }
-            _ => return Err(Err(EvaluatorError::DifferentStatement {
-                stmt: stmt.clone(),
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
                 expected: "return".to_string(),
-            })),
+            }),
+        }
+    }
+
+    fn visit_try_stmt(&mut self, stmt: &Stmt) -> Exec {
+        match stmt {
+            Stmt::Try { body, name, handler, finally } => {
+                let mut result = self.execute_all(body);
+
+                if let (Exec::Error(e), Some(handler)) = (&result, handler) {
+                    if e.is_catchable() {
+                        // A raised value is caught as it is, and any other error as its message
+                        if let Some(name) = name {
+                            let error = match e {
+                                EvaluatorError::Raised { value, .. } => value.as_ref().clone(),
+                                _ => Value::Literal(LiteralType::Str(e.to_string())),
+                            };
+                            self.scopes.define(name.lexeme.to_string(), error);
+                        }
+                        result = self.execute_all(handler);
+                    }
+                }
+
+                // A return or error from the finally branch replaces the result of the others
+                if let Some(finally) = finally {
+                    let exec = self.execute_all(finally);
+                    if !exec.is_normal() {
+                        return exec;
+                    }
+                }
+
+                return result;
+            }
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "try".to_string(),
+            }),
         }
     }
 
-    fn visit_var_stmt(&mut self, stmt: &Stmt) -> StmtResult {
+    fn visit_var_stmt(&mut self, stmt: &Stmt) -> Exec {
         match stmt {
-            Stmt::Var { name, initializer } => {
+            Stmt::Var { name, initializer, .. } => {
                 let mut value = Value::Literal(LiteralType::Null);
                 
                 if let Some(initializer_expr) = initializer {
                     value = match self.evaluate(initializer_expr) {
                         Ok(v) => v,
-                        Err(e) => return Err(Err(e)),
+                        Err(e) => return Exec::Error(e),
                     };
                 }
                 
-                self.environment
-                    .borrow_mut()
-                    .define(name.lexeme.clone(), value);
+                self.scopes.define(name.lexeme.to_string(), value);
 
-                return Ok(());
+                return Exec::Normal;
             }
-            _ => return Err(Err(EvaluatorError::DifferentStatement {
-                stmt: stmt.clone(),
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
                 expected: "var".to_string(),
-            })),
+            }),
         }
     }
 
-    fn visit_while_stmt(&mut self, stmt: &Stmt) -> StmtResult {
+    fn visit_while_stmt(&mut self, stmt: &Stmt) -> Exec {
         match stmt {
-            Stmt::While { condition, body } => {
+            Stmt::While { label, condition, body } => {
                 let mut condition_evaluation = match self.evaluate(condition) {
                     Ok(v) => v,
-                    Err(e) => return Err(Err(e)),
+                    Err(e) => return Exec::Error(e),
                 };
 
-                let mut condition_result = match self.is_truthy(&condition_evaluation) {
-                    Ok(v) => v,
-                    Err(e) => return Err(Err(e)),
-                };
+                let mut condition_result = condition_evaluation.is_truthy();
 
                 while condition_result {
-                    for stmt in body {
-                        match self.execute(stmt) {
-                            Ok(_) => {},
-                            Err(r) => return Err(Ok(r)?)
-                        };
+                    if let Err(e) = self.check_deadline() {
+                        return Exec::Error(e);
+                    }
+
+                    match self.execute_loop_body(label, body) {
+                        Ok(true) => {},
+                        Ok(false) => return Exec::Normal,
+                        Err(exec) => return exec,
                     }
 
                     condition_evaluation = match self.evaluate(condition) {
                         Ok(v) => v,
-                        Err(e) => return Err(Err(e)),
+                        Err(e) => return Exec::Error(e),
                     };
 
-                    condition_result = match self.is_truthy(&condition_evaluation) {
-                        Ok(v) => v,
-                        Err(e) => return Err(Err(e)),
-                    };
+                    condition_result = condition_evaluation.is_truthy();
                 }
 
-                return Ok(());
+                return Exec::Normal;
             }
-            _ => return Err(Err(EvaluatorError::DifferentStatement {
-                stmt: stmt.clone(),
+            _ => return Exec::Error(EvaluatorError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
                 expected: "while".to_string(),
-            })),
+            }),
         }
     }
 }
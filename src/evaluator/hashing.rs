@@ -0,0 +1,109 @@
+//! The `hashing` module implements the hash algorithms offered by the `hash` native function.
+//!
+//! ## Overview
+//!
+//! SHA-1, SHA-256 and SHA-512 come from the `sha1` and `sha2` crates, while MD5 is implemented
+//! here, following RFC 1321, as it is only offered for checking values against existing
+//! checksums. Every digest is returned as lowercase hexadecimal.
+
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+/// A hash algorithm that the `hash` native function can use.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Algorithm {
+    Md5,
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl Algorithm {
+    /// Returns the algorithm with the given name, ignoring case, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        return match name.to_ascii_lowercase().as_str() {
+            "md5" => Some(Algorithm::Md5),
+            "sha1" => Some(Algorithm::Sha1),
+            "sha256" => Some(Algorithm::Sha256),
+            "sha512" => Some(Algorithm::Sha512),
+            _ => None,
+        };
+    }
+
+    /// Hashes the bytes, returning the digest as hexadecimal.
+    pub fn hash(&self, bytes: &[u8]) -> String {
+        return match self {
+            Algorithm::Md5 => hex(&md5(bytes)),
+            Algorithm::Sha1 => hex(&Sha1::digest(bytes)),
+            Algorithm::Sha256 => hex(&Sha256::digest(bytes)),
+            Algorithm::Sha512 => hex(&Sha512::digest(bytes)),
+        };
+    }
+}
+
+/// Formats bytes as lowercase hexadecimal.
+fn hex(bytes: &[u8]) -> String {
+    return bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+}
+
+/// The number of bits each word is rotated left by in each step of MD5.
+const SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+    5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+    4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+    6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+/// Computes the MD5 digest of the bytes.
+fn md5(bytes: &[u8]) -> [u8; 16] {
+    // The constants are the integer parts of the sines of the step numbers, scaled by 2^32
+    let constants: Vec<u32> = (0..64)
+        .map(|i: u32| ((i as f64 + 1.0).sin().abs() * 4_294_967_296.0) as u32)
+        .collect();
+
+    // The message is padded to a multiple of 64 bytes, ending with its length in bits
+    let mut message = bytes.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((bytes.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for chunk in message.chunks(64) {
+        let words: Vec<u32> = chunk
+            .chunks(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a
+                .wrapping_add(f)
+                .wrapping_add(constants[i])
+                .wrapping_add(words[g])
+                .rotate_left(SHIFTS[i]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+
+        state[0] = state[0].wrapping_add(a);
+        state[1] = state[1].wrapping_add(b);
+        state[2] = state[2].wrapping_add(c);
+        state[3] = state[3].wrapping_add(d);
+    }
+
+    let mut digest = [0; 16];
+    for (i, word) in state.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+    }
+    return digest;
+}
@@ -0,0 +1,760 @@
+//! The `natives` module holds the registry of built in native functions, which are implemented
+//! in Rust and defined in the global environment of every program.
+//!
+//! ## Overview
+//!
+//! The registry is created the first time it is used and shared from then on, including between
+//! threads, so that each evaluator only has to look the functions up rather than build them.
+//! It is the one list of built in functions: the evaluator defines the functions in it that its
+//! `NativePolicy` permits, the semantic analyser treats their names as declared, and the server
+//! describes them to the frontend.
+//!
+//! A new built in function only has to be added to `create`.
+
+use std::{
+    f64::consts,
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::{
+    callable::NativeFunc,
+    csv,
+    deque::Deque,
+    error::EvaluatorError,
+    evaluator::{Evaluator, MAX_STRING_LENGTH},
+    hashing::Algorithm,
+    list::List,
+    set::Set,
+    value::{LiteralType, Value},
+};
+
+/// A built in native function, along with how it is documented.
+///
+/// ## Fields
+/// - `name`: The name the function is called by.
+/// - `signature`: How the function is called, naming its parameters.
+/// - `description`: What the function does.
+/// - `native`: The function itself.
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub signature: &'static str,
+    pub description: &'static str,
+    #[serde(skip)]
+    native: NativeFunc,
+}
+
+impl Builtin {
+    /// Creates a new `Builtin` that runs the given function.
+    fn new(
+        name: &'static str,
+        arity: usize,
+        signature: &'static str,
+        description: &'static str,
+        fun: fn(&mut Evaluator, Vec<Value>) -> Result<Value, EvaluatorError>,
+    ) -> Self {
+        let native = NativeFunc::new(name.to_string(), arity, fun);
+        return Self { name, signature, description, native };
+    }
+
+    /// Lets the given number of the last parameters be left out of a call.
+    fn with_optional(mut self, optional: usize) -> Self {
+        self.native = self.native.with_optional(optional);
+        return self;
+    }
+
+    /// Lets any number of arguments follow the parameters.
+    fn with_variadic(mut self) -> Self {
+        self.native = self.native.with_variadic();
+        return self;
+    }
+
+    /// Returns the native function, to be defined in a global environment.
+    pub fn native(&self) -> &NativeFunc {
+        return &self.native;
+    }
+}
+
+/// Returns every built in native function, in alphabetical order of their names.
+pub fn builtins() -> &'static [Builtin] {
+    static BUILTINS: OnceLock<Vec<Builtin>> = OnceLock::new();
+    return BUILTINS.get_or_init(create);
+}
+
+/// The names of the built in native functions that read from the machine running the program or
+/// the network it is on, which programs run by the server are never allowed to call.
+pub const HOST_ONLY: [&str; 2] = ["fetch", "getEnv"];
+
+/// How long `fetch` waits for a response before failing.
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The name and value of every built in constant, in alphabetical order of their names. They
+/// are defined in the global environment of every program, whatever its `NativePolicy`.
+pub const CONSTANTS: [(&str, f64); 2] = [("E", consts::E), ("PI", consts::PI)];
+
+/// Checks whether there is a built in native function or constant with the given name.
+pub fn is_builtin(name: &str) -> bool {
+    return builtins().iter().any(|builtin| builtin.name == name)
+        || CONSTANTS.iter().any(|(constant, _)| *constant == name);
+}
+
+/// Returns the number held by an argument, or an error if it is not a number.
+fn expect_number(value: &Value) -> Result<f64, EvaluatorError> {
+    if let Value::Literal(LiteralType::Num(n)) = value {
+        return Ok(*n);
+    }
+    return Err(EvaluatorError::ExpectedNumber);
+}
+
+/// Returns the numbers in a list passed to the native function with the given name, or an error
+/// if it is not a list or holds anything other than numbers.
+fn number_list(name: &str, value: &Value) -> Result<Vec<f64>, EvaluatorError> {
+    let Value::List(list) = value else {
+        return Err(EvaluatorError::ExpectedListArgument { name: name.to_string() });
+    };
+    return list.values()
+        .iter()
+        .map(|value| match value {
+            Value::Literal(LiteralType::Num(n)) => Ok(*n),
+            _ => Err(EvaluatorError::ExpectedNumberList {
+                name: name.to_string(),
+                type_name: value.type_name().to_string(),
+            }),
+        })
+        .collect();
+}
+
+/// Replaces each placeholder in a template with the text of a value: `{}` takes the values in
+/// order, `{0}` takes the value at an index and `{name}` takes the value of a key of the dictionary
+/// given as the only value. `{{` and `}}` stand for literal braces.
+fn format_template(
+    evaluator: &Evaluator,
+    template: &str,
+    values: &[Value],
+) -> Result<String, EvaluatorError> {
+    let invalid = |reason: String| EvaluatorError::InvalidFormat { reason };
+    let mut text = String::new();
+    let mut next = 0;
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(invalid("a '{' is never closed".to_string())),
+                    }
+                }
+
+                let value = if placeholder.is_empty() {
+                    next += 1;
+                    values.get(next - 1).cloned()
+                } else if let Ok(index) = placeholder.parse::<usize>() {
+                    values.get(index).cloned()
+                } else if let [Value::Dict(dict)] = values {
+                    dict.get(&Value::Literal(LiteralType::Str(placeholder.clone()))).ok()
+                } else {
+                    None
+                };
+
+                match value {
+                    Some(value) => text.push_str(&evaluator.to_text(&value)?),
+                    None => return Err(invalid(format!("there is no value for {{{placeholder}}}"))),
+                }
+            }
+            '}' => return Err(invalid("a '}' is never opened".to_string())),
+            c => text.push(c),
+        }
+    }
+
+    return Ok(text);
+}
+
+/// Wraps a number as a value.
+fn number(n: f64) -> Result<Value, EvaluatorError> {
+    return Ok(Value::Literal(LiteralType::Num(n)));
+}
+
+/// Creates the built in native functions.
+fn create() -> Vec<Builtin> {
+    let abs = Builtin::new(
+        "abs",
+        1,
+        "abs(x)",
+        "Returns the absolute value of a number",
+        |_, args| number(expect_number(&args[0])?.abs()),
+    );
+
+    let approx_equal = Builtin::new(
+        "approxEqual",
+        3,
+        "approxEqual(a, b, eps)",
+        "Checks whether two numbers differ by at most eps, allowing for the rounding errors of \
+        floating point arithmetic, e.g. approxEqual(0.1 + 0.2, 0.3, 0.000001)",
+        |_, args| {
+            if let [
+                Value::Literal(LiteralType::Num(a)),
+                Value::Literal(LiteralType::Num(b)),
+                Value::Literal(LiteralType::Num(eps)),
+            ] = args.as_slice() {
+                if (a - b).abs() <= *eps {
+                    return Ok(Value::Literal(LiteralType::True));
+                }
+                return Ok(Value::Literal(LiteralType::False));
+            }
+            return Err(EvaluatorError::ExpectedNumber);
+        },
+    );
+
+    let bsearch = Builtin::new(
+        "bsearch",
+        2,
+        "bsearch(list, target)",
+        "Finds the index of target in a list sorted from smallest to largest using binary search, \
+        or returns -1 if the list does not contain it",
+        |_, args| {
+            if let Value::List(list) = &args[0] {
+                let index = match list.binary_search(&args[1])? {
+                    Some(index) => index as f64,
+                    None => -1.0,
+                };
+                return Ok(Value::Literal(LiteralType::Num(index)));
+            }
+            return Err(EvaluatorError::ExpectedListArgument { name: "bsearch".to_string() });
+        },
+    );
+
+    let ceil = Builtin::new(
+        "ceil",
+        1,
+        "ceil(x)",
+        "Rounds a number up to the nearest whole number",
+        |_, args| number(expect_number(&args[0])?.ceil()),
+    );
+
+    let clock = Builtin::new(
+        "clock",
+        0,
+        "clock()",
+        "Returns the number of seconds since the Unix epoch",
+        |_, _| {
+            Ok(Value::Literal(LiteralType::Num(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs_f64(),
+            )))
+        },
+    );
+
+    let csv_parse = Builtin::new(
+        "csvParse",
+        1,
+        "csvParse(text)",
+        "Parses comma separated values into a list of rows, each a list of strings. A field \
+        holding commas, quotes or newlines is wrapped in quotes, with its quotes doubled",
+        |_, args| {
+            if let Value::Literal(LiteralType::Str(text)) = &args[0] {
+                let rows = csv::parse(text).map_err(|line| EvaluatorError::InvalidCsv { line })?;
+                let rows = rows
+                    .into_iter()
+                    .map(|row| {
+                        let fields = row.into_iter().map(|field| Value::Literal(LiteralType::Str(field)));
+                        Value::List(List::new(fields.collect()))
+                    })
+                    .collect();
+                return Ok(Value::List(List::new(rows)));
+            }
+            return Err(EvaluatorError::ExpectedStringArgument);
+        },
+    );
+
+    let csv_stringify = Builtin::new(
+        "csvStringify",
+        1,
+        "csvStringify(rows)",
+        "Writes a list of rows, each a list of values, as comma separated values, with a newline \
+        after each row",
+        |evaluator, args| {
+            let Value::List(rows) = &args[0] else {
+                return Err(EvaluatorError::ExpectedCsvRows);
+            };
+            // The rows are copied, as turning a value into text can call a method of the program
+            let mut text_rows = Vec::new();
+            for row in rows.values().clone() {
+                let fields = match row {
+                    Value::List(list) => list.into_values(),
+                    Value::Tuple(items) => items,
+                    _ => return Err(EvaluatorError::ExpectedCsvRows),
+                };
+                let fields = fields
+                    .iter()
+                    .map(|field| evaluator.to_text(field))
+                    .collect::<Result<Vec<String>, EvaluatorError>>()?;
+                text_rows.push(fields);
+            }
+            return Ok(Value::Literal(LiteralType::Str(csv::stringify(&text_rows))));
+        },
+    );
+
+    let deque = Builtin::new(
+        "deque",
+        1,
+        "deque(items)",
+        "Creates a deque from the items of a list, tuple or deque, which can be pushed onto and \
+        popped from either end in constant time, e.g. to use it as a queue",
+        |_, args| {
+            return match &args[0] {
+                Value::List(list) => Ok(Value::Deque(Deque::new(list.values().clone()))),
+                Value::Tuple(items) => Ok(Value::Deque(Deque::new(items.clone()))),
+                Value::Deque(deque) => Ok(Value::Deque(deque.clone())),
+                _ => Err(EvaluatorError::CannotCreateDeque),
+            };
+        },
+    );
+
+    let doc = Builtin::new(
+        "doc",
+        1,
+        "doc(function)",
+        "Returns the docstring of a function, which is a string written as the first statement of \
+        its body, or the description of a built in function, or null if it has neither",
+        |_, args| {
+            let doc = match &args[0] {
+                Value::Function(function) => function.doc().map(str::to_string),
+                Value::NativeFunction(native) => builtins()
+                    .iter()
+                    .find(|builtin| builtin.name == native.name)
+                    .map(|builtin| builtin.description.to_string()),
+                _ => return Err(EvaluatorError::ExpectedFunctionArgument { name: "doc".to_string() }),
+            };
+            return match doc {
+                Some(doc) => Ok(Value::Literal(LiteralType::Str(doc))),
+                None => Ok(Value::Literal(LiteralType::Null)),
+            };
+        },
+    );
+
+    let fetch = Builtin::new(
+        "fetch",
+        1,
+        "fetch(url)",
+        "Sends a GET request to a URL and returns the body of the response, failing if there is \
+        no successful response within 10 seconds. It is not available to programs run by the server",
+        |_, args| {
+            if let Value::Literal(LiteralType::Str(url)) = &args[0] {
+                let failed = |reason: String| EvaluatorError::FetchFailed { url: url.clone(), reason };
+                let client = reqwest::blocking::Client::builder()
+                    .timeout(FETCH_TIMEOUT)
+                    .build()
+                    .map_err(|e| failed(e.to_string()))?;
+                let response = client
+                    .get(url)
+                    .send()
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|e| failed(e.to_string()))?;
+                let body = response.text().map_err(|e| failed(e.to_string()))?;
+                if body.len() > MAX_STRING_LENGTH {
+                    return Err(failed("the response is too large".to_string()));
+                }
+                return Ok(Value::Literal(LiteralType::Str(body)));
+            }
+            return Err(EvaluatorError::ExpectedStringArgument);
+        },
+    );
+
+    let filter = Builtin::new(
+        "filter",
+        2,
+        "filter(function, list)",
+        "Returns a new list holding the items of a list for which calling the function returns a \
+        truthy value",
+        |evaluator, args| {
+            if let Value::List(list) = &args[1] {
+                // The items are copied, as the function can change the list
+                let items = list.values().clone();
+                let mut values = Vec::new();
+                for value in &items {
+                    if evaluator.call(args[0].clone(), vec![value.clone()])?.is_truthy() {
+                        values.push(value.clone());
+                    }
+                }
+                return Ok(Value::List(List::new(values)));
+            }
+            return Err(EvaluatorError::ExpectedListArgument { name: "filter".to_string() });
+        },
+    );
+
+    let floor = Builtin::new(
+        "floor",
+        1,
+        "floor(x)",
+        "Rounds a number down to the nearest whole number",
+        |_, args| number(expect_number(&args[0])?.floor()),
+    );
+
+    let get_env = Builtin::new(
+        "getEnv",
+        1,
+        "getEnv(name)",
+        "Returns the value of an environment variable, or null if it is not set. It is not \
+        available to programs run by the server",
+        |_, args| {
+            if let Value::Literal(LiteralType::Str(name)) = &args[0] {
+                return match std::env::var(name) {
+                    Ok(value) => Ok(Value::Literal(LiteralType::Str(value))),
+                    Err(_) => Ok(Value::Literal(LiteralType::Null)),
+                };
+            }
+            return Err(EvaluatorError::ExpectedStringArgument);
+        },
+    );
+
+    let format = Builtin::new(
+        "format",
+        1,
+        "format(template, ...values)",
+        "Returns the template with each {} replaced by the next value, each {0} by the value at \
+        that index and each {name} by that key of a dictionary given as the only value, e.g. \
+        format(\"x={} y={}\", 1, 2). Write {{ and }} for literal braces",
+        |evaluator, args| {
+            if let Value::Literal(LiteralType::Str(template)) = &args[0] {
+                let text = format_template(evaluator, template, &args[1..])?;
+                return Ok(Value::Literal(LiteralType::Str(text)));
+            }
+            return Err(EvaluatorError::ExpectedStringArgument);
+        },
+    )
+    .with_variadic();
+
+    let hash = Builtin::new(
+        "hash",
+        2,
+        "hash(value, algorithm)",
+        "Returns the hash of a value as hexadecimal, using sha256 unless the algorithm is given \
+        as sha512, sha1 or md5. A value that is not a string is hashed as it would be printed",
+        |evaluator, args| {
+            let algorithm = match args.get(1) {
+                None => Algorithm::Sha256,
+                Some(Value::Literal(LiteralType::Str(name))) => match Algorithm::from_name(name) {
+                    Some(algorithm) => algorithm,
+                    None => return Err(EvaluatorError::UnknownHashAlgorithm { name: name.clone() }),
+                },
+                Some(_) => return Err(EvaluatorError::ExpectedStringArgument),
+            };
+            let text = evaluator.to_text(&args[0]).map_err(|_| EvaluatorError::CannotHashValue {
+                type_name: args[0].type_name().to_string(),
+            })?;
+            return Ok(Value::Literal(LiteralType::Str(algorithm.hash(text.as_bytes()))));
+        },
+    )
+    .with_optional(1);
+
+    let input = Builtin::new(
+        "input",
+        1,
+        "input(prompt)",
+        "Shows the prompt and reads a line of input, without its line ending, or returns null once \
+        there is no more input. When run by the server, the lines come from the stdin of the request",
+        |evaluator, args| {
+            if let Value::Literal(LiteralType::Str(prompt)) = &args[0] {
+                return match evaluator.read_line(prompt) {
+                    Some(line) => Ok(Value::Literal(LiteralType::Str(line))),
+                    None => Ok(Value::Literal(LiteralType::Null)),
+                };
+            }
+            return Err(EvaluatorError::ExpectedStringArgument);
+        },
+    );
+
+    let len = Builtin::new(
+        "len",
+        1,
+        "len(value)",
+        "Returns the number of characters in a string or the number of items in a list, tuple, \
+        dictionary, set or deque",
+        |_, args| {
+            let length = match &args[0] {
+                Value::Literal(LiteralType::Str(s)) => s.chars().count(),
+                Value::List(list) => list.len(),
+                Value::Tuple(items) => items.len(),
+                Value::Dict(dict) => dict.len(),
+                Value::Set(set) => set.len(),
+                Value::Deque(deque) => deque.len(),
+                value => return Err(EvaluatorError::CannotGetLength {
+                    type_name: value.type_name().to_string(),
+                }),
+            };
+            return Ok(Value::Literal(LiteralType::Num(length as f64)));
+        },
+    );
+
+    let map = Builtin::new(
+        "map",
+        2,
+        "map(function, list)",
+        "Returns a new list holding the result of calling the function on each item of a list",
+        |evaluator, args| {
+            if let Value::List(list) = &args[1] {
+                // The items are copied, as the function can change the list
+                let items = list.values().clone();
+                let mut values = Vec::new();
+                for value in &items {
+                    values.push(evaluator.call(args[0].clone(), vec![value.clone()])?);
+                }
+                return Ok(Value::List(List::new(values)));
+            }
+            return Err(EvaluatorError::ExpectedListArgument { name: "map".to_string() });
+        },
+    );
+
+    let max = Builtin::new(
+        "max",
+        2,
+        "max(a, b)",
+        "Returns the larger of two numbers",
+        |_, args| number(expect_number(&args[0])?.max(expect_number(&args[1])?)),
+    );
+
+    let max_of = Builtin::new(
+        "maxOf",
+        1,
+        "maxOf(list)",
+        "Returns the largest number in a list of numbers, which must not be empty",
+        |_, args| {
+            let numbers = number_list("maxOf", &args[0])?;
+            return match numbers.into_iter().reduce(f64::max) {
+                Some(max) => number(max),
+                None => Err(EvaluatorError::EmptyListArgument { name: "maxOf".to_string() }),
+            };
+        },
+    );
+
+    let min = Builtin::new(
+        "min",
+        2,
+        "min(a, b)",
+        "Returns the smaller of two numbers",
+        |_, args| number(expect_number(&args[0])?.min(expect_number(&args[1])?)),
+    );
+
+    let min_of = Builtin::new(
+        "minOf",
+        1,
+        "minOf(list)",
+        "Returns the smallest number in a list of numbers, which must not be empty",
+        |_, args| {
+            let numbers = number_list("minOf", &args[0])?;
+            return match numbers.into_iter().reduce(f64::min) {
+                Some(min) => number(min),
+                None => Err(EvaluatorError::EmptyListArgument { name: "minOf".to_string() }),
+            };
+        },
+    );
+
+    let num = Builtin::new(
+        "num",
+        1,
+        "num(value)",
+        "Converts a string holding a number, such as \"3.5\" or \" -2 \", to that number, or \
+        returns a number unchanged",
+        |_, args| {
+            return match &args[0] {
+                Value::Literal(LiteralType::Num(n)) => Ok(Value::Literal(LiteralType::Num(*n))),
+                Value::Literal(LiteralType::Str(s)) => match s.trim().parse::<f64>() {
+                    Ok(n) if n.is_finite() => Ok(Value::Literal(LiteralType::Num(n))),
+                    _ => Err(EvaluatorError::InvalidNumber { text: s.clone() }),
+                },
+                value => Err(EvaluatorError::CannotConvertToNumber {
+                    type_name: value.type_name().to_string(),
+                }),
+            };
+        },
+    );
+
+    let pow = Builtin::new(
+        "pow",
+        2,
+        "pow(base, exponent)",
+        "Raises a number to a power, e.g. pow(2, 10) is 1024",
+        |_, args| number(expect_number(&args[0])?.powf(expect_number(&args[1])?)),
+    );
+
+    let reduce = Builtin::new(
+        "reduce",
+        3,
+        "reduce(function, list, initial)",
+        "Combines the items of a list into one value by calling the function with the value so \
+        far and each item in turn, starting from the initial value, e.g. \
+        reduce(add, [1, 2, 3], 0) is 6",
+        |evaluator, args| {
+            if let Value::List(list) = &args[1] {
+                let items = list.values().clone();
+                let mut result = args[2].clone();
+                for value in &items {
+                    result = evaluator.call(args[0].clone(), vec![result, value.clone()])?;
+                }
+                return Ok(result);
+            }
+            return Err(EvaluatorError::ExpectedListArgument { name: "reduce".to_string() });
+        },
+    );
+
+    let reverse_sorted = Builtin::new(
+        "reverseSorted",
+        1,
+        "reverseSorted(list)",
+        "Returns a copy of a list sorted from largest to smallest, leaving the list unchanged",
+        |_, args| {
+            if let Value::List(list) = &args[0] {
+                return Ok(Value::List(list.reverse_sorted()?));
+            }
+            return Err(EvaluatorError::ExpectedListArgument { name: "reverseSorted".to_string() });
+        },
+    );
+
+    let round = Builtin::new(
+        "round",
+        1,
+        "round(x)",
+        "Rounds a number to the nearest whole number, rounding halves away from zero",
+        |_, args| number(expect_number(&args[0])?.round()),
+    );
+
+    let set = Builtin::new(
+        "set",
+        1,
+        "set(items)",
+        "Creates a set from the items of a list, tuple or set",
+        |_, args| {
+            return match &args[0] {
+                Value::List(list) => Ok(Value::Set(Set::new(list.values().clone())?)),
+                Value::Tuple(items) => Ok(Value::Set(Set::new(items.clone())?)),
+                Value::Set(set) => Ok(Value::Set(set.clone())),
+                _ => Err(EvaluatorError::CannotCreateSet),
+            };
+        },
+    );
+
+    let shuffle = Builtin::new(
+        "shuffle",
+        2,
+        "shuffle(list, seed)",
+        "Returns a copy of a list with its items in a random order, where the same seed number \
+        always gives the same order",
+        |_, args| {
+            return match (&args[0], &args[1]) {
+                (Value::List(list), Value::Literal(LiteralType::Num(seed))) => {
+                    Ok(Value::List(list.shuffled(*seed)))
+                },
+                (Value::List(_), _) => Err(EvaluatorError::ExpectedNumber),
+                _ => Err(EvaluatorError::ExpectedListArgument { name: "shuffle".to_string() }),
+            };
+        },
+    );
+
+    let sqrt = Builtin::new(
+        "sqrt",
+        1,
+        "sqrt(x)",
+        "Returns the square root of a number, which must not be negative",
+        |_, args| {
+            let x = expect_number(&args[0])?;
+            if x < 0.0 {
+                return Err(EvaluatorError::NegativeSquareRoot);
+            }
+            return number(x.sqrt());
+        },
+    );
+
+    let sum = Builtin::new(
+        "sum",
+        1,
+        "sum(list)",
+        "Returns the total of a list of numbers, which is 0 for an empty list",
+        |_, args| number(number_list("sum", &args[0])?.iter().fold(0.0, |total, n| total + n)),
+    );
+
+    let uuid = Builtin::new(
+        "uuid",
+        0,
+        "uuid()",
+        "Returns a new random (version 4) UUID, e.g. to use as an identifier",
+        |_, _| {
+            let mut bytes = [0u8; 16];
+            getrandom::getrandom(&mut bytes).map_err(|_| EvaluatorError::RandomnessUnavailable)?;
+            // The version and variant are fixed by RFC 4122, leaving the other 122 bits random
+            bytes[6] = (bytes[6] & 0x0f) | 0x40;
+            bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+            let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+            let uuid = format!(
+                "{}-{}-{}-{}-{}",
+                &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32]
+            );
+            return Ok(Value::Literal(LiteralType::Str(uuid)));
+        },
+    );
+
+    let string = Builtin::new(
+        "str",
+        1,
+        "str(value)",
+        "Converts a value to a string, written as printing it would show it, e.g. to join it to \
+        another string with +",
+        |evaluator, args| {
+            return Ok(Value::Literal(LiteralType::Str(evaluator.to_text(&args[0])?)));
+        },
+    );
+
+    return vec![
+        abs,
+        approx_equal,
+        bsearch,
+        ceil,
+        clock,
+        csv_parse,
+        csv_stringify,
+        deque,
+        doc,
+        fetch,
+        filter,
+        floor,
+        format,
+        get_env,
+        hash,
+        input,
+        len,
+        map,
+        max,
+        max_of,
+        min,
+        min_of,
+        num,
+        pow,
+        reduce,
+        reverse_sorted,
+        round,
+        set,
+        shuffle,
+        sqrt,
+        string,
+        sum,
+        uuid,
+    ];
+}
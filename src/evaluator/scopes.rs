@@ -0,0 +1,209 @@
+//! The `scopes` module defines the stack of scopes that the evaluator keeps variables in.
+//!
+//! ## Overview
+//!
+//! Every function call and loop pushes a scope onto the stack, which is popped once it is left.
+//! A scope on the stack is enclosed by the one below it, so reading or assigning a variable
+//! steps down the stack by the variable's depth, without following a reference counted pointer
+//! or borrowing a `RefCell` for each scope on the way.
+//!
+//! A function refers to the scope it was declared in for as long as it exists, which can be
+//! long after the scope is popped. So when a function or class is declared, the scope it is
+//! declared in, along with every scope enclosing it, is moved into an `Environment` shared by
+//! reference counting, which the stack then holds instead. A shared environment is enclosed by
+//! the environment it refers to, rather than by the scope below it, so looking a variable up
+//! stops stepping down the stack at the first shared scope. The globals are always shared, and
+//! enclose the whole stack, while the closure of a function being called is pushed below the
+//! scope of the call.
+
+use std::{cell::RefCell, mem, rc::Rc};
+
+use crate::{
+    environment::Environment,
+    error::EvaluatorError,
+    evaluator::Env,
+    token::Token,
+    value::Value,
+};
+
+/// A scope on the stack.
+///
+/// ## Variants
+/// - `Local`: A scope that nothing but the stack refers to, enclosed by the scope below it.
+/// - `Shared`: An environment that functions can refer to, enclosed by the environment it
+///   refers to.
+#[derive(Debug)]
+pub enum Scope {
+    Local(Environment),
+    Shared(Env),
+}
+
+/// The `Scopes` struct is the stack of scopes the evaluator is running in, with the innermost
+/// on top.
+///
+/// ## Fields
+/// - `globals`: The global environment, which encloses every scope on the stack.
+/// - `stack`: The scopes inside of the globals.
+#[derive(Debug)]
+pub struct Scopes {
+    globals: Env,
+    stack: Vec<Scope>,
+}
+
+impl Scopes {
+    /// Creates a new stack holding only the globals.
+    pub fn new(globals: Env) -> Self {
+        return Self { globals, stack: Vec::new() };
+    }
+
+    /// Discards every scope, leaving only the given globals.
+    pub fn reset(&mut self, globals: Env) {
+        self.globals = globals;
+        self.stack.clear();
+    }
+
+    /// Pushes a new scope enclosed by the innermost one, which counts the memory it uses along
+    /// with the globals. The names that the resolver found declared in the scope are declared
+    /// in it, in order, so that each is at the index of its slot.
+    pub fn push(&mut self, names: &[String]) {
+        let mut environment = match self.stack.last() {
+            Some(Scope::Local(environment)) => environment.inner(),
+            Some(Scope::Shared(environment)) => environment.borrow().inner(),
+            None => self.globals.borrow().inner(),
+        };
+        for name in names {
+            environment.declare(name);
+        }
+        self.stack.push(Scope::Local(environment));
+    }
+
+    /// Pushes an environment that is already shared, such as the closure of a function being
+    /// called.
+    pub fn push_shared(&mut self, environment: Env) {
+        self.stack.push(Scope::Shared(environment));
+    }
+
+    /// Pops the innermost scope. The globals cannot be popped.
+    pub fn pop(&mut self) -> Option<Scope> {
+        return self.stack.pop();
+    }
+
+    /// Moves the innermost scope, and every scope enclosing it, into environments shared by
+    /// reference counting, returning the innermost one so that a function declared in it can
+    /// refer to it.
+    pub fn share(&mut self) -> Env {
+        let (first, mut enclosing) = self.stack
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, scope)| match scope {
+                Scope::Shared(environment) => Some((i + 1, Rc::clone(environment))),
+                Scope::Local(_) => None,
+            })
+            .unwrap_or((0, Rc::clone(&self.globals)));
+
+        for scope in &mut self.stack[first..] {
+            if let Scope::Local(mut environment) = mem::replace(scope, Scope::Shared(Rc::clone(&enclosing))) {
+                environment.set_enclosing(enclosing);
+                enclosing = Rc::new(RefCell::new(environment));
+                *scope = Scope::Shared(Rc::clone(&enclosing));
+            }
+        }
+        return enclosing;
+    }
+
+    /// Defines a variable in the innermost scope.
+    pub fn define(&mut self, name: String, value: Value) {
+        match self.stack.last_mut() {
+            Some(Scope::Local(environment)) => environment.define(name, value),
+            Some(Scope::Shared(environment)) => environment.borrow_mut().define(name, value),
+            None => self.globals.borrow_mut().define(name, value),
+        }
+    }
+
+    /// Removes a variable from the innermost scope, returning its value.
+    pub fn remove(&mut self, name: &Token) -> Result<Value, EvaluatorError> {
+        return match self.stack.last_mut() {
+            Some(Scope::Local(environment)) => environment.remove(name),
+            Some(Scope::Shared(environment)) => environment.borrow_mut().remove(name),
+            None => self.globals.borrow_mut().remove(name),
+        };
+    }
+
+    /// Retrieves the value of a variable from the innermost scope that defines it.
+    pub fn get(&self, name: &Token) -> Result<Value, EvaluatorError> {
+        for scope in self.stack.iter().rev() {
+            match scope {
+                Scope::Local(environment) if environment.contains(&name.lexeme) => {
+                    return environment.get(name);
+                },
+                Scope::Local(_) => {},
+                Scope::Shared(environment) => return environment.borrow().get(name),
+            }
+        }
+        return self.globals.borrow().get(name);
+    }
+
+    /// Retrieves the value of the variable with the given index in the scope the given number of
+    /// scopes out from the innermost, if it is defined.
+    pub fn get_at(&self, depth: usize, index: usize) -> Option<Value> {
+        let mut depth = depth;
+        for scope in self.stack.iter().rev() {
+            match scope {
+                Scope::Local(environment) if depth == 0 => return environment.get_at(0, index),
+                Scope::Local(_) => depth -= 1,
+                Scope::Shared(environment) => return environment.borrow().get_at(depth, index),
+            }
+        }
+        return self.globals.borrow().get_at(depth, index);
+    }
+
+    /// Assigns a new value to a variable in the innermost scope that defines it.
+    pub fn assign(&mut self, name: &Token, value: Value) -> Result<Value, EvaluatorError> {
+        for scope in self.stack.iter_mut().rev() {
+            match scope {
+                Scope::Local(environment) if environment.contains(&name.lexeme) => {
+                    return environment.assign(name, value);
+                },
+                Scope::Local(_) => {},
+                Scope::Shared(environment) => return environment.borrow_mut().assign(name, value),
+            }
+        }
+        return self.globals.borrow_mut().assign(name, value);
+    }
+
+    /// Assigns a new value to the variable with the given index in the scope the given number of
+    /// scopes out from the innermost, returning whether it is defined and so was assigned to.
+    pub fn assign_at(&mut self, depth: usize, index: usize, value: Value) -> bool {
+        let mut depth = depth;
+        for scope in self.stack.iter_mut().rev() {
+            match scope {
+                Scope::Local(environment) if depth == 0 => {
+                    return environment.assign_at(0, index, value);
+                },
+                Scope::Local(_) => depth -= 1,
+                Scope::Shared(environment) => {
+                    return environment.borrow_mut().assign_at(depth, index, value);
+                },
+            }
+        }
+        return self.globals.borrow_mut().assign_at(depth, index, value);
+    }
+
+    /// Changes the size of a variable in the innermost scope that defines it, after its value
+    /// was changed in place.
+    pub fn resize_variable(&mut self, name: &Token, removed: usize, added: usize) {
+        for scope in self.stack.iter_mut().rev() {
+            match scope {
+                Scope::Local(environment) if environment.contains(&name.lexeme) => {
+                    return environment.resize_variable(name, removed, added);
+                },
+                Scope::Local(_) => {},
+                Scope::Shared(environment) => {
+                    return environment.borrow_mut().resize_variable(name, removed, added);
+                },
+            }
+        }
+        self.globals.borrow_mut().resize_variable(name, removed, added);
+    }
+}
@@ -0,0 +1,57 @@
+//! The `examples` module embeds the example programs in the `examples/` directory so that the
+//! server can offer them as templates.
+//!
+//! ## Overview
+//!
+//! The examples are the same programs that are run against their golden `.out` files by the
+//! example tests, so every template served is known to run and to produce the output listed
+//! with it. A new example must be added both to the directory and to `EXAMPLES`.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// An example program.
+///
+/// ## Fields
+/// - `name`: The name of the file the example is stored in, without its extension.
+/// - `description`: The comment on the first line of the example.
+/// - `source`: The source code of the example.
+/// - `output`: The output the example produces, one line per printed value.
+#[derive(Clone, Debug, JsonSchema, PartialEq, Serialize)]
+pub struct Example {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub source: &'static str,
+    pub output: &'static str,
+}
+
+/// Embeds an example from the `examples/` directory by its name.
+macro_rules! example {
+    ( $name:literal ) => {{
+        let source = include_str!(concat!("../examples/", $name, ".pyru"));
+        Example {
+            name: $name,
+            description: describe(source),
+            source,
+            output: include_str!(concat!("../examples/", $name, ".out")),
+        }
+    }};
+}
+
+/// Returns every example, in alphabetical order of their names.
+pub fn examples() -> Vec<Example> {
+    return vec![
+        example!("closures"),
+        example!("fibonacci"),
+        example!("hello"),
+        example!("lists"),
+        example!("loops"),
+        example!("queues"),
+    ];
+}
+
+/// Returns the text of the comment on the first line of an example.
+fn describe(source: &'static str) -> &'static str {
+    let first = source.lines().next().unwrap_or("");
+    return first.strip_prefix("//").map(str::trim).unwrap_or("");
+}
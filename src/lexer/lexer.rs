@@ -8,17 +8,15 @@
 //! ## Example
 //! 
 //! ```rust
-//! use crate::lexer::Lexer;
-//! use crate::token::TokenType;
+//! use pyru::lexer::Lexer;
 //! 
 //! let source_code = r#"
-//!     var x = 10;
-//!     if (x > 5) {
-//!         print "Hello, world!";
-//!     }
+//! let x = 10;
+//! if x > 5:
+//!     print("Hello, world!");
 //! "#;
 //! 
-//! let mut lexer = Lexer::new(source_code.to_string());
+//! let mut lexer = Lexer::new(source_code.to_string(), 4);
 //! let tokens = lexer.run().unwrap();
 //! 
 //! for token in tokens {
@@ -33,15 +31,15 @@
 //! 1. The lexer reads the source code character by character
 //! 2. It matches the character to a specific token such as identifiers, operators, numbers, etc.
 //! 3. It creates a Token object for each token in the source, which contains information about its
-//! position, contents, and type.
+//!    position, contents, and type.
 //! 4. The lexer continues to process the source code and tokenizes it until it reaches the end of
-//! the source.
+//!    the source.
 //! 5. Finally, it returns the vector of tokens that represent the source code.
 //! 
 //! However, the source code is not just made up of characters that each individually represent
 //! tokens. It also contains:
 //! - Operators: `==` and `++`
-//! - Comments: `// This is a comment`
+//! - Comments: `// This is a comment`, unless `//` follows an operand, where it is floor division
 //! - Identifiers: `foo` and `bar`
 //! - Literals: `"Hello World!"` and `123.456`
 //! - Keywords: `if`, `else` and `def`
@@ -63,6 +61,8 @@ use crate::{
     token::{Token, TokenType},
 };
 
+/// The maximum number of bytes of source code that the lexer accepts.
+pub const MAX_SOURCE_LENGTH: usize = 256 * 1024;
 
 /// Carries out the lexical analysis process.
 /// 
@@ -70,8 +70,8 @@ use crate::{
 /// 
 /// - `source`: The source code as a [`String`]
 /// - `tokens`: A vector of tokens that represent the source code
-/// - `start`: The starting index of the current token being processed
-/// - `curr`: The current index of the lexer's position in the source code
+/// - `start`: The byte index of the start of the current token being processed
+/// - `curr`: The byte index of the lexer's position in the source code
 /// - `line`: The current line number in the source code
 /// - `keywords`: A HashMap that maps keyword strings to their corresponding [`TokenType`]
 pub struct Lexer {
@@ -95,8 +95,9 @@ impl Lexer {
         let mut kw: HashMap<String, TokenType> = HashMap::new();
         keywords!(
             kw;
-            And, Def, Else, False, For, If, In, Let, Not,
-            Null, Or, Print, Return, Step, True, While
+            And, Break, Case, Class, Continue, Def, Del, Elif, Else, Enum, Except, False, Finally,
+            For, If, Import, In, Let, Loop, Match, Not, Null, Or, Pass, Print, Raise, Return, Step,
+            True, Try, While
         );
 
         return Self {
@@ -115,6 +116,13 @@ impl Lexer {
 
     /// Runs the lexer and tokenizes `self.source`.
     pub fn run(&mut self) -> Result<Vec<Token>, LexerError> {
+        if self.source.len() > MAX_SOURCE_LENGTH {
+            return Err(LexerError::SourceTooLarge {
+                length: self.source.len(),
+                limit: MAX_SOURCE_LENGTH,
+            });
+        }
+
         while !self.is_at_end() {
 
             // Resets the start pointer to the current position to be ready for a new token
@@ -128,8 +136,8 @@ impl Lexer {
         if self.is_indented {
             self.tokens.push(Token::new(
                 TokenType::Dedent,
-                "".to_string(),
-                "".to_string(),
+                "",
+                "",
                 self.line,
                 self.start,
                 self.curr
@@ -139,41 +147,67 @@ impl Lexer {
         // Adds the End of File token to mark the end of the source code
         self.tokens.push(Token::new(
             TokenType::Eof,
-            "".to_string(),
-            "".to_string(),
+            "",
+            "",
             self.line,
             self.start,
             self.curr,
         ));
 
-        return Ok(self.tokens.clone());
+        // The vector grew in doubling steps, so its spare capacity is given back
+        let mut tokens = std::mem::take(&mut self.tokens);
+        tokens.shrink_to_fit();
+        return Ok(tokens);
     }
 
     /// Adds a token to `self.tokens`
     fn add_token(&mut self, token_type: TokenType) {
-        let text = String::from(&self.source[self.start..self.curr]);
+        let text = &self.source[self.start..self.curr];
         self.tokens.push(Token::new(
             token_type,
             text,
-            String::new(),
+            "",
             self.line,
             self.start,
             self.curr,
         ));
     }
 
+    /// Returns whether the previous token is on the current line and ends an operand, such as a
+    /// number, an identifier or a closing bracket.
+    fn follows_operand(&self) -> bool {
+        return match self.tokens.last() {
+            Some(token) => token.line == self.line && matches!(
+                token.token_type,
+                TokenType::Identifier
+                    | TokenType::Num
+                    | TokenType::String
+                    | TokenType::RParen
+                    | TokenType::RBrack
+                    | TokenType::RBrace
+                    | TokenType::True
+                    | TokenType::False
+                    | TokenType::Null
+            ),
+            None => false,
+        };
+    }
+
     /// Adds a string or number token to `self.tokens`.
     fn add_string_token(&mut self, token_type: TokenType, literal: String) {
-        let text = String::from(&self.source[self.start..self.curr]);
+        let text = &self.source[self.start..self.curr];
         self.tokens.push(Token::new(
             token_type, text, literal, self.line, self.start, self.curr,
         ));
     }
 
     /// Processes a string token once `"` is found, and repeatedly advances, as long as another `"`
-    /// is found or the end of the source code is not reached.
+    /// is found or the end of the source code is not reached. Escape sequences are replaced by the
+    /// characters they represent as the string is read.
     fn string(&mut self) -> Result<(), LexerError> {
-        while self.peek()? != '"' && !self.is_at_end() {
+        let mut value = String::new();
+
+        while !self.is_at_end() && self.peek()? != '"' {
             if self.peek()? == '\n' {
                 return Err(LexerError::UnterminatedString {
                     line: self.line,
@@ -181,7 +215,17 @@ impl Lexer {
                     end: self.curr
                 });
             }
-            self.advance()?;
+
+            let c = self.advance()?;
+            if c != '\\' {
+                value.push(c);
+                continue;
+            }
+
+            if self.is_at_end() {
+                break;
+            }
+            value.push(self.escape()?);
         }
 
         if self.is_at_end() {
@@ -194,39 +238,116 @@ impl Lexer {
 
         self.advance()?;
 
-        let value: String = String::from(&self.source[self.start + 1..self.curr - 1]);
         self.add_string_token(TokenType::String, value);
         Ok(())
     }
 
+    /// Processes a multi-line string token once `"""` is found, advancing until the closing
+    /// `"""`. Newlines inside the string are kept in its value and counted, but they do not start
+    /// a new line for the indentation machinery, so no `Indent` or `Dedent` tokens are produced.
+    fn multiline_string(&mut self) -> Result<(), LexerError> {
+        let mut value = String::new();
+
+        loop {
+            if self.is_at_end() {
+                return Err(LexerError::UnterminatedString {
+                    line: self.line,
+                    start: self.start,
+                    end: self.curr
+                });
+            }
+
+            let c = self.advance()?;
+            match c {
+                '"' if self.match_token('"') => {
+                    if self.match_token('"') {
+                        break;
+                    }
+                    value.push_str("\"\"");
+                },
+                '\\' => {
+                    if self.is_at_end() {
+                        continue;
+                    }
+                    value.push(self.escape()?);
+                },
+                // Windows line endings are stored as a single newline
+                '\r' => {},
+                '\n' => {
+                    self.line += 1;
+                    value.push(c);
+                },
+                _ => value.push(c),
+            }
+        }
+
+        self.add_string_token(TokenType::String, value);
+        Ok(())
+    }
+
+    /// Processes the character after a backslash in a string, returning the character that the
+    /// escape sequence represents.
+    fn escape(&mut self) -> Result<char, LexerError> {
+        let escaped = self.advance()?;
+        return match escaped {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '"' => Ok('"'),
+            '\\' => Ok('\\'),
+            _ => Err(LexerError::InvalidEscape {
+                c: escaped,
+                line: self.line,
+                start: self.curr - 1 - escaped.len_utf8(),
+                end: self.curr
+            }),
+        };
+    }
+
     /// Processes numbers when a digit is found, and, similarly to `string()`, it repeatedly 
     /// advances as long as the next character is a digit or is not a decimal point followed by the
     /// fractional part of the number
     fn number(&mut self) -> Result<(), LexerError> {
-        while self.is_digit(self.peek()?) {
-            self.advance()?;
-        }
+        self.digits()?;
 
         if self.peek()? == '.' && self.is_digit(self.peek_next()?) {
             self.advance()?;
+            self.digits()?;
+        }
 
-            while self.is_digit(self.peek()?) {
-                self.advance()?;
-            }
+        // Underscores can only separate digits, and are left out of the value
+        let text = &self.source[self.start..self.curr];
+        if text.contains("__") || text.contains("_.") || text.ends_with('_') {
+            return Err(LexerError::InvalidNumericSeparator {
+                lexeme: text.to_string(),
+                line: self.line,
+                start: self.start,
+                end: self.curr,
+            });
         }
 
-        let value = String::from(&self.source[self.start..self.curr]);
+        let value = text.replace('_', "");
         self.add_string_token(TokenType::Num, value);
         Ok(())
     }
 
+    /// Advances past the digits of a number, which can be separated by underscores for
+    /// readability, e.g. `1_000_000`.
+    fn digits(&mut self) -> Result<(), LexerError> {
+        while self.is_digit(self.peek()?) || self.peek()? == '_' {
+            self.advance()?;
+        }
+        Ok(())
+    }
+
     fn identifier(&mut self) -> Result<(), LexerError> {
         while !self.is_at_end() && self.is_alpha(self.peek()?) {
             self.advance()?;
         }
 
-        let text = String::from(&self.source[self.start..self.curr]);
-        let token_type: TokenType = match self.keywords.get(&text) {
+        let text = &self.source[self.start..self.curr];
+        let token_type: TokenType = match self.keywords.get(text) {
             Some(v) => *v,
             None => TokenType::Identifier,
         };
@@ -267,6 +388,8 @@ impl Lexer {
             '-' => {
                 if self.match_token('-') {
                     token = TokenType::Decr;
+                } else if self.match_token('>') {
+                    token = TokenType::Arrow;
                 } else {
                     token = TokenType::Minus;
                 }
@@ -323,6 +446,11 @@ impl Lexer {
             ' ' | '\t' => return Ok(()),
             '/' => {
                 if self.match_token('/') {
+                    // `//` straight after an operand is floor division, otherwise it is a comment
+                    if self.follows_operand() {
+                        self.add_token(TokenType::FSlashFSlash);
+                        return Ok(());
+                    }
                     while self.peek()? != '\n' && !self.is_at_end() {
                         self.advance()?;
                     }
@@ -332,6 +460,12 @@ impl Lexer {
                 return Ok(());
             }
             '"' => {
+                // Three quotes in a row start a multi-line string
+                if !self.is_at_end() && self.peek()? == '"' && self.peek_next().is_ok_and(|c| c == '"') {
+                    self.advance()?;
+                    self.advance()?;
+                    return self.multiline_string();
+                }
                 return match self.string() {
                     Err(e) => Err(e),
                     Ok(()) => Ok(()),
@@ -373,16 +507,19 @@ impl Lexer {
             let indent_count = if col % self.tabsize == 0 {
                 (col / self.tabsize) as usize
             } else {
-                println!("Incorrect Indentation Error, Col: {col}");
-                return Err(LexerError::IncorrectIndentation { line: self.line });
+                return Err(LexerError::IncorrectIndentation {
+                    line: self.line,
+                    spaces: col,
+                    tabsize: self.tabsize,
+                });
             };
 
             if indent_count > self.indent {
                 for _ in 0..indent_count - self.indent {
                     self.tokens.push(Token::new(
                         TokenType::Indent,
-                        "".to_string(),
-                        "".to_string(),
+                        "",
+                        "",
                         self.line,
                         self.start,
                         self.curr
@@ -393,8 +530,8 @@ impl Lexer {
                     if !self.is_at_end() {
                         self.tokens.push(Token::new(
                             TokenType::Dedent,
-                            "".to_string(),
-                            "".to_string(),
+                            "",
+                            "",
                             self.line,
                             self.start,
                             self.curr
@@ -402,11 +539,7 @@ impl Lexer {
                     }
                 }
             }
-            if indent_count > 0 {
-                self.is_indented = true;
-            } else {
-                self.is_indented = false;
-            }
+            self.is_indented = indent_count > 0;
             self.indent = indent_count;
             self.is_new_line = false;
             return Ok(());
@@ -418,8 +551,8 @@ impl Lexer {
     /// Advances to the next character in the program and returns it. If there are no more
     /// characters left it will return `LexerError::NoCharactersLeft`
     fn advance(&mut self) -> Result<char, LexerError> {
-        return if let Some(c) = self.source.chars().nth(self.curr) {
-            self.curr += 1;
+        return if let Some(c) = self.source[self.curr..].chars().next() {
+            self.curr += c.len_utf8();
             Ok(c)
         } else {
             Err(LexerError::NoCharactersLeft {
@@ -441,20 +574,20 @@ impl Lexer {
                 end: self.curr
             });
         }
-        return Ok(self.source.chars().nth(self.curr).unwrap());
+        return Ok(self.source[self.curr..].chars().next().unwrap());
     }
 
     /// Takes a look at the next character in the source code, and returns it if the scanner is not
     /// at the end of the source code, otherwise it will return `LexerError::CannotPeekAtTheEnd`
     fn peek_next(&self) -> Result<char, LexerError> {
-        if self.curr + 1 >= self.source.len() {
-            return Err(LexerError::NoCharactersLeft {
+        return match self.source[self.curr..].chars().nth(1) {
+            Some(c) => Ok(c),
+            None => Err(LexerError::NoCharactersLeft {
                 line: self.line,
                 start: self.start,
                 end: self.curr
-            });
-        }
-        return Ok(self.source.chars().nth(self.curr + 1).unwrap());
+            }),
+        };
     }
 
     /// Checks if the current character in the source code is the expected character, and if it is,
@@ -465,11 +598,11 @@ impl Lexer {
             return false;
         };
 
-        if self.source.chars().nth(self.curr).unwrap() != expected {
+        if !self.source[self.curr..].starts_with(expected) {
             return false;
         };
 
-        self.curr += 1;
+        self.curr += expected.len_utf8();
         return true;
     }
 
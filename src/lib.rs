@@ -0,0 +1,114 @@
+//! Pyru is a small interpreted language with Python-like syntax. A program is run in four
+//! phases: the lexer turns the source into tokens, the parser builds a syntax tree from them,
+//! the semantic analyser checks the tree and the evaluator executes it. Between the last two,
+//! the resolver works out where each variable the program refers to is stored.
+//!
+//! The `run` module carries out all of the phases for a single program, while the `engine`
+//! module lets another Rust program embed the interpreter, registering its own native functions
+//! and running several scripts that share their globals.
+
+// Functions end with an explicit `return` throughout the interpreter
+#![allow(clippy::needless_return)]
+
+pub mod engine;
+
+pub mod error;
+
+pub mod examples;
+
+#[path = "./semanticanalyser/complexity.rs"]
+pub mod complexity;
+
+#[path = "./evaluator/cycles.rs"]
+pub mod cycles;
+
+#[path = "./evaluator/environment.rs"]
+pub mod environment;
+
+#[path = "./evaluator/evaluator.rs"]
+pub mod evaluator;
+
+#[path = "./evaluator/scopes.rs"]
+pub mod scopes;
+
+#[path = "./evaluator/natives.rs"]
+pub mod natives;
+
+#[path = "./evaluator/hashing.rs"]
+pub mod hashing;
+
+#[path = "./lexer/lexer.rs"]
+pub mod lexer;
+
+mod macros;
+
+pub mod metrics;
+
+#[path = "./parser/parser.rs"]
+pub mod parser;
+
+#[path = "./semanticanalyser/resolver.rs"]
+pub mod resolver;
+
+#[path = "./semanticanalyser/semanticanalyser.rs"]
+pub mod semanticanalyser;
+
+#[path = "./values/callable.rs"]
+pub mod callable;
+
+#[path = "./values/class.rs"]
+pub mod class;
+
+#[path = "./evaluator/csv.rs"]
+pub mod csv;
+
+#[path = "./values/deque.rs"]
+pub mod deque;
+
+#[path = "./values/dict.rs"]
+pub mod dict;
+
+#[path = "./values/enumeration.rs"]
+pub mod enumeration;
+
+#[path = "./values/expr.rs"]
+pub mod expr;
+
+#[path = "./values/list.rs"]
+pub mod list;
+
+#[path = "./values/marshal.rs"]
+pub mod marshal;
+
+#[path = "./values/number.rs"]
+pub mod number;
+
+pub mod run;
+
+pub mod sandbox;
+
+#[path = "./values/set.rs"]
+pub mod set;
+
+#[path = "./values/smallstr.rs"]
+pub mod smallstr;
+
+pub mod stdlib;
+
+#[path = "./values/stmt.rs"]
+pub mod stmt;
+
+#[path = "./values/string.rs"]
+pub mod string;
+
+#[path = "./values/token.rs"]
+pub mod token;
+
+#[path = "./semanticanalyser/types.rs"]
+pub mod types;
+
+#[path = "./values/value.rs"]
+pub mod value;
+
+#[cfg(test)]
+mod tests;
@@ -1,13 +1,17 @@
 #[macro_export]
 // Carries out arithmetic operations when binary expressions are evaluated
 macro_rules! arithmetic {
-    ( $operator:tt ; $num1:expr ; $num2:expr ) => {
+    ( $operator:tt ; $num1:expr ; $num2:expr ; $line:expr ) => {
         if let Value::Literal(LiteralType::Num(ln)) = $num1 {
             if let Value::Literal(LiteralType::Num(rn)) = $num2 {
                 return Ok(Value::Literal(LiteralType::Num(ln $operator rn)));
             }
         } else if let Value::Literal(LiteralType::Str(ls)) = $num1 {
             if let Value::Literal(LiteralType::Str(rs)) = $num2 {
+                // The length is checked before concatenating, so nothing too large is allocated
+                if ls.len() + rs.len() > MAX_STRING_LENGTH {
+                    return Err(EvaluatorError::ValueTooLarge { line: $line });
+                }
                 return Ok(Value::Literal(LiteralType::Str(format!("{}{}", ls, rs))));
             }
         }
@@ -17,17 +21,21 @@ macro_rules! arithmetic {
 #[macro_export]
 // Carries out comparison operations when binary expressions are evaluated
 macro_rules! comparison {
-    ( $operator:tt ; $num1:expr ; $num2:expr ) => {
-        if let Value::Literal(LiteralType::Num(ln)) = $num1 {
-            if let Value::Literal(LiteralType::Num(rn)) = $num2 {
-                return Ok(
-                    if ln $operator rn {
-                        Value::Literal(LiteralType::True)
-                    } else {
-                        Value::Literal(LiteralType::False)
-                    }
-                );
-            }
+    ( $operator:tt ; $left:expr ; $right:expr ) => {
+        let result = match ($left, $right) {
+            (Value::Literal(LiteralType::Num(ln)), Value::Literal(LiteralType::Num(rn))) => Some(ln $operator rn),
+            // Strings are ordered lexicographically, by the code points of their characters
+            (Value::Literal(LiteralType::Str(ls)), Value::Literal(LiteralType::Str(rs))) => Some(ls $operator rs),
+            _ => None,
+        };
+        if let Some(result) = result {
+            return Ok(
+                if result {
+                    Value::Literal(LiteralType::True)
+                } else {
+                    Value::Literal(LiteralType::False)
+                }
+            );
         }
     };
 }
@@ -35,10 +43,10 @@ macro_rules! comparison {
 #[macro_export]
 // Increments or decrements the value in the alteration expression
 macro_rules! alteration {
-    ( $self:ident ; $operator:tt ; $name:expr ; $value:expr ) => {
+    ( $self:ident ; $operator:tt ; $name:expr ; $slot:expr ; $value:expr ) => {
         if let Value::Literal(LiteralType::Num(n)) = $value {
-            return $self.environment.borrow_mut().assign(
-                $name, Value::Literal(LiteralType::Num(n $operator 1.0))
+            return $self.assign_variable(
+                $name, $slot, Value::Literal(LiteralType::Num(n $operator 1.0))
             );
         };
         return Err(EvaluatorError::ExpectedNumber);
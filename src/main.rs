@@ -1,56 +1,417 @@
-mod error;
+#![allow(clippy::needless_return)]
 
-#[path = "./evaluator/environment.rs"]
-mod environment;
+use base64::{ engine::general_purpose::URL_SAFE_NO_PAD, Engine };
+use flate2::{ write::{ GzEncoder, ZlibEncoder }, Compression };
+use hmac::{ Hmac, Mac };
+#[allow(unused)]
+use rocket::{
+    data::{ Limits, ToByteUnit },
+    fairing::{ Fairing, Info, Kind },
+    figment::Figment,
+    get,
+    http::{ Header, Method, Status },
+    post,
+    request::{ FromRequest, Outcome, Request },
+    routes,
+    Build,
+    Orbit,
+    Response,
+    Rocket,
+    State,
+};
+use rocket::serde::{ Deserialize, Serialize, json::{ json, Json, Value } };
+use rocket_cors::{ AllowedHeaders, AllowedOrigins, Cors, CorsOptions };
+use schemars::{ generate::{ Contract, SchemaSettings }, JsonSchema };
+use serde_json::Map;
+use sha2::Sha256;
+use std::{ io::{ Cursor, Write }, time::{ Duration, SystemTime, UNIX_EPOCH } };
 
-#[path = "./evaluator/evaluator.rs"]
-mod evaluator;
+use pyru::{
+    complexity::FunctionMetrics,
+    evaluator::{ CancelToken, NativePolicy },
+    examples::{ examples, Example },
+    lexer,
+    metrics::Metrics,
+    natives::{ builtins, Builtin },
+    run::{ ast, output, run_events_on, tokens, Backend, RunEvent },
+    sandbox::{ self, Sandbox, WORKER_ENV },
+};
 
-#[path = "./lexer/lexer.rs"]
-mod lexer;
+/// The origins that may call the API when `PYRU_ALLOWED_ORIGINS` is not set.
+const DEFAULT_ALLOWED_ORIGINS: [&str; 6] = [
+    "http://localhost:8080",
+    "http://127.0.0.1:8080",
+    "http://localhost:8000",
+    "http://0.0.0.0:8000",
+    "http://localhost:5173",
+    "https://pyru-playground.vercel.app",
+];
 
-mod macros;
+/// How long a program run by the server may run for when `PYRU_TIMEOUT_MS` is not set.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
 
-#[path = "./parser/parser.rs"]
-mod parser;
+/// The settings of the server, read from environment variables so that it can be configured
+/// when run in a container. Rocket's own `ROCKET_` variables are still read, but the variables
+/// below take precedence over them.
+///
+/// ## Fields
+/// - `address`: The address to listen on, from `PYRU_ADDRESS`.
+/// - `port`: The port to listen on, from `PYRU_PORT`.
+/// - `shutdown_grace`: The number of seconds that requests in flight are given to finish once
+///   the server is asked to shut down, from `PYRU_SHUTDOWN_GRACE`.
+/// - `allowed_origins`: The origins that may call the API, as a comma separated list in
+///   `PYRU_ALLOWED_ORIGINS`.
+/// - `api_key`: The key that requests which run code must present, from `PYRU_API_KEY`. If
+///   neither it nor `jwt_secret` is set, those requests are not authenticated.
+/// - `jwt_secret`: The key that JSON Web Tokens presented by requests which run code must be
+///   signed with using HS256, from `PYRU_JWT_SECRET`.
+/// - `telemetry`: Whether the metrics count which errors users run into, enabled by setting
+///   `PYRU_TELEMETRY` to `1` or `true`.
+/// - `backend`: Where programs are run, from `PYRU_BACKEND`, which is either `inprocess` (the
+///   default) or `process` to run each program in a worker process. The limits of a worker are
+///   read from `PYRU_WORKER_MEMORY_MB`, `PYRU_WORKER_CPU_SECONDS` and `PYRU_WORKER_TIMEOUT_MS`.
+/// - `timeout`: How long each program may run for before it is stopped, keeping the output it
+///   printed, from `PYRU_TIMEOUT_MS`.
+struct ServerConfig {
+    address: Option<String>,
+    port: Option<u16>,
+    shutdown_grace: Option<u32>,
+    allowed_origins: Vec<String>,
+    api_key: Option<String>,
+    jwt_secret: Option<String>,
+    telemetry: bool,
+    backend: Backend,
+    timeout: Duration,
+}
 
-#[path = "./semanticanalyser/semanticanalyser.rs"]
-mod semanticanalyser;
+impl ServerConfig {
+    /// Reads the settings from the environment, panicking if a variable holds an invalid value.
+    fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
 
-#[path = "./values/callable.rs"]
-mod callable;
+        let allowed_origins = match var("PYRU_ALLOWED_ORIGINS") {
+            Some(origins) => origins.split(',').map(|origin| origin.trim().to_string()).collect(),
+            None => DEFAULT_ALLOWED_ORIGINS.iter().map(|origin| origin.to_string()).collect(),
+        };
 
-#[path = "./values/expr.rs"]
-mod expr;
+        return Self {
+            address: var("PYRU_ADDRESS"),
+            port: var("PYRU_PORT")
+                .map(|port| port.parse().expect("PYRU_PORT must be a port number")),
+            shutdown_grace: var("PYRU_SHUTDOWN_GRACE")
+                .map(|grace| grace.parse().expect("PYRU_SHUTDOWN_GRACE must be whole seconds")),
+            allowed_origins,
+            api_key: var("PYRU_API_KEY"),
+            jwt_secret: var("PYRU_JWT_SECRET"),
+            telemetry: var("PYRU_TELEMETRY").is_some_and(|value| value == "1" || value == "true"),
+            backend: Self::backend(&var),
+            timeout: var("PYRU_TIMEOUT_MS")
+                .map(|timeout| Duration::from_millis(
+                    timeout.parse().expect("PYRU_TIMEOUT_MS must be whole milliseconds")
+                ))
+                .unwrap_or(DEFAULT_TIMEOUT),
+        };
+    }
 
-#[path = "./values/list.rs"]
-mod list;
+    /// Reads the backend and, for the process backend, the limits of its workers.
+    fn backend(var: &dyn Fn(&str) -> Option<String>) -> Backend {
+        match var("PYRU_BACKEND").as_deref() {
+            None | Some("inprocess") => return Backend::InProcess,
+            Some("process") => {},
+            Some(_) => panic!("PYRU_BACKEND must be either inprocess or process"),
+        }
 
-mod run;
+        let mut sandbox = Sandbox::current_exe().expect("the server executable must be readable");
+        if let Some(memory) = var("PYRU_WORKER_MEMORY_MB") {
+            sandbox = sandbox.with_memory_limit(
+                memory.parse().expect("PYRU_WORKER_MEMORY_MB must be whole megabytes")
+            );
+        }
+        if let Some(cpu) = var("PYRU_WORKER_CPU_SECONDS") {
+            sandbox = sandbox.with_cpu_limit(
+                cpu.parse().expect("PYRU_WORKER_CPU_SECONDS must be whole seconds")
+            );
+        }
+        if let Some(timeout) = var("PYRU_WORKER_TIMEOUT_MS") {
+            sandbox = sandbox.with_timeout(Duration::from_millis(
+                timeout.parse().expect("PYRU_WORKER_TIMEOUT_MS must be whole milliseconds")
+            ));
+        }
+        return Backend::Isolated(sandbox);
+    }
 
-#[path = "./values/stmt.rs"]
-mod stmt;
+    /// Builds Rocket's configuration, applying the settings that were given.
+    fn figment(&self) -> Figment {
+        // Escaping can make the JSON body up to six times longer than the source it carries, as
+        // in `\u001f`, so the body limit leaves room above the lexer's limit on the source itself,
+        // and on top of that for the rest of the body, such as the input
+        let limits = Limits::default().limit("json", (8 * lexer::MAX_SOURCE_LENGTH).bytes());
+        let mut figment = rocket::Config::figment().merge(("limits", limits));
 
-#[path = "./values/token.rs"]
-mod token;
+        if let Some(address) = &self.address {
+            figment = figment.merge(("address", address));
+        }
+        if let Some(port) = self.port {
+            figment = figment.merge(("port", port));
+        }
+        if let Some(grace) = self.shutdown_grace {
+            figment = figment.merge(("shutdown.grace", grace));
+        }
+        return figment;
+    }
+}
 
-#[path = "./values/value.rs"]
-mod value;
+/// A request guard for the routes that run code. When an API key is configured, the request
+/// may present it in an `X-Api-Key` header or as an `Authorization: Bearer` token. When a JWT
+/// secret is configured, the request may instead present a JSON Web Token signed with it as an
+/// `Authorization: Bearer` token. If neither is configured, every request is let through.
+struct ApiKey;
 
-#[cfg(test)]
-mod tests;
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiKey {
+    type Error = ();
 
-#[allow(unused)]
-use rocket::{ http::Method, launch, post, routes };
-use rocket::serde::{ Deserialize, Serialize, json::Json };
-use rocket_cors::{ AllowedHeaders, AllowedOrigins, Cors, CorsOptions };
-use std::io::Write;
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let (api_key, jwt_secret) = match request.rocket().state::<ServerConfig>() {
+            Some(config) if config.api_key.is_some() || config.jwt_secret.is_some() => {
+                (config.api_key.as_deref(), config.jwt_secret.as_deref())
+            },
+            _ => return Outcome::Success(ApiKey),
+        };
+
+        let headers = request.headers();
+        let bearer = headers.get_one("Authorization").and_then(|value| value.strip_prefix("Bearer "));
+        let given = headers.get_one("X-Api-Key").or(bearer);
+
+        if api_key.is_some() && given == api_key {
+            return Outcome::Success(ApiKey);
+        }
+        if let (Some(secret), Some(token)) = (jwt_secret, bearer) {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            if valid_jwt(token, secret.as_bytes(), now) {
+                return Outcome::Success(ApiKey);
+            }
+        }
+        return Outcome::Error((Status::Unauthorized, ()));
+    }
+}
+
+/// Checks that a JSON Web Token is signed with the given secret using HS256, and that it has an
+/// `exp` claim that has not passed at `now`, in seconds since the Unix epoch. A `nbf` claim, if
+/// the token has one, must also have passed.
+fn valid_jwt(token: &str, secret: &[u8], now: u64) -> bool {
+    let parts: Vec<&str> = token.split('.').collect();
+    let [header, payload, signature] = parts[..] else {
+        return false;
+    };
+    let decode = |part: &str| {
+        URL_SAFE_NO_PAD
+            .decode(part)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok())
+    };
+
+    // The algorithm is fixed rather than taken from the header, so that a token cannot choose a
+    // weaker one, such as `none`
+    match decode(header) {
+        Some(header) if header["alg"] == "HS256" => {},
+        _ => return false,
+    }
+
+    let Ok(signature) = URL_SAFE_NO_PAD.decode(signature) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(format!("{header}.{payload}").as_bytes());
+    if mac.verify_slice(&signature).is_err() {
+        return false;
+    }
+
+    let Some(claims) = decode(payload) else {
+        return false;
+    };
+    let not_expired = claims["exp"].as_u64().is_some_and(|exp| now < exp);
+    let not_before = claims.get("nbf").is_none_or(|nbf| nbf.as_u64().is_some_and(|nbf| nbf <= now));
+    return not_expired && not_before;
+}
+
+/// A fairing that cancels every program still running once the server is asked to shut down,
+/// so that the requests running them finish within the grace period rather than being cut off.
+struct CancelOnShutdown;
+
+#[rocket::async_trait]
+impl Fairing for CancelOnShutdown {
+    fn info(&self) -> Info {
+        return Info { name: "Cancel programs on shutdown", kind: Kind::Shutdown };
+    }
+
+    async fn on_shutdown(&self, rocket: &Rocket<Orbit>) {
+        if let Some(cancel) = rocket.state::<CancelToken>() {
+            cancel.cancel();
+        }
+    }
+}
+
+/// How short a response body can be before it is sent without being compressed, as compressing
+/// it would save little.
+const MIN_COMPRESSED_LENGTH: usize = 1024;
+
+/// A fairing that compresses the bodies of responses with gzip or deflate, whichever the client
+/// accepts in its `Accept-Encoding` header, preferring gzip when it accepts both. Bodies of an
+/// unknown length, or shorter than `MIN_COMPRESSED_LENGTH`, are left as they are.
+struct ResponseCompression;
+
+/// A coding that a response body can be compressed with.
+#[derive(Clone, Copy)]
+enum Coding {
+    Gzip,
+    Deflate,
+}
+
+impl Coding {
+    /// Returns the coding to compress a response with, from the `Accept-Encoding` header of the
+    /// request, or `None` if the client does not accept either coding.
+    fn negotiate(accept: &str) -> Option<Self> {
+        let accepts = |name: &str| {
+            accept.split(',').any(|coding| {
+                // A coding given a quality of zero is one the client refuses
+                let mut parts = coding.split(';').map(str::trim);
+                let named = parts.next() == Some(name);
+                let quality = parts
+                    .find_map(|part| part.strip_prefix("q="))
+                    .map_or(1.0, |q| q.parse().unwrap_or(0.0));
+                return named && quality > 0.0;
+            })
+        };
 
-use run::run;
+        if accepts("gzip") {
+            return Some(Coding::Gzip);
+        }
+        if accepts("deflate") {
+            return Some(Coding::Deflate);
+        }
+        return None;
+    }
 
-#[derive(Serialize, Deserialize)]
+    /// Returns the name of the coding in the `Content-Encoding` header.
+    fn name(self) -> &'static str {
+        return match self {
+            Coding::Gzip => "gzip",
+            Coding::Deflate => "deflate",
+        };
+    }
+
+    /// Compresses the given body.
+    fn compress(self, body: &[u8]) -> std::io::Result<Vec<u8>> {
+        return match self {
+            Coding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()
+            },
+            Coding::Deflate => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()
+            },
+        };
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for ResponseCompression {
+    fn info(&self) -> Info {
+        return Info { name: "Response compression", kind: Kind::Response };
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let length = response.body().preset_size().unwrap_or(0);
+        if length < MIN_COMPRESSED_LENGTH || response.headers().contains("Content-Encoding") {
+            return;
+        }
+        response.adjoin_header(Header::new("Vary", "Accept-Encoding"));
+
+        let accept = request.headers().get("Accept-Encoding").collect::<Vec<_>>().join(",");
+        let Some(coding) = Coding::negotiate(&accept) else {
+            return;
+        };
+        let body = match response.body_mut().to_bytes().await {
+            Ok(body) => body,
+            Err(_) => return,
+        };
+
+        // The body has been read by now, so it is put back as it was if it cannot be compressed
+        match coding.compress(&body) {
+            Ok(compressed) => {
+                response.set_header(Header::new("Content-Encoding", coding.name()));
+                response.set_sized_body(compressed.len(), Cursor::new(compressed));
+            },
+            Err(_) => response.set_sized_body(body.len(), Cursor::new(body)),
+        }
+    }
+}
+
+/// The body of a request to run some code.
+///
+/// ## Fields
+/// - `source`: The source code to run.
+/// - `stdin`: The input the program can read with `input`, one line at a time.
+/// - `allow_natives`: If given, only these native functions are defined.
+/// - `deny_natives`: If given, these native functions are not defined. Ignored when
+///   `allow_natives` is given.
+#[derive(Serialize, Deserialize, JsonSchema)]
 struct Message {
     source: String,
+    #[serde(default)]
+    stdin: String,
+    #[serde(default)]
+    allow_natives: Option<Vec<String>>,
+    #[serde(default)]
+    deny_natives: Option<Vec<String>>,
+}
+
+/// The body of a request to run some code and inspect it in a single call.
+///
+/// ## Fields
+/// - `message`: The code to run and the natives it may call, as for `/runcode`.
+/// - `tokens`: Whether to return the tokens of the code.
+/// - `ast`: Whether to return the syntax tree of the code.
+/// - `complexity`: Whether to return the complexity of each function in the code.
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct BatchMessage {
+    #[serde(flatten)]
+    message: Message,
+    #[serde(default)]
+    tokens: bool,
+    #[serde(default)]
+    ast: bool,
+    #[serde(default)]
+    complexity: bool,
+}
+
+/// The response to a batch request. The tokens and syntax tree are only present if they were
+/// requested, and hold the message of the error that stopped them if the code could not be
+/// lexed or parsed. The complexity is only present if it was requested, and is empty if the
+/// code did not get through the semantic analyser.
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct BatchResponse {
+    output: Vec<String>,
+    tokens: Option<Result<Vec<String>, String>>,
+    ast: Option<Result<String, String>>,
+    complexity: Option<Vec<FunctionMetrics>>,
+}
+
+impl Message {
+    /// Returns the native functions the request permits the program to call.
+    fn natives(&self) -> NativePolicy {
+        if let Some(names) = &self.allow_natives {
+            return NativePolicy::Allow(names.clone());
+        }
+        if let Some(names) = &self.deny_natives {
+            return NativePolicy::Deny(names.clone());
+        }
+        return NativePolicy::All;
+    }
 }
 
 // fn _repl() -> String {
@@ -72,21 +433,12 @@ struct Message {
 //     }
 // }
 
-fn make_cors() -> Cors {
-    let allowed_origins = AllowedOrigins::some_exact(
-        &[
-            "http://localhost:8080",
-            "http://127.0.0.1:8080",
-            "http://localhost:8000",
-            "http://0.0.0.0:8000",
-            "http://localhost:5173",
-            "https://pyru-playground.vercel.app",
-        ]
-    );
+fn make_cors(origins: &[String]) -> Cors {
+    let allowed_origins = AllowedOrigins::some_exact(origins);
 
     (CorsOptions {
         allowed_origins,
-        allowed_methods: vec![Method::Post, Method::Options].into_iter().map(From::from).collect(),
+        allowed_methods: vec![Method::Get, Method::Post, Method::Options].into_iter().map(From::from).collect(),
         allowed_headers: AllowedHeaders::all(),
         allow_credentials: true,
         ..Default::default()
@@ -96,16 +448,269 @@ fn make_cors() -> Cors {
 }
 
 #[post("/runcode", format = "json", data = "<message>")]
-fn run_code(message: Json<Message>) -> Json<String> {
-    let debug = false;
-    let output = run(message.source.as_str(), debug);
+fn run_code(
+    _key: ApiKey,
+    message: Json<Message>,
+    config: &State<ServerConfig>,
+    metrics: &State<Metrics>,
+    cancel: &State<CancelToken>,
+) -> Json<String> {
+    let events: Vec<RunEvent> = run_events_on(
+        &config.backend,
+        message.source.as_str(),
+        message.natives(),
+        message.stdin.as_str(),
+        Some(config.timeout),
+        cancel.inner().clone(),
+    )
+    .collect();
+    metrics.record(&events);
+
+    Json(format!("{:?}", output(events)))
+}
+
+#[post("/batch", format = "json", data = "<batch>")]
+fn run_batch(
+    _key: ApiKey,
+    batch: Json<BatchMessage>,
+    config: &State<ServerConfig>,
+    metrics: &State<Metrics>,
+    cancel: &State<CancelToken>,
+) -> Json<BatchResponse> {
+    let source = batch.message.source.as_str();
+    let events: Vec<RunEvent> = run_events_on(
+        &config.backend,
+        source,
+        batch.message.natives(),
+        &batch.message.stdin,
+        Some(config.timeout),
+        cancel.inner().clone(),
+    )
+    .collect();
+    metrics.record(&events);
+
+    let functions = match events.last() {
+        Some(RunEvent::Finished { stats }) => stats.functions.clone(),
+        _ => Vec::new(),
+    };
+
+    Json(BatchResponse {
+        output: output(events),
+        tokens: batch.tokens.then(|| tokens(source)),
+        ast: batch.ast.then(|| ast(source)),
+        complexity: batch.complexity.then_some(functions),
+    })
+}
+
+#[get("/examples")]
+fn get_examples() -> Json<Vec<Example>> {
+    Json(examples())
+}
 
-    Json(format!("{:?}", output))
+#[get("/natives")]
+fn get_natives() -> Json<&'static [Builtin]> {
+    Json(builtins())
 }
 
-#[launch]
-async fn rocket() -> _ {
-    rocket::build().mount("/v1", routes![run_code]).attach(make_cors())
+#[get("/metrics")]
+fn get_metrics(metrics: &State<Metrics>) -> String {
+    metrics.render()
+}
+
+/// Describes the `/v1` routes, and the bodies they accept and return, as an OpenAPI document.
+/// The schemas of the bodies are generated from the types they are read into and written from.
+#[get("/openapi.json")]
+fn openapi() -> Json<Value> {
+    Json(json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "pyru",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{ "url": "/v1" }],
+        "paths": {
+            "/runcode": {
+                "post": {
+                    "summary": "Runs a program and returns its output",
+                    "security": [{}, { "apiKey": [] }, { "bearer": [] }],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": { "$ref": "#/components/schemas/Message" },
+                            },
+                        },
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "The lines of output, or the message of the error that \
+                                stopped the program, formatted as a list",
+                            "content": {
+                                "application/json": { "schema": { "type": "string" } },
+                            },
+                        },
+                        "401": { "description": "The API key is missing or incorrect" },
+                    },
+                },
+            },
+            "/batch": {
+                "post": {
+                    "summary": "Runs a program and returns its output, tokens and syntax tree",
+                    "security": [{}, { "apiKey": [] }, { "bearer": [] }],
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": {
+                                "schema": { "$ref": "#/components/schemas/BatchMessage" },
+                            },
+                        },
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "The output of the program, along with the tokens \
+                                and syntax tree if they were requested",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/BatchResponse" },
+                                },
+                            },
+                        },
+                        "401": { "description": "The API key is missing or incorrect" },
+                    },
+                },
+            },
+            "/examples": {
+                "get": {
+                    "summary": "Returns example programs to use as templates",
+                    "responses": {
+                        "200": {
+                            "description": "Every example, with the output it produces",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": { "$ref": "#/components/schemas/Example" },
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+            "/natives": {
+                "get": {
+                    "summary": "Returns the built in native functions that programs can call",
+                    "responses": {
+                        "200": {
+                            "description": "Every built in native function, with how it is called",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": { "$ref": "#/components/schemas/Builtin" },
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+            "/metrics": {
+                "get": {
+                    "summary": "Returns statistics about the programs run by the server",
+                    "responses": {
+                        "200": {
+                            "description": "The metrics in the Prometheus text format",
+                            "content": {
+                                "text/plain": { "schema": { "type": "string" } },
+                            },
+                        },
+                    },
+                },
+            },
+            "/openapi.json": {
+                "get": {
+                    "summary": "Returns this document",
+                    "responses": {
+                        "200": {
+                            "description": "The OpenAPI document for the API",
+                            "content": {
+                                "application/json": { "schema": { "type": "object" } },
+                            },
+                        },
+                    },
+                },
+            },
+        },
+        "components": {
+            "securitySchemes": {
+                "apiKey": { "type": "apiKey", "in": "header", "name": "X-Api-Key" },
+                "bearer": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "The API key, or a JSON Web Token signed with HS256",
+                },
+            },
+            "schemas": schemas(),
+        },
+    }))
+}
+
+/// Returns the schemas of the bodies the routes accept and return, keyed by the name of their
+/// type. Requests are described as they are deserialized and responses as they are serialized,
+/// so a field with a default is only required in a response.
+fn schemas() -> Map<String, Value> {
+    let mut requests = SchemaSettings::openapi3().into_generator();
+    requests.subschema_for::<Message>();
+    requests.subschema_for::<BatchMessage>();
+
+    let mut responses = SchemaSettings::openapi3()
+        .with(|settings| settings.contract = Contract::Serialize)
+        .into_generator();
+    responses.subschema_for::<BatchResponse>();
+    responses.subschema_for::<Example>();
+    responses.subschema_for::<Builtin>();
+
+    let mut schemas = requests.take_definitions(true);
+    schemas.extend(responses.take_definitions(true));
+    return schemas;
+}
+
+/// Builds the server from the settings in the environment. Rocket shuts it down gracefully on
+/// Ctrl-C or `SIGTERM`, which is how container runtimes stop it, cancelling the programs that
+/// are still running and waiting for the requests in flight to finish before exiting.
+fn rocket() -> Rocket<Build> {
+    server(ServerConfig::from_env())
+}
+
+/// Builds the server with the given settings.
+fn server(config: ServerConfig) -> Rocket<Build> {
+    let cors = make_cors(&config.allowed_origins);
+
+    rocket::custom(config.figment())
+        .mount("/v1", routes![run_code, run_batch, get_examples, get_natives, get_metrics, openapi])
+        .manage(Metrics::new().with_telemetry(config.telemetry))
+        .manage(config)
+        .manage(CancelToken::new())
+        .attach(cors)
+        .attach(ResponseCompression)
+        .attach(CancelOnShutdown)
+}
+
+/// Launches the server, or runs a single program and exits when started as the worker process
+/// of a sandbox.
+fn main() {
+    if std::env::var_os(WORKER_ENV).is_some() {
+        if sandbox::run_worker().is_err() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Err(e) = rocket::execute(rocket().launch()) {
+        eprintln!("The server stopped with an error: {e}");
+        std::process::exit(1);
+    }
 }
 
 // fn _main() {
@@ -115,3 +720,253 @@ async fn rocket() -> _ {
 
 //     let _ = run(source.as_str(), debug);
 // }
+
+#[cfg(test)]
+mod tests {
+    use std::{ collections::BTreeSet, io::Read };
+
+    use flate2::read::{ GzDecoder, ZlibDecoder };
+    use rocket::local::blocking::Client;
+
+    use super::*;
+
+    /// Returns the settings of a server that runs programs in process, without an API key.
+    fn config() -> ServerConfig {
+        return ServerConfig {
+            address: None,
+            port: None,
+            shutdown_grace: None,
+            allowed_origins: Vec::new(),
+            api_key: None,
+            jwt_secret: None,
+            telemetry: false,
+            backend: Backend::InProcess,
+            timeout: DEFAULT_TIMEOUT,
+        };
+    }
+
+    /// Returns the names of the fields a value is serialized with.
+    fn fields<T: Serialize>(value: &T) -> BTreeSet<String> {
+        return match serde_json::to_value(value).unwrap() {
+            Value::Object(object) => object.keys().cloned().collect(),
+            value => panic!("{value} is not an object"),
+        };
+    }
+
+    /// Returns the names of the properties of a schema, including those of the schemas it is
+    /// made of.
+    fn properties(document: &Value, schema: &Value) -> BTreeSet<String> {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            return properties(document, &document["components"]["schemas"][name]);
+        }
+        if let Some(parts) = schema["allOf"].as_array() {
+            return parts.iter().flat_map(|part| properties(document, part)).collect();
+        }
+        return schema["properties"].as_object().unwrap().keys().cloned().collect();
+    }
+
+    /// Returns every schema that the given part of the OpenAPI document refers to.
+    fn references(value: &Value) -> Vec<String> {
+        return match value {
+            Value::Object(map) => map
+                .iter()
+                .flat_map(|(key, value)| match (key.as_str(), value) {
+                    ("$ref", Value::String(reference)) => vec![reference.clone()],
+                    _ => references(value),
+                })
+                .collect(),
+            Value::Array(items) => items.iter().flat_map(references).collect(),
+            _ => Vec::new(),
+        };
+    }
+
+    #[test]
+    fn test_openapi_schemas() {
+        // Tests that each schema in the OpenAPI document lists exactly the fields that the type
+        // it describes is serialized with, and only requires fields that the type has
+        let document = openapi().into_inner();
+        let message = Message {
+            source: String::new(),
+            stdin: String::new(),
+            allow_natives: None,
+            deny_natives: None,
+        };
+        let metrics = FunctionMetrics { name: String::new(), line: 0, complexity: 0, nesting: 0, length: 0 };
+        let response = BatchResponse { output: Vec::new(), tokens: None, ast: None, complexity: None };
+        let schemas = [
+            ("Message", fields(&message)),
+            ("BatchMessage", fields(&BatchMessage { message, tokens: false, ast: false, complexity: false })),
+            ("BatchResponse", fields(&response)),
+            ("Example", fields(&examples()[0])),
+            ("Builtin", fields(&builtins()[0])),
+            ("FunctionMetrics", fields(&metrics)),
+        ];
+
+        let described = document["components"]["schemas"].as_object().unwrap();
+        for (name, fields) in schemas {
+            let schema = &described[name];
+            assert_eq!(properties(&document, schema), fields, "The properties of {name}");
+
+            let required = schema["required"].as_array().into_iter().flatten();
+            for field in required {
+                assert!(fields.contains(field.as_str().unwrap()), "{name} requires {field}");
+            }
+        }
+
+        // Tests that every schema the document refers to is defined in it
+        for reference in references(&document) {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            assert!(described.contains_key(name), "{reference} is not defined");
+        }
+    }
+
+    #[test]
+    fn test_timeouts_metric() {
+        let config = ServerConfig { timeout: Duration::from_millis(50), ..config() };
+        let client = Client::tracked(server(config)).unwrap();
+        let run = |source: &str| {
+            let response = client.post("/v1/runcode").json(&json!({ "source": source })).dispatch();
+            assert_eq!(response.status(), Status::Ok);
+        };
+
+        // Tests that a run stopped by the timeout is counted as a timeout, while a run stopped by
+        // another limit or finishing normally is not
+        run("print(1);");
+        run("let i = 0;\nwhile i >= 0:\n  i = i + 1;\n");
+        run("def f(n):\n  return f(n + 1);\nf(0);\n");
+
+        let metrics = client.get("/v1/metrics").dispatch().into_string().unwrap();
+        assert!(metrics.contains("pyru_executions_total 3\n"));
+        assert!(metrics.contains("pyru_timeouts_total 1\n"));
+        assert!(metrics.contains("pyru_errors_total{phase=\"interpreter\"} 2\n"));
+    }
+
+    #[test]
+    fn test_response_compression() {
+        let client = Client::tracked(server(config())).unwrap();
+        let get = |accept: Option<&str>| {
+            let mut request = client.get("/v1/examples");
+            if let Some(accept) = accept {
+                request = request.header(Header::new("Accept-Encoding", accept.to_string()));
+            }
+            let response = request.dispatch();
+            let encoding = response.headers().get_one("Content-Encoding").map(str::to_string);
+            return (encoding, response.into_bytes().unwrap());
+        };
+
+        // Tests that the body is only compressed when the client accepts a coding
+        let (encoding, plain) = get(None);
+        assert_eq!(encoding, None);
+        assert_eq!(get(Some("br")).0, None);
+        assert_eq!(get(Some("gzip;q=0")).0, None);
+
+        // Tests that gzip is preferred, and that either coding decompresses to the same body
+        let (encoding, body) = get(Some("deflate, gzip"));
+        assert_eq!(encoding.as_deref(), Some("gzip"));
+        let mut decompressed = Vec::new();
+        GzDecoder::new(body.as_slice()).read_to_end(&mut decompressed).unwrap();
+        assert_eq!(decompressed, plain);
+        assert!(body.len() < plain.len());
+
+        let (encoding, body) = get(Some("gzip;q=0, deflate"));
+        assert_eq!(encoding.as_deref(), Some("deflate"));
+        let mut decompressed = Vec::new();
+        ZlibDecoder::new(body.as_slice()).read_to_end(&mut decompressed).unwrap();
+        assert_eq!(decompressed, plain);
+    }
+
+    #[test]
+    fn test_json_limit() {
+        // Tests that the longest source the lexer accepts is not turned away by the JSON body
+        // limit, even when escaping it makes the body longer than Rocket's default limit of 1 MiB
+        let source = format!("//{}\n", "\u{1}".repeat(lexer::MAX_SOURCE_LENGTH - 3));
+        let body = json!({ "source": source }).to_string();
+        assert!(body.len() > 1024 * 1024);
+
+        let client = Client::tracked(server(config())).unwrap();
+        let response = client
+            .post("/v1/runcode")
+            .header(rocket::http::ContentType::JSON)
+            .body(body)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_json::<String>().unwrap(), "[]");
+    }
+
+    /// Returns a JSON Web Token with the given header and claims, signed with the given secret.
+    fn jwt(header: Value, claims: Value, secret: &str) -> String {
+        let encode = |value: Value| URL_SAFE_NO_PAD.encode(value.to_string());
+        let signed = format!("{}.{}", encode(header), encode(claims));
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(signed.as_bytes());
+        return format!("{signed}.{}", URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes()));
+    }
+
+    #[test]
+    fn test_valid_jwt() {
+        let header = json!({ "alg": "HS256", "typ": "JWT" });
+        let valid = |token: &str| valid_jwt(token, b"secret", 1000);
+
+        assert!(valid(&jwt(header.clone(), json!({ "exp": 1001 }), "secret")));
+        assert!(valid(&jwt(header.clone(), json!({ "exp": 1001, "nbf": 1000 }), "secret")));
+
+        // Tests that a token is rejected once it has expired, before it is valid, without an
+        // expiry, or when it is signed with another secret or algorithm
+        assert!(!valid(&jwt(header.clone(), json!({ "exp": 1000 }), "secret")));
+        assert!(!valid(&jwt(header.clone(), json!({ "exp": 1001, "nbf": 1001 }), "secret")));
+        assert!(!valid(&jwt(header.clone(), json!({ "sub": "user" }), "secret")));
+        assert!(!valid(&jwt(header, json!({ "exp": 1001 }), "other")));
+        assert!(!valid(&jwt(json!({ "alg": "HS512" }), json!({ "exp": 1001 }), "secret")));
+
+        let unsigned = jwt(json!({ "alg": "none" }), json!({ "exp": 1001 }), "secret");
+        let unsigned = &unsigned[..=unsigned.rfind('.').unwrap()];
+        assert!(!valid(unsigned));
+        assert!(!valid("not a token"));
+    }
+
+    #[test]
+    fn test_api_key_guard() {
+        let config = ServerConfig {
+            api_key: Some("key".to_string()),
+            jwt_secret: Some("secret".to_string()),
+            ..config()
+        };
+        let client = Client::tracked(server(config)).unwrap();
+        let status = |header: Option<Header<'static>>| {
+            let mut request = client.post("/v1/runcode").json(&json!({ "source": "print(1);" }));
+            if let Some(header) = header {
+                request = request.header(header);
+            }
+            return request.dispatch().status();
+        };
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let header = json!({ "alg": "HS256" });
+        let token = jwt(header.clone(), json!({ "exp": now + 60 }), "secret");
+        let expired = jwt(header, json!({ "exp": now - 60 }), "secret");
+
+        // Tests that either the API key or a valid token lets the request through
+        assert_eq!(status(None), Status::Unauthorized);
+        assert_eq!(status(Some(Header::new("X-Api-Key", "key"))), Status::Ok);
+        assert_eq!(status(Some(Header::new("Authorization", "Bearer key"))), Status::Ok);
+        assert_eq!(status(Some(Header::new("Authorization", format!("Bearer {token}")))), Status::Ok);
+        assert_eq!(
+            status(Some(Header::new("Authorization", format!("Bearer {expired}")))),
+            Status::Unauthorized
+        );
+        assert_eq!(status(Some(Header::new("X-Api-Key", token))), Status::Unauthorized);
+    }
+
+    #[test]
+    fn test_cancel_on_shutdown() {
+        // Tests that shutting the server down cancels the token that the programs it runs are
+        // given, which stops those still running
+        let client = Client::tracked(server(config())).unwrap();
+        let cancel = client.rocket().state::<CancelToken>().unwrap().clone();
+        assert!(!cancel.is_cancelled());
+
+        client.terminate();
+        assert!(cancel.is_cancelled());
+    }
+}
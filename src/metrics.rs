@@ -0,0 +1,146 @@
+//! The `metrics` module keeps counts of the programs run by the server, so that operators can
+//! monitor the playground.
+//!
+//! ## Overview
+//!
+//! The `Metrics` struct is shared between requests and is updated from the events of each run.
+//! It is rendered in the Prometheus text exposition format, which can be scraped directly.
+//!
+//! When telemetry is enabled, the code of each diagnostic is also counted, showing which errors
+//! users run into most. Only the phase and the code are kept, never the source or the message.
+
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex, time::Duration};
+
+use crate::{
+    evaluator::Limit,
+    run::{Phase, RunEvent},
+};
+
+/// The phases, in the order they are rendered.
+const PHASES: [Phase; 4] = [Phase::Lexer, Phase::Parser, Phase::SemanticAnalyser, Phase::Evaluator];
+
+/// The counters that are updated after each run.
+///
+/// ## Fields
+/// - `executions`: The number of programs run.
+/// - `errors`: The number of runs stopped by a diagnostic, indexed in the same order as `PHASES`.
+/// - `timeouts`: The number of runs stopped for running too long, by either the time limit or
+///   the step limit. These are also counted as errors of the evaluator.
+/// - `duration`: The total time spent running programs.
+/// - `diagnostics`: The number of times each diagnostic was reported, by phase and code. Only
+///   recorded when telemetry is enabled.
+#[derive(Debug, Default)]
+struct Counters {
+    executions: u64,
+    errors: [u64; 4],
+    timeouts: u64,
+    duration: Duration,
+    diagnostics: BTreeMap<(String, String), u64>,
+}
+
+/// The `Metrics` struct records statistics about the programs run by the server.
+///
+/// ## Fields
+/// - `counters`: The counters, shared between requests.
+/// - `telemetry`: Whether the code of each diagnostic is counted.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    counters: Mutex<Counters>,
+    telemetry: bool,
+}
+
+impl Metrics {
+    /// Creates a new `Metrics` instance with every counter at zero and telemetry disabled.
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Sets whether the code of each diagnostic is counted.
+    pub fn with_telemetry(mut self, telemetry: bool) -> Self {
+        self.telemetry = telemetry;
+        return self;
+    }
+
+    /// Records the events produced by a single run.
+    pub fn record(&self, events: &[RunEvent]) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        counters.executions += 1;
+
+        for event in events {
+            match event {
+                RunEvent::Diagnostic { phase, code, .. } => self.record_error(&mut counters, *phase, code),
+                RunEvent::LimitExceeded { limit, code, .. } => {
+                    if matches!(limit, Limit::Time | Limit::Steps) {
+                        counters.timeouts += 1;
+                    }
+                    self.record_error(&mut counters, Phase::Evaluator, code);
+                },
+                RunEvent::Finished { stats } => counters.duration += stats.elapsed,
+                RunEvent::Output(_) | RunEvent::Notice(_) | RunEvent::Globals(_) => {},
+            }
+        }
+    }
+
+    /// Records an error that stopped a run, and its code if telemetry is enabled.
+    fn record_error(&self, counters: &mut Counters, phase: Phase, code: &str) {
+        if let Some(i) = PHASES.iter().position(|p| *p == phase) {
+            counters.errors[i] += 1;
+        }
+        if self.telemetry {
+            let key = (phase.to_string(), code.to_string());
+            *counters.diagnostics.entry(key).or_default() += 1;
+        }
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+
+        // Writing to a `String` cannot fail, so the results are ignored
+        let _ = writeln!(out, "# HELP pyru_executions_total The number of programs run.");
+        let _ = writeln!(out, "# TYPE pyru_executions_total counter");
+        let _ = writeln!(out, "pyru_executions_total {}", counters.executions);
+
+        let _ = writeln!(
+            out,
+            "# HELP pyru_errors_total The number of runs stopped by an error, by phase."
+        );
+        let _ = writeln!(out, "# TYPE pyru_errors_total counter");
+        for (phase, count) in PHASES.iter().zip(counters.errors) {
+            let _ = writeln!(out, "pyru_errors_total{{phase=\"{phase}\"}} {count}");
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP pyru_timeouts_total The number of runs stopped for running too long."
+        );
+        let _ = writeln!(out, "# TYPE pyru_timeouts_total counter");
+        let _ = writeln!(out, "pyru_timeouts_total {}", counters.timeouts);
+
+        let _ = writeln!(
+            out,
+            "# HELP pyru_execution_duration_seconds The time spent running programs."
+        );
+        let _ = writeln!(out, "# TYPE pyru_execution_duration_seconds summary");
+        let seconds = counters.duration.as_secs_f64();
+        let _ = writeln!(out, "pyru_execution_duration_seconds_sum {seconds}");
+        let _ = writeln!(out, "pyru_execution_duration_seconds_count {}", counters.executions);
+
+        if self.telemetry {
+            let _ = writeln!(
+                out,
+                "# HELP pyru_diagnostics_total The number of times each error was reported."
+            );
+            let _ = writeln!(out, "# TYPE pyru_diagnostics_total counter");
+            for ((phase, code), count) in &counters.diagnostics {
+                let _ = writeln!(
+                    out,
+                    "pyru_diagnostics_total{{phase=\"{phase}\",code=\"{code}\"}} {count}"
+                );
+            }
+        }
+
+        return out;
+    }
+}
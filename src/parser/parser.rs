@@ -18,18 +18,17 @@
 //! ## Example
 //! 
 //! ```rust
-//! use crate::parser::Parser;
-//! use crate::lexer::Lexer;
+//! use pyru::parser::Parser;
+//! use pyru::lexer::Lexer;
 //!
 //! fn main() {
 //!     let source_code = r#"
-//!         var x = 10;
-//!         if (x > 5) {
-//!             print "Hello, world!";
-//!         }
-//!     "#;
+//! let x = 10;
+//! if x > 5:
+//!     print("Hello, world!");
+//! "#;
 //!
-//!     let mut lexer = Lexer::new(source_code);
+//!     let mut lexer = Lexer::new(source_code.to_string(), 4);
 //!     let tokens = lexer.run().unwrap();
 //!
 //!     let mut parser = Parser::new(tokens);
@@ -64,30 +63,40 @@
 //!    represents the hierarchical structure of the source code and is used by the evaluator
 //!    to execute the program.
 
+use std::{cell::{Cell, OnceCell}, mem, rc::Rc};
+
 use crate::{
     error::ParserError,
     expr::Expr,
-    stmt::Stmt,
+    stmt::{Pattern, Stmt},
     token::{Token, TokenType},
     value::LiteralType,
 };
 
+/// The number of expressions and blocks that can be nested inside of each other. Parsing, and
+/// every later phase, recurses once for each level, so a limit stops a deeply nested program from
+/// overflowing the stack. The limit leaves room for `max_depth_for(DEFAULT_STACK_SIZE)` nested
+/// calls on a stack of `DEFAULT_STACK_SIZE` bytes, even in a debug build.
+pub const MAX_NESTING: usize = 32;
+
 /// The `Parser` struct is responsible for parsing tokens generated by the lexer and constructing
 /// an abstract syntax tree (AST) for the evaluator, by maintaining the state of the parsing process.
 /// 
 /// ## Fields
 /// - `tokens`: The list of tokens that are iterated over
 /// - `current`: A pointer referencing the current token in the tokens vector
+/// - `depth`: How many expressions and blocks enclose the current token
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    depth: usize,
 }
 
 impl Parser {
     
     /// Creates a new `Parser` instance with the given tokens.
     pub fn new(tokens: Vec<Token>) -> Self {
-        return Self { tokens, current: 0 };
+        return Self { tokens, current: 0, depth: 0 };
     }
 
     /// Starts the parsing process and returns the resulting AST.
@@ -104,9 +113,37 @@ impl Parser {
         return Ok(statements);
     }
 
-    /// Parses a declaration, which can be a function or variable declaration, or a statement.
+    /// Parses a declaration, which can be a class, function or variable declaration, or a
+    /// statement.
     fn declaration(&mut self) -> Result<Stmt, ParserError> {
-        if self.match_token(vec![&TokenType::Def]) {
+        if self.check(TokenType::Indent) {
+            // Every indent that is expected is consumed by the statement that opens the block
+            let line = self.peek().line;
+            let found = self.indent_level(line);
+            let extra = self.tokens[self.current..]
+                .iter()
+                .take_while(|token| token.token_type == TokenType::Indent)
+                .count();
+            return Err(ParserError::UnexpectedIndent { line, found, expected: found - extra });
+        }
+
+        if self.match_token(&[TokenType::Class]) {
+            return match self.class_declaration() {
+                Ok(v) => Ok(v),
+                Err(e) => {
+                    self.synchronize();
+                    Err(e)
+                }
+            }
+        } else if self.match_token(&[TokenType::Enum]) {
+            return match self.enum_declaration() {
+                Ok(v) => Ok(v),
+                Err(e) => {
+                    self.synchronize();
+                    Err(e)
+                }
+            }
+        } else if self.match_token(&[TokenType::Def]) {
             return match self.function("function") {
                 Ok(v) => Ok(v),
                 Err(e) => {
@@ -114,7 +151,7 @@ impl Parser {
                     Err(e)
                 }
             }
-        } else if self.match_token(vec![&TokenType::Let]) {
+        } else if self.match_token(&[TokenType::Let]) {
             return match self.var_declaration() {
                 Ok(v) => Ok(v),
                 Err(e) => {
@@ -127,9 +164,52 @@ impl Parser {
         }
     }
 
+    /// Parses a class declaration, whose body is made up of method declarations.
+    fn class_declaration(&mut self) -> Result<Stmt, ParserError> {
+        let name = self.consume(TokenType::Identifier, "ExpectedClassName")?;
+
+        self.consume(TokenType::Colon, "ExpectedColon")?;
+
+        self.consume(TokenType::Indent, "ExpectedClassBody")?;
+
+        let mut methods: Vec<Stmt> = Vec::new();
+        while !self.check(TokenType::Dedent) && !self.is_at_end() {
+            if self.match_token(&[TokenType::Def]) {
+                methods.push(self.function("method")?);
+            } else if self.match_token(&[TokenType::Pass]) {
+                // A class without any methods has a body of just `pass;`
+                self.terminate("ExpectedSemicolonAfterPass")?;
+            } else {
+                let token = self.peek();
+                return Err(ParserError::ExpectedMethodDeclaration { line: token.line });
+            }
+        }
+        if self.peek().token_type != TokenType::Eof {
+            self.consume(TokenType::Dedent, "ExpectedDedentAfterStmt")?;
+        }
+
+        return Ok(Stmt::Class { name: self.take(name), methods });
+    }
+
+    /// Parses an enum declaration, which names its variants on the same line.
+    fn enum_declaration(&mut self) -> Result<Stmt, ParserError> {
+        let name = self.consume(TokenType::Identifier, "ExpectedEnumName")?;
+
+        self.consume(TokenType::Colon, "ExpectedColon")?;
+
+        let mut variants = vec![self.consume(TokenType::Identifier, "ExpectedVariantName")?];
+        while self.match_token(&[TokenType::Comma]) {
+            variants.push(self.consume(TokenType::Identifier, "ExpectedVariantName")?);
+        }
+        self.terminate("ExpectedSemicolonAfterEnum")?;
+
+        let variants = variants.into_iter().map(|variant| self.take(variant)).collect();
+        return Ok(Stmt::Enum { name: self.take(name), variants });
+    }
+
     /// Parses a function declaration.
     fn function(&mut self, kind: &str) -> Result<Stmt, ParserError> {
-        let name = match self.consume(
+        let name = self.consume(
             TokenType::Identifier,
             format!(
                 "Expected{}Name",
@@ -141,10 +221,7 @@ impl Parser {
                     + &kind[1..]
             )
             .as_str(),
-        ) {
-            Ok(v) => v,
-            Err(e) => return Err(e),
-        };
+        )?;
 
         self.consume(
             TokenType::LParen,
@@ -160,21 +237,24 @@ impl Parser {
             .as_str(),
         )?;
 
-        let mut params: Vec<Token> = Vec::new();
+        let mut params: Vec<usize> = Vec::new();
+        let mut param_types: Vec<Option<usize>> = Vec::new();
         if !self.check(TokenType::RParen) {
             loop {
                 if params.len() >= 255 {
                     let token = self.peek();
                     return Err(ParserError::TooManyParameters {
-                        name: name.lexeme,
+                        name: self.tokens[name].lexeme.to_string(),
                         line: token.line,
                     });
                 }
 
                 let parameter = self.consume(TokenType::Identifier, "ExpectedParameterName")?;
                 params.push(parameter);
+                param_types.push(self.annotation(TokenType::Colon)?);
 
-                if !self.match_token(vec![&TokenType::Comma]) {
+                // A trailing comma may follow the last parameter
+                if !self.match_token(&[TokenType::Comma]) || self.check(TokenType::RParen) {
                     break;
                 };
             }
@@ -182,54 +262,162 @@ impl Parser {
 
         self.consume(TokenType::RParen, "ExpectedRParenAfterParameters")?;
 
+        let return_type = self.annotation(TokenType::Arrow)?;
+
         self.consume(TokenType::Colon, "ExpectedColon")?;
 
         self.consume(TokenType::Indent, "ExpectedFunctionBody")?;
 
         let body = self.body()?;
-
-        return Ok(Stmt::Function { name, params, body });
+        let doc = docstring(&body);
+
+        let params = params.into_iter().map(|param| self.take(param)).collect();
+        let param_types = param_types.into_iter().map(|type_| type_.map(|type_| self.take(type_))).collect();
+        return Ok(Stmt::Function {
+            name: self.take(name),
+            params,
+            param_types,
+            return_type: return_type.map(|type_| self.take(type_)),
+            body: Rc::from(body),
+            doc,
+            locals: OnceCell::new(),
+        });
     }
 
     /// Begins the recursive descent with parsing a variable declaration
     fn var_declaration(&mut self) -> Result<Stmt, ParserError> {
         let name = self.consume(TokenType::Identifier, "ExpectedVariableName")?;
 
-        let initializer = if self.match_token(vec![&TokenType::Equal]) {
+        if self.match_token(&[TokenType::Comma]) {
+            let mut names = vec![name];
+            loop {
+                names.push(self.consume(TokenType::Identifier, "ExpectedVariableName")?);
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+
+            self.consume(TokenType::Equal, "ExpectedDestructureInitializer")?;
+            let initializer = self.destructure_initializer()?;
+            self.terminate("ExpectedSemicolonAfterVariableDeclaration")?;
+
+            let targets = names
+                .into_iter()
+                .map(|name| Expr::Var { name: self.take(name), slot: Cell::new(None) })
+                .collect();
+            return Ok(Stmt::Destructure { targets, initializer, declare: true });
+        }
+
+        let annotation = self.annotation(TokenType::Colon)?;
+
+        let initializer = if self.match_token(&[TokenType::Equal]) {
             let expr = self.expression()?;
             Some(expr)
         } else {
             None
         };
 
-        self.consume(TokenType::Semicolon, "ExpectedSemicolonAfterVariableDeclaration")?;
+        self.terminate("ExpectedSemicolonAfterVariableDeclaration")?;
 
-        return Ok(Stmt::Var { name, initializer });
+        return Ok(Stmt::Var {
+            name: self.take(name),
+            annotation: annotation.map(|annotation| self.take(annotation)),
+            initializer,
+        });
     }
 
-    /// Parses a statement, which can be a for, if, print, return, while, or expression statement.
+    /// Parses a statement, which can be a break, continue, del, for, if, import, pass, print,
+    /// return, while, or expression statement.
     fn statement(&mut self) -> Result<Stmt, ParserError> {
-        if self.match_token(vec![&TokenType::For]) {
-            return self.for_statement();
+        if self.check_label() {
+            return self.labelled_statement();
         };
-        if self.match_token(vec![&TokenType::If]) {
+        if self.match_token(&[TokenType::Break]) {
+            return self.break_statement();
+        };
+        if self.match_token(&[TokenType::Continue]) {
+            return self.continue_statement();
+        };
+        if self.match_token(&[TokenType::Del]) {
+            return self.del_statement();
+        };
+        if self.match_token(&[TokenType::For]) {
+            return self.for_statement(None);
+        };
+        if self.match_token(&[TokenType::If]) {
             return self.if_statement();
         };
-        if self.match_token(vec![&TokenType::Print]) {
+        if self.match_token(&[TokenType::Import]) {
+            return self.import_statement();
+        };
+        if self.match_token(&[TokenType::Loop]) {
+            return self.loop_statement(None);
+        };
+        if self.match_token(&[TokenType::Match]) {
+            return self.match_statement();
+        };
+        if self.match_token(&[TokenType::Pass]) {
+            return self.pass_statement();
+        };
+        if self.match_token(&[TokenType::Print]) {
             return self.print_statement();
         };
-        if self.match_token(vec![&TokenType::Return]) {
+        if self.match_token(&[TokenType::Raise]) {
+            return self.raise_statement();
+        };
+        if self.match_token(&[TokenType::Return]) {
             return self.return_statement();
         };
-        if self.match_token(vec![&TokenType::While]) {
-            return self.while_statement();
+        if self.match_token(&[TokenType::Try]) {
+            return self.try_statement();
+        };
+        if self.match_token(&[TokenType::While]) {
+            return self.while_statement(None);
         };
 
         return self.expression_statement();
     }
 
-    /// Parses a for statement.
-    fn for_statement(&mut self) -> Result<Stmt, ParserError> {
+    /// Parses a labelled loop, e.g. `outer: while true:`. The label is only parsed when it is
+    /// followed by a loop, which `check_label` has already made sure of.
+    fn labelled_statement(&mut self) -> Result<Stmt, ParserError> {
+        let label = Some(self.current);
+        self.advance();
+        self.advance();
+
+        if self.match_token(&[TokenType::For]) {
+            return self.for_statement(label);
+        };
+        if self.match_token(&[TokenType::Loop]) {
+            return self.loop_statement(label);
+        };
+
+        // Otherwise the label is in front of a while loop
+        self.advance();
+        return self.while_statement(label);
+    }
+
+    /// Parses a break statement, with an optional label naming the loop to break out of.
+    fn break_statement(&mut self) -> Result<Stmt, ParserError> {
+        let keyword = self.current - 1;
+        let label = self.loop_label();
+        self.terminate("ExpectedSemicolonAfterBreak")?;
+
+        return Ok(Stmt::Break { keyword: self.take(keyword), label: label.map(|label| self.take(label)) });
+    }
+
+    /// Parses a continue statement, with an optional label naming the loop to continue.
+    fn continue_statement(&mut self) -> Result<Stmt, ParserError> {
+        let keyword = self.current - 1;
+        let label = self.loop_label();
+        self.terminate("ExpectedSemicolonAfterContinue")?;
+
+        return Ok(Stmt::Continue { keyword: self.take(keyword), label: label.map(|label| self.take(label)) });
+    }
+
+    /// Parses a for statement, which loops over either a range of numbers or the elements of a
+    /// list or string.
+    fn for_statement(&mut self, label: Option<usize>) -> Result<Stmt, ParserError> {
 
         let name = self.consume(TokenType::Identifier, "ExpectedInitializer")?;
 
@@ -237,32 +425,48 @@ impl Parser {
 
         let start = self.expression()?;
 
-        self.consume(TokenType::DotDot, "ExpectedDotDot")?;
+        if !self.match_token(&[TokenType::DotDot]) {
+            self.consume(TokenType::Colon, "ExpectedColon")?;
+            self.consume(TokenType::Indent, "ExpectedForBody")?;
+            let body = self.body()?;
+
+            return Ok(Stmt::ForEach {
+                label: label.map(|label| self.take(label)),
+                name: self.take(name),
+                iterable: start,
+                body,
+                locals: OnceCell::new(),
+            });
+        }
 
         let end = self.expression()?;
+        // The name is needed in several places of the loop that the range is turned into
+        let name = self.take(name);
 
-        let step = if self.match_token(vec![&TokenType::Step]) {
+        let step = if self.match_token(&[TokenType::Step]) {
             let value = self.expression()?;
             Expr::Assign {
                 name: name.clone(),
                 value: Box::new(Expr::Binary {
-                    left: Box::new(Expr::Var { name: name.clone() }),
+                    left: Box::new(Expr::Var { name: name.clone(), slot: Cell::new(None) }),
                     operator: Token::new(
                         TokenType::Plus,
-                        "+".to_string(),
-                        "".to_string(),
+                        "+",
+                        "",
                         0,
                         0,
                         0,
                     ),
                     right: Box::new(value)
-                })
+                }),
+                slot: Cell::new(None),
             }
             
         } else {
             Expr::Alteration {
                 name: name.clone(),
                 alteration_type: TokenType::Incr,
+                slot: Cell::new(None),
             }
         };
 
@@ -270,14 +474,12 @@ impl Parser {
         
         self.consume(TokenType::Indent, "ExpectedForBody")?;
 
-        let initializer = Stmt::Var { name: name.clone(), initializer: Some(start) };
-
         let condition = Expr::Binary {
-            left: Box::new(Expr::Var { name: name.clone() }),
+            left: Box::new(Expr::Var { name: name.clone(), slot: Cell::new(None) }),
             operator: Token::new(
                 TokenType::Less,
-                "<".to_string(),
-                "".to_string(),
+                "<",
+                "",
                 0,
                 0,
                 0,
@@ -286,12 +488,16 @@ impl Parser {
         };
 
         let body = self.body()?;
+
+        let initializer = Stmt::Var { name, annotation: None, initializer: Some(start) };
         
         return Ok(Stmt::For {
+            label: label.map(|label| self.take(label)),
             initializer: Box::new(initializer),
             condition,
             step,
             body,
+            locals: OnceCell::new(),
         });
     }
 
@@ -305,25 +511,25 @@ impl Parser {
         
         let then_branch = self.body()?;
         
-        let mut else_branch = None;
-        if self.match_token(vec![&TokenType::Else]) {
-            if self.match_token(vec![&TokenType::Colon]) {
-                self.consume(TokenType::Indent, "ExpectedIfBody")?;
-                let result = self.statement()?;
-                else_branch = Some(Box::new(result));
-                if self.match_token(vec![&TokenType::Eof]) {}
-                else{
-                    self.consume(TokenType::Dedent, "ExpectedDedentAfterStmt")?;
-                }
+        // Each `elif` becomes an if statement nested as the only statement of the else branch,
+        // and `else if` is treated the same as `elif`
+        let else_branch = if self.match_token(&[TokenType::Elif]) {
+            Some(vec![self.if_statement()?])
+        } else if self.match_token(&[TokenType::Else]) {
+            if self.match_token(&[TokenType::If]) {
+                Some(vec![self.if_statement()?])
             } else {
-                let result = self.statement()?;
-                else_branch = Some(Box::new(result));
+                self.consume(TokenType::Colon, "ExpectedColon")?;
+                self.consume(TokenType::Indent, "ExpectedElseBody")?;
+                Some(self.body()?)
             }
+        } else {
+            None
         };
 
         return Ok(Stmt::If {
             condition,
-            then_branch: then_branch,
+            then_branch,
             else_branch,
         });
     }
@@ -331,59 +537,231 @@ impl Parser {
     /// Parses a print statement.
     fn print_statement(&mut self) -> Result<Stmt, ParserError> {
         self.consume(TokenType::LParen, "ExpectedLParenBeforePrintValue")?;
-        let value = self.expression()?;
+        let mut expressions = vec![self.expression()?];
+        // A trailing comma may follow the last value
+        while self.match_token(&[TokenType::Comma]) && !self.check(TokenType::RParen) {
+            expressions.push(self.expression()?);
+        }
         self.consume(TokenType::RParen, "ExpectedRParenAfterPrintValue")?;
-        self.consume(TokenType::Semicolon, "ExpectedSemicolonAfterPrint")?;
+        self.terminate("ExpectedSemicolonAfterPrint")?;
 
-        return Ok(Stmt::Print { expression: value });
+        return Ok(Stmt::Print { expressions });
+    }
+
+    /// Parses a del statement.
+    fn del_statement(&mut self) -> Result<Stmt, ParserError> {
+        // Only a variable, or an item of a list or dictionary held in one, can be deleted
+        let line = self.peek().line;
+        if !self.check(TokenType::Identifier) {
+            return Err(ParserError::ExpectedVariableNameAfterDel { line });
+        }
+        let target = self.call()?;
+        if !is_place(&target) {
+            return Err(ParserError::ExpectedVariableNameAfterDel { line });
+        }
+        self.terminate("ExpectedSemicolonAfterDel")?;
+
+        return Ok(Stmt::Del { target });
+    }
+
+    /// Parses an import statement.
+    fn import_statement(&mut self) -> Result<Stmt, ParserError> {
+        let name = self.consume(TokenType::Identifier, "ExpectedModuleNameAfterImport")?;
+        self.terminate("ExpectedSemicolonAfterImport")?;
+
+        return Ok(Stmt::Import { name: self.take(name), module: OnceCell::new() });
+    }
+
+    /// Parses a pass statement.
+    fn pass_statement(&mut self) -> Result<Stmt, ParserError> {
+        let keyword = self.current - 1;
+        self.terminate("ExpectedSemicolonAfterPass")?;
+
+        return Ok(Stmt::Pass { keyword: self.take(keyword) });
+    }
+
+    /// Parses a loop statement.
+    fn loop_statement(&mut self, label: Option<usize>) -> Result<Stmt, ParserError> {
+        let keyword = self.current - 1;
+
+        self.consume(TokenType::Colon, "ExpectedColon")?;
+        self.consume(TokenType::Indent, "ExpectedLoopBody")?;
+        let body = self.body()?;
+
+        return Ok(Stmt::Loop {
+            keyword: self.take(keyword),
+            label: label.map(|label| self.take(label)),
+            body,
+        });
+    }
+
+    /// Parses a match statement, whose body is made up of `case` arms.
+    fn match_statement(&mut self) -> Result<Stmt, ParserError> {
+        let keyword = self.current - 1;
+        let subject = self.expression()?;
+
+        self.consume(TokenType::Colon, "ExpectedColon")?;
+        self.consume(TokenType::Indent, "ExpectedMatchBody")?;
+
+        let mut arms: Vec<(Pattern, Vec<Stmt>)> = Vec::new();
+        while !self.check(TokenType::Dedent) && !self.is_at_end() {
+            if !self.match_token(&[TokenType::Case]) {
+                let token = self.peek();
+                return Err(ParserError::ExpectedCase { line: token.line });
+            }
+            if let Some((Pattern::Wildcard, _)) = arms.last() {
+                let token = self.previous();
+                return Err(ParserError::UnreachableCase { line: token.line });
+            }
+
+            let pattern = self.pattern()?;
+            self.consume(TokenType::Colon, "ExpectedColon")?;
+            self.consume(TokenType::Indent, "ExpectedCaseBody")?;
+            let body = self.body()?;
+
+            arms.push((pattern, body));
+        }
+        if self.peek().token_type != TokenType::Eof {
+            self.consume(TokenType::Dedent, "ExpectedDedentAfterStmt")?;
+        }
+
+        return Ok(Stmt::Match { keyword: self.take(keyword), subject, arms });
+    }
+
+    /// Parses the pattern of a `case` arm, which is a literal, a range of numbers or `_`.
+    fn pattern(&mut self) -> Result<Pattern, ParserError> {
+        if self.peek().token_type == TokenType::Identifier && self.peek().lexeme.as_str() == "_" {
+            self.advance();
+            return Ok(Pattern::Wildcard);
+        }
+
+        let start = self.literal_pattern()?;
+        if !self.match_token(&[TokenType::DotDot]) {
+            return Ok(Pattern::Literal(start));
+        }
+        let end = self.literal_pattern()?;
+
+        return match (start, end) {
+            (LiteralType::Num(start), LiteralType::Num(end)) => Ok(Pattern::Range { start, end }),
+            _ => {
+                let token = self.previous();
+                Err(ParserError::ExpectedPattern { line: token.line })
+            },
+        };
+    }
+
+    /// Parses a literal in a pattern, which may be a negative number.
+    fn literal_pattern(&mut self) -> Result<LiteralType, ParserError> {
+        let negative = self.match_token(&[TokenType::Minus]);
+        let is_literal = match self.peek().token_type {
+            TokenType::Num => true,
+            TokenType::String | TokenType::True | TokenType::False | TokenType::Null => !negative,
+            _ => false,
+        };
+        if !is_literal {
+            let token = self.peek();
+            return Err(ParserError::ExpectedPattern { line: token.line });
+        }
+
+        return match self.primary()? {
+            Expr::Literal { value: LiteralType::Num(n) } if negative => Ok(LiteralType::Num(-n)),
+            Expr::Literal { value } => Ok(value),
+            _ => {
+                let token = self.previous();
+                Err(ParserError::ExpectedPattern { line: token.line })
+            },
+        };
+    }
+
+    /// Parses a raise statement.
+    fn raise_statement(&mut self) -> Result<Stmt, ParserError> {
+        let keyword = self.current - 1;
+        let value = self.expression()?;
+        self.terminate("ExpectedSemicolonAfterRaise")?;
+
+        return Ok(Stmt::Raise { keyword: self.take(keyword), value });
     }
 
     /// Parses a return statement.
     fn return_statement(&mut self) -> Result<Stmt, ParserError> {
-        let keyword = self.previous().clone();
+        let keyword = self.current - 1;
         let mut value = None;
-        if !self.check(TokenType::Semicolon) {
+        if !self.at_terminator() {
             value = Some(self.expression()?);
         }
-        self.consume(TokenType::Semicolon, "ExpectedSemicolonAfterReturnValue")?;
+        self.terminate("ExpectedSemicolonAfterReturnValue")?;
+
+        return Ok(Stmt::Return { keyword: self.take(keyword), value });
+    }
+
+    /// Parses a try statement, followed by an `except` branch, a `finally` branch or both.
+    fn try_statement(&mut self) -> Result<Stmt, ParserError> {
+        self.consume(TokenType::Colon, "ExpectedColon")?;
+        self.consume(TokenType::Indent, "ExpectedTryBody")?;
+        let body = self.body()?;
+
+        let mut name = None;
+        let mut handler = None;
+        if self.match_token(&[TokenType::Except]) {
+            if self.match_token(&[TokenType::Identifier]) {
+                name = Some(self.current - 1);
+            }
+            self.consume(TokenType::Colon, "ExpectedColon")?;
+            self.consume(TokenType::Indent, "ExpectedExceptBody")?;
+            handler = Some(self.body()?);
+        }
 
-        return Ok(Stmt::Return { keyword, value });
+        let mut finally = None;
+        if self.match_token(&[TokenType::Finally]) {
+            self.consume(TokenType::Colon, "ExpectedColon")?;
+            self.consume(TokenType::Indent, "ExpectedFinallyBody")?;
+            finally = Some(self.body()?);
+        }
+
+        if handler.is_none() && finally.is_none() {
+            let token = self.peek();
+            return Err(ParserError::ExpectedExceptOrFinally { line: token.line });
+        }
+
+        return Ok(Stmt::Try { body, name: name.map(|name| self.take(name)), handler, finally });
     }
 
     /// Parses a while statement.
-    fn while_statement(&mut self) -> Result<Stmt, ParserError> {
+    fn while_statement(&mut self, label: Option<usize>) -> Result<Stmt, ParserError> {
         let condition = self.expression()?;
 
         self.consume(TokenType::Colon, "ExpectedColonAfterWhileCondition")?;
-        self.consume(TokenType::Indent, "ExpectWhileBody")?;
+        self.consume(TokenType::Indent, "ExpectedWhileBody")?;
         
         let body = self.body()?;
 
-        return Ok(Stmt::While { condition, body });
+        return Ok(Stmt::While { label: label.map(|label| self.take(label)), condition, body });
     }
 
     /// Parses an expression.
     fn expression(&mut self) -> Result<Expr, ParserError> {
-        return self.assignment();
+        return self.nested(Self::assignment);
     }
 
     /// Parses an assignment expression.
     fn assignment(&mut self) -> Result<Expr, ParserError> {
         let expr = self.or()?;
 
-        if self.match_token(vec![&TokenType::Incr, &TokenType::Decr]) {
+        if self.match_token(&[TokenType::Incr, TokenType::Decr]) {
             match expr {
-                Expr::Var { name } => match self.previous().token_type {
+                Expr::Var { name, .. } => match self.previous().token_type {
                     TokenType::Incr => {
                         return Ok(Expr::Alteration {
                             name,
                             alteration_type: TokenType::Incr,
+                            slot: Cell::new(None),
                         })
                     }
                     TokenType::Decr => {
                         return Ok(Expr::Alteration {
                             name,
                             alteration_type: TokenType::Decr,
+                            slot: Cell::new(None),
                         })
                     }
                     _ => {
@@ -396,25 +774,50 @@ impl Parser {
                 _ => {
                     let token = self.previous();
                     return Err(ParserError::InvalidAlterationTarget {
-                        target: token.lexeme.clone(),
+                        target: token.lexeme.to_string(),
                         line: token.line,
                     });
                 }
             }
-        } else if self.match_token(vec![&TokenType::Equal]) {
+        } else if self.match_token(&[TokenType::Equal]) {
             let value = self.assignment()?;
 
             match expr {
-                Expr::Var { name } => {
+                Expr::Var { name, .. } => {
                     return Ok(Expr::Assign {
                         name,
                         value: Box::new(value),
+                        slot: Cell::new(None),
+                    })
+                }
+                Expr::Splice { list, is_splice: false, start: Some(index), end: None }
+                    if is_place(&list) => {
+                    return Ok(Expr::IndexAssign {
+                        object: list,
+                        index,
+                        value: Box::new(value),
+                    })
+                }
+                Expr::Splice { list, is_splice: true, start, end } if is_place(&list) => {
+                    return Ok(Expr::SliceAssign {
+                        object: list,
+                        start,
+                        end,
+                        value: Box::new(value),
+                    })
+                }
+                Expr::Property { object, name, slot } => {
+                    return Ok(Expr::PropertyAssign {
+                        object,
+                        name,
+                        value: Box::new(value),
+                        slot,
                     })
                 }
                 _ => {
                     let token = self.previous();
                     return Err(ParserError::InvalidAssignmentTarget {
-                        target: token.lexeme.clone(),
+                        target: token.lexeme.to_string(),
                         line: token.line,
                     });
                 }
@@ -428,12 +831,12 @@ impl Parser {
     fn or(&mut self) -> Result<Expr, ParserError> {
         let mut expr = self.and()?;
 
-        while self.match_token(vec![&TokenType::Or]) {
-            let operator = self.previous().clone();
+        while self.match_token(&[TokenType::Or]) {
+            let operator = self.current - 1;
             let right = self.and()?;
             expr = Expr::Logical {
                 left: Box::new(expr),
-                operator,
+                operator: self.take(operator),
                 right: Box::new(right),
             };
         }
@@ -445,12 +848,12 @@ impl Parser {
     fn and(&mut self) -> Result<Expr, ParserError> {
         let mut expr = self.equality()?;
 
-        while self.match_token(vec![&TokenType::And]) {
-            let operator = self.previous().clone();
+        while self.match_token(&[TokenType::And]) {
+            let operator = self.current - 1;
             let right = self.equality()?;
             expr = Expr::Logical {
                 left: Box::new(expr),
-                operator,
+                operator: self.take(operator),
                 right: Box::new(right),
             }
         }
@@ -462,12 +865,12 @@ impl Parser {
     fn equality(&mut self) -> Result<Expr, ParserError> {
         let mut expr: Expr = self.comparison()?;
 
-        while self.match_token(vec![&TokenType::Bang, &TokenType::EqualEqual]) {
-            let operator = self.previous().clone();
+        while self.match_token(&[TokenType::Bang, TokenType::EqualEqual]) {
+            let operator = self.current - 1;
             let right = self.comparison()?;
             expr = Expr::Binary {
-                left: Box::new(expr.clone()),
-                operator,
+                left: Box::new(expr),
+                operator: self.take(operator),
                 right: Box::new(right),
             };
         }
@@ -479,21 +882,35 @@ impl Parser {
     fn comparison(&mut self) -> Result<Expr, ParserError> {
         let mut expr: Expr = self.membership()?;
 
-        while self.match_token(vec![
-            &TokenType::Greater,
-            &TokenType::GreaterEqual,
-            &TokenType::Less,
-            &TokenType::LessEqual,
-            &TokenType::BangEqual,
-            &TokenType::EqualEqual,
+        let mut comparisons = Vec::new();
+        while self.match_token(&[
+            TokenType::Greater,
+            TokenType::GreaterEqual,
+            TokenType::Less,
+            TokenType::LessEqual,
+            TokenType::BangEqual,
+            TokenType::EqualEqual,
         ]) {
-            let operator = self.previous().clone();
+            let operator = self.current - 1;
             let right = self.membership()?;
+            comparisons.push((operator, right));
+        }
+
+        // A single comparison is a binary expression, and a chain such as `a < b < c` is
+        // evaluated as `a < b and b < c`
+        if comparisons.len() == 1 {
+            let (operator, right) = comparisons.remove(0);
             expr = Expr::Binary {
-                left: Box::new(expr.clone()),
-                operator,
+                left: Box::new(expr),
+                operator: self.take(operator),
                 right: Box::new(right),
             };
+        } else if !comparisons.is_empty() {
+            let comparisons = comparisons
+                .into_iter()
+                .map(|(operator, right)| (self.take(operator), right))
+                .collect();
+            expr = Expr::Comparison { left: Box::new(expr), comparisons };
         }
 
         return Ok(expr);
@@ -504,11 +921,11 @@ impl Parser {
         let mut expr = self.term()?;
         let mut not = false;
 
-        if self.match_token(vec![&TokenType::Not]) {
+        if self.match_token(&[TokenType::Not]) {
             not = true;
         }
 
-        while self.match_token(vec![&TokenType::In]) {
+        while self.match_token(&[TokenType::In]) {
             let right = self.term()?;
             expr = Expr::Membership {
                 left: Box::new(expr),
@@ -524,12 +941,12 @@ impl Parser {
     fn term(&mut self) -> Result<Expr, ParserError> {
         let mut expr = self.factor()?;
 
-        while self.match_token(vec![&TokenType::Minus, &TokenType::Plus]) {
-            let operator = self.previous().clone();
+        while self.match_token(&[TokenType::Minus, TokenType::Plus]) {
+            let operator = self.current - 1;
             let right = self.factor()?;
             expr = Expr::Binary {
-                left: Box::new(expr.clone()),
-                operator,
+                left: Box::new(expr),
+                operator: self.take(operator),
                 right: Box::new(right),
             };
         }
@@ -541,12 +958,12 @@ impl Parser {
     fn factor(&mut self) -> Result<Expr, ParserError> {
         let mut expr = self.unary()?;
 
-        while self.match_token(vec![&TokenType::FSlash, &TokenType::Asterisk]) {
-            let operator = self.previous().clone();
+        while self.match_token(&[TokenType::FSlash, TokenType::FSlashFSlash, TokenType::Asterisk]) {
+            let operator = self.current - 1;
             let right = self.unary()?;
             expr = Expr::Binary {
-                left: Box::new(expr.clone()),
-                operator,
+                left: Box::new(expr),
+                operator: self.take(operator),
                 right: Box::new(right),
             };
         }
@@ -556,11 +973,11 @@ impl Parser {
 
     /// Parses a unary expression.
     fn unary(&mut self) -> Result<Expr, ParserError> {
-        if self.match_token(vec![&TokenType::Bang, &TokenType::Minus]) {
-            let operator = self.previous().clone();
-            let right = self.unary()?;
+        if self.match_token(&[TokenType::Bang, TokenType::Minus]) {
+            let operator = self.current - 1;
+            let right = self.nested(Self::unary)?;
             return Ok(Expr::Unary {
-                operator,
+                operator: self.take(operator),
                 right: Box::new(right),
             });
         }
@@ -573,22 +990,34 @@ impl Parser {
         let mut expr = self.primary()?;
 
         loop {
-            if self.match_token(vec![&TokenType::LParen]) {
+            // A bracket at the start of a line begins a new statement rather than calling or
+            // indexing the end of the one before it
+            if first_line(self.peek()) > self.previous().line {
+                break;
+            }
+            if self.match_token(&[TokenType::LParen]) {
                 expr = self.finish_call(expr)?;
-            } else if self.match_token(vec![&TokenType::Dot]) {
+            } else if self.match_token(&[TokenType::LBrack]) {
+                expr = self.finish_index(expr)?;
+            } else if self.match_token(&[TokenType::Dot]) {
                 let call = self.call()?;
                 let name = match expr {
-                    Expr::Var { ref name } => name,
+                    Expr::Var { name, .. } => name,
                     _ => {
                         let token = self.peek();
                         return Err(ParserError::CanOnlyCallIdentifiers {
-                            value: token.lexeme.clone(),
+                            value: token.lexeme.to_string(),
                             line: token.line,
                         })
                     },
                 };
 
-                return Ok(Expr::ListMethodCall { object: name.clone(), call: Box::new(call) })
+                // Without any parentheses after the name, a field of the object is being read
+                if let Expr::Var { name: property, .. } = call {
+                    return Ok(Expr::Property { object: name, name: property, slot: Cell::new(None) });
+                }
+
+                return Ok(Expr::ListMethodCall { object: name, call: Box::new(call), slot: Cell::new(None) })
             } else {
                 break;
             }
@@ -604,11 +1033,19 @@ impl Parser {
         if !self.check(TokenType::RParen) {
             loop {
                 if arguments.len() >= 255 {
-                    return Err(ParserError::TooManyArguments { callee });
+                    return Err(ParserError::TooManyArguments { callee: Box::new(callee) });
                 }
-                let expr = self.expression()?;
+                // A list or tuple after `*` is expanded into several arguments
+                let expr = if self.match_token(&[TokenType::Asterisk]) {
+                    let operator = self.current - 1;
+                    let value = self.expression()?;
+                    Expr::Spread { operator: self.take(operator), value: Box::new(value) }
+                } else {
+                    self.expression()?
+                };
                 arguments.push(expr);
-                if !self.match_token(vec![&TokenType::Comma]) {
+                // A trailing comma may follow the last argument
+                if !self.match_token(&[TokenType::Comma]) || self.check(TokenType::RParen) {
                     break;
                 };
             }
@@ -622,38 +1059,59 @@ impl Parser {
         });
     }
 
+    /// Finishes parsing an index or a slice of the value of an expression, e.g. `a[i]`, `a[i:j]`
+    /// or `a[i][j]`.
+    fn finish_index(&mut self, list: Expr) -> Result<Expr, ParserError> {
+        let mut start: Option<Box<Expr>> = None;
+        let mut end: Option<Box<Expr>> = None;
+        let mut is_splice = false;
+
+        if self.peek().token_type != TokenType::Colon {
+            start = Some(Box::new(self.expression()?));
+        }
+        if self.match_token(&[TokenType::Colon]) {
+            is_splice = true;
+            if self.peek().token_type != TokenType::RBrack {
+                end = Some(Box::new(self.expression()?));
+            }
+        }
+        self.consume(TokenType::RBrack, "ExpectedRBrackAfterIndex")?;
+
+        return Ok(Expr::Splice { list: Box::new(list), is_splice, start, end });
+    }
+
     /// Parses a primary expression.
     fn primary(&mut self) -> Result<Expr, ParserError> {
-        if self.match_token(vec![&TokenType::True]) {
+        if self.match_token(&[TokenType::True]) {
             return Ok(Expr::Literal {
                 value: LiteralType::True,
             });
         };
-        if self.match_token(vec![&TokenType::False]) {
+        if self.match_token(&[TokenType::False]) {
             return Ok(Expr::Literal {
                 value: LiteralType::False,
             });
         };
-        if self.match_token(vec![&TokenType::Null]) {
+        if self.match_token(&[TokenType::Null]) {
             return Ok(Expr::Literal {
                 value: LiteralType::Null,
             });
         };
 
-        if self.match_token(vec![&TokenType::Num, &TokenType::String]) {
+        if self.match_token(&[TokenType::Num, TokenType::String]) {
             match self.previous().token_type {
                 TokenType::String => {
                     return Ok(Expr::Literal {
-                        value: LiteralType::Str(self.previous().literal.clone()),
+                        value: LiteralType::Str(self.previous().literal.to_string()),
                     })
                 }
                 TokenType::Num => {
-                    let n = match self.previous().literal.clone().trim().parse() {
+                    let n = match self.previous().literal.trim().parse() {
                         Ok(v) => v,
                         Err(_) => {
                             let token = self.previous();
                             return Err(ParserError::UnableToParseLiteralToFloat {
-                                value: token.lexeme.clone(),
+                                value: token.lexeme.to_string(),
                                 line: token.line,
                             });
                         }
@@ -665,62 +1123,45 @@ impl Parser {
                 _ => {
                     let token = self.previous();
                     return Err(ParserError::ExpectedStringOrNumber {
-                        value: token.lexeme.clone(),
+                        value: token.lexeme.to_string(),
                         line: token.line,
                     });
                 }
             }
         }
 
-        if self.match_token(vec![&TokenType::Identifier]) {
-            let name = self.previous().clone();
-            let expr = if self.match_token(vec![&TokenType::LBrack]) {
-                let mut start: Option<Box<Expr>> = None;
-                let mut end: Option<Box<Expr>> = None;
-                let mut is_splice = false;
-                if self.peek().token_type != TokenType::Colon {
-                    start = Some(Box::new(self.expression()?));
-                }
-                start = if start.is_some() {
-                    Some(start.unwrap())
-                } else {
-                    None
-                };
-                if self.match_token(vec![&TokenType::Colon]) {
-                    is_splice = true;
-                    if self.peek().token_type != TokenType::RBrack {
-                        end = Some(Box::new(self.expression()?));
-                    }
-                    end = if end.is_some() {
-                        Some(end.unwrap())
-                    } else {
-                        None
-                    };
-                }
-                self.consume(TokenType::RBrack, "ExpectedRBrackAfterIndex")?;
-                Expr::Splice { list: name, is_splice, start, end }
-            } else {
-                Expr::Var { name: name.clone() }
-            };
-            return Ok(expr);
+        if self.match_token(&[TokenType::Identifier]) {
+            return Ok(Expr::Var { name: self.previous().clone(), slot: Cell::new(None) });
         }
 
-        if self.match_token(vec![&TokenType::LParen]) {
+        if self.match_token(&[TokenType::LParen]) {
             let expr = self.expression()?;
+
+            // A comma after the first expression makes the parentheses a tuple, not a grouping
+            if self.match_token(&[TokenType::Comma]) {
+                let mut items = vec![expr];
+                while !self.check(TokenType::RParen) && !self.is_at_end() {
+                    items.push(self.expression()?);
+                    if !self.match_token(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::RParen, "ExpectedRParenAfterValues")?;
+                return Ok(Expr::Tuple { items });
+            }
+
             self.consume(TokenType::RParen, "ExpectedRParenAfterExpression")?;
             return Ok(Expr::Grouping {
                 expression: Box::new(expr),
             });
         }
 
-        if self.match_token(vec![&TokenType::LBrack]) {
+        if self.match_token(&[TokenType::LBrack]) {
             let mut items: Vec<Expr> = Vec::new();
-            loop {
-                if self.match_token(vec![&TokenType::RBrack]) {
-                    break;
-                }
+            // A trailing comma may follow the last item
+            while !self.check(TokenType::RBrack) && !self.is_at_end() {
                 items.push(self.expression()?);
-                if !self.match_token(vec![&TokenType::Comma]) {
+                if !self.match_token(&[TokenType::Comma]) {
                     break;
                 }
             }
@@ -730,11 +1171,43 @@ impl Parser {
             return Ok(Expr::List { items });
         }
 
+        if self.match_token(&[TokenType::LBrace]) {
+            let mut items: Vec<(Expr, Expr)> = Vec::new();
+            if !self.check(TokenType::RBrace) {
+                let first = self.expression()?;
+
+                // Without a colon after the first item, the braces contain a set
+                if !self.check(TokenType::Colon) {
+                    let mut items = vec![first];
+                    while self.match_token(&[TokenType::Comma]) && !self.check(TokenType::RBrace) {
+                        items.push(self.expression()?);
+                    }
+                    self.consume(TokenType::RBrace, "ExpectedRBraceAfterEntries")?;
+                    return Ok(Expr::Set { items });
+                }
+
+                self.consume(TokenType::Colon, "ExpectedColonAfterKey")?;
+                let value = self.expression()?;
+                items.push((first, value));
+
+                while self.match_token(&[TokenType::Comma]) && !self.check(TokenType::RBrace) {
+                    let key = self.expression()?;
+                    self.consume(TokenType::Colon, "ExpectedColonAfterKey")?;
+                    let value = self.expression()?;
+                    items.push((key, value));
+                }
+            }
+
+            self.consume(TokenType::RBrace, "ExpectedRBraceAfterEntries")?;
+
+            return Ok(Expr::Dict { items });
+        }
+
         let prev = self.previous();
         let token = self.peek();
 
         return Err(ParserError::ExpectedExpression {
-            prev: prev.lexeme.clone(),
+            prev: prev.lexeme.to_string(),
             line: token.line,
         });
     }
@@ -743,17 +1216,58 @@ impl Parser {
     fn expression_statement(&mut self) -> Result<Stmt, ParserError> {
         let expr = self.expression()?;
 
-        self.consume(TokenType::Semicolon, "ExpectedExpression")?;
+        // An assignment target followed by a comma starts an assignment to several targets
+        if is_assignment_target(&expr) && self.match_token(&[TokenType::Comma]) {
+            let mut targets = vec![expr];
+            loop {
+                // The targets are parsed below assignments, so that the `=` is left to be consumed
+                let target = self.or()?;
+                if !is_assignment_target(&target) {
+                    let token = self.previous();
+                    return Err(ParserError::InvalidAssignmentTarget {
+                        target: token.lexeme.to_string(),
+                        line: token.line,
+                    });
+                }
+                targets.push(target);
+                if !self.match_token(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+
+            self.consume(TokenType::Equal, "ExpectedDestructureInitializer")?;
+            let initializer = self.destructure_initializer()?;
+            self.terminate("ExpectedExpression")?;
+
+            return Ok(Stmt::Destructure { targets, initializer, declare: false });
+        }
+
+        self.terminate("ExpectedExpression")?;
 
         return Ok(Stmt::Expression { expression: expr });
     }
 
+    /// Parses the value being destructured, where several values separated by commas are
+    /// gathered into a tuple, e.g. the `b, a` in `a, b = b, a;`.
+    fn destructure_initializer(&mut self) -> Result<Expr, ParserError> {
+        let first = self.expression()?;
+        if !self.check(TokenType::Comma) {
+            return Ok(first);
+        }
+
+        let mut items = vec![first];
+        while self.match_token(&[TokenType::Comma]) {
+            items.push(self.expression()?);
+        }
+        return Ok(Expr::Tuple { items });
+    }
+
     /// Parses a block of statements.
     fn body(&mut self) -> Result<Vec<Stmt>, ParserError> {
         let mut body = Vec::new();
 
         while !self.check(TokenType::Dedent) && !self.is_at_end() {
-            let stmt = self.declaration()?;
+            let stmt = self.nested(Self::declaration)?;
             body.push(stmt);
         }
         if self.peek().token_type == TokenType::Eof {}
@@ -762,8 +1276,21 @@ impl Parser {
         return Ok(body);
     }
 
+    /// Parses something nested one level deeper than the current token, failing if that is
+    /// deeper than `MAX_NESTING`.
+    fn nested<T>(&mut self, parse: fn(&mut Self) -> Result<T, ParserError>) -> Result<T, ParserError> {
+        if self.depth >= MAX_NESTING {
+            return Err(ParserError::TooDeeplyNested { limit: MAX_NESTING, line: self.peek().line });
+        }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        return result;
+    }
+
     /// Matches the current token with the given token types.
-    fn match_token(&mut self, types: Vec<&TokenType>) -> bool {
+    fn match_token(&mut self, types: &[TokenType]) -> bool {
         for token_type in types {
             if self.check(*token_type) {
                 self.advance();
@@ -775,7 +1302,7 @@ impl Parser {
     }
 
     /// Checks if the current token matches the given token type.
-    fn check(&mut self, token_type: TokenType) -> bool {
+    fn check(&self, token_type: TokenType) -> bool {
         if self.is_at_end() {
             return false;
         };
@@ -803,8 +1330,108 @@ impl Parser {
         return &self.tokens[self.current];
     }
 
+    /// Moves the token at the given index out of the list, for a node of the AST to own. Only its
+    /// type, line and position are left behind, as that is all that is read of the tokens the
+    /// parser has moved past. The exception is the previous token, whose lexeme errors may name,
+    /// so that one is cloned instead.
+    fn take(&mut self, index: usize) -> Token {
+        if index + 1 == self.current {
+            return self.tokens[index].clone();
+        }
+
+        let token = &self.tokens[index];
+        let rest = Token::new(token.token_type, "", "", token.line, token.start, token.end);
+        return mem::replace(&mut self.tokens[index], rest);
+    }
+
+    /// Returns how many levels the given line is indented, from the `Indent` and `Dedent` tokens
+    /// the lexer produced up to and including that line.
+    fn indent_level(&self, line: usize) -> usize {
+        let mut level: usize = 0;
+        for token in self.tokens.iter().take_while(|token| token.line <= line) {
+            match token.token_type {
+                TokenType::Indent => level += 1,
+                TokenType::Dedent => level = level.saturating_sub(1),
+                _ => {},
+            }
+        }
+        return level;
+    }
+
+    /// Creates the error for a block whose body is not indented one level further than the line
+    /// that opened it.
+    fn expected_body(&self, type_: &str) -> ParserError {
+        let line = self.peek().line;
+        return ParserError::ExpectedBody {
+            type_: type_.to_string(),
+            line,
+            found: self.indent_level(line),
+            expected: self.indent_level(self.previous().line) + 1,
+        };
+    }
+
+    /// Checks if the current token is the label of a loop, which is an identifier followed by a
+    /// colon and then `for`, `while` or `loop`.
+    fn check_label(&self) -> bool {
+        let token_type = |offset: usize| {
+            self.tokens.get(self.current + offset).map(|token| token.token_type)
+        };
+
+        return token_type(0) == Some(TokenType::Identifier)
+            && token_type(1) == Some(TokenType::Colon)
+            && matches!(
+                token_type(2),
+                Some(TokenType::For | TokenType::While | TokenType::Loop)
+            );
+    }
+
+    /// Parses an optional type annotation, which is the name of a type after the given token,
+    /// e.g. the `: num` in `let x: num = 5;` or the `-> str` after the parameters of a function.
+    /// The name is checked by the semantic analyser.
+    fn annotation(&mut self, before: TokenType) -> Result<Option<usize>, ParserError> {
+        if !self.match_token(&[before]) {
+            return Ok(None);
+        }
+        if self.match_token(&[TokenType::Identifier, TokenType::Null]) {
+            return Ok(Some(self.current - 1));
+        }
+
+        let token = self.peek();
+        return Err(ParserError::ExpectedTypeName {
+            found: token.lexeme.to_string(),
+            line: token.line,
+        });
+    }
+
+    /// Parses the optional label after a `break` or `continue` keyword.
+    fn loop_label(&mut self) -> Option<usize> {
+        if !self.at_terminator() && self.match_token(&[TokenType::Identifier]) {
+            return Some(self.current - 1);
+        }
+        return None;
+    }
+
+    /// Checks whether the simple statement being parsed has ended, which it has if the next
+    /// token is a semicolon or is on a later line, or if the block or the source ends there.
+    fn at_terminator(&self) -> bool {
+        return match self.peek().token_type {
+            TokenType::Semicolon | TokenType::Eof | TokenType::Indent | TokenType::Dedent => true,
+            _ => first_line(self.peek()) > self.previous().line,
+        };
+    }
+
+    /// Consumes the end of a simple statement. The semicolon can be left out when the statement
+    /// is the last one on its line, and is only needed between statements on the same line.
+    fn terminate(&mut self, error: &str) -> Result<(), ParserError> {
+        if self.match_token(&[TokenType::Semicolon]) || self.at_terminator() {
+            return Ok(());
+        }
+        self.consume(TokenType::Semicolon, error)?;
+        return Ok(());
+    }
+
     /// Checks if the parser has reached the end of the tokens.
-    fn is_at_end(&mut self) -> bool {
+    fn is_at_end(&self) -> bool {
         return self.peek().token_type == TokenType::Eof;
     }
 
@@ -818,13 +1445,21 @@ impl Parser {
             };
 
             match self.peek().token_type {
-                TokenType::Def
+                TokenType::Class
+                | TokenType::Def
+                | TokenType::Enum
                 | TokenType::Let
                 | TokenType::For
                 | TokenType::If
+                | TokenType::Import
+                | TokenType::Loop
+                | TokenType::Match
                 | TokenType::While
                 | TokenType::Print
-                | TokenType::Return => return,
+                | TokenType::Del
+                | TokenType::Raise
+                | TokenType::Return
+                | TokenType::Try => return,
                 _ => {
                     self.advance();
                 }
@@ -833,37 +1468,88 @@ impl Parser {
     }
 
     /// Consumes the current token if it matches the given token type, otherwise returns an error.
-    fn consume(&mut self, token_type: TokenType, error: &str) -> Result<Token, ParserError> {
+    /// The index of the consumed token is returned, so that callers can `take` it once the node
+    /// that needs it is built.
+    fn consume(&mut self, token_type: TokenType, error: &str) -> Result<usize, ParserError> {
         if self.check(token_type) {
-            return Ok(self.advance().clone());
+            self.advance();
+            return Ok(self.current - 1);
         };
 
         return match error {
             "ExpectedVariableName" => {
-                let token = self.previous().clone();
+                let token = self.previous();
                 Err(ParserError::ExpectedVariableName {
-                    lexeme: token.lexeme,
+                    lexeme: token.lexeme.to_string(),
                     line: token.line,
                 })
             },
             "ExpectedSemicolonAfterVariableDeclaration" => {
-                let token = self.previous().clone();
+                let token = self.previous();
                 Err(ParserError::ExpectedSemicolonAfterVariableDeclaration {
-                    lexeme: token.lexeme,
+                    lexeme: token.lexeme.to_string(),
                     line: token.line,
                 })
             },
             "ExpectedSemicolonAfterPrint" => {
                 let token = self.previous();
                 Err(ParserError::ExpectedSemicolonAfterPrint {
-                    value: token.lexeme.clone(),
+                    value: token.lexeme.to_string(),
                     line: token.line,
                 })
             },
             "ExpectedSemicolonAfterReturnValue" => {
                 let token = self.previous();
                 Err(ParserError::ExpectedSemicolonAfterReturnValue {
-                    value: token.lexeme.clone(),
+                    value: token.lexeme.to_string(),
+                    line: token.line,
+                })
+            },
+            "ExpectedVariableNameAfterDel" => {
+                let token = self.peek();
+                Err(ParserError::ExpectedVariableNameAfterDel {
+                    line: token.line,
+                })
+            },
+            "ExpectedSemicolonAfterDel" => {
+                let token = self.previous();
+                Err(ParserError::ExpectedSemicolonAfterDel {
+                    line: token.line,
+                })
+            },
+            "ExpectedSemicolonAfterRaise" => {
+                let token = self.previous();
+                Err(ParserError::ExpectedSemicolonAfterRaise {
+                    line: token.line,
+                })
+            },
+            "ExpectedModuleNameAfterImport" => {
+                let token = self.peek();
+                Err(ParserError::ExpectedModuleNameAfterImport {
+                    line: token.line,
+                })
+            },
+            "ExpectedSemicolonAfterImport" => {
+                let token = self.previous();
+                Err(ParserError::ExpectedSemicolonAfterImport {
+                    line: token.line,
+                })
+            },
+            "ExpectedSemicolonAfterPass" => {
+                let token = self.previous();
+                Err(ParserError::ExpectedSemicolonAfterPass {
+                    line: token.line,
+                })
+            },
+            "ExpectedSemicolonAfterBreak" => {
+                let token = self.previous();
+                Err(ParserError::ExpectedSemicolonAfterBreak {
+                    line: token.line,
+                })
+            },
+            "ExpectedSemicolonAfterContinue" => {
+                let token = self.previous();
+                Err(ParserError::ExpectedSemicolonAfterContinue {
                     line: token.line,
                 })
             },
@@ -895,7 +1581,7 @@ impl Parser {
                 let prev = self.previous();
                 let token = self.peek();
                 Err(ParserError::ExpectedExpression {
-                    prev: prev.lexeme.clone(),
+                    prev: prev.lexeme.to_string(),
                     line: token.line,
                 })
             },
@@ -911,70 +1597,120 @@ impl Parser {
                     line: token.line,
                 })
             },
+            "ExpectedClassName" => {
+                let token = self.peek();
+                Err(ParserError::ExpectedClassName {
+                    line: token.line,
+                })
+            },
+            "ExpectedEnumName" => {
+                let token = self.peek();
+                Err(ParserError::ExpectedEnumName {
+                    line: token.line,
+                })
+            },
+            "ExpectedVariantName" => {
+                let token = self.peek();
+                Err(ParserError::ExpectedVariantName {
+                    line: token.line,
+                })
+            },
+            "ExpectedSemicolonAfterEnum" => {
+                let token = self.previous();
+                Err(ParserError::ExpectedSemicolonAfterEnum {
+                    line: token.line,
+                })
+            },
+            "ExpectedMethodName" => {
+                let token = self.peek();
+                Err(ParserError::ExpectedMethodName {
+                    line: token.line,
+                })
+            },
+            "ExpectedLParenAfterMethodName" => {
+                let token = self.peek();
+                Err(ParserError::ExpectedLParenAfterMethodName {
+                    line: token.line,
+                })
+            },
             "ExpectedParameterName" => {
                 let token = self.peek();
                 Err(ParserError::ExpectedParameterName {
                     line: token.line,
                 })
             },
+            "ExpectedRBrackAfterIndex" => {
+                let token = self.peek();
+                Err(ParserError::ExpectedRBrackAfterIndex {
+                    line: token.line,
+                })
+            },
             "ExpectedRBrackAfterValues" => {
                 let token = self.peek();
                 Err(ParserError::ExpectedRBrackAfterValues {
                     line: token.line,
                 })
             },
-            "ExpectedInitialiser" => {
+            "ExpectedRParenAfterValues" => {
                 let token = self.peek();
-                Err(ParserError::ExpectedInitializer {
+                Err(ParserError::ExpectedRParenAfterValues {
                     line: token.line,
                 })
             },
-            "ExpectedInAfterIdentifier" => {
+            "ExpectedDestructureInitializer" => {
                 let token = self.peek();
-                Err(ParserError::ExpectedInAfterIdentifier {
-                    line: token.line
+                Err(ParserError::ExpectedDestructureInitializer {
+                    line: token.line,
                 })
             },
-            "ExpectedDotDot" => {
+            "ExpectedColonAfterKey" => {
                 let token = self.peek();
-                Err(ParserError::ExpectedDotDot {
-                    line: token.line
+                Err(ParserError::ExpectedColonAfterKey {
+                    line: token.line,
                 })
             },
-            "ExpectedColon" => {
+            "ExpectedRBraceAfterEntries" => {
                 let token = self.peek();
-                Err(ParserError::ExpectedColon {
-                    line: token.line
+                Err(ParserError::ExpectedRBraceAfterEntries {
+                    line: token.line,
                 })
             },
-            "ExpectedForBody" => {
+            "ExpectedInitialiser" => {
                 let token = self.peek();
-                Err(ParserError::ExpectedBody {
-                    type_: "for".to_string(),
-                    line: token.line
+                Err(ParserError::ExpectedInitializer {
+                    line: token.line,
                 })
             },
-            "ExpectedFunctionBody" => {
+            "ExpectedInAfterIdentifier" => {
                 let token = self.peek();
-                Err(ParserError::ExpectedBody {
-                    type_: "function".to_string(),
+                Err(ParserError::ExpectedInAfterIdentifier {
                     line: token.line
                 })
             },
-            "ExpectedIfBody" => {
+            "ExpectedDotDot" => {
                 let token = self.peek();
-                Err(ParserError::ExpectedBody {
-                    type_: "if".to_string(),
+                Err(ParserError::ExpectedDotDot {
                     line: token.line
                 })
             },
-            "ExpectedWhileBody" => {
+            "ExpectedColon" => {
                 let token = self.peek();
-                Err(ParserError::ExpectedBody {
-                    type_: "while".to_string(),
+                Err(ParserError::ExpectedColon {
                     line: token.line
                 })
             },
+            "ExpectedForBody" => Err(self.expected_body("for")),
+            "ExpectedFunctionBody" => Err(self.expected_body("function")),
+            "ExpectedClassBody" => Err(self.expected_body("class")),
+            "ExpectedIfBody" => Err(self.expected_body("if")),
+            "ExpectedElseBody" => Err(self.expected_body("else")),
+            "ExpectedLoopBody" => Err(self.expected_body("loop")),
+            "ExpectedMatchBody" => Err(self.expected_body("match")),
+            "ExpectedCaseBody" => Err(self.expected_body("case")),
+            "ExpectedTryBody" => Err(self.expected_body("try")),
+            "ExpectedExceptBody" => Err(self.expected_body("except")),
+            "ExpectedFinallyBody" => Err(self.expected_body("finally")),
+            "ExpectedWhileBody" => Err(self.expected_body("while")),
             "ExpectedDedentAfterStmt" => {
                 let token = self.peek();
                 Err(ParserError::ExpectedDedent {
@@ -1003,3 +1739,50 @@ impl Parser {
         }
     }
 }
+
+/// Checks whether an expression can be assigned to, which a variable, an item of a list or
+/// dictionary, or a field of an instance can be.
+fn is_assignment_target(expr: &Expr) -> bool {
+    return is_place(expr) || matches!(expr, Expr::Property { .. });
+}
+
+/// Checks whether an expression names a place that holds a value, which a variable or an item
+/// of a list or dictionary held in a place does, e.g. `grid[i][j]`.
+fn is_place(expr: &Expr) -> bool {
+    return match expr {
+        Expr::Var { .. } => true,
+        Expr::Splice { list, is_splice: false, start: Some(_), end: None } => is_place(list),
+        _ => false,
+    };
+}
+
+/// Returns the docstring of a function, which is a string written as the first statement of its
+/// body. As in Python, the indentation shared by the lines after the first is removed, along with
+/// any blank lines at the start and end.
+fn docstring(body: &[Stmt]) -> Option<String> {
+    let Some(Stmt::Expression { expression: Expr::Literal { value: LiteralType::Str(text) } }) =
+        body.first()
+    else {
+        return None;
+    };
+
+    let mut lines = text.lines();
+    let first = lines.next().unwrap_or("").trim();
+    let rest: Vec<&str> = lines.collect();
+    let indent = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut doc = vec![first];
+    doc.extend(rest.iter().map(|line| line.get(indent..).unwrap_or("").trim_end()));
+    return Some(doc.join("\n").trim().to_string());
+}
+
+/// Returns the line a token starts on. A token is given the line it ends on, which is a later
+/// line for a string that spans several lines.
+fn first_line(token: &Token) -> usize {
+    return token.line.saturating_sub(token.lexeme.matches('\n').count());
+}
@@ -1,11 +1,392 @@
+//! The `run` module orchestrates the phases of the interpreter (lexing, parsing, semantic
+//! analysis and evaluation) for a piece of source code.
+//!
+//! Rather than only returning the final output, a run is reported as a stream of `RunEvent`s,
+//! which lets the CLI, the API routes and the tests consume the same orchestration and decide
+//! for themselves how to present output, diagnostics and statistics. The program runs on a
+//! thread of its own, which sends each event as it happens, so the lines a program prints can
+//! be received while it is still running.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use pyru::run::{run_events, RunEvent};
+//!
+//! for event in run_events("print(1 + 2);", false) {
+//!     match event {
+//!         RunEvent::Output(line) => println!("{line}"),
+//!         RunEvent::Diagnostic { phase, message, .. } => eprintln!("{phase}: {message}"),
+//!         RunEvent::LimitExceeded { limit, line, .. } => eprintln!("{limit} limit hit on {line:?}"),
+//!         RunEvent::Notice(notice) => eprintln!("{notice}"),
+//!         RunEvent::Globals(globals) => println!("Defined {} globals", globals.len()),
+//!         RunEvent::Finished { stats } => println!("{stats:?}"),
+//!     }
+//! }
+//! ```
+
+use std::{
+    fmt,
+    panic,
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
 use crate::{
-    evaluator::Evaluator,
+    complexity::FunctionMetrics,
+    evaluator::{
+        max_depth_for, CancelToken, Evaluator, GlobalDefinition, InputSource, Limit, NativePolicy,
+        OutputSink, DEFAULT_STACK_SIZE, MAX_STEPS,
+    },
     lexer::Lexer,
     parser::Parser,
+    sandbox::Sandbox,
     semanticanalyser::SemanticAnalyser
 };
 
+/// The phase of the interpreter that produced a diagnostic.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Phase {
+    Lexer,
+    Parser,
+    SemanticAnalyser,
+    Evaluator,
+}
+
+/// Statistics gathered over the course of a run.
+///
+/// ## Fields
+/// - `tokens`: The number of tokens produced by the lexer.
+/// - `statements`: The number of top-level statements produced by the parser.
+/// - `output_lines`: The number of lines of output produced by the evaluator.
+/// - `functions`: The complexity of each function defined by the program, measured by the
+///   semantic analyser.
+/// - `elapsed`: The total time taken by the run.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct RunStats {
+    pub tokens: usize,
+    pub statements: usize,
+    pub output_lines: usize,
+    pub functions: Vec<FunctionMetrics>,
+    pub elapsed: Duration,
+}
+
+/// An event emitted while running a program.
+///
+/// ## Variants
+/// - `Output`: A line of output printed by the program.
+/// - `Diagnostic`: An error reported by one of the phases, which ends the run, after any output
+///   the program printed before then. The code is the name of the error, e.g.
+///   `UndefinedVariable`, and never contains any of the source.
+/// - `LimitExceeded`: The program was stopped by one of its limits, after the output it printed
+///   before then. The line is that of the statement it was stopped at, if it is known, and the
+///   code and message are those of the error, as for a diagnostic.
+/// - `Notice`: A message from the semantic analyser about code that is valid but likely to be a
+///   mistake, such as comparing the results of floating point arithmetic with `==`.
+/// - `Globals`: The global variables that a successful program defined, sorted by name.
+/// - `Finished`: Always the last event of a run, carrying the statistics of the run.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum RunEvent {
+    Output(String),
+    Diagnostic { phase: Phase, code: String, message: String },
+    LimitExceeded { limit: Limit, line: Option<usize>, code: String, message: String },
+    Notice(String),
+    Globals(Vec<GlobalDefinition>),
+    Finished { stats: RunStats },
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Phase::Lexer => write!(f, "lexer"),
+            Phase::Parser => write!(f, "parser"),
+            Phase::SemanticAnalyser => write!(f, "semantic"),
+            Phase::Evaluator => write!(f, "interpreter"),
+        };
+    }
+}
+
+/// The events of a run, received from the thread running the program as each of them happens.
+/// The last event is always `RunEvent::Finished`, after which the iterator ends.
+///
+/// Dropping the iterator does not stop the program, which runs to the end of its limits
+/// without anyone receiving its events.
+///
+/// ## Fields
+/// - `receiver`: The end of the channel that the running program sends its events down.
+/// - `handle`: The thread running the program, which is joined once it has sent every event so
+///   that a panic on it is raised again on the receiving thread.
+pub struct RunEvents {
+    receiver: mpsc::Receiver<RunEvent>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RunEvents {
+    /// Creates the iterator over the events sent down the channel by the given thread, if the
+    /// events are sent by a thread of their own.
+    pub(crate) fn new(receiver: mpsc::Receiver<RunEvent>, handle: Option<JoinHandle<()>>) -> Self {
+        return Self { receiver, handle };
+    }
+}
+
+impl Iterator for RunEvents {
+    type Item = RunEvent;
+
+    fn next(&mut self) -> Option<RunEvent> {
+        if let Ok(event) = self.receiver.recv() {
+            return Some(event);
+        }
+
+        // Every sender has been dropped, so the thread has finished or is about to
+        if let Some(handle) = self.handle.take() {
+            if let Err(payload) = handle.join() {
+                panic::resume_unwind(payload);
+            }
+        }
+        return None;
+    }
+}
+
+/// Runs the source code through every phase of the interpreter on a new thread, returning the
+/// events produced along the way as they happen, ending with `RunEvent::Finished`.
+pub fn run_events(source: &str, debug: bool) -> RunEvents {
+    let input = InputSource::Stdin;
+    return run_events_with(source, debug, NativePolicy::All, OutputSink::Echo, input);
+}
+
+/// Runs the source code like `run_events`, but only defines the native functions permitted by
+/// the given policy, also sends printed lines to the given sink, and reads input from the given
+/// source.
+pub fn run_events_with(
+    source: &str,
+    debug: bool,
+    natives: NativePolicy,
+    sink: OutputSink,
+    input: InputSource,
+) -> RunEvents {
+    return run_events_limited(source, debug, natives, sink, input, RunOptions::default());
+}
+
+/// Runs the source code as the server runs it, without writing printed lines to stdout and
+/// stopping it once it has executed `MAX_STEPS` statements, run for the timeout, if one is
+/// given, or been cancelled. Dividing by zero is an error. Each line of `stdin` is a line of
+/// input that the program can read.
+pub fn run_events_untrusted(
+    source: &str,
+    natives: NativePolicy,
+    stdin: &str,
+    timeout: Option<Duration>,
+    cancel: CancelToken,
+) -> RunEvents {
+    let input = InputSource::buffer(stdin);
+    let options = RunOptions {
+        timeout,
+        max_steps: Some(MAX_STEPS),
+        checked_division: true,
+        cancel,
+    };
+    return run_events_limited(source, false, natives, OutputSink::Quiet, input, options);
+}
+
+/// The options that a run is made with, on top of the limits that every program has.
+///
+/// ## Fields
+/// - `timeout`: How long the program may run for, if it is limited.
+/// - `max_steps`: The number of statements the program may execute, if it is limited.
+/// - `checked_division`: Whether dividing by zero is an error.
+/// - `cancel`: The token that stops the program when it is cancelled.
+#[derive(Clone, Debug, Default)]
+struct RunOptions {
+    timeout: Option<Duration>,
+    max_steps: Option<usize>,
+    checked_division: bool,
+    cancel: CancelToken,
+}
+
+/// Runs the source code like `run_events_with`, with the given options.
+fn run_events_limited(
+    source: &str,
+    debug: bool,
+    natives: NativePolicy,
+    sink: OutputSink,
+    input: InputSource,
+    options: RunOptions,
+) -> RunEvents {
+    let started = Instant::now();
+    let (sender, receiver) = mpsc::channel();
+    let source = source.to_string();
+
+    let handle = spawn(move || {
+        let events = sender.clone();
+        let sink = OutputSink::stream(move |line| {
+            sink.write(line);
+            send(&events, RunEvent::Output(line.to_string()));
+        });
+        let interpreter = Evaluator::with_natives(natives)
+            .with_max_depth(max_depth_for(DEFAULT_STACK_SIZE))
+            .with_sink(sink)
+            .with_input(input)
+            .with_timeout(options.timeout)
+            .with_max_steps(options.max_steps)
+            .with_checked_division(options.checked_division)
+            .with_cancel(options.cancel);
+
+        let mut stats = RunStats::default();
+        execute(&source, debug, interpreter, &sender, &mut stats);
+        stats.elapsed = started.elapsed();
+        send(&sender, RunEvent::Finished { stats });
+    });
+
+    return RunEvents::new(receiver, handle);
+}
+
+/// Runs the function on a new thread with a stack of `DEFAULT_STACK_SIZE` bytes, returning the
+/// thread without waiting for it to finish. If the thread cannot be started, the function is
+/// run on the calling thread instead and no thread is returned.
+fn spawn(f: impl FnOnce() + Send + 'static) -> Option<JoinHandle<()>> {
+    let job = Arc::new(Mutex::new(Some(f)));
+    let spawned = {
+        let job = Arc::clone(&job);
+        thread::Builder::new().stack_size(DEFAULT_STACK_SIZE).spawn(move || {
+            if let Some(f) = job.lock().ok().and_then(|mut job| job.take()) {
+                f();
+            }
+        })
+    };
+
+    if let Ok(handle) = spawned {
+        return Some(handle);
+    }
+    if let Some(f) = job.lock().ok().and_then(|mut job| job.take()) {
+        f();
+    }
+    return None;
+}
+
+/// Where programs are run.
+///
+/// ## Variants
+/// - `InProcess`: Programs are run in the calling process, which is the fastest.
+/// - `Isolated`: Each program is run in a new worker process started by the sandbox, so that
+///   untrusted code cannot use more memory or CPU time than the sandbox allows.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Backend {
+    #[default]
+    InProcess,
+    Isolated(Sandbox),
+}
+
+/// Runs the source code on the given backend, without writing printed lines to stdout, and
+/// returns the events produced along the way as they happen, ending with `RunEvent::Finished`.
+/// Each line of `stdin` is a line of input that the program can read. The native functions that
+/// read from the host are never defined, whatever the policy permits, and the program is
+/// stopped once it has run for the timeout, if one is given, or once the token is cancelled.
+pub fn run_events_on(
+    backend: &Backend,
+    source: &str,
+    natives: NativePolicy,
+    stdin: &str,
+    timeout: Option<Duration>,
+    cancel: CancelToken,
+) -> RunEvents {
+    let natives = natives.sandboxed();
+    return match backend {
+        Backend::InProcess => run_events_untrusted(source, natives, stdin, timeout, cancel),
+        Backend::Isolated(sandbox) => sandbox.run_events(source, natives, stdin, timeout, cancel),
+    };
+}
+
+/// Runs the source code and returns its output, or the message of the diagnostic that
+/// stopped it.
 pub fn run(source: &str, debug: bool) -> Vec<String> {
+    return run_with(source, debug, NativePolicy::All);
+}
+
+/// Runs the source code like `run`, but only defines the native functions permitted by the
+/// given policy.
+pub fn run_with(source: &str, debug: bool, natives: NativePolicy) -> Vec<String> {
+    let events = run_events_with(source, debug, natives, OutputSink::Echo, InputSource::Stdin);
+    return output(events);
+}
+
+/// Collects the output of a run from its events, or the message of the diagnostic that
+/// stopped it.
+pub fn output(events: impl IntoIterator<Item = RunEvent>) -> Vec<String> {
+    return events
+        .into_iter()
+        .filter_map(|event| match event {
+            RunEvent::Output(line) => Some(line),
+            RunEvent::Diagnostic { phase, message, .. } => {
+                let article = if phase == Phase::Evaluator { "An" } else { "A" };
+                eprintln!("{article} {phase} error occured: {message}");
+                Some(message)
+            }
+            RunEvent::LimitExceeded { limit, line, message, .. } => {
+                match line {
+                    Some(line) => eprintln!("The {limit} limit was exceeded on line {line}: {message}"),
+                    None => eprintln!("The {limit} limit was exceeded: {message}"),
+                }
+                Some(message)
+            }
+            RunEvent::Notice(notice) => {
+                eprintln!("Notice: {notice}");
+                None
+            }
+            RunEvent::Globals(_) | RunEvent::Finished { .. } => None,
+        })
+        .collect();
+}
+
+/// Lexes the source code and returns each of its tokens as it would be printed, or the message
+/// of the error that stopped the lexer.
+pub fn tokens(source: &str) -> Result<Vec<String>, String> {
+    let mut lexer = Lexer::new(source.to_string(), 2);
+    return match lexer.run() {
+        Ok(tokens) => Ok(tokens.iter().map(|token| format!("{token}")).collect()),
+        Err(e) => Err(format!("{e}")),
+    };
+}
+
+/// Lexes and parses the source code and returns its syntax tree as it would be printed in debug
+/// mode, or the message of the error that stopped the lexer or the parser.
+pub fn ast(source: &str) -> Result<String, String> {
+    let mut lexer = Lexer::new(source.to_string(), 2);
+    let tokens = lexer.run().map_err(|e| format!("{e}"))?;
+
+    let mut parser = Parser::new(tokens);
+    return match parser.parse() {
+        Ok(ast) => Ok(format!("{ast:#?}")),
+        Err(e) => Err(format!("{e}")),
+    };
+}
+
+/// Creates the diagnostic event for an error, taking its code from the name of its variant.
+fn diagnostic<E: fmt::Debug + fmt::Display>(phase: Phase, error: &E) -> RunEvent {
+    return RunEvent::Diagnostic { phase, code: code(error), message: format!("{error}") };
+}
+
+/// Returns the code of an error, which is the name of its variant.
+fn code<E: fmt::Debug>(error: &E) -> String {
+    let debug = format!("{error:?}");
+    return debug
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+}
+
+/// Carries out each phase in turn, evaluating the program with the given evaluator and sending
+/// the events it produces. The lines the program prints are sent by the sink of the evaluator
+/// as they are printed.
+fn execute(
+    source: &str,
+    debug: bool,
+    mut interpreter: Evaluator,
+    events: &mpsc::Sender<RunEvent>,
+    stats: &mut RunStats,
+) {
     if debug {
         println!("{:?}", source.chars().collect::<Vec<char>>());
     }
@@ -14,10 +395,11 @@ pub fn run(source: &str, debug: bool) -> Vec<String> {
     let tokens = match lexer.run() {
         Ok(tokens) => tokens,
         Err(e) => {
-            eprintln!("A lexer error occured: {e}");
-            return vec![format!("{e}")];
+            send(events, diagnostic(Phase::Lexer, &e));
+            return;
         }
     };
+    stats.tokens = tokens.len();
 
     if debug {
         println!("Tokens:");
@@ -30,10 +412,11 @@ pub fn run(source: &str, debug: bool) -> Vec<String> {
     let ast = match parser.parse() {
         Ok(ast) => ast,
         Err(e) => {
-            eprintln!("A parser error occured: {e}");
-            return vec![format!("{e}")];
+            send(events, diagnostic(Phase::Parser, &e));
+            return;
         }
     };
+    stats.statements = ast.len();
 
     if debug {
         println!("AST:");
@@ -41,20 +424,38 @@ pub fn run(source: &str, debug: bool) -> Vec<String> {
     }
 
     let mut semantic_analyser = SemanticAnalyser::new(ast.clone());
-    match semantic_analyser.run() {
-        Ok(_) => {}
-        Err(e) => {
-            eprintln!("A semantic error occured: {e}");
-            return vec![format!("{e}")];
-        }
+    if let Err(e) = semantic_analyser.run() {
+        send(events, diagnostic(Phase::SemanticAnalyser, &e));
+        return;
     }
-
-    let mut interpreter = Evaluator::new();
+    for notice in semantic_analyser.take_notices() {
+        send(events, RunEvent::Notice(notice));
+    }
+    stats.functions = semantic_analyser.take_function_metrics();
+    interpreter.resolve(&ast);
     match interpreter.interpret(ast) {
-        Ok(output) => return output,
-        Err(e) => {
-            eprintln!("An interpreter error occured: {e}");
-            return vec![format!("{e}")];
+        Ok(output) => {
+            stats.output_lines = output.len();
+            send(events, RunEvent::Globals(interpreter.defined_globals().to_vec()));
+        }
+        Err(e) => match e.limit() {
+            // The output a program printed before it was stopped by a limit was already sent
+            Some(limit) => {
+                stats.output_lines = interpreter.take_output().len();
+                send(events, RunEvent::LimitExceeded {
+                    limit,
+                    line: interpreter.stopped_at(),
+                    code: code(&e),
+                    message: format!("{e}"),
+                });
+            }
+            None => send(events, diagnostic(Phase::Evaluator, &e)),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Sends an event to whoever is receiving the events of the run. If they have stopped
+/// receiving, the run carries on regardless.
+fn send(events: &mpsc::Sender<RunEvent>, event: RunEvent) {
+    let _ = events.send(event);
+}
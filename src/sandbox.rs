@@ -0,0 +1,286 @@
+//! The `sandbox` module runs programs in a separate worker process, for deployments that must
+//! run untrusted code without letting it take down or starve the server.
+//!
+//! ## Overview
+//!
+//! A `Sandbox` starts a new worker process for every program, limiting the memory and CPU time
+//! the process may use with `ulimit`, and kills it if it is still running once its wall clock
+//! timeout has passed. The worker is the server's own executable run with `WORKER_ENV` set, which
+//! reads a `WorkerRequest` as JSON from stdin, runs the program through
+//! `run::run_events_untrusted` and writes each event to stdout as a line of JSON as it happens,
+//! so that the events of a run in the sandbox stream back like those of a run in process.
+//!
+//! Whatever happens to the worker, the run still ends with `RunEvent::Finished`, so the callers
+//! of `run::run_events_on` cannot tell the two backends apart except by the events for a
+//! worker that was stopped or crashed.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::EvaluatorError,
+    evaluator::{CancelToken, Limit, NativePolicy},
+    run::{run_events_untrusted, Phase, RunEvent, RunEvents, RunStats},
+};
+
+/// The environment variable that makes the executable run as a worker rather than a server.
+pub const WORKER_ENV: &str = "PYRU_WORKER";
+
+/// How often a running worker is checked on to see whether it has finished.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// The program a worker is asked to run.
+///
+/// ## Fields
+/// - `source`: The source code to run.
+/// - `natives`: The native functions the program is allowed to call.
+/// - `stdin`: The input the program can read, one line at a time.
+/// - `timeout`: How long the program may run for before the worker stops it, if it is limited.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct WorkerRequest {
+    pub source: String,
+    pub natives: NativePolicy,
+    #[serde(default)]
+    pub stdin: String,
+    #[serde(default)]
+    pub timeout: Option<Duration>,
+}
+
+/// Runs each program in a new worker process with limits on the resources it may use.
+///
+/// ## Fields
+/// - `program`: The executable started as the worker.
+/// - `memory_limit`: The most virtual memory the worker may use, in kilobytes.
+/// - `cpu_limit`: The most CPU time the worker may use, in seconds.
+/// - `timeout`: How long the worker may run for before it is killed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sandbox {
+    program: PathBuf,
+    memory_limit: u64,
+    cpu_limit: u64,
+    timeout: Duration,
+}
+
+impl Sandbox {
+    /// Creates a sandbox that starts the given executable as its worker, with a limit of 256MB
+    /// of memory, 5 seconds of CPU time and 10 seconds of wall clock time.
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        return Self {
+            program: program.into(),
+            memory_limit: 256 * 1024,
+            cpu_limit: 5,
+            timeout: Duration::from_secs(10),
+        };
+    }
+
+    /// Creates a sandbox that starts the current executable as its worker.
+    pub fn current_exe() -> io::Result<Self> {
+        return Ok(Self::new(std::env::current_exe()?));
+    }
+
+    /// Sets the most virtual memory the worker may use, in megabytes.
+    pub fn with_memory_limit(mut self, megabytes: u64) -> Self {
+        self.memory_limit = megabytes * 1024;
+        return self;
+    }
+
+    /// Sets the most CPU time the worker may use, in whole seconds.
+    pub fn with_cpu_limit(mut self, seconds: u64) -> Self {
+        self.cpu_limit = seconds;
+        return self;
+    }
+
+    /// Sets how long the worker may run for before it is killed.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        return self;
+    }
+
+    /// Runs the source code in a new worker process, returning the events it produces as the
+    /// worker writes them back, ending with `RunEvent::Finished`. The worker stops the program
+    /// itself once it has run for the timeout, if one is given, while a worker that is still
+    /// running at the sandbox's own timeout, or once the token is cancelled, is killed, keeping
+    /// the events it wrote before then.
+    pub fn run_events(
+        &self,
+        source: &str,
+        natives: NativePolicy,
+        stdin: &str,
+        timeout: Option<Duration>,
+        cancel: CancelToken,
+    ) -> RunEvents {
+        let started = Instant::now();
+        let request = WorkerRequest {
+            source: source.to_string(),
+            natives,
+            stdin: stdin.to_string(),
+            timeout,
+        };
+        let (sender, receiver) = mpsc::channel();
+        let sandbox = self.clone();
+
+        let handle = thread::spawn(move || {
+            let mut stats = match sandbox.execute(&request, &sender, &cancel) {
+                Ok(stats) => stats,
+                Err(failure) => {
+                    let _ = sender.send(failure.event());
+                    RunStats::default()
+                }
+            };
+
+            // The statistics are those of the worker, apart from the time taken to start it
+            stats.elapsed = started.elapsed();
+            let _ = sender.send(RunEvent::Finished { stats });
+        });
+
+        return RunEvents::new(receiver, Some(handle));
+    }
+
+    /// Starts a worker, sends it the request and passes on the events it writes back, returning
+    /// the statistics of the run once the worker has finished.
+    fn execute(
+        &self,
+        request: &WorkerRequest,
+        events: &mpsc::Sender<RunEvent>,
+        cancel: &CancelToken,
+    ) -> Result<RunStats, WorkerFailure> {
+        // The limits are set by the shell before it replaces itself with the worker
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg("ulimit -v \"$1\" && ulimit -t \"$2\" && exec \"$0\"")
+            .arg(&self.program)
+            .arg(self.memory_limit.to_string())
+            .arg(self.cpu_limit.to_string())
+            .env(WORKER_ENV, "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|_| WorkerFailure::Crashed)?;
+
+        let body = serde_json::to_vec(request).map_err(|_| WorkerFailure::Crashed)?;
+        let mut stdin = child.stdin.take().ok_or(WorkerFailure::Crashed)?;
+        let writer = thread::spawn(move || stdin.write_all(&body));
+
+        let stdout = child.stdout.take().ok_or(WorkerFailure::Crashed)?;
+        let events = events.clone();
+        let reader = thread::spawn(move || forward(stdout, &events));
+
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait().map_err(|_| WorkerFailure::Crashed)? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(WorkerFailure::TimedOut(self.timeout));
+            }
+            if cancel.is_cancelled() {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(WorkerFailure::Cancelled);
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        // A worker that stops reading its request early has failed in some other way
+        let _ = writer.join();
+        let stats = match reader.join() {
+            Ok(Some(stats)) => stats,
+            _ => return Err(WorkerFailure::Crashed),
+        };
+
+        if !status.success() {
+            return Err(WorkerFailure::Crashed);
+        }
+        return Ok(stats);
+    }
+}
+
+/// Passes on each event written by a worker as it is read, apart from the last, returning the
+/// statistics it carries. If the worker writes something other than events, or stops before its
+/// last event, no statistics are returned.
+fn forward(output: impl Read, events: &mpsc::Sender<RunEvent>) -> Option<RunStats> {
+    for line in BufReader::new(output).lines() {
+        match serde_json::from_str(&line.ok()?).ok()? {
+            RunEvent::Finished { stats } => return Some(stats),
+            event => {
+                let _ = events.send(event);
+            }
+        }
+    }
+    return None;
+}
+
+/// The ways in which a worker can fail to run a program to completion.
+///
+/// ## Variants
+/// - `TimedOut`: The worker was still running when its timeout passed, so it was killed.
+/// - `Cancelled`: The worker was still running when the run was cancelled, so it was killed.
+/// - `Crashed`: The worker could not be started, exited unsuccessfully or wrote back something
+///   other than events, which is what happens when it runs out of memory or CPU time.
+enum WorkerFailure {
+    TimedOut(Duration),
+    Cancelled,
+    Crashed,
+}
+
+impl WorkerFailure {
+    /// Returns the event reported in place of the events of the run.
+    fn event(&self) -> RunEvent {
+        return match self {
+            WorkerFailure::TimedOut(limit) => RunEvent::LimitExceeded {
+                limit: Limit::Time,
+                line: None,
+                code: "WorkerTimedOut".to_string(),
+                message: format!("The program ran for longer than its time limit of {limit:?}"),
+            },
+            WorkerFailure::Cancelled => RunEvent::Diagnostic {
+                phase: Phase::Evaluator,
+                code: "Cancelled".to_string(),
+                message: EvaluatorError::Cancelled.to_string(),
+            },
+            WorkerFailure::Crashed => RunEvent::Diagnostic {
+                phase: Phase::Evaluator,
+                code: "WorkerCrashed".to_string(),
+                message: "The process running the program stopped unexpectedly, which happens \
+                    when it uses more memory or CPU time than it is allowed".to_string(),
+            },
+        };
+    }
+}
+
+/// Reads a request from the input, runs it and writes each event it produces to the output as a
+/// line of JSON as it happens, as a worker does.
+pub fn serve(input: impl Read, mut output: impl Write) -> io::Result<()> {
+    let request: WorkerRequest = serde_json::from_reader(input)?;
+    // The worker is killed when its run is cancelled, so it does not need a token of its own
+    let cancel = CancelToken::new();
+    let events = run_events_untrusted(
+        &request.source,
+        request.natives,
+        &request.stdin,
+        request.timeout,
+        cancel,
+    );
+    for event in events {
+        serde_json::to_writer(&mut output, &event)?;
+        output.write_all(b"\n")?;
+        output.flush()?;
+    }
+    return Ok(());
+}
+
+/// Serves a single request from stdin to stdout, as the worker process started by a `Sandbox`.
+pub fn run_worker() -> io::Result<()> {
+    return serve(io::stdin().lock(), io::stdout().lock());
+}
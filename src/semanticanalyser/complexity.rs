@@ -0,0 +1,127 @@
+//! The `complexity` module measures how complex each function of a program is, so that a
+//! frontend can point out functions that would be easier to follow if they were split up.
+//!
+//! ## Overview
+//!
+//! The semantic analyser measures every function it analyses, including methods and functions
+//! defined inside other functions, and reports the following for each of them:
+//!
+//! - The cyclomatic complexity, which is the number of paths through the function. It starts at
+//!   1 and increases by 1 for each `if`, `elif`, `while`, `for`, `loop`, `except`, `case` other
+//!   than `case _`, and each `and` or `or` in a condition.
+//! - The maximum nesting depth, which is how many `if`, `while`, `for`, `loop`, `try` and
+//!   `match` statements the most deeply nested statement is inside of. An `elif` is at the same
+//!   depth as its `if`.
+//! - The length, which is the number of statements in the function, including nested ones.
+//!
+//! The body of a function defined inside another function counts towards its own measurements
+//! only, although its definition counts as one statement of the function it is defined in.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    expr::Expr,
+    stmt::{Pattern, Stmt},
+    token::Token,
+};
+
+/// The measurements of a single function.
+///
+/// ## Fields
+/// - `name`: The name of the function.
+/// - `line`: The line the function is defined on.
+/// - `complexity`: The cyclomatic complexity of the function.
+/// - `nesting`: The maximum nesting depth of the statements in the function.
+/// - `length`: The number of statements in the function.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct FunctionMetrics {
+    pub name: String,
+    pub line: usize,
+    pub complexity: usize,
+    pub nesting: usize,
+    pub length: usize,
+}
+
+impl FunctionMetrics {
+    /// Measures the function with the given name and body.
+    pub fn measure(name: &Token, body: &[Stmt]) -> Self {
+        let mut metrics = Self {
+            name: name.lexeme.to_string(),
+            line: name.line,
+            complexity: 1,
+            nesting: 0,
+            length: 0,
+        };
+        metrics.measure_block(body, 0);
+        return metrics;
+    }
+
+    /// Measures each statement of a block that is nested at the given depth.
+    fn measure_block(&mut self, statements: &[Stmt], depth: usize) {
+        for statement in statements {
+            self.measure_stmt(statement, depth);
+        }
+    }
+
+    /// Measures a statement that is nested at the given depth, along with the statements inside
+    /// of it.
+    fn measure_stmt(&mut self, stmt: &Stmt, depth: usize) {
+        self.length += 1;
+        self.nesting = self.nesting.max(depth);
+
+        match stmt {
+            Stmt::If { condition, then_branch, else_branch } => {
+                self.complexity += 1 + count_conditions(condition);
+                self.measure_block(then_branch, depth + 1);
+
+                match else_branch.as_deref() {
+                    // An `elif` is stored as the only statement of the `else` branch
+                    Some([elif @ Stmt::If { .. }]) => {
+                        self.length -= 1;
+                        self.measure_stmt(elif, depth);
+                    },
+                    Some(else_branch) => self.measure_block(else_branch, depth + 1),
+                    None => {},
+                }
+            },
+            Stmt::While { condition, body, .. } => {
+                self.complexity += 1 + count_conditions(condition);
+                self.measure_block(body, depth + 1);
+            },
+            Stmt::For { body, .. } | Stmt::ForEach { body, .. } | Stmt::Loop { body, .. } => {
+                self.complexity += 1;
+                self.measure_block(body, depth + 1);
+            },
+            Stmt::Match { arms, .. } => {
+                for (pattern, body) in arms {
+                    if *pattern != Pattern::Wildcard {
+                        self.complexity += 1;
+                    }
+                    self.measure_block(body, depth + 1);
+                }
+            },
+            Stmt::Try { body, handler, finally, .. } => {
+                self.measure_block(body, depth + 1);
+                if let Some(handler) = handler {
+                    self.complexity += 1;
+                    self.measure_block(handler, depth + 1);
+                }
+                if let Some(finally) = finally {
+                    self.measure_block(finally, depth + 1);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+/// Counts the `and` and `or` operators that join the parts of a condition.
+fn count_conditions(expr: &Expr) -> usize {
+    return match expr {
+        Expr::Logical { left, right, .. } => 1 + count_conditions(left) + count_conditions(right),
+        Expr::Grouping { expression } => count_conditions(expression),
+        Expr::Unary { right, .. } => count_conditions(right),
+        _ => 0,
+    };
+}
@@ -0,0 +1,421 @@
+//! The `resolver` module works out where each variable a program refers to is stored, so that
+//! the evaluator can read it without looking its name up.
+//!
+//! ## Overview
+//!
+//! The resolver runs after the semantic analyser and before the evaluator. It follows the
+//! environments the evaluator creates: one for each call of a function, one holding the
+//! instance bound to `self` for a method, one for each iteration of a `for ... in` loop and one
+//! for each iteration of a `for` loop, while the top level of the program is the global
+//! environment. Every name declared directly in one of these scopes, anywhere in it, is given an
+//! index in the order it is first declared. The names are kept as the `locals` of the function
+//! or loop, and the evaluator declares them in each environment it creates for the scope before
+//! running any of it, so each name is at its index whatever order the declarations run in.
+//!
+//! A variable reference or an assignment to a name declared in an enclosing scope is given the
+//! depth of that scope, which is how many environments out from the innermost one it is, and
+//! the index of the name in it. Any other name is global, and is declared in the global
+//! environment the program is resolved against, which gives it an index there. A name keeps its
+//! index in an environment for as long as the environment exists, so the evaluator reads and
+//! assigns a variable through its slot alone.
+//!
+//! A reference that comes before the declaration of a name in the same scope refers to the
+//! variable of that name in an enclosing scope, as the local is not defined yet when it runs.
+//! A reference inside a function declared in the scope refers to the local wherever the
+//! declaration is, as the function can only be called once it has been declared, so functions
+//! declared next to each other can call each other. Reading a local after a declaration that
+//! did not run, such as one in an `if` branch, or after it has been deleted, is an error.
+//!
+//! The module an `import` loads is resolved in the scope it is imported into, as that is the
+//! scope it runs in, and is kept on the statement for the evaluator to run.
+
+use std::{
+    cell::{Cell, OnceCell},
+    collections::HashMap,
+    rc::Rc,
+};
+
+use crate::{
+    evaluator::Env,
+    expr::Expr,
+    stdlib,
+    stmt::Stmt,
+    token::Token,
+};
+
+/// Where the variable named by a variable reference or an assignment is stored.
+///
+/// ## Variants
+/// - `Local`: The variable is in the environment `depth` environments out from the current
+///   one, at `index` in the order the variables of that environment are defined.
+/// - `Global`: The variable is in the global environment, at `index`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Slot {
+    Local { depth: usize, index: usize },
+    Global { index: usize },
+}
+
+/// The `Resolver` struct fills in the slot of each variable reference and assignment in a
+/// program.
+///
+/// ## Fields
+/// - `scopes`: The scopes enclosing the statement being resolved, innermost last. The top level
+///   of the program is not included, as its names are globals.
+/// - `globals`: The global environment the program will run in, in which the globals it refers
+///   to are declared.
+pub struct Resolver {
+    scopes: Vec<Scope>,
+    globals: Env,
+}
+
+/// A scope that the resolver is in.
+///
+/// ## Fields
+/// - `names`: The index of each name declared anywhere in the scope, and whether its
+///   declaration has been resolved yet.
+/// - `function`: Whether the scope is run when a function is called, rather than straight away.
+struct Scope {
+    names: HashMap<String, (usize, bool)>,
+    function: bool,
+}
+
+impl Resolver {
+    /// Creates a new `Resolver` at the top level of a program that will run in the given global
+    /// environment.
+    pub fn new(globals: Env) -> Self {
+        return Self { scopes: Vec::new(), globals };
+    }
+
+    /// Resolves every variable reference and assignment in the statements.
+    pub fn resolve(&mut self, statements: &[Stmt]) {
+        for stmt in statements {
+            self.resolve_stmt(stmt);
+        }
+    }
+
+    /// Begins a new scope with the given names declared and defined in it, in order.
+    fn begin_scope<'a>(&mut self, names: impl IntoIterator<Item = &'a Token>, function: bool) {
+        self.scopes.push(Scope { names: HashMap::new(), function });
+        for name in names {
+            self.declare(name);
+            self.define(name);
+        }
+    }
+
+    /// Ends the innermost scope, keeping the names declared in it, in order, as the locals of
+    /// the function or loop it belongs to.
+    fn end_scope(&mut self, locals: &OnceCell<Rc<[String]>>) {
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+
+        let mut names = vec![String::new(); scope.names.len()];
+        for (name, (index, _)) in scope.names {
+            names[index] = name;
+        }
+        let _ = locals.set(Rc::from(names));
+    }
+
+    /// Declares a name in the innermost scope, unless it is already declared there. Nothing is
+    /// declared at the top level.
+    fn declare(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            let index = scope.names.len();
+            scope.names.entry(name.lexeme.to_string()).or_insert((index, false));
+        }
+    }
+
+    /// Marks the declaration of a name in the innermost scope as resolved, so that the
+    /// references after it refer to the local.
+    fn define(&mut self, name: &Token) {
+        if let Some((_, defined)) = self.scopes
+            .last_mut()
+            .and_then(|scope| scope.names.get_mut(name.lexeme.as_str()))
+        {
+            *defined = true;
+        }
+    }
+
+    /// Declares every name that the statements declare in the scope they run in, including
+    /// those inside `if`, `match`, `try` and `while` statements, which do not have scopes of
+    /// their own.
+    fn declare_block(&mut self, statements: &[Stmt]) {
+        for stmt in statements {
+            match stmt {
+                Stmt::Class { name, .. }
+                | Stmt::Enum { name, .. }
+                | Stmt::Function { name, .. }
+                | Stmt::Var { name, .. } => self.declare(name),
+                Stmt::Destructure { targets, declare: true, .. } => {
+                    for target in targets {
+                        if let Expr::Var { name, .. } = target {
+                            self.declare(name);
+                        }
+                    }
+                },
+                Stmt::Import { name, module } => {
+                    if let Some(module) = Self::load(name, module) {
+                        self.declare_block(module);
+                    }
+                },
+                Stmt::For { initializer, .. } => self.declare_block(std::slice::from_ref(&**initializer)),
+                Stmt::If { then_branch, else_branch, .. } => {
+                    self.declare_block(then_branch);
+                    if let Some(else_branch) = else_branch {
+                        self.declare_block(else_branch);
+                    }
+                },
+                Stmt::Loop { body, .. } | Stmt::While { body, .. } => self.declare_block(body),
+                Stmt::Match { arms, .. } => {
+                    for (_, body) in arms {
+                        self.declare_block(body);
+                    }
+                },
+                Stmt::Try { body, name, handler, finally } => {
+                    self.declare_block(body);
+                    if let Some(name) = name {
+                        self.declare(name);
+                    }
+                    if let Some(handler) = handler {
+                        self.declare_block(handler);
+                    }
+                    if let Some(finally) = finally {
+                        self.declare_block(finally);
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    /// Loads the module an `import` statement imports, keeping it on the statement so that it
+    /// is resolved and run as the same statements. Returns `None` if there is no such module.
+    fn load<'a>(name: &Token, module: &'a OnceCell<Rc<[Stmt]>>) -> Option<&'a [Stmt]> {
+        if module.get().is_none() {
+            let _ = module.set(stdlib::load(&name.lexeme)?.into());
+        }
+        return module.get().map(|module| &**module);
+    }
+
+    /// Fills in the slot of a variable reference or assignment to the given name.
+    fn resolve_name(&self, name: &Token, slot: &Cell<Option<Slot>>) {
+        // Whether the reference is inside a function declared in the scope being searched
+        let mut deferred = false;
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some(&(index, defined)) = scope.names.get(name.lexeme.as_str()) {
+                if defined || deferred {
+                    slot.set(Some(Slot::Local { depth, index }));
+                    return;
+                }
+            }
+            deferred |= scope.function;
+        }
+        let index = self.globals.borrow_mut().declare(&name.lexeme);
+        slot.set(Some(Slot::Global { index }));
+    }
+
+    /// Resolves the body of a function, which runs in a scope holding its parameters.
+    fn resolve_function(&mut self, params: &[Token], body: &[Stmt], locals: &OnceCell<Rc<[String]>>) {
+        self.begin_scope(params, true);
+        self.declare_block(body);
+        self.resolve(body);
+        self.end_scope(locals);
+    }
+
+    /// Resolves the variables in a statement and the statements inside of it.
+    fn resolve_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Class { name, methods, .. } => {
+                self.define(name);
+                for method in methods {
+                    let Stmt::Function { params, body, locals, .. } = method else {
+                        continue;
+                    };
+
+                    // A bound method has an environment of its own holding the instance, which
+                    // is defined in it when the method is bound
+                    match params.split_first() {
+                        Some((receiver, params)) => {
+                            self.begin_scope([receiver], true);
+                            self.resolve_function(params, body, locals);
+                            self.scopes.pop();
+                        },
+                        None => self.resolve_function(params, body, locals),
+                    }
+                }
+            },
+            Stmt::Del { target } => self.resolve_expr(target),
+            Stmt::Destructure { targets, initializer, declare } => {
+                self.resolve_expr(initializer);
+                for target in targets {
+                    if let (true, Expr::Var { name, .. }) = (declare, target) {
+                        self.define(name);
+                    }
+                    self.resolve_expr(target);
+                }
+            },
+            Stmt::Expression { expression } => self.resolve_expr(expression),
+            Stmt::For { initializer, condition, step, body, locals, .. } => {
+                self.resolve_stmt(initializer);
+
+                // The condition, step and body run in an environment of each iteration's own,
+                // which holds a copy of the loop variable
+                let names = match &**initializer {
+                    Stmt::Var { name, .. } => Some(name),
+                    _ => None,
+                };
+                self.begin_scope(names, false);
+                self.declare_block(body);
+                self.resolve_expr(condition);
+                self.resolve_expr(step);
+                self.resolve(body);
+                self.end_scope(locals);
+            },
+            Stmt::ForEach { name, iterable, body, locals, .. } => {
+                self.resolve_expr(iterable);
+
+                self.begin_scope([name], false);
+                self.declare_block(body);
+                self.resolve(body);
+                self.end_scope(locals);
+            },
+            Stmt::Function { name, params, body, locals, .. } => {
+                self.define(name);
+                self.resolve_function(params, body, locals);
+            },
+            Stmt::If { condition, then_branch, else_branch } => {
+                self.resolve_expr(condition);
+                self.resolve(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.resolve(else_branch);
+                }
+            },
+            Stmt::Loop { body, .. } => self.resolve(body),
+            Stmt::Match { subject, arms, .. } => {
+                self.resolve_expr(subject);
+                for (_, body) in arms {
+                    self.resolve(body);
+                }
+            },
+            Stmt::Print { expressions } => {
+                for expression in expressions {
+                    self.resolve_expr(expression);
+                }
+            },
+            Stmt::Raise { value, .. } => self.resolve_expr(value),
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.resolve_expr(value);
+                }
+            },
+            Stmt::Try { body, name, handler, finally } => {
+                self.resolve(body);
+                if let Some(name) = name {
+                    self.define(name);
+                }
+                if let Some(handler) = handler {
+                    self.resolve(handler);
+                }
+                if let Some(finally) = finally {
+                    self.resolve(finally);
+                }
+            },
+            Stmt::Var { name, initializer, .. } => {
+                if let Some(initializer) = initializer {
+                    self.resolve_expr(initializer);
+                }
+                self.define(name);
+            },
+            Stmt::While { condition, body, .. } => {
+                self.resolve_expr(condition);
+                self.resolve(body);
+            },
+            Stmt::Enum { name, .. } => self.define(name),
+            Stmt::Import { name, module } => {
+                // The module runs in the scope it is imported into, declaring its names there
+                if let Some(module) = Self::load(name, module) {
+                    self.resolve(module);
+                }
+            },
+            Stmt::Break { .. } | Stmt::Continue { .. } | Stmt::Pass { .. } => {},
+        }
+    }
+
+    /// Resolves the variables in an expression and the expressions inside of it.
+    fn resolve_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Assign { name, value, slot } => {
+                self.resolve_expr(value);
+                self.resolve_name(name, slot);
+            },
+            Expr::Var { name, slot } => self.resolve_name(name, slot),
+            Expr::Binary { left, right, .. }
+            | Expr::Logical { left, right, .. }
+            | Expr::Membership { left, right, .. } => {
+                self.resolve_expr(left);
+                self.resolve_expr(right);
+            },
+            Expr::Call { callee, arguments } => {
+                self.resolve_expr(callee);
+                for argument in arguments {
+                    self.resolve_expr(argument);
+                }
+            },
+            Expr::Comparison { left, comparisons } => {
+                self.resolve_expr(left);
+                for (_, right) in comparisons {
+                    self.resolve_expr(right);
+                }
+            },
+            Expr::Dict { items } => {
+                for (key, value) in items {
+                    self.resolve_expr(key);
+                    self.resolve_expr(value);
+                }
+            },
+            Expr::Grouping { expression } => self.resolve_expr(expression),
+            Expr::IndexAssign { object, index, value } => {
+                self.resolve_expr(object);
+                self.resolve_expr(index);
+                self.resolve_expr(value);
+            },
+            Expr::List { items } | Expr::Set { items } | Expr::Tuple { items } => {
+                for item in items {
+                    self.resolve_expr(item);
+                }
+            },
+            Expr::ListMethodCall { object, call, slot } => {
+                self.resolve_name(object, slot);
+                // The name of the method is looked up on the object, so only the arguments
+                // refer to variables
+                if let Expr::Call { arguments, .. } = &**call {
+                    for argument in arguments {
+                        self.resolve_expr(argument);
+                    }
+                }
+            },
+            Expr::Property { object, slot, .. } => self.resolve_name(object, slot),
+            Expr::PropertyAssign { object, value, slot, .. } => {
+                self.resolve_expr(value);
+                self.resolve_name(object, slot);
+            },
+            Expr::SliceAssign { object, start, end, value } => {
+                self.resolve_expr(object);
+                for bound in [start, end].into_iter().flatten() {
+                    self.resolve_expr(bound);
+                }
+                self.resolve_expr(value);
+            },
+            Expr::Splice { list, start, end, .. } => {
+                self.resolve_expr(list);
+                for bound in [start, end].into_iter().flatten() {
+                    self.resolve_expr(bound);
+                }
+            },
+            Expr::Spread { value, .. } => self.resolve_expr(value),
+            Expr::Unary { right, .. } => self.resolve_expr(right),
+            Expr::Alteration { name, slot, .. } => self.resolve_name(name, slot),
+            Expr::Literal { .. } => {},
+        }
+    }
+}
@@ -1,11 +1,79 @@
-use std::collections::HashMap;
+use std::{cell::Cell, collections::HashMap};
 
 use crate::{
+    complexity::FunctionMetrics,
     error::SemanticAnalyserError,
     expr::{self, Expr},
+    natives::is_builtin,
+    stdlib,
     stmt::{self, Stmt},
+    token::{Token, TokenType},
+    types::{self, Known, Signature, Type},
+    value::LiteralType,
 };
 
+/// Checks whether an expression is arithmetic whose result may have been rounded, which is any
+/// division, or any arithmetic involving a number with a fractional part.
+fn is_float_arithmetic(expr: &Expr) -> bool {
+    fn has_fraction(expr: &Expr) -> bool {
+        return match expr {
+            Expr::Literal { value: LiteralType::Num(n) } => n.fract() != 0.0,
+            Expr::Binary { left, right, .. } => has_fraction(left) || has_fraction(right),
+            Expr::Grouping { expression } => has_fraction(expression),
+            Expr::Unary { right, .. } => has_fraction(right),
+            _ => false,
+        };
+    }
+
+    return match expr {
+        Expr::Binary { operator, .. } => match operator.token_type {
+            TokenType::FSlash => true,
+            TokenType::Plus | TokenType::Minus | TokenType::Asterisk => has_fraction(expr),
+            _ => false,
+        },
+        Expr::Grouping { expression } => is_float_arithmetic(expression),
+        _ => false,
+    };
+}
+
+/// Checks whether a block can leave the loop it is the body of, which it can if it has a
+/// reachable `break` of that loop or a reachable `return`. Statements after a `break`,
+/// `continue`, `return` or `raise` in the same block can never be reached. A `break` inside a
+/// nested loop only leaves the nested loop unless it names the label of the loop being checked,
+/// and a function defined in the block is not run by it.
+fn can_exit_loop(block: &[Stmt], label: Option<&str>, nested: bool) -> bool {
+    for stmt in block {
+        let exits = match stmt {
+            Stmt::Break { label: None, .. } => !nested,
+            Stmt::Break { label: Some(target), .. } => label == Some(target.lexeme.as_str()),
+            Stmt::Return { .. } => true,
+            Stmt::If { then_branch, else_branch, .. } => {
+                can_exit_loop(then_branch, label, nested)
+                    || else_branch.as_ref().is_some_and(|branch| can_exit_loop(branch, label, nested))
+            },
+            Stmt::Match { arms, .. } => arms.iter().any(|(_, body)| can_exit_loop(body, label, nested)),
+            Stmt::Try { body, handler, finally, .. } => {
+                can_exit_loop(body, label, nested)
+                    || handler.as_ref().is_some_and(|handler| can_exit_loop(handler, label, nested))
+                    || finally.as_ref().is_some_and(|finally| can_exit_loop(finally, label, nested))
+            },
+            Stmt::For { body, .. }
+            | Stmt::ForEach { body, .. }
+            | Stmt::Loop { body, .. }
+            | Stmt::While { body, .. } => can_exit_loop(body, label, true),
+            _ => false,
+        };
+        if exits {
+            return true;
+        }
+
+        if matches!(stmt, Stmt::Continue { .. } | Stmt::Raise { .. }) {
+            return false;
+        }
+    }
+    return false;
+}
+
 /// Represents the type of a function.
 #[derive(Clone, PartialEq)]
 enum FunctionType {
@@ -13,6 +81,18 @@ enum FunctionType {
     None,
 }
 
+/// The rules used to decide whether a declaration at the top level is valid.
+///
+/// ## Variants
+/// - `Script`: Every name can only be declared once in a scope.
+/// - `Repl`: Names at the top level can be declared again, as a REPL session builds up its
+///   globals over many inputs. Redefining a function is reported as a notice.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScopingMode {
+    Script,
+    Repl,
+}
+
 /// The `SemanticAnalyser` struct is responsible for performing semantic analysis on the AST.
 /// It checks for semantic errors such as variable declarations, function declarations, and
 /// ensures that the program is semantically correct.
@@ -24,11 +104,26 @@ enum FunctionType {
 /// `symbol_tables` - A vector of hash maps, each representing a symbol table for different scopes.
 /// `curr` - An index representing the current position in the AST.
 /// `func_type` - An enum representing the type of the current function being analysed.
+/// `loop_depth` - The number of loops enclosing the statement being analysed.
+/// `labels` - The labels of the loops enclosing the statement being analysed.
+/// `types` - What is known from annotations about the types of the names in each scope.
+/// `return_type` - The name and annotated return type of the function being analysed.
+/// `mode` - The scoping rules applied to top level declarations.
+/// `notices` - Messages about redefinitions that were allowed by the scoping mode, and about code
+/// that is valid but likely to be a mistake.
+/// `function_metrics` - The complexity measurements of each function analysed so far.
 pub struct SemanticAnalyser {
     ast: Vec<Stmt>,
     symbol_tables: Vec<HashMap<String, bool>>, // Stack of HashMaps
     curr: usize,
     func_type: FunctionType,
+    loop_depth: usize,
+    labels: Vec<String>,
+    types: Vec<HashMap<String, Known>>,
+    return_type: Option<(Token, Type)>,
+    mode: ScopingMode,
+    notices: Vec<String>,
+    function_metrics: Vec<FunctionMetrics>,
 }
 
 impl SemanticAnalyser {
@@ -45,7 +140,34 @@ impl SemanticAnalyser {
             symbol_tables: vec![HashMap::<String, bool>::new()],
             curr: 0,
             func_type: FunctionType::None,
+            loop_depth: 0,
+            labels: Vec::new(),
+            types: vec![HashMap::new()],
+            return_type: None,
+            mode: ScopingMode::Script,
+            notices: Vec::new(),
+            function_metrics: Vec::new(),
+        }
+    }
+
+    /// Sets the scoping rules applied to top level declarations.
+    pub fn with_mode(mut self, mode: ScopingMode) -> Self {
+        self.mode = mode;
+        return self;
+    }
+
+    /// Declares globals that are defined outside of the program, such as the native functions
+    /// registered by the program embedding the interpreter.
+    pub fn with_globals(mut self, names: impl IntoIterator<Item = String>) -> Self {
+        for name in names {
+            self.declare_global(name);
         }
+        return self;
+    }
+
+    /// Declares a global that is defined outside of the program after the analyser is created.
+    pub fn declare_global(&mut self, name: String) {
+        self.symbol_tables[0].insert(name, true);
     }
 
     /// Runs the semantic analysis on the AST.
@@ -60,21 +182,122 @@ impl SemanticAnalyser {
         return Ok(());
     }
 
+    /// Runs the semantic analysis on another part of the program, keeping the declarations made
+    /// by the parts analysed before it. This is used by the REPL, where each input is analysed
+    /// on its own.
+    pub fn run_next(&mut self, ast: Vec<Stmt>) -> Result<(), SemanticAnalyserError> {
+        self.ast = ast;
+        return self.run();
+    }
+
+    /// Returns the notices produced so far, leaving none behind.
+    pub fn take_notices(&mut self) -> Vec<String> {
+        return std::mem::take(&mut self.notices);
+    }
+
+    /// Returns the complexity measurements of the functions analysed so far, in the order
+    /// they were defined, leaving none behind.
+    pub fn take_function_metrics(&mut self) -> Vec<FunctionMetrics> {
+        return std::mem::take(&mut self.function_metrics);
+    }
+
+    /// Checks whether a name can be declared in the current scope, which it can be if it has
+    /// not been declared before, or if it is at the top level of a REPL session.
+    fn can_redeclare(&self) -> bool {
+        return self.mode == ScopingMode::Repl && self.curr == 0;
+    }
+
+    /// Declares the functions defined directly in the body of a function before the body is
+    /// analysed, so that an inner function can refer to a sibling function defined after it, as
+    /// in mutual recursion. The functions are declared without being initialised, so they can
+    /// still be defined, and are looked up when they are called, so calling one before its
+    /// definition has run is an error at runtime. Functions at the top level of a program are
+    /// not hoisted, and must be defined before they are referred to.
+    fn declare_functions(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            if let Stmt::Function { name, .. } = statement {
+                self.symbol_tables[self.curr]
+                    .entry(name.lexeme.to_string())
+                    .or_insert(false);
+            }
+        }
+    }
+
     /// Begins a new scope by pushing a new symbol table onto the stack.
     fn begin_scope(&mut self) {
         let st: HashMap<String, bool> = HashMap::new();
         self.curr += 1;
-        self.symbol_tables.push(st)
+        self.symbol_tables.push(st);
+        self.types.push(HashMap::new());
     }
 
     /// Ends the current scope by popping the symbol table from the stack.
     fn end_scope(&mut self) {
         self.curr -= 1;
         self.symbol_tables.pop();
+        self.types.pop();
+    }
+
+    /// Returns what is known about the type of a variable from the innermost scope that it is
+    /// declared in.
+    fn known_type(&self, name: &Token) -> Option<Known> {
+        for i in (0..=self.curr).rev() {
+            if self.symbol_tables[i].contains_key(name.lexeme.as_str()) {
+                return self.types[i].get(name.lexeme.as_str()).cloned();
+            }
+        }
+        return None;
+    }
+
+    /// Records what is known about the type of a name declared in the current scope, forgetting
+    /// anything known about a name it replaces.
+    fn declare_type(&mut self, name: &Token, known: Option<Known>) {
+        match known {
+            Some(known) => self.types[self.curr].insert(name.lexeme.to_string(), known),
+            None => self.types[self.curr].remove(name.lexeme.as_str()),
+        };
+    }
+
+    /// Returns the type named by an annotation, if there is one.
+    fn resolve_type(&self, annotation: &Option<Token>) -> Result<Option<Type>, SemanticAnalyserError> {
+        let Some(annotation) = annotation else {
+            return Ok(None);
+        };
+
+        return match Type::from_annotation(annotation) {
+            Some(type_) => Ok(Some(type_)),
+            None => Err(SemanticAnalyserError::UnknownType {
+                name: annotation.lexeme.to_string(),
+                line: annotation.line,
+            }),
+        };
+    }
+
+    /// Checks that a value given to the named variable, parameter or function does not have a
+    /// different type to the one it is annotated with, where both types are known.
+    fn check_type(
+        &self,
+        name: &Token,
+        expected: Option<Type>,
+        value: &Expr,
+        line: usize,
+    ) -> Result<(), SemanticAnalyserError> {
+        let found = types::infer(value, &|name| self.known_type(name));
+        if let (Some(expected), Some(found)) = (expected, found) {
+            if expected != found {
+                return Err(SemanticAnalyserError::TypeMismatch {
+                    name: name.lexeme.to_string(),
+                    expected: expected.to_string(),
+                    found: found.to_string(),
+                    line: value.line().unwrap_or(line),
+                });
+            }
+        }
+        return Ok(());
     }
 
     /// Checks if a variable is declared in any of the symbol tables.
-    fn check_declared(&mut self, name: &String) -> bool {
+    fn check_declared(&mut self, name: &str) -> bool {
         if self.curr == 0 {
             if self.symbol_tables[0].contains_key(name) {
                 return true;
@@ -91,7 +314,7 @@ impl SemanticAnalyser {
     }
 
     /// Checks if a variable is defined in the current scope.
-    fn check_defined(&mut self, ident_name: &String) -> bool {
+    fn check_defined(&mut self, ident_name: &str) -> bool {
         if let Some(is_initialised) = self.symbol_tables[self.curr].get(ident_name) {
             return *is_initialised;
         }
@@ -99,41 +322,112 @@ impl SemanticAnalyser {
         return false;
     }
 
+    /// Checks the body of a loop, which can be broken out of by its own `break` statements and
+    /// by those naming its label.
+    fn pass_loop(&mut self, label: &Option<Token>, body: &[Stmt]) -> Result<(), SemanticAnalyserError> {
+        if let Some(label) = label {
+            if self.labels.iter().any(|name| name == label.lexeme.as_str()) {
+                return Err(SemanticAnalyserError::LabelAlreadyInUse {
+                    name: label.lexeme.to_string(),
+                });
+            }
+            self.labels.push(label.lexeme.to_string());
+        }
+
+        self.loop_depth += 1;
+        for stmt in body {
+            stmt.accept_stmt(self)?;
+        }
+        self.loop_depth -= 1;
+
+        if label.is_some() {
+            self.labels.pop();
+        }
+
+        return Ok(());
+    }
+
+    /// Checks that the label of a `break` or `continue` statement, if it has one, is the label of
+    /// an enclosing loop.
+    fn check_label(&self, label: &Option<Token>) -> Result<(), SemanticAnalyserError> {
+        match label {
+            Some(label) if !self.labels.iter().any(|name| name == label.lexeme.as_str()) => {
+                return Err(SemanticAnalyserError::UndefinedLabel {
+                    name: label.lexeme.to_string(),
+                });
+            },
+            _ => return Ok(()),
+        }
+    }
+
     /// Checks and resolves a function declaration.
     fn pass_function(&mut self, stmt: &Stmt, declaration: FunctionType) -> Result<(), SemanticAnalyserError> {
         match stmt {
-            Stmt::Function { name, params, body } => {
+            Stmt::Function { name, params, param_types, return_type, body, .. } => {
                 let is_initialised = true;
+
+                let mut signature = Signature {
+                    params: Vec::new(),
+                    returns: self.resolve_type(return_type)?,
+                };
+                for param_type in param_types {
+                    signature.params.push(self.resolve_type(param_type)?);
+                }
                 
-                if self.symbol_tables[self.curr].contains_key(&name.lexeme) {
-                    return Err(SemanticAnalyserError::VariableAlreadyAssignedInScope {
-                        name: name.lexeme.clone(),
-                    });
+                if self.check_defined(&name.lexeme) {
+                    if !self.can_redeclare() {
+                        return Err(SemanticAnalyserError::VariableAlreadyAssignedInScope {
+                            name: name.lexeme.to_string(),
+                        });
+                    }
+                    self.notices.push(format!("Redefined '{}'", name.lexeme));
                 }
-                self.symbol_tables[self.curr].insert(name.lexeme.clone(), is_initialised);
+                self.symbol_tables[self.curr].insert(name.lexeme.to_string(), is_initialised);
+                self.function_metrics.push(FunctionMetrics::measure(name, body));
+
+                // Only annotated functions are recorded, so unannotated code stays dynamic
+                let is_annotated = signature.returns.is_some()
+                    || signature.params.iter().any(Option::is_some);
+                let enclosing_return_type = self.return_type.take();
+                self.return_type = signature.returns.map(|returns| (name.clone(), returns));
+                self.declare_type(name, is_annotated.then(|| Known::Function(signature.clone())));
 
                 self.begin_scope();
 
                 let is_closure = self.func_type.clone() == FunctionType::Function;
                 self.func_type = declaration;
 
+                // Loops outside of the function cannot be broken out of from inside it
+                let enclosing_loop_depth = self.loop_depth;
+                self.loop_depth = 0;
+                let enclosing_labels = std::mem::take(&mut self.labels);
+
                 for param in params {
                     let is_initialised: bool = true;
 
-                    if self.symbol_tables[self.curr].contains_key(&param.lexeme) {
+                    if self.symbol_tables[self.curr].contains_key(param.lexeme.as_str()) {
                         return Err(SemanticAnalyserError::VariableAlreadyAssignedInScope {
-                            name: param.lexeme.clone(),
+                            name: param.lexeme.to_string(),
                         });
                     }
-                    self.symbol_tables[self.curr].insert(param.lexeme.clone(), is_initialised);
+                    self.symbol_tables[self.curr].insert(param.lexeme.to_string(), is_initialised);
                 }
+                for (param, param_type) in params.iter().zip(&signature.params) {
+                    self.declare_type(param, param_type.map(Known::Value));
+                }
+
+                self.declare_functions(body);
 
-                for statement in body {
+                for statement in body.iter() {
                     statement.accept_stmt(self)?;
                 }
 
                 self.end_scope();
 
+                self.loop_depth = enclosing_loop_depth;
+                self.labels = enclosing_labels;
+                self.return_type = enclosing_return_type;
+
                 if !is_closure {
                     self.func_type = FunctionType::None;
                 }
@@ -142,7 +436,7 @@ impl SemanticAnalyser {
             },
             _ => {
                 return Err(SemanticAnalyserError::DifferentStatement {
-                    stmt: stmt.clone(),
+                    stmt: Box::new(stmt.clone()),
                     expected: "function".to_string(),
                 })
             }
@@ -158,11 +452,11 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
                     return Ok(());
                 }
                 return Err(SemanticAnalyserError::VariableNotFound {
-                    name: name.lexeme.clone(),
+                    name: name.lexeme.to_string(),
                 });
             }
             _ => return Err(SemanticAnalyserError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "alteration".to_string(),
             }),
         }
@@ -170,19 +464,22 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
 
     fn visit_assign_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
         match expr {
-            Expr::Assign { name, value } => {
+            Expr::Assign { name, value, .. } => {
                 value.accept_expr(self)?;
 
                 if self.check_declared(&name.lexeme) {
+                    if let Some(Known::Value(expected)) = self.known_type(name) {
+                        self.check_type(name, Some(expected), value, name.line)?;
+                    }
                     return Ok(());
                 }
 
                 return Err(SemanticAnalyserError::VariableNotFound {
-                    name: name.lexeme.clone(),
+                    name: name.lexeme.to_string(),
                 });
             }
             _ => return Err(SemanticAnalyserError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "assign".to_string(),
             }),
         }
@@ -190,13 +487,24 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
 
     fn visit_binary_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
         match expr {
-            Expr::Binary { left, operator: _, right } => {
+            Expr::Binary { left, operator, right } => {
                 left.accept_expr(self)?;
                 right.accept_expr(self)?;
+
+                if matches!(operator.token_type, TokenType::EqualEqual | TokenType::BangEqual)
+                    && (is_float_arithmetic(left) || is_float_arithmetic(right))
+                {
+                    self.notices.push(format!(
+                        "Comparing the result of floating point arithmetic with '{}' on line {} \
+                        may not behave as expected, as in 0.1 + 0.2 == 0.3 being false. \
+                        Consider using approxEqual(a, b, eps) instead",
+                        operator.lexeme, operator.line
+                    ));
+                }
                 return Ok(());
             }
             _ => return Err(SemanticAnalyserError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "binary".to_string(),
             }),
         }
@@ -211,15 +519,62 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
                     argument.accept_expr(self)?;
                 }
 
+                // The arguments of a call to an annotated function are checked against the
+                // types of its parameters, up to the first spread argument, after which the
+                // parameter each argument is passed to is not known
+                if let Expr::Var { name, .. } = &**callee {
+                    if let Some(Known::Function(signature)) = self.known_type(name) {
+                        let positional = arguments
+                            .iter()
+                            .take_while(|argument| !matches!(argument, Expr::Spread { .. }));
+                        for (argument, expected) in positional.zip(signature.params) {
+                            self.check_type(name, expected, argument, name.line)?;
+                        }
+                    }
+                }
+
                 return Ok(());
             }
             _ => return Err(SemanticAnalyserError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "call".to_string(),
             }),
         }
     }
 
+    fn visit_comparison_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
+        match expr {
+            Expr::Comparison { left, comparisons } => {
+                left.accept_expr(self)?;
+                for (_, right) in comparisons {
+                    right.accept_expr(self)?;
+                }
+                return Ok(());
+            }
+            _ => return Err(SemanticAnalyserError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "comparison".to_string(),
+            }),
+        }
+    }
+
+    fn visit_dict_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
+        match expr {
+            Expr::Dict { items } => {
+                for (key, value) in items {
+                    key.accept_expr(self)?;
+                    value.accept_expr(self)?;
+                }
+
+                return Ok(());
+            },
+            _ => return Err(SemanticAnalyserError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "dict".to_string(),
+            }),
+        }
+    }
+
     fn visit_grouping_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
         match expr {
             Expr::Grouping { expression } => {
@@ -227,12 +582,28 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
                 return Ok(());
             },
             _ => return Err(SemanticAnalyserError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "grouping".to_string(),
             }),
         }
     }
 
+    fn visit_indexassign_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
+        match expr {
+            Expr::IndexAssign { object, index, value } => {
+                object.accept_expr(self)?;
+                index.accept_expr(self)?;
+                value.accept_expr(self)?;
+
+                return Ok(());
+            },
+            _ => return Err(SemanticAnalyserError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "indexassign".to_string(),
+            }),
+        }
+    }
+
     fn visit_list_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
         match expr {
             Expr::List { items } => {
@@ -243,7 +614,7 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
                 return Ok(());
             },
             _ => return Err(SemanticAnalyserError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "list".to_string(),
             }),
         }
@@ -251,13 +622,23 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
 
     fn visit_listmethodcall_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
         match expr {
-            Expr::ListMethodCall { object, call } => {
-                Expr::Var { name: object.clone() }.accept_expr(self)?;
+            Expr::ListMethodCall { object, call, .. } => {
+                Expr::Var { name: object.clone(), slot: Cell::new(None) }.accept_expr(self)?;
+                // The name of a method is looked up on the object when it is called, so only the
+                // arguments refer to variables
+                if let Expr::Call { callee, arguments } = &**call {
+                    if let Expr::Var { .. } = &**callee {
+                        for argument in arguments {
+                            argument.accept_expr(self)?;
+                        }
+                        return Ok(());
+                    }
+                }
                 call.accept_expr(self)?;
                 return Ok(());
             },
             _ => return Err(SemanticAnalyserError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "listmethodcall".to_string(),
             }),
         }
@@ -267,7 +648,7 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
         match expr {
             Expr::Literal { .. } => return Ok(()),
             _ => return Err(SemanticAnalyserError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "literal".to_string(),
             }),
         }
@@ -282,7 +663,7 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
                 return Ok(());
             }
             _ => return Err(SemanticAnalyserError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "logical".to_string(),
             }),
         }
@@ -297,17 +678,80 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
                 return Ok(());
             },
             _ => return Err(SemanticAnalyserError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "membership".to_string(),
             }),
         }
     }
 
+    fn visit_property_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
+        match expr {
+            Expr::Property { object, .. } => {
+                return Expr::Var { name: object.clone(), slot: Cell::new(None) }.accept_expr(self);
+            },
+            _ => return Err(SemanticAnalyserError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "property".to_string(),
+            }),
+        }
+    }
+
+    fn visit_propertyassign_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
+        match expr {
+            Expr::PropertyAssign { object, value, .. } => {
+                Expr::Var { name: object.clone(), slot: Cell::new(None) }.accept_expr(self)?;
+                value.accept_expr(self)?;
+
+                return Ok(());
+            },
+            _ => return Err(SemanticAnalyserError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "propertyassign".to_string(),
+            }),
+        }
+    }
+
+    fn visit_set_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
+        match expr {
+            Expr::Set { items } => {
+                for item in items {
+                    item.accept_expr(self)?;
+                }
+
+                return Ok(());
+            },
+            _ => return Err(SemanticAnalyserError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "set".to_string(),
+            }),
+        }
+    }
+
+    fn visit_sliceassign_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
+        match expr {
+            Expr::SliceAssign { object, start, end, value } => {
+                object.accept_expr(self)?;
+                if let Some(start) = start {
+                    start.accept_expr(self)?;
+                }
+                if let Some(end) = end {
+                    end.accept_expr(self)?;
+                }
+                value.accept_expr(self)?;
+
+                return Ok(());
+            },
+            _ => return Err(SemanticAnalyserError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "sliceassign".to_string(),
+            }),
+        }
+    }
+
     fn visit_splice_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
         match expr {
             Expr::Splice { list, is_splice: _, start, end } => {
-                let var = Expr::Var { name: list.clone() };
-                var.accept_expr(self)?;
+                list.accept_expr(self)?;
                 if let Some(start) = start {
                     start.accept_expr(self)?;
                 }
@@ -318,12 +762,42 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
                 return Ok(());
             },
             _ => return Err(SemanticAnalyserError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "splice".to_string(),
             }),
         }
     }
 
+    fn visit_spread_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
+        match expr {
+            Expr::Spread { value, .. } => {
+                value.accept_expr(self)?;
+
+                return Ok(());
+            },
+            _ => return Err(SemanticAnalyserError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "spread".to_string(),
+            }),
+        }
+    }
+
+    fn visit_tuple_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
+        match expr {
+            Expr::Tuple { items } => {
+                for item in items {
+                    item.accept_expr(self)?;
+                }
+
+                return Ok(());
+            },
+            _ => return Err(SemanticAnalyserError::DifferentExpression {
+                expr: Box::new(expr.clone()),
+                expected: "tuple".to_string(),
+            }),
+        }
+    }
+
     fn visit_unary_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
         match expr {
             Expr::Unary { operator: _, right } => {
@@ -331,7 +805,7 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
                 return Ok(());
             }
             _ => return Err(SemanticAnalyserError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "unary".to_string(),
             }),
         }
@@ -339,24 +813,30 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
 
     fn visit_var_expr(&mut self, expr: &Expr) -> Result<(), SemanticAnalyserError> {
         match expr {
-            Expr::Var { name } => {
+            Expr::Var { name, .. } => {
                 if self.check_declared(&name.lexeme) {
                     return Ok(());
                 }
 
-                let keywords = vec!["hash", "clock", "push", "pop", "remove",
-                "insertAt", "index", "len", "sort"];
+                if is_builtin(&name.lexeme) {
+                    return Ok(());
+                }
+
+                let methods = ["push", "pop", "remove", "insertAt", "index", "len", "sort", "join",
+                "keys", "values", "has", "add", "contains", "union", "intersection", "upper", "lower",
+                "trim", "split", "chars", "replace", "startsWith", "endsWith", "pushFront", "popFront",
+                "peek", "peekFront"];
 
-                if keywords.contains(&name.lexeme.as_str()) {
+                if methods.contains(&name.lexeme.as_str()) {
                     return Ok(());
                 }
 
                 return Err(SemanticAnalyserError::VariableNotFound {
-                    name: name.lexeme.clone(),
+                    name: name.lexeme.to_string(),
                 });
             },
             _ => return Err(SemanticAnalyserError::DifferentExpression {
-                expr: expr.clone(),
+                expr: Box::new(expr.clone()),
                 expected: "var".to_string(),
             }),
         }
@@ -364,6 +844,184 @@ impl expr::ExprVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
 }
 
 impl stmt::StmtVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
+    fn visit_break_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
+        match stmt {
+            Stmt::Break { label, .. } => {
+                if self.loop_depth == 0 {
+                    return Err(SemanticAnalyserError::CannotBreakOutsideLoop);
+                }
+
+                return self.check_label(label);
+            }
+            _ => return Err(SemanticAnalyserError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "break".to_string(),
+            }),
+        }
+    }
+
+    fn visit_class_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
+        match stmt {
+            Stmt::Class { name, methods } => {
+                if self.check_defined(&name.lexeme) && !self.can_redeclare() {
+                    return Err(SemanticAnalyserError::VariableAlreadyAssignedInScope {
+                        name: name.lexeme.to_string(),
+                    });
+                }
+                self.symbol_tables[self.curr].insert(name.lexeme.to_string(), true);
+                self.declare_type(name, None);
+
+                // The methods are declared in a scope of their own, so that two methods cannot
+                // share a name but a method can share a name with a variable outside the class
+                self.begin_scope();
+                for method in methods {
+                    if let Stmt::Function { name, params, .. } = method {
+                        if params.is_empty() {
+                            return Err(SemanticAnalyserError::MethodWithoutSelf {
+                                name: name.lexeme.to_string(),
+                            });
+                        }
+                    }
+                    self.pass_function(method, FunctionType::Function)?;
+                }
+                self.end_scope();
+
+                return Ok(());
+            },
+            _ => return Err(SemanticAnalyserError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "class".to_string(),
+            }),
+        }
+    }
+
+    fn visit_enum_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
+        match stmt {
+            Stmt::Enum { name, variants } => {
+                if self.check_defined(&name.lexeme) && !self.can_redeclare() {
+                    return Err(SemanticAnalyserError::VariableAlreadyAssignedInScope {
+                        name: name.lexeme.to_string(),
+                    });
+                }
+
+                for (i, variant) in variants.iter().enumerate() {
+                    if variants[..i].iter().any(|other| other.lexeme == variant.lexeme) {
+                        return Err(SemanticAnalyserError::DuplicateEnumVariant {
+                            enum_name: name.lexeme.to_string(),
+                            name: variant.lexeme.to_string(),
+                            line: variant.line,
+                        });
+                    }
+                }
+
+                self.symbol_tables[self.curr].insert(name.lexeme.to_string(), true);
+                self.declare_type(name, None);
+
+                return Ok(());
+            },
+            _ => return Err(SemanticAnalyserError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "enum".to_string(),
+            }),
+        }
+    }
+
+    fn visit_continue_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
+        match stmt {
+            Stmt::Continue { label, .. } => {
+                if self.loop_depth == 0 {
+                    return Err(SemanticAnalyserError::CannotContinueOutsideLoop);
+                }
+
+                return self.check_label(label);
+            }
+            _ => return Err(SemanticAnalyserError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "continue".to_string(),
+            }),
+        }
+    }
+
+    fn visit_del_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
+        match stmt {
+            Stmt::Del { target } => {
+                let Expr::Var { name, .. } = target else {
+                    return target.accept_expr(self);
+                };
+                if self.symbol_tables[self.curr].remove(name.lexeme.as_str()).is_none() {
+                    return Err(SemanticAnalyserError::CannotDeleteUndeclaredVariable {
+                        name: name.lexeme.to_string(),
+                    });
+                }
+                self.declare_type(name, None);
+
+                return Ok(());
+            }
+            _ => {
+                return Err(SemanticAnalyserError::DifferentStatement {
+                    stmt: Box::new(stmt.clone()),
+                    expected: "del".to_string(),
+                });
+            }
+        }
+    }
+
+    fn visit_destructure_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
+        match stmt {
+            Stmt::Destructure { targets, initializer, declare } => {
+                initializer.accept_expr(self)?;
+
+                // Assigning to several targets only requires each of the variables to exist
+                if !declare {
+                    for target in targets {
+                        match target {
+                            Expr::Var { name, .. } if !self.check_declared(&name.lexeme) => {
+                                return Err(SemanticAnalyserError::VariableNotFound {
+                                    name: name.lexeme.to_string(),
+                                });
+                            },
+                            Expr::Var { .. } => {},
+                            _ => target.accept_expr(self)?,
+                        }
+                    }
+                    return Ok(());
+                }
+
+                // The parser only allows variables to be declared
+                let names: Vec<&Token> = targets
+                    .iter()
+                    .filter_map(|target| match target {
+                        Expr::Var { name, .. } => Some(name),
+                        _ => None,
+                    })
+                    .collect();
+
+                for (i, name) in names.iter().enumerate() {
+                    let repeated = names[..i].iter().any(|n| n.lexeme == name.lexeme);
+                    let redeclared = self.check_defined(&name.lexeme) && !self.can_redeclare();
+                    if repeated || redeclared {
+                        return Err(SemanticAnalyserError::VariableAlreadyAssignedInScope {
+                            name: name.lexeme.to_string(),
+                        });
+                    }
+                }
+
+                for name in names {
+                    self.symbol_tables[self.curr].insert(name.lexeme.to_string(), true);
+                    self.declare_type(name, None);
+                }
+
+                return Ok(());
+            }
+            _ => {
+                return Err(SemanticAnalyserError::DifferentStatement {
+                    stmt: Box::new(stmt.clone()),
+                    expected: "destructure".to_string(),
+                });
+            }
+        }
+    }
+
     fn visit_expression_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
         match stmt {
             Stmt::Expression { expression } => {
@@ -372,7 +1030,7 @@ impl stmt::StmtVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
             }
             _ => {
                 return Err(SemanticAnalyserError::DifferentStatement {
-                    stmt: stmt.clone(),
+                    stmt: Box::new(stmt.clone()),
                     expected: "expression".to_string(),
                 })
             }
@@ -382,30 +1040,57 @@ impl stmt::StmtVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
     fn visit_for_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
         match stmt {
             Stmt::For {
+                label,
                 initializer,
                 condition,
                 step,
                 body,
+                ..
             } => {
                 initializer.accept_stmt(self)?;
 
+                // Only the initializer is declared outside of the loop
+                self.begin_scope();
+
                 condition.accept_expr(self)?;
 
                 step.accept_expr(self)?;
 
-                for stmt in body {
-                    stmt.accept_stmt(self)?;
-                }
+                self.pass_loop(label, body)?;
+
+                self.end_scope();
 
                 return Ok(());
             }
             _ => return Err(SemanticAnalyserError::DifferentStatement {
-                stmt: stmt.clone(),
+                stmt: Box::new(stmt.clone()),
                 expected: "for".to_string(),
             }),
         }
     }
 
+    fn visit_foreach_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
+        match stmt {
+            Stmt::ForEach { label, name, iterable, body, .. } => {
+                iterable.accept_expr(self)?;
+
+                // The element is only defined inside the loop
+                self.begin_scope();
+                self.symbol_tables[self.curr].insert(name.lexeme.to_string(), true);
+
+                self.pass_loop(label, body)?;
+
+                self.end_scope();
+
+                return Ok(());
+            }
+            _ => return Err(SemanticAnalyserError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "for each".to_string(),
+            }),
+        }
+    }
+
     fn visit_function_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
         return self.pass_function(stmt, FunctionType::Function);
     }
@@ -420,72 +1105,213 @@ impl stmt::StmtVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
                 }
 
                 if let Some(e_branch) = else_branch {
-                    e_branch.accept_stmt(self)?;
+                    for stmt in e_branch {
+                        stmt.accept_stmt(self)?;
+                    }
                 };
 
                 return Ok(());
             }
             _ => return Err(SemanticAnalyserError::DifferentStatement {
-                stmt: stmt.clone(),
+                stmt: Box::new(stmt.clone()),
                 expected: "if".to_string(),
             }),
         }
     }
 
+    fn visit_import_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
+        match stmt {
+            Stmt::Import { name, .. } => {
+                let Some(module) = stdlib::load(&name.lexeme) else {
+                    return Err(SemanticAnalyserError::UnknownModule {
+                        name: name.lexeme.to_string(),
+                        line: name.line,
+                    });
+                };
+
+                // The module is checked by its own tests, so only the names it declares are needed
+                for export in stdlib::exports(&module) {
+                    if self.check_defined(&export.lexeme) {
+                        if !self.can_redeclare() {
+                            return Err(SemanticAnalyserError::VariableAlreadyAssignedInScope {
+                                name: export.lexeme.to_string(),
+                            });
+                        }
+                        self.notices.push(format!("Redefined '{}'", export.lexeme));
+                    }
+                    self.symbol_tables[self.curr].insert(export.lexeme.to_string(), true);
+                    self.declare_type(export, None);
+                }
+
+                return Ok(());
+            }
+            _ => return Err(SemanticAnalyserError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "import".to_string(),
+            }),
+        }
+    }
+
+    fn visit_loop_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
+        match stmt {
+            Stmt::Loop { keyword, label, body } => {
+                let name = label.as_ref().map(|label| label.lexeme.as_str());
+                if !can_exit_loop(body, name, false) {
+                    self.notices.push(format!(
+                        "The loop on line {} has no reachable 'break' or 'return', so it will \
+                        never stop",
+                        keyword.line
+                    ));
+                }
+
+                self.pass_loop(label, body)?;
+
+                return Ok(());
+            }
+            _ => return Err(SemanticAnalyserError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "loop".to_string(),
+            }),
+        }
+    }
+
+    fn visit_match_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
+        match stmt {
+            Stmt::Match { subject, arms, .. } => {
+                subject.accept_expr(self)?;
+
+                for (_, body) in arms {
+                    for stmt in body {
+                        stmt.accept_stmt(self)?;
+                    }
+                }
+
+                return Ok(());
+            }
+            _ => return Err(SemanticAnalyserError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "match".to_string(),
+            }),
+        }
+    }
+
+    fn visit_pass_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
+        match stmt {
+            Stmt::Pass { .. } => return Ok(()),
+            _ => {
+                return Err(SemanticAnalyserError::DifferentStatement {
+                    stmt: Box::new(stmt.clone()),
+                    expected: "pass".to_string(),
+                });
+            }
+        }
+    }
+
     fn visit_print_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
         match stmt {
-            Stmt::Print { expression } => {
-                expression.accept_expr(self)?;
+            Stmt::Print { expressions } => {
+                for expression in expressions {
+                    expression.accept_expr(self)?;
+                }
                 return Ok(());
             }
             _ => return Err(SemanticAnalyserError::DifferentStatement {
-                stmt: stmt.clone(),
+                stmt: Box::new(stmt.clone()),
                 expected: "print".to_string(),
             }),
         }
     }
 
+    fn visit_raise_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
+        match stmt {
+            Stmt::Raise { keyword: _, value } => return value.accept_expr(self),
+            _ => return Err(SemanticAnalyserError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "raise".to_string(),
+            }),
+        }
+    }
+
     fn visit_return_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
         match stmt {
-            Stmt::Return { keyword: _, value } => {
+            Stmt::Return { keyword, value } => {
                 if self.func_type == FunctionType::None {
                     return Err(SemanticAnalyserError::CannotReturnOutsideFunction);
                 }
 
-                if let Some(v) = value {
-                    v.accept_expr(self)?;
-                };
+                // A return without a value returns null
+                let null = Expr::Literal { value: LiteralType::Null };
+                let v = value.as_ref().unwrap_or(&null);
+                v.accept_expr(self)?;
+                if let Some((name, expected)) = &self.return_type {
+                    self.check_type(name, Some(*expected), v, keyword.line)?;
+                }
 
                 return Ok(());
             }
             _ => return Err(SemanticAnalyserError::DifferentStatement {
-                stmt: stmt.clone(),
+                stmt: Box::new(stmt.clone()),
                 expected: "return".to_string(),
             }),
         }
     }
 
+    fn visit_try_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
+        match stmt {
+            Stmt::Try { body, name, handler, finally } => {
+                for stmt in body {
+                    stmt.accept_stmt(self)?;
+                }
+
+                if let Some(handler) = handler {
+                    // The error message is bound to the name as though it were assigned
+                    if let Some(name) = name {
+                        self.symbol_tables[self.curr].insert(name.lexeme.to_string(), true);
+                    }
+                    for stmt in handler {
+                        stmt.accept_stmt(self)?;
+                    }
+                }
+
+                if let Some(finally) = finally {
+                    for stmt in finally {
+                        stmt.accept_stmt(self)?;
+                    }
+                }
+
+                return Ok(());
+            }
+            _ => return Err(SemanticAnalyserError::DifferentStatement {
+                stmt: Box::new(stmt.clone()),
+                expected: "try".to_string(),
+            }),
+        }
+    }
+
     fn visit_var_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
         match stmt {
-            Stmt::Var { name, initializer } => {
-                if self.check_defined(&name.lexeme) {
+            Stmt::Var { name, annotation, initializer } => {
+                if self.check_defined(&name.lexeme) && !self.can_redeclare() {
                     return Err(SemanticAnalyserError::VariableAlreadyAssignedInScope {
-                        name: name.lexeme.clone(),
+                        name: name.lexeme.to_string(),
                     });
                 }
 
+                let expected = self.resolve_type(annotation)?;
                 if let Some(x) = initializer {
                     x.accept_expr(self)?;
+                    self.check_type(name, expected, x, name.line)?;
                 }
 
                 let is_initialised = initializer.is_some();
-                self.symbol_tables[self.curr].insert(name.lexeme.clone(), is_initialised);
+                self.symbol_tables[self.curr].insert(name.lexeme.to_string(), is_initialised);
+                self.declare_type(name, expected.map(Known::Value));
 
                 return Ok(());
             }
             _ => {
                 return Err(SemanticAnalyserError::DifferentStatement {
-                    stmt: stmt.clone(),
+                    stmt: Box::new(stmt.clone()),
                     expected: "var".to_string(),
                 });
             }
@@ -494,18 +1320,16 @@ impl stmt::StmtVisitor<Result<(), SemanticAnalyserError>> for SemanticAnalyser {
 
     fn visit_while_stmt(&mut self, stmt: &Stmt) -> Result<(), SemanticAnalyserError> {
         match stmt {
-            Stmt::While { condition, body } => {
+            Stmt::While { label, condition, body } => {
                 condition.accept_expr(self)?;
                 
-                for stmt in body {
-                    stmt.accept_stmt(self)?;
-                }
+                self.pass_loop(label, body)?;
 
                 return Ok(());
             }
             _ => {
                 return Err(SemanticAnalyserError::DifferentStatement {
-                    stmt: stmt.clone(),
+                    stmt: Box::new(stmt.clone()),
                     expected: "while".to_string(),
                 });
             }
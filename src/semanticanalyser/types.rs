@@ -0,0 +1,154 @@
+//! The `types` module defines the types that variables, parameters and return values can be
+//! annotated with, and works out the type of an expression where it is obvious from the source.
+//!
+//! ## Overview
+//!
+//! Annotations are optional, e.g. `let x: num = 5;` or `def f(a: str) -> num:`, and the
+//! semantic analyser uses them to report values that can be seen to have the wrong type without
+//! running the program. The type of an expression is only known when it is a literal, a
+//! variable or parameter with an annotation, a call to a function with an annotated return
+//! type, or an operation on values whose types are known. Anything else is left to be checked
+//! when the program runs, so code without annotations behaves exactly as it would otherwise.
+
+use std::fmt;
+
+use crate::{
+    expr::Expr,
+    token::{Token, TokenType},
+    value::LiteralType,
+};
+
+/// A type that can be written in an annotation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Type {
+    Bool,
+    Deque,
+    Dict,
+    Function,
+    List,
+    Null,
+    Num,
+    Set,
+    Str,
+    Tuple,
+}
+
+impl Type {
+    /// Returns the type named by an annotation, if there is a type with that name.
+    pub fn from_annotation(annotation: &Token) -> Option<Self> {
+        return match annotation.lexeme.as_str() {
+            "bool" => Some(Type::Bool),
+            "deque" => Some(Type::Deque),
+            "dict" => Some(Type::Dict),
+            "function" => Some(Type::Function),
+            "list" => Some(Type::List),
+            "null" => Some(Type::Null),
+            "num" => Some(Type::Num),
+            "set" => Some(Type::Set),
+            "str" => Some(Type::Str),
+            "tuple" => Some(Type::Tuple),
+            _ => None,
+        };
+    }
+}
+
+impl fmt::Display for Type {
+    /// Writes the type as it is written in an annotation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Type::Bool => "bool",
+            Type::Deque => "deque",
+            Type::Dict => "dict",
+            Type::Function => "function",
+            Type::List => "list",
+            Type::Null => "null",
+            Type::Num => "num",
+            Type::Set => "set",
+            Type::Str => "str",
+            Type::Tuple => "tuple",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The annotated types of a function's parameters and return value, where they are annotated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signature {
+    pub params: Vec<Option<Type>>,
+    pub returns: Option<Type>,
+}
+
+/// What is known about the type of a declared name.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Known {
+    Value(Type),
+    Function(Signature),
+}
+
+/// Works out the type of an expression, if it is obvious from the source. The `lookup` function
+/// gives what is known about the type of a variable.
+pub fn infer(expr: &Expr, lookup: &impl Fn(&Token) -> Option<Known>) -> Option<Type> {
+    return match expr {
+        Expr::Literal { value } => Some(match value {
+            LiteralType::Num(_) => Type::Num,
+            LiteralType::Str(_) => Type::Str,
+            LiteralType::True | LiteralType::False => Type::Bool,
+            LiteralType::Null => Type::Null,
+        }),
+        Expr::List { .. } => Some(Type::List),
+        Expr::Dict { .. } => Some(Type::Dict),
+        Expr::Set { .. } => Some(Type::Set),
+        Expr::Tuple { .. } => Some(Type::Tuple),
+        Expr::Comparison { .. } | Expr::Membership { .. } => Some(Type::Bool),
+        Expr::Grouping { expression } => infer(expression, lookup),
+        Expr::Assign { value, .. } => infer(value, lookup),
+        Expr::Var { name, .. } => match lookup(name)? {
+            Known::Value(type_) => Some(type_),
+            Known::Function(_) => Some(Type::Function),
+        },
+        Expr::Call { callee, .. } => match &**callee {
+            Expr::Var { name, .. } => match lookup(name)? {
+                Known::Function(signature) => signature.returns,
+                Known::Value(_) => None,
+            },
+            _ => None,
+        },
+        Expr::Unary { operator, right } => match operator.token_type {
+            TokenType::Bang => Some(Type::Bool),
+            TokenType::Minus if infer(right, lookup) == Some(Type::Num) => Some(Type::Num),
+            _ => None,
+        },
+        Expr::Binary { left, operator, right } => {
+            if matches!(
+                operator.token_type,
+                TokenType::EqualEqual
+                    | TokenType::BangEqual
+                    | TokenType::Greater
+                    | TokenType::GreaterEqual
+                    | TokenType::Less
+                    | TokenType::LessEqual
+            ) {
+                return Some(Type::Bool);
+            }
+
+            let operands = (infer(left, lookup)?, infer(right, lookup)?);
+            match (operator.token_type, operands) {
+                (TokenType::Plus, (Type::Str, Type::Str)) => Some(Type::Str),
+                (TokenType::Plus, (Type::List, Type::List)) => Some(Type::List),
+                (TokenType::Asterisk, (Type::List, Type::Num) | (Type::Num, Type::List)) => {
+                    Some(Type::List)
+                },
+                (
+                    TokenType::Plus
+                    | TokenType::Minus
+                    | TokenType::Asterisk
+                    | TokenType::FSlash
+                    | TokenType::FSlashFSlash,
+                    (Type::Num, Type::Num),
+                ) => Some(Type::Num),
+                _ => None,
+            }
+        },
+        _ => None,
+    };
+}
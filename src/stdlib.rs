@@ -0,0 +1,52 @@
+//! The `stdlib` module embeds the standard library, a set of modules written in Pyru itself in
+//! the `stdlib/` directory, which a program loads with an `import` statement.
+//!
+//! ## Overview
+//!
+//! Each module is compiled into the interpreter as source code and is only lexed and parsed
+//! when a program imports it, so a program pays nothing for the modules it does not use. Every
+//! function, class and variable declared at the top level of a module is declared in the scope
+//! the module is imported into. A new module must be added both to the directory and to
+//! `MODULES`.
+
+use crate::{lexer::Lexer, parser::Parser, stmt::Stmt, token::Token};
+
+/// The name and source code of every module, in alphabetical order of their names.
+const MODULES: [(&str, &str); 3] = [
+    ("lists", include_str!("../stdlib/lists.pyru")),
+    ("math", include_str!("../stdlib/math.pyru")),
+    ("strings", include_str!("../stdlib/strings.pyru")),
+];
+
+/// Returns the names of every module, in alphabetical order.
+pub fn modules() -> Vec<&'static str> {
+    return MODULES.iter().map(|(name, _)| *name).collect();
+}
+
+/// Returns the source code of the module with the given name, if there is one.
+pub fn source(name: &str) -> Option<&'static str> {
+    return MODULES.iter().find(|(module, _)| *module == name).map(|(_, source)| *source);
+}
+
+/// Lexes and parses the module with the given name, returning its statements, or `None` if
+/// there is no such module. The modules are tested to parse, so a module that fails to is
+/// treated as missing rather than reported as an error in the importing program.
+pub fn load(name: &str) -> Option<Vec<Stmt>> {
+    let tokens = Lexer::new(source(name)?.to_string(), 2).run().ok()?;
+    return Parser::new(tokens).parse().ok();
+}
+
+/// Returns the names declared at the top level of a module, which are the names importing it
+/// declares.
+pub fn exports(statements: &[Stmt]) -> Vec<&Token> {
+    return statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Stmt::Class { name, .. }
+            | Stmt::Enum { name, .. }
+            | Stmt::Function { name, .. }
+            | Stmt::Var { name, .. } => Some(name),
+            _ => None,
+        })
+        .collect();
+}
@@ -0,0 +1,438 @@
+use std::{collections::HashMap, thread, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::Engine,
+    error::{EngineError, EvaluatorError},
+    evaluator::{
+        max_depth_for, Evaluator, Limit, NativePolicy, CALL_STACK_SIZE, DEFAULT_STACK_SIZE,
+        MAX_CALL_DEPTH,
+    },
+    marshal::{from_value, to_value},
+    number::NumberFormat,
+    value::{LiteralType, Value},
+};
+
+fn double(_: &mut Evaluator, args: Vec<Value>) -> Result<Value, EvaluatorError> {
+    return match &args[0] {
+        Value::Literal(LiteralType::Num(n)) => Ok(Value::Literal(LiteralType::Num(n * 2.0))),
+        _ => Err(EvaluatorError::ExpectedNumber),
+    };
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Order {
+    item: String,
+    quantity: u32,
+    price: f64,
+    tags: Vec<String>,
+    discount: Option<f64>,
+}
+
+#[test]
+fn test_engine() {
+    // Tests that scripts share their globals
+    let mut engine = Engine::new();
+    assert_eq!(engine.run("let a = 5;").unwrap(), Vec::<String>::new());
+    assert_eq!(engine.run("print(a * 2);").unwrap(), vec!["10".to_string()]);
+
+    // Tests that each phase reports its errors
+    assert!(matches!(engine.run("let b = \"unterminated;"), Err(EngineError::Lexer(_))));
+    assert!(matches!(engine.run("print(;"), Err(EngineError::Parser(_))));
+    assert!(matches!(engine.run("print(c);"), Err(EngineError::SemanticAnalyser(_))));
+    assert!(matches!(engine.run("print(1 + true);"), Err(EngineError::Evaluator(_))));
+}
+
+#[test]
+fn test_engine_natives() {
+    let mut engine = Engine::builder()
+        .with_native("double", 1, double)
+        .with_natives(NativePolicy::Deny(vec!["clock".to_string()]))
+        .build();
+
+    assert_eq!(engine.run("print(double(21));").unwrap(), vec!["42".to_string()]);
+    assert!(matches!(
+        engine.run("double(\"a\");"),
+        Err(EngineError::Evaluator(EvaluatorError::ExpectedNumber))
+    ));
+    assert!(engine.run("print(clock());").is_err());
+}
+
+#[test]
+fn test_engine_timeout() {
+    let mut engine = Engine::builder().with_timeout(Duration::from_millis(50)).build();
+
+    assert!(matches!(
+        engine.run("while true:\n  pass;\n"),
+        Err(EngineError::LimitExceeded { limit: Limit::Time, .. })
+    ));
+    assert!(matches!(
+        engine.run("for i in 0..1000000000:\n  pass;\n"),
+        Err(EngineError::LimitExceeded { error: EvaluatorError::TimeLimitExceeded { .. }, .. })
+    ));
+
+    // Tests that the output printed before the limit was exceeded is kept, along with the line
+    // of the statement the script was stopped at
+    match engine.run("print(\"start\");\nlet i = 0;\nwhile i >= 0:\n  i = i + 1;\n") {
+        Err(EngineError::LimitExceeded { limit, line, output, .. }) => {
+            assert_eq!(limit, Limit::Time);
+            assert_eq!(line, Some(3));
+            assert_eq!(output, vec!["start".to_string()]);
+        },
+        _ => panic!("Expected the time limit to be exceeded"),
+    }
+
+    // The limit applies to each script on its own
+    assert_eq!(engine.run("print(1);").unwrap(), vec!["1".to_string()]);
+}
+
+#[test]
+fn test_engine_max_depth() {
+    let mut engine = Engine::builder().with_max_depth(20).build();
+    engine.run("def count(n):\n  if n == 0:\n    return 0;\n  return count(n - 1) + 1;\n").unwrap();
+
+    assert_eq!(engine.run("print(count(19));").unwrap(), vec!["19".to_string()]);
+    assert!(matches!(
+        engine.run("print(count(20));"),
+        Err(EngineError::LimitExceeded {
+            limit: Limit::Recursion,
+            error: EvaluatorError::RecursionLimitExceeded { limit: 20 },
+            ..
+        })
+    ));
+
+    // Tests that the limit cannot be caught, and that the depth is reset for the next script
+    assert!(matches!(
+        engine.run("try:\n  count(100);\nexcept:\n  pass;\n"),
+        Err(EngineError::LimitExceeded { limit: Limit::Recursion, .. })
+    ));
+    assert_eq!(engine.run("print(count(19));").unwrap(), vec!["19".to_string()]);
+
+    // Tests that calls which have returned do not count towards the limit
+    assert_eq!(
+        engine.run("let total = 0;\nfor i in 0..50:\n  total = total + count(10);\nprint(total);\n").unwrap(),
+        vec!["500".to_string()]
+    );
+
+    // Tests that the limit is lowered to the number of calls that fit on the stack
+    let mut engine = Engine::builder().with_stack_size(10 * CALL_STACK_SIZE).with_max_depth(20).build();
+    engine.run("def count(n):\n  if n == 0:\n    return 0;\n  return count(n - 1) + 1;\n").unwrap();
+    assert!(matches!(
+        engine.run("print(count(10));"),
+        Err(EngineError::LimitExceeded { error: EvaluatorError::RecursionLimitExceeded { limit: 10 }, .. })
+    ));
+}
+
+#[test]
+fn test_engine_deep_recursion() {
+    let count = "def count(n):\n  if n == 0:\n    return 0;\n  return count(n - 1) + 1;\n";
+    let num = |n: f64| Value::Literal(LiteralType::Num(n));
+
+    // Tests that by default a script recursing deeply is stopped rather than overflowing the
+    // stack of the thread it is run on, both when it is run and when it is called by the host
+    let limit = max_depth_for(DEFAULT_STACK_SIZE);
+    let mut engine = Engine::new();
+    engine.run(count).unwrap();
+    assert!(matches!(
+        engine.run("print(count(999));"),
+        Err(EngineError::LimitExceeded { error: EvaluatorError::RecursionLimitExceeded { limit: l }, .. }) if l == limit
+    ));
+    assert!(matches!(
+        engine.get_function("count").unwrap().call(&[num(999.0)]),
+        Err(EngineError::LimitExceeded { limit: Limit::Recursion, .. })
+    ));
+    assert_eq!(engine.get_function("count").unwrap().call(&[num(limit as f64 - 1.0)]).unwrap(), num(limit as f64 - 1.0));
+
+    // Tests that an engine told it has a larger stack can recurse as deeply as it allows
+    let stack_size = 4 * MAX_CALL_DEPTH * CALL_STACK_SIZE;
+    thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(move || {
+            let mut engine = Engine::builder().with_stack_size(stack_size).build();
+            engine.run(count).unwrap();
+            assert_eq!(engine.run("print(count(999));").unwrap(), vec!["999".to_string()]);
+            assert_eq!(engine.get_function("count").unwrap().call(&[num(999.0)]).unwrap(), num(999.0));
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn test_engine_max_steps() {
+    let mut engine = Engine::builder().with_max_steps(100).build();
+
+    // Tests that a script is stopped at the statement that exceeds the limit
+    match engine.run("let i = 0;\nwhile true:\n  i = i + 1;\n") {
+        Err(EngineError::LimitExceeded { limit, line, error, .. }) => {
+            assert_eq!(limit, Limit::Steps);
+            assert_eq!(line, Some(3));
+            assert!(matches!(error, EvaluatorError::StepLimitExceeded { limit: 100 }));
+        },
+        _ => panic!("Expected the step limit to be exceeded"),
+    }
+    assert_eq!(engine.get_global::<f64>("i").unwrap(), 98.0);
+
+    // Tests that the limit applies to each script on its own, counting the statements run by
+    // the functions it calls
+    assert_eq!(engine.run("for i in 0..90:\n  pass;\nprint(\"done\");\n").unwrap(), vec!["done".to_string()]);
+    engine.run("def work(n):\n  for i in 0..n:\n    pass;\n").unwrap();
+    assert!(engine.run("work(10);").is_ok());
+    assert!(matches!(
+        engine.run("work(200);"),
+        Err(EngineError::LimitExceeded { limit: Limit::Steps, .. })
+    ));
+}
+
+#[test]
+fn test_engine_max_memory() {
+    let mut engine = Engine::builder().with_max_memory(1 << 20).build();
+
+    // Tests that a script is stopped once its variables take up more than the limit
+    match engine.run("let items = [];\nwhile true:\n  items.push(\"item\");\n") {
+        Err(EngineError::LimitExceeded { limit, line, error, .. }) => {
+            assert_eq!(limit, Limit::Memory);
+            assert_eq!(line, Some(3));
+            assert!(matches!(error, EvaluatorError::MemoryLimitExceeded { limit: 1048576 }));
+        },
+        _ => panic!("Expected the memory limit to be exceeded"),
+    }
+
+    // Tests that the memory held by a global counts towards later scripts until it is deleted
+    assert!(matches!(
+        engine.run("let more = [\"item\"];\n"),
+        Err(EngineError::LimitExceeded { limit: Limit::Memory, .. })
+    ));
+    assert!(engine.run("del items;\nlet more = [\"item\"];\n").is_ok());
+
+    // Tests that the memory of the variables of a function is freed once it returns
+    engine.run("def work():\n  let items = [0] * 1000;\n  return len(items);\n").unwrap();
+    assert_eq!(
+        engine.run("let total = 0;\nfor i in 0..100:\n  total = total + work();\nprint(total);\n").unwrap(),
+        vec!["100000".to_string()]
+    );
+
+    // Tests that a list is not repeated into one larger than the limit
+    assert!(matches!(
+        engine.run("let grid = [[0] * 1000] * 1000;\n"),
+        Err(EngineError::LimitExceeded { error: EvaluatorError::MemoryLimitExceeded { .. }, .. })
+    ));
+}
+
+#[test]
+fn test_engine_checked_division() {
+    // Tests that dividing by zero gives infinity or NaN unless division is checked
+    let mut engine = Engine::new();
+    assert_eq!(engine.run("print(1 / 0);\nprint(-1 // 0);").unwrap(), vec!["inf".to_string(), "-inf".to_string()]);
+
+    let mut engine = Engine::builder().with_checked_division(true).build();
+    match engine.run("let a = 0;\nprint(7 / a);\n") {
+        Err(EngineError::Evaluator(error)) => {
+            assert!(matches!(error, EvaluatorError::DivisionByZero { line: 2, .. }));
+            assert_eq!(error.to_string(), "Cannot divide by zero in 7 / 0 on line 2");
+        },
+        _ => panic!("Expected dividing by zero to be an error"),
+    }
+    assert!(matches!(engine.run("print(0 // 0);"), Err(EngineError::Evaluator(EvaluatorError::DivisionByZero { .. }))));
+
+    // Tests that the error can be caught, and that other division is unchanged
+    assert_eq!(
+        engine.run("try:\n  print(1 / 0);\nexcept e:\n  print(e);\nprint(1 / 4);\n").unwrap(),
+        vec!["Cannot divide by zero in 1 / 0 on line 2".to_string(), "0.25".to_string()]
+    );
+}
+
+#[test]
+fn test_engine_number_format() {
+    // Tests that numbers are rounded to the significant digits of the format, wherever they are
+    // written
+    let format = NumberFormat::default().with_significant_digits(Some(4));
+    let mut engine = Engine::builder().with_number_format(format).build();
+    assert_eq!(
+        engine.run("print(1 / 3, 2 / 3);\nprint([1 / 3], {\"a\": 123456});\nprint(str(100));").unwrap(),
+        vec!["0.3333 0.6667".to_string(), "[0.3333] {\"a\": 123500}".to_string(), "100".to_string()]
+    );
+
+    // Tests that the thresholds decide which numbers are written in scientific notation
+    let format = NumberFormat::default().with_exponent_thresholds(-2, 3);
+    let mut engine = Engine::builder().with_number_format(format).build();
+    assert_eq!(
+        engine.run("print(999, 1000, 0.01, 0.001, -12345.5);").unwrap(),
+        vec!["999 1e+3 0.01 1e-3 -1.23455e+4".to_string()]
+    );
+}
+
+#[test]
+fn test_engine_closure_cycles() {
+    let mut engine = Engine::builder().with_max_memory(1 << 20).build();
+
+    // Tests that the environment of a call is freed once it returns, even though the function
+    // declared inside of it refers back to it
+    engine.run(r#"
+def work():
+  let items = [0] * 1000;
+  def size():
+    return len(items);
+  return size();
+"#).unwrap();
+    assert_eq!(
+        engine.run("let total = 0;\nfor i in 0..1000:\n  total = total + work();\nprint(total);\n").unwrap(),
+        vec!["1000000".to_string()]
+    );
+
+    // Tests that the same holds for the environments of loops
+    assert!(engine.run(r#"
+for i in 0..1000:
+  let items = [0] * 1000;
+  def size():
+    return len(items);
+"#).is_ok());
+
+    // Tests that a closure which is still referred to keeps its environment, along with the
+    // environments of the calls and loops enclosing it
+    assert_eq!(engine.run(r#"
+def counter():
+  let count = 0;
+  def next():
+    count = count + 1;
+    return count;
+  return next;
+let tick = counter();
+tick();
+let nested = [];
+for i in [0, 1, 2]:
+  def get():
+    return i * 10;
+  nested.push(get);
+print(tick());
+print(nested[2]());
+"#).unwrap(), vec!["2".to_string(), "20".to_string()]);
+
+    // Tests that methods bound to an instance keep the instance's class alive
+    assert_eq!(engine.run(r#"
+def make():
+  class Box:
+    def __init__(self, value):
+      self.value = value;
+    def get(self):
+      return self.value;
+  return Box(7);
+let boxed = make();
+print(boxed.get());
+"#).unwrap(), vec!["7".to_string()]);
+}
+
+#[test]
+fn test_engine_closure_cycles_memory() {
+    // Tests that calling a function which declares a class or nested closures leaves the memory
+    // in use where it started, however many times it is called, once the loop variable exists
+    let mut engine = Engine::new();
+    engine.run(r#"
+def make():
+  let items = [0] * 100;
+  class Box:
+    def size(self):
+      return len(items);
+  def outer():
+    def inner():
+      return len(items);
+    return inner;
+  let boxed = Box();
+  let size = outer();
+  return boxed.size() + size();
+"#).unwrap();
+    engine.run("let i = 0;\n").unwrap();
+    let baseline = engine.memory_usage();
+    engine.run("for i in 0..100:\n  make();\n").unwrap();
+    assert_eq!(engine.memory_usage(), baseline);
+}
+
+#[test]
+fn test_engine_get_function() {
+    let mut engine = Engine::new();
+    engine.run("let scale = 10;\ndef area(w, h):\n  return w * h * scale;\n").unwrap();
+
+    let num = |n: f64| Value::Literal(LiteralType::Num(n));
+    let mut area = engine.get_function("area").unwrap();
+    assert_eq!(area.call(&[num(2.0), num(3.0)]).unwrap(), num(60.0));
+    assert!(matches!(
+        area.call(&[num(2.0)]),
+        Err(EngineError::Evaluator(EvaluatorError::ArgsDifferFromArity { args: 1, arity: 2 }))
+    ));
+
+    // Only functions defined by scripts can be fetched
+    assert!(matches!(
+        engine.get_function("scale"),
+        Err(EngineError::FunctionNotFound { .. })
+    ));
+    assert!(matches!(
+        engine.get_function("missing"),
+        Err(EngineError::FunctionNotFound { .. })
+    ));
+    assert!(matches!(
+        engine.get_function("clock"),
+        Err(EngineError::FunctionNotFound { .. })
+    ));
+}
+
+#[test]
+fn test_engine_globals() {
+    let order = Order {
+        item: "pen".to_string(),
+        quantity: 3,
+        price: 1.5,
+        tags: vec!["office".to_string()],
+        discount: None,
+    };
+
+    // Tests that a struct passed in as a global can be read and changed by a script
+    let mut engine = Engine::new();
+    engine.set_global("order", &order).unwrap();
+    assert_eq!(
+        engine.run(r#"
+print(order["item"]);
+print(order["quantity"] * order["price"]);
+print(order["discount"]);
+order["quantity"] = 4;
+let total = order["quantity"] * order["price"];
+"#).unwrap(),
+        vec!["pen".to_string(), "4.5".to_string(), "null".to_string()]
+    );
+
+    // Tests that globals are read back into Rust types
+    let changed: Order = engine.get_global("order").unwrap();
+    assert_eq!(changed, Order { quantity: 4, ..order });
+    assert_eq!(engine.get_global::<f64>("total").unwrap(), 6.0);
+    assert!(matches!(engine.get_global::<u32>("missing"), Err(EngineError::GlobalNotFound { .. })));
+    assert!(matches!(engine.get_global::<u32>("order"), Err(EngineError::Marshal(_))));
+
+    // Tests that instances are read back as structs and functions cannot be converted
+    engine.run(r#"
+class Point:
+  def __init__(self, x, y):
+    self.x = x;
+    self.y = y;
+let point = Point(1, 2);
+def f():
+  pass;
+"#).unwrap();
+    let point: HashMap<String, i32> = engine.get_global("point").unwrap();
+    assert_eq!(point, HashMap::from([("x".to_string(), 1), ("y".to_string(), 2)]));
+    assert!(matches!(engine.get_global::<String>("f"), Err(EngineError::Marshal(_))));
+}
+
+#[test]
+fn test_marshal() {
+    // Tests that values convert to and from Rust values
+    assert_eq!(to_value(&(1, "a")).unwrap().to_string(), "[1, \"a\"]");
+    assert_eq!(to_value(&true).unwrap(), Value::Literal(LiteralType::True));
+    assert_eq!(to_value(&Option::<u8>::None).unwrap(), Value::Literal(LiteralType::Null));
+    assert_eq!(from_value::<i64>(&Value::Literal(LiteralType::Num(-3.0))).unwrap(), -3);
+    assert_eq!(from_value::<f64>(&Value::Literal(LiteralType::Num(0.5))).unwrap(), 0.5);
+    assert!(from_value::<i64>(&Value::Literal(LiteralType::Num(0.5))).is_err());
+    assert_eq!(
+        from_value::<Vec<u8>>(&Value::Tuple(vec![Value::Literal(LiteralType::Num(1.0))])).unwrap(),
+        vec![1]
+    );
+}
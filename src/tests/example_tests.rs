@@ -0,0 +1,63 @@
+use std::{fs, path::Path};
+
+use crate::{examples::examples, run::run};
+
+/// Runs every `.pyru` program in the `examples/` directory and compares its output, line by
+/// line, with the golden file of the same name ending in `.out`.
+#[test]
+fn test_examples() {
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let mut failures: Vec<String> = Vec::new();
+    let mut count = 0;
+
+    for entry in fs::read_dir(&examples).expect("Unable to read the examples directory") {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "pyru") {
+            continue;
+        }
+
+        let source = fs::read_to_string(&path).unwrap();
+        let expected: Vec<String> = match fs::read_to_string(path.with_extension("out")) {
+            Ok(golden) => golden.lines().map(String::from).collect(),
+            Err(_) => {
+                failures.push(format!("{}: missing golden file", path.display()));
+                continue;
+            }
+        };
+
+        let output = run(&source, false);
+        if output != expected {
+            failures.push(format!(
+                "{}:\n  expected: {expected:?}\n  got:      {output:?}",
+                path.display()
+            ));
+        }
+        count += 1;
+    }
+
+    assert!(count > 0, "No examples were found in {}", examples.display());
+    assert!(failures.is_empty(), "Examples did not match their golden files:\n{}", failures.join("\n"));
+}
+
+/// Checks that every example in the `examples/` directory is embedded for the server, and that
+/// the embedded examples produce the output they are served with.
+#[test]
+fn test_embedded_examples() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples");
+    let mut names: Vec<String> = fs::read_dir(&directory)
+        .expect("Unable to read the examples directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pyru"))
+        .map(|path| path.file_stem().unwrap().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+
+    let embedded = examples();
+    assert_eq!(embedded.iter().map(|example| example.name).collect::<Vec<&str>>(), names);
+
+    for example in embedded {
+        assert!(!example.description.is_empty(), "{} has no description", example.name);
+        let expected: Vec<String> = example.output.lines().map(String::from).collect();
+        assert_eq!(run(example.source, false), expected, "{} did not match", example.name);
+    }
+}
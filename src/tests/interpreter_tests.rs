@@ -1,6 +1,7 @@
 use std::vec;
 
 use crate::{
+    error::EvaluatorError,
     evaluator::Evaluator,
     lexer::Lexer,
     parser::Parser,
@@ -38,8 +39,8 @@ pub fn run(source: &str) -> Vec<String> {
             return vec!["error".to_string()];
         }
     }
-
     let mut evaluator = Evaluator::new();
+    evaluator.resolve(&ast);
     match evaluator.interpret(ast) {
         Ok(output) => return output,
         Err(e) => {
@@ -49,6 +50,22 @@ pub fn run(source: &str) -> Vec<String> {
     }
 }
 
+#[test]
+fn test_aggregates() {
+    // Tests sum, minOf and maxOf on lists of numbers
+    assert_eq!(
+        run("let xs = [3, -1.5, 10];\nprint(sum(xs));\nprint(minOf(xs));\nprint(maxOf(xs));\nprint(sum([]));"),
+        vec!["11.5".to_string(), "-1.5".to_string(), "10".to_string(), "0".to_string()]
+    );
+
+    // Tests that lists holding anything other than numbers, empty lists and other values are errors
+    assert_eq!(run("print(sum([1, \"2\"]));"), vec!["error".to_string()]);
+    assert_eq!(run("print(maxOf([1, null]));"), vec!["error".to_string()]);
+    assert_eq!(run("print(minOf([]));"), vec!["error".to_string()]);
+    assert_eq!(run("print(maxOf([]));"), vec!["error".to_string()]);
+    assert_eq!(run("print(sum(5));"), vec!["error".to_string()]);
+}
+
 #[test]
 fn test_assignment() {
     // Tests for associativity of assignments
@@ -194,6 +211,127 @@ fn test_bool() {
     );
 }
 
+#[test]
+fn test_break() {
+    // Test for breaking out of a while loop
+    assert_eq!(
+        run(r#"
+let i = 0;
+while true:
+    if i == 3:
+        break;
+    print(i);
+    i++;
+print("done");
+
+"#
+        ),
+        vec!["0".to_string(), "1".to_string(), "2".to_string(), "done".to_string()]
+    );
+
+    // Test for breaking out of a for loop
+    assert_eq!(
+        run(r#"
+for i in 0..10:
+    if i > 1:
+        break;
+    print(i);
+
+"#
+        ),
+        vec!["0".to_string(), "1".to_string()]
+    );
+
+    // Test that break only exits the innermost loop
+    assert_eq!(
+        run(r#"
+for i in 0..2:
+    let j = 0;
+    while true:
+        j++;
+        if j == 2:
+            break;
+    print(j);
+
+"#
+        ),
+        vec!["2".to_string(), "2".to_string()]
+    );
+
+    // Tests that a labelled break leaves every loop up to and including the one it names
+    assert_eq!(
+        run(r#"
+outer: for i in 0..3:
+    let j = 0;
+    while true:
+        if i == 1 and j == 1:
+            break outer;
+        print(i * 10 + j);
+        j++;
+        if j == 2:
+            break;
+print("done");
+"#
+        ),
+        vec!["0".to_string(), "1".to_string(), "10".to_string(), "done".to_string()]
+    );
+
+    // Tests that a labelled break can name the innermost loop
+    assert_eq!(
+        run(r#"
+search: loop:
+    for item in [1, 2, 3]:
+        if item == 2:
+            break search;
+        print(item);
+print("found");
+"#
+        ),
+        vec!["1".to_string(), "found".to_string()]
+    );
+
+    // Tests for labels that do not belong to an enclosing loop
+    assert_eq!(
+        run(r#"
+outer: for i in 0..3:
+    pass;
+for j in 0..3:
+    break outer;
+"#
+        ),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+outer: for i in 0..3:
+    def f():
+        break outer;
+    f();
+"#
+        ),
+        vec!["error".to_string()]
+    );
+
+    // Tests for breaks outside of a loop
+    assert_eq!(
+        run("break;"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+while true:
+    def f():
+        break;
+    f();
+
+"#
+        ),
+        vec!["error".to_string()]
+    );
+}
+
 #[test]
 fn test_call() {
     // Tests for calling non-functions
@@ -216,6 +354,190 @@ fn test_call() {
         run("\"str\"();"),
         vec!["error".to_string()]
     );
+
+    // Tests for spreading lists and tuples into the arguments of a call
+    assert_eq!(
+        run(r#"
+def add3(a, b, c):
+    return a + b + c;
+let args = [1, 2, 3];
+print(add3(*args));
+print(add3(10, *[20, 30]));
+print(add3(*(1, 2), 3));
+print(add3(*[], 1, *[2], 3));
+let xs = [4];
+xs.push(*[5]);
+print(xs);
+"#
+        ),
+        vec![
+            "6".to_string(),
+            "60".to_string(),
+            "6".to_string(),
+            "6".to_string(),
+            "[4, 5]".to_string(),
+        ]
+    );
+
+    // Tests that the number of arguments is checked after they are spread
+    assert_eq!(
+        run("def f(a, b):\n    return a;\nprint(f(*[1, 2, 3]));"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("def f(a, b):\n    return a;\nprint(f(*[1]));"),
+        vec!["error".to_string()]
+    );
+
+    // Tests for spreading values that are not lists or tuples
+    assert_eq!(
+        run("def f(a):\n    return a;\nprint(f(*1));"),
+        vec!["error".to_string()]
+    );
+}
+
+#[test]
+fn test_classes() {
+    // Tests for creating instances, reading and assigning fields and calling methods
+    assert_eq!(
+        run(r#"
+class Counter:
+    def __init__(self, start):
+        self.count = start;
+    def increment(self, by):
+        self.count = self.count + by;
+        return self.count;
+let counter = Counter(5);
+print(counter.increment(2));
+print(counter.increment(3));
+print(counter.count);
+counter.count = 0;
+print(counter.count);
+print(counter);
+print(Counter);
+"#
+        ),
+        vec![
+            "7".to_string(),
+            "10".to_string(),
+            "10".to_string(),
+            "0".to_string(),
+            "<Counter instance>".to_string(),
+            "<class Counter>".to_string(),
+        ]
+    );
+
+    // Tests that instances are shared rather than copied, and only equal to themselves
+    assert_eq!(
+        run(r#"
+class Box:
+    pass;
+let a = Box();
+let b = a;
+b.value = 1;
+print(a.value);
+print(a == b);
+print(a == Box());
+"#
+        ),
+        vec!["1".to_string(), "true".to_string(), "false".to_string()]
+    );
+
+    // Tests for methods calling other methods and creating instances of their class
+    assert_eq!(
+        run(r#"
+class Point:
+    def __init__(self, x, y):
+        self.x = x;
+        self.y = y;
+    def add(self, other):
+        return Point(self.x + other.x, self.y + other.y);
+    def sum(self):
+        return self.x + self.y;
+    def double(self):
+        let doubled = self.add(self);
+        return doubled.sum();
+let p = Point(1, 2);
+print(p.double());
+"#
+        ),
+        vec!["6".to_string()]
+    );
+
+    // Tests that a method read from an instance stays bound to it
+    assert_eq!(
+        run(r#"
+class Greeter:
+    def __init__(self, name):
+        self.name = name;
+    def greet(self, greeting):
+        return greeting + ", " + self.name;
+let alice = Greeter("alice");
+let greet = alice.greet;
+alice.name = "Alice";
+print(greet("Hello"));
+"#
+        ),
+        vec!["Hello, Alice".to_string()]
+    );
+
+    // Test for a method that does not take self
+    assert_eq!(
+        run(r#"
+class Bad:
+    def method():
+        pass;
+"#
+        ),
+        vec!["error".to_string()]
+    );
+
+    // Test for the wrong number of arguments being passed to a class
+    assert_eq!(
+        run(r#"
+class Point:
+    def __init__(self, x, y):
+        self.x = x;
+let p = Point(1);
+"#
+        ),
+        vec!["error".to_string()]
+    );
+
+    // Test for reading a field that has not been assigned
+    assert_eq!(
+        run(r#"
+class Empty:
+    pass;
+let e = Empty();
+print(e.missing);
+"#
+        ),
+        vec!["error".to_string()]
+    );
+
+    // Test for calling a method that does not exist
+    assert_eq!(
+        run(r#"
+class Empty:
+    pass;
+let e = Empty();
+e.missing();
+"#
+        ),
+        vec!["error".to_string()]
+    );
+
+    // Test for a class body containing something other than methods
+    assert_eq!(
+        run(r#"
+class Bad:
+    let x = 1;
+"#
+        ),
+        vec!["error".to_string()]
+    );
 }
 
 #[test]
@@ -295,6 +617,29 @@ f();
         ),
         vec!["a".to_string(), "b".to_string(), "c".to_string()]
     );
+
+    // Tests that variables assigned after a closure is declared are seen by the closure, and
+    // that each iteration of a loop is seen as its own scope
+    assert_eq!(
+        run(r#"
+def outer():
+    let total = 0;
+    let adders = [];
+    for i in [1, 2]:
+        let amount = i;
+        def add():
+            total = total + amount;
+        adders.push(add);
+        total = total + 10;
+    for add in adders:
+        add();
+    return total;
+print(outer());
+
+"#
+        ),
+        vec!["23".to_string()]
+    );
 }
 
 #[test]
@@ -383,1167 +728,3380 @@ fn test_comparison() {
         run("print(-0 <= 0);"),
         vec!["true".to_string()]
     );
-}
-
-#[test]
-fn test_equality() {
-    // Test null equality
-    assert_eq!(
-        run("print(null == null);"),
-        vec!["true".to_string()]
-    );
-
-    // Test boolean equality
-    assert_eq!(
-        run("print(true == true);"),
-        vec!["true".to_string()]
-    );
-
-    assert_eq!(
-        run("print(true == false);"),
-        vec!["false".to_string()]
-    );
-
-    // Test numerical equality
-    assert_eq!(
-        run("print(1 == 1);"),
-        vec!["true".to_string()]
-    );
-
-    assert_eq!(
-        run("print(1 == 2);"),
-        vec!["false".to_string()]
-    );
 
-    // Test string equality
+    // Test chained comparisons
     assert_eq!(
-        run("print(\"str\" == \"str\");"),
-        vec!["true".to_string()]
+        run("let x = 5;\nprint(0 <= x < 10);\nprint(0 <= x < 5);\nprint(10 > x > 0 >= -1);"),
+        vec!["true".to_string(), "false".to_string(), "true".to_string()]
     );
 
+    // The middle operand is only evaluated once
     assert_eq!(
-        run("print(\"str\" == \"ing\");"),
-        vec!["false".to_string()]
+        run(r#"
+def middle():
+    print("called");
+    return 5;
+print(0 < middle() < 10);
+"#),
+        vec!["called".to_string(), "true".to_string()]
     );
 
-    // Test mixed type equality
+    // The chain stops at the first false comparison
     assert_eq!(
-        run("print(false == null);"),
+        run("print(1 > 2 < \"a\");"),
         vec!["false".to_string()]
     );
 
+    // Test comparing strings, which are ordered lexicographically
     assert_eq!(
-        run("print(false == 0);"),
-        vec!["false".to_string()]
+        run(r#"
+print("apple" < "banana");
+print("apple" < "app");
+print("abc" <= "abc");
+print("Zebra" > "apple");
+print("b" >= "abc");
+print("a" <= "b" <= "c");
+"#),
+        vec![
+            "true".to_string(),
+            "false".to_string(),
+            "true".to_string(),
+            "false".to_string(),
+            "true".to_string(),
+            "true".to_string(),
+        ]
     );
 
+    // Test comparing a string with a number
     assert_eq!(
-        run("print(0 == \"0\");"),
-        vec!["false".to_string()]
+        run("print(\"1\" < 2);"),
+        vec!["error".to_string()]
     );
 }
 
 #[test]
-fn test_for_loops() {
-    // Test for simple for loop
+fn test_continue() {
+    // Test for skipping iterations of a for loop
     assert_eq!(
         run(r#"
-for i in 0..3:
+for i in 0..5:
+    if i == 1 or i == 3:
+        continue;
     print(i);
 
 "#
         ),
-        vec!["0".to_string(), "1".to_string(), "2".to_string()]
+        vec!["0".to_string(), "2".to_string(), "4".to_string()]
     );
 
-    // Test for for loop in functions
+    // Test for skipping iterations of a while loop
     assert_eq!(
         run(r#"
-def foo():
-    for _ in 0..1:
-        return "done";
-print(foo());
+let i = 0;
+while i < 4:
+    i++;
+    if i == 2:
+        continue;
+    print(i);
 
 "#
         ),
-        vec!["done".to_string()]
+        vec!["1".to_string(), "3".to_string(), "4".to_string()]
     );
 
-    // Test for closures in for loop
+    // Tests that the step of a for loop still runs when an iteration is continued from inside
+    // nested statements, so the loop does not repeat or run forever
     assert_eq!(
         run(r#"
-def f():
-    for _ in 0..1:
-        let i = "i";
-        def g():
-            print(i);
-        return g;
-let h = f();
-h();
-
+for i in 0..10 step 2:
+    if i > 0:
+        if i == 4:
+            continue;
+    print(i);
+for j in 0..4:
+    try:
+        if j == 1:
+            continue;
+    finally:
+        print("finally");
+    match j:
+        case 2:
+            continue;
+    print(j);
 "#
         ),
-        vec!["i".to_string()]
+        vec![
+            "0".to_string(),
+            "2".to_string(),
+            "6".to_string(),
+            "8".to_string(),
+            "finally".to_string(),
+            "0".to_string(),
+            "finally".to_string(),
+            "finally".to_string(),
+            "finally".to_string(),
+            "3".to_string(),
+        ]
     );
 
-    // Test for for loop with step
+    // Tests that continuing an inner loop does not skip the step of the outer loop
     assert_eq!(
         run(r#"
-for i in 0..5 step 2:
+for i in 0..2:
+    for j in 0..3:
+        continue;
+        print("unreachable");
     print(i);
-
+let n = 0;
+for k in 0..3:
+    n = n + 1;
+    continue;
+print(n);
 "#
         ),
-        vec!["0".to_string(), "2".to_string(), "4".to_string()]
+        vec!["0".to_string(), "1".to_string(), "3".to_string()]
     );
-}
 
-#[test]
-fn test_functions() {
-    // Test for extra arguments
+    // Tests that a labelled continue moves on to the next iteration of the loop it names,
+    // running its step
     assert_eq!(
         run(r#"
-def f(x, y):
-    print(x);
-    print(y);
-f(1, 2, 3, 4);
-
+rows: for i in 0..3:
+    for j in 0..3:
+        if j > i:
+            continue rows;
+        print(i * 10 + j);
+    print("unreachable");
 "#
         ),
+        vec![
+            "0".to_string(),
+            "10".to_string(),
+            "11".to_string(),
+            "20".to_string(),
+            "21".to_string(),
+            "22".to_string(),
+            "unreachable".to_string(),
+        ]
+    );
+
+    // Tests for continues outside of a loop
+    assert_eq!(
+        run("continue;"),
         vec!["error".to_string()]
     );
 
-    // Test for missing arguments
     assert_eq!(
         run(r#"
-def add(x, y):
-    return x + y;
-print(add(1));
+for i in 0..3:
+    def f():
+        continue;
 
 "#
         ),
         vec!["error".to_string()]
     );
+}
 
-    // Tests mutual recursion
+#[test]
+fn test_conversions() {
+    // Tests that str converts values as print shows them
     assert_eq!(
         run(r#"
-def isEven(n):
-    if n == 0:
-        return true;
-    return isOdd(n - 1);
-def isOdd(n):
-    if n == 0:
-        return false;
-    return isEven(n - 1);
-print(isEven(4));
+print("value: " + str(3));
+print(str(2.5) + str(true) + str(null));
+print(str([1, "a"]) == "[1, \"a\"]");
+print(str("same"));
+"#),
+        vec![
+            "value: 3".to_string(),
+            "2.5truenull".to_string(),
+            "true".to_string(),
+            "same".to_string(),
+        ]
+    );
+
+    // Tests that num parses strings holding numbers
+    assert_eq!(
+        run("print(num(\"42\") + 1);\nprint(num(\" -2.5 \"));\nprint(num(7));\nprint(num(str(0.1)) == 0.1);"),
+        vec!["43".to_string(), "-2.5".to_string(), "7".to_string(), "true".to_string()]
+    );
+
+    // Tests that bad input is an error
+    assert_eq!(run("print(num(\"12abc\"));"), vec!["error".to_string()]);
+    assert_eq!(run("print(num(\"\"));"), vec!["error".to_string()]);
+    assert_eq!(run("print(num(\"inf\"));"), vec!["error".to_string()]);
+    assert_eq!(run("print(num(true));"), vec!["error".to_string()]);
+    assert_eq!(run("def f():\n    pass;\nprint(str(f));"), vec!["error".to_string()]);
+}
+
+#[test]
+fn test_csv() {
+    // Tests that CSV text is parsed into rows of strings, including quoted fields
+    assert_eq!(
+        run(r#"
+let rows = csvParse("name,age\r\nAda,36\n\"Lovelace, A\",\"say \"\"hi\"\"\"\n,\n");
+print(len(rows));
+print(rows[0]);
+print(rows[2][0]);
+print(rows[2][1]);
+print(rows[3]);
+print(csvParse(""));
+"#),
+        vec![
+            "4".to_string(),
+            "[\"name\", \"age\"]".to_string(),
+            "Lovelace, A".to_string(),
+            "say \"hi\"".to_string(),
+            "[\"\", \"\"]".to_string(),
+            "[]".to_string(),
+        ]
+    );
+
+    // Tests that rows are written back with fields quoted where needed
+    assert_eq!(
+        run(r#"
+let text = csvStringify([["id", "note"], [1, "a, b"], (true, "say \"hi\"")]);
+print(text);
+print(csvParse(text)[2][1]);
+"#),
+        vec![
+            "id,note\n1,\"a, b\"\ntrue,\"say \"\"hi\"\"\"\n".to_string(),
+            "say \"hi\"".to_string(),
+        ]
+    );
+
+    // Tests that an unclosed quote and rows that are not lists are errors
+    assert_eq!(run("print(csvParse(\"a,\\\"b\\nc\"));"), vec!["error".to_string()]);
+    assert_eq!(run("print(csvStringify([1, 2]));"), vec!["error".to_string()]);
+    assert_eq!(run("print(csvStringify(\"a,b\"));"), vec!["error".to_string()]);
+}
 
+#[test]
+fn test_del() {
+    // Tests for deleting a variable and declaring it again
+    assert_eq!(
+        run(r#"
+let a = 1;
+del a;
+let a = "again";
+print(a);
+def f():
+    let b = 2;
+    del b;
+    return 3;
+print(f());
 "#
         ),
+        vec!["again".to_string(), "3".to_string()]
+    );
+
+    // Tests for using a deleted variable
+    assert_eq!(
+        run("let a = 1;\ndel a;\nprint(a);"),
         vec!["error".to_string()]
     );
 
-    // Test recursion
+    // Tests for deleting variables that are not declared in the current scope
     assert_eq!(
-        run(r#"
-def fib(n):
-    if n < 2:
-        return n;
-    return fib(n - 1) + fib(n - 2);
-print(fib(8));
+        run("del a;"),
+        vec!["error".to_string()]
+    );
 
+    assert_eq!(
+        run(r#"
+let a = 1;
+def f():
+    del a;
 "#
         ),
-        vec!["21".to_string()]
+        vec!["error".to_string()]
     );
 
-    // Test missing comma in parameters
     assert_eq!(
-        run(r#"
-def f(a, b c, d, e, f):
-    return a;
+        run("let a = 1;\ndel a\nlet a = 2\nprint(a)"),
+        vec!["2".to_string()]
+    );
 
+    // Tests for deleting items of lists and dictionaries
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3];
+del a[1];
+print(a);
+del a[1];
+print(a);
+let d = {"x": 1, "y": 2};
+del d["x"];
+print(d);
+let grid = [[1, 2], [3, 4]];
+del grid[1][0];
+print(grid);
 "#
         ),
+        vec![
+            "[1, 3]".to_string(),
+            "[1]".to_string(),
+            "{\"y\": 2}".to_string(),
+            "[[1, 2], [4]]".to_string(),
+        ]
+    );
+
+    // Tests for deleting items that do not exist, or from values without items
+    assert_eq!(
+        run("let a = [1];\ndel a[1];"),
         vec!["error".to_string()]
     );
-}
 
-#[test]
-fn test_hash() {
-    // Tests for hash function
     assert_eq!(
-        run("print(hash(\"123\"));"),
-        vec!["a665a45920422f9d417e4867efdc4fb8a04a1f3fff1fa07e998e86f7f7a27ae3".to_string()]
+        run("let d = {\"x\": 1};\ndel d[\"y\"];"),
+        vec!["error".to_string()]
     );
 
     assert_eq!(
-        run("print(hash(\"a4b j2%2@6HK\"));"),
-        vec!["0ddff3ce9c7152874283c174235342d9e9dae2d9c4a486215beae162ace030b4".to_string()]
+        run("let a = 1;\ndel a[0];"),
+        vec!["error".to_string()]
     );
 
-    // Tests for hash equality
     assert_eq!(
-        run("print(hash(\"abc\") == hash(\"abc\"));"),
-        vec!["true".to_string()]
+        run("del b[0];"),
+        vec!["error".to_string()]
     );
 
     assert_eq!(
-        run("print(hash(\"abc\") == hash(\"def\"));"),
-        vec!["false".to_string()]
+        run("let a = [1];\ndel a[0:0];"),
+        vec!["error".to_string()]
     );
 }
 
 #[test]
-fn test_if() {
-    // Test for simple if condition
+fn test_deques() {
+    // Tests for using a deque as a queue
     assert_eq!(
         run(r#"
-if true:
-    print("true");
-else:
-    print("false");
-
+let queue = deque([1, 2]);
+queue.push(3);
+print(queue.popFront());
+print(queue.peekFront());
+print(queue);
+print(queue.len());
 "#
         ),
-        vec!["true".to_string()]
+        vec!["1".to_string(), "2".to_string(), "deque([2, 3])".to_string(), "2".to_string()]
     );
 
-    // Test for variable condition
+    // Tests for pushing and popping at both ends
     assert_eq!(
         run(r#"
-let a = 3;
-if a == 2:
-    print("true");
-else if a == 3:
-    print("false");
-else:
-    print("else");
-
+let d = deque(("b",));
+d.pushFront("a");
+d.push("c");
+print(d.pop());
+print(d.peek());
+print("a" in d);
+let items = "";
+for item in d:
+    items = items + item;
+print(items);
 "#
         ),
-        vec!["false".to_string()]
+        vec!["c".to_string(), "b".to_string(), "true".to_string(), "ab".to_string()]
     );
 
-    // Tests for truthy values in condition
+    // Tests that taking from an empty deque gives null
     assert_eq!(
         run(r#"
-if 1:
-    print("true");
-else:
-    print("false");
-
+let d = deque([]);
+print(d.popFront());
+print(d.peek());
+print(d);
 "#
         ),
-        vec!["true".to_string()]
+        vec!["null".to_string(), "null".to_string(), "deque([])".to_string()]
     );
 
+    // Tests for invalid deques and methods
     assert_eq!(
-        run(r#"
-if "string":
-    print("true");
-else:
-    print("false");
+        run("let d = deque(1);"),
+        vec!["error".to_string()]
+    );
 
-"#
-        ),
-        vec!["true".to_string()]
+    assert_eq!(
+        run("let d = deque([]);\nd.sort();"),
+        vec!["error".to_string()]
     );
+}
 
+#[test]
+fn test_dicts() {
+    // Tests for creating and indexing dictionaries
     assert_eq!(
         run(r#"
-if "":
-    print("empty");
-
+let d = {"a": 1, 2: "two", true: [3]};
+print(d["a"]);
+print(d[2]);
+print(d[true]);
+print({});
 "#
         ),
-        vec!["empty".to_string()]
+        vec!["1".to_string(), "two".to_string(), "[3]".to_string(), "{}".to_string()]
     );
 
-    // Test for assignment in if condition
+    // Tests for assigning to keys
     assert_eq!(
         run(r#"
-let a = false;
-if a = true:
-    print(a);
-
+let d = {"a": 1};
+d["a"] = d["a"] + 1;
+d["b"] = "new";
+print(d["a"]);
+print(d["b"]);
+print(d.len());
 "#
         ),
-        vec!["true".to_string()]
+        vec!["2".to_string(), "new".to_string(), "2".to_string()]
     );
 
-    // Test for not in if condition
+    // Tests for dictionary methods and membership
     assert_eq!(
         run(r#"
-let a = [1, 2, 3];
-if 4 not in a:
-    print("true");
-
+let d = {"x": 10, "y": 20};
+let keys = d.keys();
+print(keys.sort());
+let values = d.values();
+print(values.sort());
+print(d.has("x"));
+print("z" in d);
+print(d.remove("x"));
+print(d);
 "#
         ),
-        vec!["true".to_string()]
+        vec![
+            "[\"x\", \"y\"]".to_string(),
+            "[10, 20]".to_string(),
+            "true".to_string(),
+            "false".to_string(),
+            "10".to_string(),
+            "{\"y\": 20}".to_string(),
+        ]
     );
 
-    // Tests for multiple conditions
+    // Tests for missing keys and invalid keys
     assert_eq!(
-        run(r#"
-let a = 3;
-if a == 2 or a == 3:
-    print("true");
+        run("let d = {\"a\": 1};\nprint(d[\"b\"]);"),
+        vec!["error".to_string()]
+    );
 
-"#
-        ),
-        vec!["true".to_string()]
+    assert_eq!(
+        run("let d = {[1]: 1};"),
+        vec!["error".to_string()]
     );
 
+    assert_eq!(
+        run("let d = {\"a\" 1};"),
+        vec!["error".to_string()]
+    );
+    // Tests that entries are kept in the order their keys were first added
     assert_eq!(
         run(r#"
-let a = 2;
-let b = 3;
-if a == 2 and b == 3:
-    print("true");
-
+let d = {"c": 3, "a": 1, "b": 2};
+d["z"] = 26;
+d["a"] = 10;
+print(d);
+d.remove("c");
+d["c"] = 30;
+print(d.keys());
+print(d.values());
+print(d == {"c": 30, "z": 26, "b": 2, "a": 10});
 "#
         ),
-        vec!["true".to_string()]
+        vec![
+            "{\"c\": 3, \"a\": 10, \"b\": 2, \"z\": 26}".to_string(),
+            "[\"a\", \"b\", \"z\", \"c\"]".to_string(),
+            "[10, 2, 26, 30]".to_string(),
+            "true".to_string(),
+        ]
     );
+}
 
-    // Test for nested if conditions
+#[test]
+fn test_docstrings() {
+    // Tests for reading the docstring of a function, which is still run as a statement
     assert_eq!(
         run(r#"
-let a = 3;
-if a == 2:
-    print("false");
-else:
-    if a == 3:
-        print("true");
-
+def area(w, h):
+    "Returns the area of a rectangle.";
+    return w * h;
+def volume(w, h, d):
+    """
+    Returns the volume of a box.
+
+        volume(1, 2, 3) == 6
+    """;
+    return area(w, h) * d;
+def plain():
+    return 1;
+print(doc(area));
+print(doc(volume));
+print(doc(plain));
+print(area(2, 3));
 "#
         ),
-        vec!["true".to_string()]
+        vec![
+            "Returns the area of a rectangle.".to_string(),
+            "Returns the volume of a box.\n\n    volume(1, 2, 3) == 6".to_string(),
+            "null".to_string(),
+            "6".to_string(),
+        ]
     );
 
-    // Test for nested if conditions
+    // Tests for the docstrings of methods and built in functions
     assert_eq!(
         run(r#"
-if 1 == 1:
-    if 2 == 2:
-        if 3 == 3:
-            print(3);
-        print(2);
-    print(1);
-print(0);
-
+class Point:
+    def norm(self):
+        "The squared distance from the origin.";
+        return 0;
+let p = Point();
+print(doc(p.norm));
+print(doc(clock));
 "#
         ),
-        vec!["3".to_string(), "2".to_string(), "1".to_string(), "0".to_string()]
+        vec![
+            "The squared distance from the origin.".to_string(),
+            "Returns the number of seconds since the Unix epoch".to_string(),
+        ]
+    );
 
-    )
+    // Tests for reading the docstring of a value that is not a function
+    assert_eq!(
+        run("print(doc(1));"),
+        vec!["error".to_string()]
+    );
 }
 
 #[test]
-fn test_lists() {
-    // Test for list creation
+fn test_enums() {
+    // Tests for reading, printing and comparing the variants of an enum
     assert_eq!(
         run(r#"
-let a = [1, 2, 3];
-print(a);
-
+enum Color: Red, Green, Blue;
+let c = Color.Green;
+print(c);
+print(Color);
+print(c == Color.Green);
+print(c == Color.Blue);
+print(c != Color.Red);
+if c == Color.Green:
+    print("go");
 "#
         ),
-        vec!["[1, 2, 3]".to_string()]
+        vec![
+            "Color.Green".to_string(),
+            "<enum Color>".to_string(),
+            "true".to_string(),
+            "false".to_string(),
+            "true".to_string(),
+            "go".to_string(),
+        ]
     );
 
-    // Test for list indexing
+    // Tests that variants of different enums are never equal, even with the same name
     assert_eq!(
         run(r#"
-let a = [1, 2, 3];
-print(a[0]);
-print(a[1]);
-print(a[2]);
-
+enum Light: Red, Amber, Green;
+enum Color: Red, Green;
+print(Light.Red == Color.Red);
+print([Light.Amber, Color.Green]);
 "#
         ),
-        vec!["1".to_string(), "2".to_string(), "3".to_string()]
+        vec!["false".to_string(), "[Light.Amber, Color.Green]".to_string()]
     );
 
-    // Tests for list slicing
+    // Tests for variants that do not exist and for invalid declarations
     assert_eq!(
-        run(r#"
-let a = [1, 2, 3, 4, 5];
-print(a[1:3]);
-
-"#
-        ),
-        vec!["[2, 3, 4]".to_string()]
+        run("enum Color: Red;
+print(Color.Purple);"),
+        vec!["error".to_string()]
     );
 
     assert_eq!(
-        run(r#"
-let a = [1, 2, 3, 4, 5];
-print(a[:3]);
+        run("enum Color: Red, Green, Red;"),
+        vec!["error".to_string()]
+    );
 
-"#
-        ),
-        vec!["[1, 2, 3, 4]".to_string()]
+    assert_eq!(
+        run("enum Color: Red;
+let Color = 1;"),
+        vec!["error".to_string()]
     );
 
     assert_eq!(
-        run(r#"
-let a = [1, 2, 3, 4, 5];
-print(a[2:]);
+        run("enum Color:;"),
+        vec!["error".to_string()]
+    );
+}
 
-"#
-        ),
-        vec!["[3, 4, 5]".to_string()]
+#[test]
+fn test_equality() {
+    // Test null equality
+    assert_eq!(
+        run("print(null == null);"),
+        vec!["true".to_string()]
     );
 
-    // Ensure that lists cannot be added together
+    // Test boolean equality
     assert_eq!(
-        run(r#"
-let a = [1, 2, 3];
-let b = [4, 5, 6];
-print(a + b);
+        run("print(true == true);"),
+        vec!["true".to_string()]
+    );
 
-"#
-        ),
-        vec!["error".to_string()]
+    assert_eq!(
+        run("print(true == false);"),
+        vec!["false".to_string()]
     );
 
-    // Test for pushing items to the end of a list
+    // Test numerical equality
     assert_eq!(
-        run(r#"
-let a = [1, 2, 3];
-a.push(4);
-print(a);
+        run("print(1 == 1);"),
+        vec!["true".to_string()]
+    );
 
-"#
-        ),
-        vec!["[1, 2, 3, 4]".to_string()]
+    assert_eq!(
+        run("print(1 == 2);"),
+        vec!["false".to_string()]
     );
 
-    // Test for popping items from a list
+    // Test string equality
     assert_eq!(
-        run(r#"
-let a = ["apple", "banana", "cherry"];
-let b = a.pop();
-print(b);
-print(a);
+        run("print(\"str\" == \"str\");"),
+        vec!["true".to_string()]
+    );
 
-"#
-        ),
-        vec!["cherry".to_string(), "[\"apple\", \"banana\"]".to_string()]
+    assert_eq!(
+        run("print(\"str\" == \"ing\");"),
+        vec!["false".to_string()]
     );
 
-    // Test for removing items from a list
+    // Test mixed type equality
     assert_eq!(
-        run(r#"
-let a = ["apple", "banana", "cherry"];
-a.remove(1);
-print(a);
+        run("print(false == null);"),
+        vec!["false".to_string()]
+    );
 
-"#
-        ),
-        vec!["[\"apple\", \"cherry\"]".to_string()]
+    assert_eq!(
+        run("print(false == 0);"),
+        vec!["false".to_string()]
     );
 
-    // Test for inserting items into a list
+    assert_eq!(
+        run("print(0 == \"0\");"),
+        vec!["false".to_string()]
+    );
+
+    // Tests that collections are equal if they hold equal items, however deeply nested
     assert_eq!(
         run(r#"
-let a = [1, 2, 3];
-a.insertAt(1, 4);
-print(a);
+let a = [1, [2, "three"], {"four": [4]}];
+let b = [1, [2, "three"], {"four": [4]}];
+print(a == b, a != b);
+print([1, 2] == [2, 1], [1] == [1, 1], [1, 2] == (1, 2));
+print({"x": 1, "y": 2} == {"y": 2, "x": 1}, {"x": [1]} == {"x": [2]});
+print(deque([1, (2, 3)]) == deque([1, (2, 3)]));
+print([0 / 0] == [0 / 0]);
+"#),
+        vec![
+            "true false".to_string(),
+            "false false false".to_string(),
+            "true false".to_string(),
+            "true".to_string(),
+            "false".to_string(),
+        ]
+    );
 
-"#
-        ),
-        vec!["[1, 4, 2, 3]".to_string()]
+    // Tests that functions are only equal to themselves, inside of collections too
+    assert_eq!(
+        run(r#"
+def make():
+    def inner():
+        return 1;
+    return inner;
+
+let f = make();
+let g = f;
+print(f == g, f == make(), [f] == [g], [f] == [make()]);
+print(f in [make(), g], len == len, len == str);
+"#),
+        vec![
+            "true false true false".to_string(),
+            "true true false".to_string(),
+        ]
     );
+}
 
-    // Test for getting the index of an item in a list
+#[test]
+fn test_for_loops() {
+    // Test for simple for loop
     assert_eq!(
         run(r#"
-let a = ["apple", "banana", "cherry"];
-print(a.index("banana"));
+for i in 0..3:
+    print(i);
 
 "#
         ),
-        vec!["1".to_string()]
+        vec!["0".to_string(), "1".to_string(), "2".to_string()]
     );
 
-    // Test for getting the length of a list
+    // Test for for loop in functions
     assert_eq!(
         run(r#"
-let a = [1, 2, 3, 4, 5, 6, 7];
-print(a.len());
+def foo():
+    for _ in 0..1:
+        return "done";
+print(foo());
 
 "#
         ),
-        vec!["7".to_string()]
+        vec!["done".to_string()]
     );
 
-    // Test for sorting a list
+    // Test for closures in for loop
     assert_eq!(
         run(r#"
-let a = [3, 2, 1, 4, 5];
-a.sort();
-print(a);
+def f():
+    for _ in 0..1:
+        let i = "i";
+        def g():
+            print(i);
+        return g;
+let h = f();
+h();
 
 "#
         ),
-        vec!["[1, 2, 3, 4, 5]".to_string()]
+        vec!["i".to_string()]
     );
 
-    // Test for looping through a list
+    // Tests that each iteration has its own copy of the loop variable, which functions declared
+    // in the body keep
     assert_eq!(
         run(r#"
-let items = ["apple", "banana", "cherry"];
-for i in 0..items.len():
-    print(items[i]);
-
+let printers = [];
+for i in 0..3:
+    def show():
+        print(i);
+    printers.push(show);
+for printer in printers:
+    printer();
 "#
         ),
-        vec!["apple".to_string(), "banana".to_string(), "cherry".to_string()]
+        vec!["0".to_string(), "1".to_string(), "2".to_string()]
     );
-}
 
-#[test]
-fn test_logical_operators() {
-    // Works because it returns the first non-true argument
+    // Tests that changing the loop variable in the body carries over to the next iteration, and
+    // that it holds its last value after the loop
     assert_eq!(
         run(r#"
-print(false and 1);
-print(true and 1);
-print(1 and 2 and false);
-
+let shown = [];
+for i in 0..10:
+    if i == 1:
+        i = 5;
+        continue;
+    def show():
+        return i;
+    shown.push(show);
+    if i == 7:
+        break;
+for f in shown:
+    print(f());
+print(i);
 "#
         ),
-        vec!["false".to_string(), "1".to_string(), "false".to_string()]
+        vec!["0".to_string(), "6".to_string(), "7".to_string(), "7".to_string()]
     );
 
-    // Works because it returns the last argument if all are true
+    // Test for for loop with step
     assert_eq!(
         run(r#"
-print(1 and true);
-print(1 and 2 and 3);
+for i in 0..5 step 2:
+    print(i);
 
 "#
         ),
-        vec!["true".to_string(), "3".to_string()]
+        vec!["0".to_string(), "2".to_string(), "4".to_string()]
     );
 
-
-    // Special cases which short-circuits at the first false argument
+    // Tests for looping over the elements of a list and the characters of a string
     assert_eq!(
         run(r#"
-let a = "before";
-let b = "before";
-(a = true) and (b = false) and (a = "bad");
-print(a);
-print(b);
-
+let items = ["a", 2, [3]];
+for item in items:
+    print(item);
+for c in "hi":
+    print(c);
+for x in []:
+    print("unreachable");
 "#
         ),
-        vec!["true".to_string(), "false".to_string()]
+        vec![
+            "a".to_string(),
+            "2".to_string(),
+            "[3]".to_string(),
+            "h".to_string(),
+            "i".to_string(),
+        ]
     );
 
+    // Tests that break and continue work, and changing the list does not change the loop
     assert_eq!(
         run(r#"
-let a = "before";
-let b = "before";
-(a = false) or (b = true) or (a = "bad");
-print(a);
-print(b);
-
+let nums = [1, 2, 3, 4, 5];
+for n in nums:
+    nums.push(n);
+    if n == 2:
+        continue;
+    if n == 4:
+        break;
+    print(n);
+print(nums.len());
 "#
         ),
-        vec!["false".to_string(), "true".to_string()]
+        vec!["1".to_string(), "3".to_string(), "9".to_string()]
     );
 
-    // Test for and operator and its precedence
+    // Tests that each iteration has its own element, which closures keep
     assert_eq!(
         run(r#"
-print(false and "bad");
-
+let printers = [];
+for word in ["one", "two"]:
+    def p():
+        print(word);
+    printers.push(p);
+let first = printers[0];
+first();
 "#
         ),
-        vec!["false".to_string()]
+        vec!["one".to_string()]
     );
 
+    // Tests that the element is not defined after the loop, and only lists and strings are looped over
+    assert_eq!(run("for a in [1]:\n    pass;\nprint(a);"), vec!["error".to_string()]);
+    assert_eq!(run("for a in 5:\n    print(a);"), vec!["error".to_string()]);
+
+    // Tests that the counter of a range loop is defined after the loop, but not the variables
+    // declared in its body
+    assert_eq!(run("for i in 0..2:\n    pass;\nprint(i);"), vec!["2".to_string()]);
     assert_eq!(
-        run(r#"
-print(null and "bad");
+        run("for i in 0..2:\n    let doubled = i * 2;\nprint(doubled);"),
+        vec!["error".to_string()]
+    );
+}
 
-"#
-        ),
-        vec!["null".to_string()]
+#[test]
+fn test_format() {
+    // Tests each kind of placeholder
+    assert_eq!(
+        run(r#"
+let x = 1;
+print(format("x={} y={}", x, [2, "b"]));
+print(format("{1} before {0}, {0} again", "a", "b"));
+print(format("{name} is {age}", {"name": "Ada", "age": 36}));
+print(format("{{literal}} {}", null));
+print(format("no placeholders"));
+"#),
+        vec![
+            "x=1 y=[2, \"b\"]".to_string(),
+            "b before a, a again".to_string(),
+            "Ada is 36".to_string(),
+            "{literal} null".to_string(),
+            "no placeholders".to_string(),
+        ]
     );
 
+    // Tests that placeholders without values and unmatched braces are errors
+    assert_eq!(run("print(format(\"{} {}\", 1));"), vec!["error".to_string()]);
+    assert_eq!(run("print(format(\"{2}\", 1));"), vec!["error".to_string()]);
+    assert_eq!(run("print(format(\"{missing}\", {\"name\": 1}));"), vec!["error".to_string()]);
+    assert_eq!(run("print(format(\"{open\", 1));"), vec!["error".to_string()]);
+    assert_eq!(run("print(format(\"close}\"));"), vec!["error".to_string()]);
+    assert_eq!(run("print(format(1));"), vec!["error".to_string()]);
+    assert_eq!(run("print(format());"), vec!["error".to_string()]);
+}
+
+#[test]
+fn test_functions() {
+    // Test for extra arguments
     assert_eq!(
         run(r#"
-print(true and "ok");
+def f(x, y):
+    print(x);
+    print(y);
+f(1, 2, 3, 4);
 
 "#
         ),
-        vec!["ok".to_string()]
+        vec!["error".to_string()]
     );
 
+    // Test for missing arguments
     assert_eq!(
         run(r#"
-print(0 and "ok");
+def add(x, y):
+    return x + y;
+print(add(1));
 
 "#
         ),
-        vec!["ok".to_string()]
+        vec!["error".to_string()]
     );
 
+    // Tests mutual recursion
     assert_eq!(
         run(r#"
-print("" and "ok");
+def isEven(n):
+    if n == 0:
+        return true;
+    return isOdd(n - 1);
+def isOdd(n):
+    if n == 0:
+        return false;
+    return isEven(n - 1);
+print(isEven(4));
 
 "#
         ),
-        vec!["ok".to_string()]
+        vec!["error".to_string()]
     );
 
-    // Tests for or operator and its precedence
+    // Tests mutual recursion of inner functions, which can refer to later siblings
     assert_eq!(
         run(r#"
-print(1 or true);
-print(false or 1);
-print(false or false or true);
+def check(n):
+    def isEven(k):
+        if k == 0:
+            return true;
+        return isOdd(k - 1);
+    def isOdd(k):
+        if k == 0:
+            return false;
+        return isEven(k - 1);
+    return isEven(n);
+print(check(4));
+print(check(7));
 
 "#
         ),
-        vec!["1".to_string(), "1".to_string(), "true".to_string()]
+        vec!["true".to_string(), "false".to_string()]
     );
 
+    // Tests that an inner function cannot be called before its definition has run
     assert_eq!(
         run(r#"
-print(false or false);
-print(false or false or 0);
+def outer():
+    let result = later();
+    def later():
+        return 1;
+    return result;
+print(outer());
 
 "#
         ),
-        vec!["false".to_string(), "0".to_string()]
+        vec!["error".to_string()]
+    );
+
+    // Tests that an inner function cannot be defined twice
+    assert_eq!(
+        run(r#"
+def outer():
+    def inner():
+        return 1;
+    def inner():
+        return 2;
+
+"#
+        ),
+        vec!["error".to_string()]
+    );
+
+    // Test recursion
+    assert_eq!(
+        run(r#"
+def fib(n):
+    if n < 2:
+        return n;
+    return fib(n - 1) + fib(n - 2);
+print(fib(8));
+
+"#
+        ),
+        vec!["21".to_string()]
+    );
+
+    // Test missing comma in parameters
+    assert_eq!(
+        run(r#"
+def f(a, b c, d, e, f):
+    return a;
+
+"#
+        ),
+        vec!["error".to_string()]
+    );
+
+    // Tests that a local declared in a branch that is not run is skipped over, and a global is
+    // found when a local of the same name has not been declared yet
+    assert_eq!(
+        run(r#"
+let total = 10;
+def f(flag):
+    if flag:
+        let skipped = 1;
+    let kept = 2;
+    kept = kept + 1;
+    return kept;
+def g():
+    let before = total;
+    let total = 1;
+    return before + total;
+print(f(false));
+print(f(true));
+print(g());
+"#
+        ),
+        vec!["3".to_string(), "3".to_string(), "11".to_string()]
+    );
+}
+
+#[test]
+fn test_hash() {
+    // Tests for hash function
+    assert_eq!(
+        run("print(hash(\"123\"));"),
+        vec!["a665a45920422f9d417e4867efdc4fb8a04a1f3fff1fa07e998e86f7f7a27ae3".to_string()]
+    );
+
+    assert_eq!(
+        run("print(hash(\"a4b j2%2@6HK\"));"),
+        vec!["0ddff3ce9c7152874283c174235342d9e9dae2d9c4a486215beae162ace030b4".to_string()]
+    );
+
+    // Tests for hash equality
+    assert_eq!(
+        run("print(hash(\"abc\") == hash(\"abc\"));"),
+        vec!["true".to_string()]
+    );
+
+    assert_eq!(
+        run("print(hash(\"abc\") == hash(\"def\"));"),
+        vec!["false".to_string()]
+    );
+    // Tests for the other hash algorithms
+    assert_eq!(
+        run(r#"
+print(hash("abc", "md5"));
+print(hash("", "md5"));
+print(hash("12345678901234567890123456789012345678901234567890123456789012345678901234567890", "md5"));
+print(hash("abc", "SHA1"));
+print(hash("abc", "sha512"));
+print(hash("abc", "sha256") == hash("abc"));
+"#),
+        vec![
+            "900150983cd24fb0d6963f7d28e17f72".to_string(),
+            "d41d8cd98f00b204e9800998ecf8427e".to_string(),
+            "57edf4a22be3c955ac49da2e2107b67a".to_string(),
+            "a9993e364706816aba3e25717850c26c9cd0d89d".to_string(),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+            2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f".to_string(),
+            "true".to_string(),
+        ]
+    );
+
+    // Tests that values other than strings are hashed as they would be printed
+    assert_eq!(
+        run("print(hash(3) == hash(\"3\"));\nprint(hash([1, 2], \"md5\") == hash(str([1, 2]), \"md5\"));"),
+        vec!["true".to_string(), "true".to_string()]
+    );
+
+    // Tests that unknown algorithms, values that cannot be printed and extra arguments are errors
+    assert_eq!(run("print(hash(\"abc\", \"sha3\"));"), vec!["error".to_string()]);
+    assert_eq!(run("print(hash(\"abc\", 256));"), vec!["error".to_string()]);
+    assert_eq!(run("print(hash(clock));"), vec!["error".to_string()]);
+    assert_eq!(run("print(hash(\"abc\", \"md5\", 1));"), vec!["error".to_string()]);
+    assert_eq!(run("print(hash());"), vec!["error".to_string()]);
+}
+
+#[test]
+fn test_higher_order_natives() {
+    // Tests that map, filter and reduce call functions defined by the program
+    assert_eq!(
+        run(r#"
+def double(x):
+    return x * 2;
+def isEven(x):
+    return x // 2 * 2 == x;
+def add(total, x):
+    return total + x;
+let xs = [1, 2, 3, 4];
+print(map(double, xs));
+print(filter(isEven, xs));
+print(reduce(add, xs, 0));
+print(xs);
+"#),
+        vec![
+            "[2, 4, 6, 8]".to_string(),
+            "[2, 4]".to_string(),
+            "10".to_string(),
+            "[1, 2, 3, 4]".to_string(),
+        ]
+    );
+
+    // Tests that natives, closures and empty lists work too
+    assert_eq!(
+        run(r#"
+print(map(str, [1, 2]));
+def adder(n):
+    def add(x):
+        return x + n;
+    return add;
+print(map(adder(10), [1, 2]));
+print(reduce(max, [3, 9, 4], 0));
+print(map(abs, []));
+print(reduce(max, [], "empty"));
+"#),
+        vec![
+            "[\"1\", \"2\"]".to_string(),
+            "[11, 12]".to_string(),
+            "9".to_string(),
+            "[]".to_string(),
+            "empty".to_string(),
+        ]
+    );
+
+    // Tests that the arguments must be a function and a list
+    assert_eq!(run("print(map(1, [1]));"), vec!["error".to_string()]);
+    assert_eq!(run("print(map(abs, 1));"), vec!["error".to_string()]);
+    assert_eq!(run("def f(a, b):\n    return a;\nprint(filter(f, [1]));"), vec!["error".to_string()]);
+}
+
+#[test]
+fn test_if() {
+    // Test for simple if condition
+    assert_eq!(
+        run(r#"
+if true:
+    print("true");
+else:
+    print("false");
+
+"#
+        ),
+        vec!["true".to_string()]
+    );
+
+    // Test for variable condition
+    assert_eq!(
+        run(r#"
+let a = 3;
+if a == 2:
+    print("true");
+else if a == 3:
+    print("false");
+else:
+    print("else");
+
+"#
+        ),
+        vec!["false".to_string()]
+    );
+
+    // Tests for elif chains
+    assert_eq!(
+        run(r#"
+let a = 3;
+if a == 1:
+    print("one");
+elif a == 2:
+    print("two");
+elif a == 3:
+    print("three");
+else:
+    print("other");
+
+"#
+        ),
+        vec!["three".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+let a = 4;
+if a == 1:
+    print("one");
+elif a == 2:
+    print("two");
+else:
+    print("other");
+    print(a);
+
+"#
+        ),
+        vec!["other".to_string(), "4".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+def describe(n):
+    if n < 0:
+        return "negative";
+    elif n == 0:
+        return "zero";
+    return "positive";
+print(describe(-1));
+print(describe(0));
+print(describe(1));
+
+"#
+        ),
+        vec!["negative".to_string(), "zero".to_string(), "positive".to_string()]
+    );
+
+    // Tests for truthy values in condition
+    assert_eq!(
+        run(r#"
+if 1:
+    print("true");
+else:
+    print("false");
+
+"#
+        ),
+        vec!["true".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+if "string":
+    print("true");
+else:
+    print("false");
+
+"#
+        ),
+        vec!["true".to_string()]
+    );
+
+    // Tests for values that are false in a condition
+    assert_eq!(
+        run(r#"
+if "":
+    print("empty");
+else:
+    print("not empty");
+
+"#
+        ),
+        vec!["not empty".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+let items = [];
+let values = [0, 1, "", "a", [], [0], {}, {"a": 1}, (0,), null, false];
+for value in values:
+    if value:
+        print("truthy");
+    else:
+        print("falsy");
+def f():
+    pass;
+if f:
+    print("function");
+if items:
+    print("items");
+items.push(1);
+while items:
+    print(items.pop());
+
+"#
+        ),
+        vec![
+            "falsy".to_string(),
+            "truthy".to_string(),
+            "falsy".to_string(),
+            "truthy".to_string(),
+            "falsy".to_string(),
+            "truthy".to_string(),
+            "falsy".to_string(),
+            "truthy".to_string(),
+            "truthy".to_string(),
+            "falsy".to_string(),
+            "falsy".to_string(),
+            "function".to_string(),
+            "1".to_string(),
+        ]
+    );
+
+    // Test for assignment in if condition
+    assert_eq!(
+        run(r#"
+let a = false;
+if a = true:
+    print(a);
+
+"#
+        ),
+        vec!["true".to_string()]
+    );
+
+    // Test for not in if condition
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3];
+if 4 not in a:
+    print("true");
+
+"#
+        ),
+        vec!["true".to_string()]
+    );
+
+    // Tests for multiple conditions
+    assert_eq!(
+        run(r#"
+let a = 3;
+if a == 2 or a == 3:
+    print("true");
+
+"#
+        ),
+        vec!["true".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+let a = 2;
+let b = 3;
+if a == 2 and b == 3:
+    print("true");
+
+"#
+        ),
+        vec!["true".to_string()]
+    );
+
+    // Test for nested if conditions
+    assert_eq!(
+        run(r#"
+let a = 3;
+if a == 2:
+    print("false");
+else:
+    if a == 3:
+        print("true");
+
+"#
+        ),
+        vec!["true".to_string()]
+    );
+
+    // Test for nested if conditions
+    assert_eq!(
+        run(r#"
+if 1 == 1:
+    if 2 == 2:
+        if 3 == 3:
+            print(3);
+        print(2);
+    print(1);
+print(0);
+
+"#
+        ),
+        vec!["3".to_string(), "2".to_string(), "1".to_string(), "0".to_string()]
+
+    )
+}
+
+#[test]
+fn test_len() {
+    // Tests that len works on strings and every collection
+    assert_eq!(
+        run("print(len(\"hello\"));\nprint(len(\"\"));\nprint(len([1, 2, 3]));\nprint(len((1, 2)));"),
+        vec!["5".to_string(), "0".to_string(), "3".to_string(), "2".to_string()]
+    );
+
+    assert_eq!(
+        run("print(len({\"a\": 1}));\nprint(len(set([1, 1, 2])));\nprint(len(deque([1, 2, 3, 4])));"),
+        vec!["1".to_string(), "2".to_string(), "4".to_string()]
+    );
+
+    // Tests that len agrees with the len method
+    assert_eq!(
+        run("let xs = [1, 2];\nxs.push(3);\nprint(len(xs) == xs.len());"),
+        vec!["true".to_string()]
+    );
+
+    // Tests that a value without a length is an error
+    assert_eq!(run("print(len(5));"), vec!["error".to_string()]);
+    assert_eq!(run("print(len(null));"), vec!["error".to_string()]);
+}
+
+#[test]
+fn test_list_algorithms() {
+    // Tests for binary search
+    assert_eq!(
+        run(r#"
+let xs = [1, 3, 5, 7, 9, 11];
+print(bsearch(xs, 1));
+print(bsearch(xs, 9));
+print(bsearch(xs, 4));
+print(bsearch([], 4));
+print(bsearch(["a", "b", "c"], "c"));
+"#
+        ),
+        vec!["0".to_string(), "4".to_string(), "-1".to_string(), "-1".to_string(), "2".to_string()]
+    );
+
+    // Tests that sorting in reverse leaves the list unchanged
+    assert_eq!(
+        run(r#"
+let xs = [3, 1, 2];
+print(reverseSorted(xs));
+print(xs);
+print(reverseSorted(["b", "c", "a"]));
+"#
+        ),
+        vec![
+            "[3, 2, 1]".to_string(),
+            "[3, 1, 2]".to_string(),
+            "[\"c\", \"b\", \"a\"]".to_string(),
+        ]
+    );
+
+    // Tests that shuffling with the same seed gives the same order, and keeps every item
+    assert_eq!(
+        run(r#"
+let xs = [1, 2, 3, 4, 5, 6, 7, 8];
+let a = shuffle(xs, 42);
+print(a == shuffle(xs, 42));
+print(a == xs);
+a.sort();
+print(a == xs);
+print(shuffle([], 1));
+"#
+        ),
+        vec!["true".to_string(), "false".to_string(), "true".to_string(), "[]".to_string()]
+    );
+
+    // Tests for invalid arguments
+    assert_eq!(
+        run("print(bsearch([1, \"a\"], 2));"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("print(shuffle(\"abc\", 1));"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("print(shuffle([1, 2], \"seed\"));"),
+        vec!["error".to_string()]
+    );
+}
+
+#[test]
+fn test_lists() {
+    // Test for list creation
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3];
+print(a);
+
+"#
+        ),
+        vec!["[1, 2, 3]".to_string()]
+    );
+
+    // Test for list indexing
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3];
+print(a[0]);
+print(a[1]);
+print(a[2]);
+
+"#
+        ),
+        vec!["1".to_string(), "2".to_string(), "3".to_string()]
+    );
+
+    // Tests for list slicing
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3, 4, 5];
+print(a[1:3]);
+
+"#
+        ),
+        vec!["[2, 3, 4]".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3, 4, 5];
+print(a[:3]);
+
+"#
+        ),
+        vec!["[1, 2, 3, 4]".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3, 4, 5];
+print(a[2:]);
+
+"#
+        ),
+        vec!["[3, 4, 5]".to_string()]
+    );
+
+    // Tests for concatenating lists
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3];
+let b = [4, 5, 6];
+print(a + b);
+print(a);
+print([] + ["x"]);
+
+"#
+        ),
+        vec!["[1, 2, 3, 4, 5, 6]".to_string(), "[1, 2, 3]".to_string(), "[\"x\"]".to_string()]
+    );
+
+    // Tests for repeating lists
+    assert_eq!(
+        run(r#"
+let a = [0, 1];
+print(a * 3);
+print(2 * a);
+print(a * 0);
+print(a * -1);
+
+"#
+        ),
+        vec![
+            "[0, 1, 0, 1, 0, 1]".to_string(),
+            "[0, 1, 0, 1]".to_string(),
+            "[]".to_string(),
+            "[]".to_string(),
+        ]
+    );
+
+    // Ensure that lists are only combined with lists and repeated a whole number of times
+    assert_eq!(run("print([1] + 2);"), vec!["error".to_string()]);
+    assert_eq!(run("print([1] * 1.5);"), vec!["error".to_string()]);
+    assert_eq!(run("print([1] * [2]);"), vec!["error".to_string()]);
+    assert_eq!(run("print([1] * 2000000);"), vec!["error".to_string()]);
+
+    // Test for pushing items to the end of a list
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3];
+a.push(4);
+print(a);
+
+"#
+        ),
+        vec!["[1, 2, 3, 4]".to_string()]
+    );
+
+    // Test for popping items from a list
+    assert_eq!(
+        run(r#"
+let a = ["apple", "banana", "cherry"];
+let b = a.pop();
+print(b);
+print(a);
+
+"#
+        ),
+        vec!["cherry".to_string(), "[\"apple\", \"banana\"]".to_string()]
+    );
+
+    // Test for removing items from a list
+    assert_eq!(
+        run(r#"
+let a = ["apple", "banana", "cherry"];
+a.remove(1);
+print(a);
+
+"#
+        ),
+        vec!["[\"apple\", \"cherry\"]".to_string()]
+    );
+
+    // Test for inserting items into a list
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3];
+a.insertAt(1, 4);
+print(a);
+
+"#
+        ),
+        vec!["[1, 4, 2, 3]".to_string()]
+    );
+
+    // Test for getting the index of an item in a list
+    assert_eq!(
+        run(r#"
+let a = ["apple", "banana", "cherry"];
+print(a.index("banana"));
+
+"#
+        ),
+        vec!["1".to_string()]
+    );
+
+    // Test for getting the length of a list
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3, 4, 5, 6, 7];
+print(a.len());
+
+"#
+        ),
+        vec!["7".to_string()]
+    );
+
+    // Test for sorting a list
+    assert_eq!(
+        run(r#"
+let a = [3, 2, 1, 4, 5];
+a.sort();
+print(a);
+
+"#
+        ),
+        vec!["[1, 2, 3, 4, 5]".to_string()]
+    );
+
+    // Test for looping through a list
+    assert_eq!(
+        run(r#"
+let items = ["apple", "banana", "cherry"];
+for i in 0..items.len():
+    print(items[i]);
+
+"#
+        ),
+        vec!["apple".to_string(), "banana".to_string(), "cherry".to_string()]
+    );
+
+    // Tests for assigning to an item of a list
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3];
+a[1] = "two";
+print(a);
+"#
+        ),
+        vec!["[1, \"two\", 3]".to_string()]
+    );
+
+    assert_eq!(
+        run("let a = [1, 2, 3];\na[3] = 4;"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("let a = [1, 2, 3];\na[\"0\"] = 4;"),
+        vec!["error".to_string()]
+    );
+
+    // Tests for assigning to a slice of a list
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3, 4, 5];
+a[1:2] = ["b", "c", "d"];
+print(a);
+a[:1] = [];
+print(a);
+a[3:] = (6, 7);
+print(a);
+a[:] = [0];
+print(a);
+"#
+        ),
+        vec![
+            "[1, \"b\", \"c\", \"d\", 4, 5]".to_string(),
+            "[\"c\", \"d\", 4, 5]".to_string(),
+            "[\"c\", \"d\", 4, 6, 7]".to_string(),
+            "[0]".to_string(),
+        ]
+    );
+
+    assert_eq!(
+        run("let a = [1, 2, 3];\na[0:5] = [4];"),
+        vec!["error".to_string()]
+    );
+
+    // Tests that a slice can start at the end of the list, but that its bounds must be whole
+    // numbers that are not negative
+    assert_eq!(
+        run("let a = [1, 2, 3];\na[3:] = [4];\nprint(a);"),
+        vec!["[1, 2, 3, 4]".to_string()]
+    );
+    for slice in ["a[-1:] = [4];", "a[:-1] = [4];", "a[0.5:1] = [4];", "a[0:1.5] = [4];", "a[4:] = [4];"] {
+        assert_eq!(
+            run(&format!("let a = [1, 2, 3];\n{slice}\nprint(a);")),
+            vec!["error".to_string()]
+        );
+    }
+    assert_eq!(run("let a = [1, 2, 3];\na[-1] = 4;"), vec!["error".to_string()]);
+
+    assert_eq!(
+        run("let a = [1, 2, 3];\na[0:1] = 4;"),
+        vec!["error".to_string()]
+    );
+
+    // Tests for nested indexing
+    assert_eq!(
+        run(r#"
+let grid = [[1, 2], [3, 4]];
+print(grid[1][0]);
+print(grid[0][0:1]);
+def row(i):
+    return grid[i];
+print(row(0)[1]);
+print([5, 6, 7][2]);
+let people = {"ann": [30, "london"]};
+print(people["ann"][1]);
+"#
+        ),
+        vec![
+            "3".to_string(),
+            "[1, 2]".to_string(),
+            "2".to_string(),
+            "7".to_string(),
+            "london".to_string(),
+        ]
+    );
+
+    // Tests for assigning to nested items
+    assert_eq!(
+        run(r#"
+let grid = [[1, 2], [3, 4]];
+let second = grid[1];
+grid[1][0] = 9;
+grid[0][0:] = [7];
+print(grid);
+print(second);
+let people = {"ann": [30, "london"]};
+people["ann"][0] = 31;
+print(people["ann"]);
+let i = 0;
+grid[i++][0] = 8;
+print(grid);
+print(i);
+"#
+        ),
+        vec![
+            "[[7], [9, 4]]".to_string(),
+            "[9, 4]".to_string(),
+            "[31, \"london\"]".to_string(),
+            "[[7], [8, 4]]".to_string(),
+            "1".to_string(),
+        ]
+    );
+
+    assert_eq!(
+        run("let grid = [[1]];\nprint(grid[0][1]);"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("def f():\n    return [1];\nf()[0] = 2;"),
+        vec!["error".to_string()]
+    );
+
+    // Tests that a list is changed in place, everywhere it is held
+    assert_eq!(
+        run(r#"
+let a = [1];
+let b = a;
+b.push(2);
+def add(items):
+    items.push(3);
+add(a);
+let popped = a.pop();
+print(a);
+print(popped);
+let nested = [a];
+a.insertAt(0, 0);
+print(nested);
+let sorted = [3, 1, 2];
+let result = sorted.sort();
+result.push(4);
+print(sorted);
+"#
+        ),
+        vec![
+            "[1, 2]".to_string(),
+            "3".to_string(),
+            "[[0, 1, 2]]".to_string(),
+            "[1, 2, 3, 4]".to_string(),
+        ]
+    );
+
+    // Tests that a list cannot be made to hold itself
+    assert_eq!(run("let a = [1];\na.push(a);"), vec!["error".to_string()]);
+    assert_eq!(run("let a = [[1]];\na[0][0] = {\"a\": a};"), vec!["error".to_string()]);
+    // Tests that a function called on each item of a list can change the list
+    assert_eq!(
+        run("let a = [1, 2];\ndef grow(x):\n    a.push(x);\n    return x;\nprint(map(grow, a));\nprint(a);"),
+        vec!["[1, 2]".to_string(), "[1, 2, 1, 2]".to_string()]
+    );
+}
+
+#[test]
+fn test_logical_operators() {
+    // Works because it returns the first non-true argument
+    assert_eq!(
+        run(r#"
+print(false and 1);
+print(true and 1);
+print(1 and 2 and false);
+
+"#
+        ),
+        vec!["false".to_string(), "1".to_string(), "false".to_string()]
+    );
+
+    // Works because it returns the last argument if all are true
+    assert_eq!(
+        run(r#"
+print(1 and true);
+print(1 and 2 and 3);
+
+"#
+        ),
+        vec!["true".to_string(), "3".to_string()]
+    );
+
+
+    // Special cases which short-circuits at the first false argument
+    assert_eq!(
+        run(r#"
+let a = "before";
+let b = "before";
+(a = true) and (b = false) and (a = "bad");
+print(a);
+print(b);
+
+"#
+        ),
+        vec!["true".to_string(), "false".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+let a = "before";
+let b = "before";
+(a = false) or (b = true) or (a = "bad");
+print(a);
+print(b);
+
+"#
+        ),
+        vec!["false".to_string(), "true".to_string()]
+    );
+
+    // Test for and operator and its precedence
+    assert_eq!(
+        run(r#"
+print(false and "bad");
+
+"#
+        ),
+        vec!["false".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+print(null and "bad");
+
+"#
+        ),
+        vec!["null".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+print(true and "ok");
+
+"#
+        ),
+        vec!["ok".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+print(0 and "ok");
+
+"#
+        ),
+        vec!["0".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+print("" and "ok");
+
+"#
+        ),
+        vec!["".to_string()]
+    );
+
+    // Tests for or operator and its precedence
+    assert_eq!(
+        run(r#"
+print(1 or true);
+print(false or 1);
+print(false or false or true);
+
+"#
+        ),
+        vec!["1".to_string(), "1".to_string(), "true".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+print(false or false);
+print(false or false or 0);
+
+"#
+        ),
+        vec!["false".to_string(), "0".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+print(false or "ok");
+print(null or "ok");
+
+"#
+        ),
+        vec!["ok".to_string(), "ok".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+print(true or "ok");
+print(0 or "ok");
+print("s" or "ok");
+
+"#
+        ),
+        vec!["true".to_string(), "ok".to_string(), "s".to_string()]
+    );
+}
+
+#[test]
+fn test_loop() {
+    // Tests that a loop runs until it is broken out of or returned from
+    assert_eq!(
+        run(r#"
+let i = 0;
+loop:
+    i++;
+    if i == 2:
+        continue;
+    print(i);
+    if i == 3:
+        break;
+def first_over(limit):
+    let n = 1;
+    loop:
+        n = n * 2;
+        if n > limit:
+            return n;
+print(first_over(100));
+"#
+        ),
+        vec!["1".to_string(), "3".to_string(), "128".to_string()]
+    );
+
+    assert_eq!(run("loop\n    break;\n"), vec!["error".to_string()]);
+}
+
+#[test]
+fn test_match() {
+    // Tests literal, range and wildcard arms, where only the first matching arm runs
+    assert_eq!(
+        run(r#"
+def describe(value):
+    match value:
+        case 0:
+            return "zero";
+        case -1:
+            return "minus one";
+        case 1..10:
+            return "small";
+        case "ten":
+            return "word";
+        case true:
+            return "true";
+        case _:
+            return "other";
+print(describe(0));
+print(describe(-1));
+print(describe(1));
+print(describe(9.5));
+print(describe(10));
+print(describe("ten"));
+print(describe(true));
+print(describe(null));
+"#
+        ),
+        vec![
+            "zero".to_string(),
+            "minus one".to_string(),
+            "small".to_string(),
+            "small".to_string(),
+            "other".to_string(),
+            "word".to_string(),
+            "true".to_string(),
+            "other".to_string(),
+        ]
+    );
+
+    // Tests that nothing runs when no arm matches, and the subject is evaluated once
+    assert_eq!(
+        run(r#"
+let count = 0;
+def next():
+    count = count + 1;
+    return count;
+match next():
+    case 5:
+        print("five");
+    case 2..4:
+        print("range");
+print(count);
+"#
+        ),
+        vec!["1".to_string()]
+    );
+
+    // Tests that the patterns must be literals, and a wildcard must be the last arm
+    assert_eq!(
+        run("let a = 1;\nmatch a:\n    case a:\n        pass;\n"),
+        vec!["error".to_string()]
+    );
+    assert_eq!(
+        run("match 1:\n    case _:\n        pass;\n    case 1:\n        pass;\n"),
+        vec!["error".to_string()]
+    );
+    assert_eq!(run("match 1:\n    print(1);\n"), vec!["error".to_string()]);
+}
+
+#[test]
+fn test_math() {
+    // Testing addition
+    assert_eq!(
+        run("print(123 + 456);"),
+        vec!["579".to_string()]
+    );
+
+    assert_eq!(
+        run("print(\"str\" + \"ing\");"),
+        vec!["string".to_string()]
+    );
+
+    // Testing invalid addition
+    assert_eq!(
+        run("print(true + null);"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("print(true + 123);"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("print(true + \"str\");"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("print(null + null);"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("print(null + 123);"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("print(null + \"str\");"),
+        vec!["error".to_string()]
+    );
+
+    // Test subtraction
+    assert_eq!(
+        run("print(123 - 456);"),
+        vec!["-333".to_string()]
+    );
+
+    assert_eq!(
+        run("print(1.2 - 1.2);"),
+        vec!["0".to_string()]
+    );
+
+    // Testing invalid subtraction
+    assert_eq!(
+        run("print(true - null);"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("print(\"1\" - 1);"),
+        vec!["error".to_string()]
+    );
+
+    // Test multiplication
+    assert_eq!(
+        run("print(5 * 3);"),
+        vec!["15".to_string()]
+    );
+
+    assert_eq!(
+        run("print(12.34 * 0.3);"),
+        vec!["3.702".to_string()]
+    );
+
+    // Test invalid multiplication
+    assert_eq!(
+        run("print(true * \"str\");"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("print(\"123\" * 123);"),
+        vec!["error".to_string()]
+    );
+
+    // Test division
+    assert_eq!(
+        run("print(10 / 2);"),
+        vec!["5".to_string()]
+    );
+
+    assert_eq!(
+        run("print(12.34 / 12.34);"),
+        vec!["1".to_string()]
+    );
+
+    // Test invalid division
+    assert_eq!(
+        run("print(true / null);"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("print(\"123\" / 123);"),
+        vec!["error".to_string()]
+    );
+
+    // Testing floor division
+    assert_eq!(
+        run("print(7 // 2);"),
+        vec!["3".to_string()]
+    );
+
+    assert_eq!(
+        run("print(-7 // 2); // Comment after floor division\n"),
+        vec!["-4".to_string()]
+    );
+
+    assert_eq!(
+        run("print(7.5 // 2 * 2);"),
+        vec!["6".to_string()]
+    );
+
+    assert_eq!(
+        run("print(\"7\" // 2);"),
+        vec!["error".to_string()]
+    );
+
+    // Test negation
+    assert_eq!(
+        run("print(-1);"),
+        vec!["-1".to_string()]
+    );
+
+    assert_eq!(
+        run("print(-(-1));"),
+        vec!["1".to_string()]
+    );
+}
+
+#[test]
+fn test_math_natives() {
+    // Tests the rounding natives
+    assert_eq!(
+        run("print(floor(2.7));\nprint(ceil(2.1));\nprint(round(2.5));\nprint(round(-2.5));\nprint(abs(-3));"),
+        vec!["2".to_string(), "3".to_string(), "3".to_string(), "-3".to_string(), "3".to_string()]
+    );
+
+    // Tests the natives that take two numbers
+    assert_eq!(
+        run("print(min(3, -1));\nprint(max(3, -1));\nprint(pow(2, 10));\nprint(pow(4, 0.5));"),
+        vec!["-1".to_string(), "3".to_string(), "1024".to_string(), "2".to_string()]
+    );
+
+    // Tests sqrt and the constants
+    assert_eq!(
+        run("print(sqrt(16));\nprint(approxEqual(PI, 3.14159, 0.00001));\nprint(approxEqual(E, 2.71828, 0.00001));"),
+        vec!["4".to_string(), "true".to_string(), "true".to_string()]
+    );
+
+    // Tests that the natives only accept numbers
+    assert_eq!(run("print(sqrt(-1));"), vec!["error".to_string()]);
+    assert_eq!(run("print(abs(\"1\"));"), vec!["error".to_string()]);
+    assert_eq!(run("print(max(1, null));"), vec!["error".to_string()]);
+}
+
+#[test]
+fn test_membership() {
+    // Test for membership in lists
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3];
+print(1 in a);
+print(4 in a);
+print(1 not in a);
+print(4 not in a);
+
+"#
+        ),
+        vec![
+            "true".to_string(),
+            "false".to_string(),
+            "false".to_string(),
+            "true".to_string()
+        ]
+    );
+
+    // Test for membership in condition
+    assert_eq!(
+        run(r#"
+let a = [1, 2, 3];
+if 1 in a:
+    print("1");
+
+if 4 in a:
+    print("2");
+
+if 1 not in a:
+    print("3");
+
+if 4 not in a:
+    print("4");
+
+"#
+        ),
+        vec![
+            "1".to_string(),
+            "4".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn test_negation() {
+    // Tests for negating booleans
+    assert_eq!(
+        run("print(!true);"),
+        vec!["false".to_string()]
+    );
+
+    assert_eq!(
+        run("print(!false);"),
+        vec!["true".to_string()]
+    );
+
+    // Tests for double negation
+    assert_eq!(
+        run("print(!!true);"),
+        vec!["true".to_string()]
+    );
+
+    assert_eq!(
+        run("print(!!false);"),
+        vec!["false".to_string()]
+    );
+
+    // Tests for negating different values
+    assert_eq!(
+        run("print(!123);"),
+        vec!["false".to_string()]
+    );
+
+    assert_eq!(
+        run("print(!0);"),
+        vec!["true".to_string()]
+    );
+
+    assert_eq!(
+        run("print(![]);"),
+        vec!["true".to_string()]
+    );
+
+    assert_eq!(
+        run("print(!null);"),
+        vec!["true".to_string()]
+    );
+
+    assert_eq!(
+        run("print(!\"\");"),
+        vec!["true".to_string()]
+    );
+
+    // Test for negating a function
+    assert_eq!(
+        run(r#"
+def foo():
+    return true;
+print(!foo());
+
+"#
+        ),
+        vec!["false".to_string()]
+    );
+}
+
+#[test]
+fn test_not_equals() {
+    // Test null
+    assert_eq!(
+        run("print(null != null);"),
+        vec!["false".to_string()]
+    );
+
+    // Test boolean
+    assert_eq!(
+        run("print(true != true);"),
+        vec!["false".to_string()]
+    );
+
+    assert_eq!(
+        run("print(true != false);"),
+        vec!["true".to_string()]
+    );
+
+    // Test numerical
+    assert_eq!(
+        run("print(1 != 1);"),
+        vec!["false".to_string()]
+    );
+
+    assert_eq!(
+        run("print(1 != 2);"),
+        vec!["true".to_string()]
+    );
+
+    // Test strings
+    assert_eq!(
+        run("print(\"str\" != \"str\");"),
+        vec!["false".to_string()]
+    );
+
+    assert_eq!(
+        run("print(\"str\" != \"ing\");"),
+        vec!["true".to_string()]
+    );
+
+    // Test mixed types
+    assert_eq!(
+        run("print(false != null);"),
+        vec!["true".to_string()]
+    );
+
+    assert_eq!(
+        run("print(false != 0);"),
+        vec!["true".to_string()]
+    );
+
+    assert_eq!(
+        run("print(0 != \"0\");"),
+        vec!["true".to_string()]
+    );
+}
+
+#[test]
+fn test_nums()  {
+    // Tests for decimal points in numbers
+    assert_eq!(
+        run("print(123.);"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("print(.123);"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("print(123.456);"),
+        vec!["123.456".to_string()]
+    );
+
+    // Test for zero
+    assert_eq!(
+        run("print(0);"),
+        vec!["0".to_string()]
+    );
+
+    assert_eq!(
+        run("print(-0);"),
+        vec!["-0".to_string()]
+    );
+
+    // Test for mix of decimals and negatives
+    assert_eq!(
+        run("print(-123.456);"),
+        vec!["-123.456".to_string()]
+    );
+
+    assert_eq!(
+        run("print(-0.001);"),
+        vec!["-0.001".to_string()]
+    );
+
+    // NaN tests
+    assert_eq!(
+        run("print(0 / 0);"),
+        vec!["NaN".to_string()]
+    );
+
+    assert_eq!(
+        run(r#"
+let nan = 0 / 0;
+print(nan == 0);
+print(nan != 1);
+print(nan == nan);
+print(nan != nan);
+
+"#
+        ),
+        vec!["false".to_string(), "true".to_string(), "false".to_string(), "true".to_string()]
+    );
+
+    // Tests that very large and very small numbers are written in scientific notation
+    assert_eq!(
+        run("print(100000000000000000000);\nprint(1000000000000000000000);\nprint(-0.00000015);\nprint(0.000001);"),
+        vec!["100000000000000000000".to_string(), "1e+21".to_string(), "-1.5e-7".to_string(), "0.000001".to_string()]
+    );
+
+    // Tests that the numbers in collections are written in the same way as printed numbers
+    assert_eq!(
+        run("print([0.1 + 0.2, 1000000000000000000000], (0.00000001,));"),
+        vec!["[0.30000000000000004, 1e+21] (1e-8,)".to_string()]
+    );
+}
+
+#[test]
+fn test_pass() {
+    // Tests for bodies that only contain a pass statement
+    assert_eq!(
+        run(r#"
+def todo():
+    pass;
+print(todo());
+if true:
+    pass;
+else:
+    print("unreachable");
+for i in 0..3:
+    pass;
+print("done");
+"#
+        ),
+        vec!["null".to_string(), "done".to_string()]
+    );
+
+    // Tests for empty lists
+    assert_eq!(
+        run("print([]);\nprint([ ]);\nlet a = [];\na.push(1);\nprint(a);"),
+        vec!["[]".to_string(), "[]".to_string(), "[1]".to_string()]
+    );
+
+    assert_eq!(
+        run("pass"),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn test_precedence() {
+    // Tests for BODMAS precedence
+    assert_eq!(
+        run("print(2 + 3 * 4);"),
+        vec!["14".to_string()]
     );
 
     assert_eq!(
-        run(r#"
-print(false or "ok");
-print(null or "ok");
-
-"#
-        ),
-        vec!["ok".to_string(), "ok".to_string()]
+        run("print(20 - 3 * 4);"),
+        vec!["8".to_string()]
     );
 
     assert_eq!(
-        run(r#"
-print(true or "ok");
-print(0 or "ok");
-print("s" or "ok");
-
-"#
-        ),
-        vec!["true".to_string(), "0".to_string(), "s".to_string()]
+        run("print(2 + 6 / 3);"),
+        vec!["4".to_string()]
     );
-}
 
-#[test]
-fn test_math() {
-    // Testing addition
     assert_eq!(
-        run("print(123 + 456);"),
-        vec!["579".to_string()]
+        run("print(2 - 6 / 3);"),
+        vec!["0".to_string()]
     );
 
+    // Test for spacing with - symbol
     assert_eq!(
-        run("print(\"str\" + \"ing\");"),
-        vec!["string".to_string()]
+        run("print(1- 1);"),
+        vec!["0".to_string()]
     );
 
-    // Testing invalid addition
+    // Test for left associativity
     assert_eq!(
-        run("print(true + null);"),
-        vec!["error".to_string()]
+        run("print(1 - 1 - 1);"),
+        vec!["-1".to_string()]
     );
 
+    // Test for right associativity
     assert_eq!(
-        run("print(true + 123);"),
-        vec!["error".to_string()]
+        run("print(1 - (1 - 1));"),
+        vec!["1".to_string()]
     );
 
+    // Test for precedence with grouping
     assert_eq!(
-        run("print(true + \"str\");"),
-        vec!["error".to_string()]
+        run("print(2 * (6 - (2 + 2)));"),
+        vec!["4".to_string()]
     );
+}
 
+#[test]
+fn test_print() {
+    // Test printing a simple string
     assert_eq!(
-        run("print(null + null);"),
-        vec!["error".to_string()]
+        run("print(\"Hello, World!\");"),
+        vec!["Hello, World!".to_string()]
     );
 
+    // Test an empty print statement
     assert_eq!(
-        run("print(null + 123);"),
+        run("print();"),
         vec!["error".to_string()]
     );
 
+    // Test printing an assignment statement
     assert_eq!(
-        run("print(null + \"str\");"),
-        vec!["error".to_string()]
+        run(r#"
+let a = 2;
+print(a = a + 1);
+print(a);
+
+"#
+        ),
+        vec!["3".to_string(), "3".to_string()]
     );
 
-    // Test subtraction
+    // Tests that several values are printed on one line, separated by spaces
     assert_eq!(
-        run("print(123 - 456);"),
-        vec!["-333".to_string()]
+        run(r#"
+let a = 1;
+print(a, "and", [2, 3], (4, 5), null,);
+print("total:", a + 2);
+"#),
+        vec!["1 and [2, 3] (4, 5) null".to_string(), "total: 3".to_string()]
     );
 
+    // Tests that a value that cannot be printed is still an error among others
+    assert_eq!(run("def f():\n    pass;\nprint(1, f);"), vec!["error".to_string()]);
+    assert_eq!(run("print(1,, 2);"), vec!["error".to_string()]);
+}
+
+#[test]
+fn test_raise() {
+    // Tests that a raised string is caught as it is, through the call stack
     assert_eq!(
-        run("print(1.2 - 1.2);"),
-        vec!["0".to_string()]
+        run(r#"
+def check(n):
+    if n < 0:
+        raise "bad input";
+    return n;
+try:
+    print(check(1));
+    print(check(-1));
+except e:
+    print(e);
+"#
+        ),
+        vec!["1".to_string(), "bad input".to_string()]
     );
 
-    // Testing invalid subtraction
+    // Tests that structured error values keep their fields when caught
     assert_eq!(
-        run("print(true - null);"),
-        vec!["error".to_string()]
+        run(r#"
+class ValueError:
+    def __init__(self, message):
+        self.message = message;
+try:
+    raise {"code": 404, "message": "not found"};
+except e:
+    print(e["code"]);
+try:
+    raise ValueError("too large");
+except e:
+    print(e.message);
+"#
+        ),
+        vec!["404".to_string(), "too large".to_string()]
     );
 
+    // Tests that an uncaught raise stops the program, and finally still runs
     assert_eq!(
-        run("print(\"1\" - 1);"),
+        run(r#"
+try:
+    raise "failed";
+finally:
+    print("finally");
+print("unreachable");
+"#
+        ),
         vec!["error".to_string()]
     );
+    assert_eq!(run("raise;"), vec!["error".to_string()]);
+}
 
-    // Test multiplication
+/// Resolves and interprets the source code with an evaluator that may have run programs before.
+fn interpret(evaluator: &mut Evaluator, source: &str) -> Result<Vec<String>, EvaluatorError> {
+    let tokens = Lexer::new(source.to_string(), 4).run().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    evaluator.resolve(&ast);
+    return evaluator.interpret(ast);
+}
+
+#[test]
+fn test_reset() {
+    let mut evaluator = Evaluator::new();
+
+    // Tests that globals persist between programs but output does not
     assert_eq!(
-        run("print(5 * 3);"),
-        vec!["15".to_string()]
+        interpret(&mut evaluator, "let a = 1;\nprint(a);").unwrap(),
+        vec!["1".to_string()]
+    );
+    assert_eq!(evaluator.defined_globals()[0].to_string(), "a: number = 1");
+    assert_eq!(
+        interpret(&mut evaluator, "a = a + 1;\nprint(a);").unwrap(),
+        vec!["2".to_string()]
     );
+    assert!(evaluator.defined_globals().is_empty());
+
+    // Tests that output produced before an error can be taken
+    assert!(interpret(&mut evaluator, "print(3);\nprint(a[0]);").is_err());
+    assert_eq!(evaluator.take_output(), vec!["3".to_string()]);
+    assert!(evaluator.output().is_empty());
 
+    // Tests that resetting discards globals but keeps the native functions
+    evaluator.reset();
+    assert!(interpret(&mut evaluator, "print(a);").is_err());
     assert_eq!(
-        run("print(12.34 * 0.3);"),
-        vec!["3.702".to_string()]
+        interpret(&mut evaluator, "print(hash(\"a\") == hash(\"a\"));").unwrap(),
+        vec!["true".to_string()]
     );
+}
 
-    // Test invalid multiplication
+#[test]
+fn test_returns() {
+    // Test for returning in an else branch
     assert_eq!(
-        run("print(true * \"str\");"),
-        vec!["error".to_string()]
+        run(r#"
+def f():
+    if false:
+        "no";
+    else:
+        return "ok";
+print(f());
+
+"#
+        ),
+        vec!["ok".to_string()]
     );
 
+    // Test for returning in an if statement
     assert_eq!(
-        run("print(\"123\" * 123);"),
-        vec!["error".to_string()]
+        run(r#"
+def f():
+    if true:
+        return "ok";
+print(f());
+
+"#
+        ),
+        vec!["ok".to_string()]
     );
 
-    // Test division
+    // Test for returning in a while loop
     assert_eq!(
-        run("print(10 / 2);"),
-        vec!["5".to_string()]
+        run(r#"
+def f():
+    while true:
+        return "ok";
+print(f());
+
+"#
+        ),
+        vec!["ok".to_string()]
     );
 
+    // Test for returning through nested loops and a match arm
     assert_eq!(
-        run("print(12.34 / 12.34);"),
-        vec!["1".to_string()]
+        run(r#"
+def find():
+    for i in 0..3:
+        for j in 0..3:
+            match i * j:
+                case 4:
+                    return [i, j];
+    return null;
+print(find());
+
+"#
+        ),
+        vec!["[2, 2]".to_string()]
     );
 
-    // Test invalid division
+    // Test for returning at the top level
     assert_eq!(
-        run("print(true / null);"),
+        run("return \"at top level\";"),
         vec!["error".to_string()]
     );
 
+    // Test for unreachable print after return
     assert_eq!(
-        run("print(\"123\" / 123);"),
-        vec!["error".to_string()]
+        run(r#"
+def f():
+    return;
+    print("unreachable");
+print(f());
+
+"#
+        ),
+        vec!["null".to_string()]
     );
+}
 
-    // Test negation
+#[test]
+fn test_semicolons() {
+    // Tests that a statement can end at the end of its line without a semicolon
     assert_eq!(
-        run("print(-1);"),
-        vec!["-1".to_string()]
+        run(r#"
+let total = 0
+for i in 0..4:
+    if i == 2:
+        continue
+    total = total + i
+print(total)
+def f(x):
+    if x > 1:
+        return
+    return x
+print(f(1)); print(f(2))
+let xs = [1,
+2]
+print(xs)
+"#
+        ),
+        vec!["4".to_string(), "1".to_string(), "null".to_string(), "[1, 2]".to_string()]
     );
 
+    // Tests that a loop label is only read from the same line as `break`
     assert_eq!(
-        run("print(-(-1));"),
+        run("let n = 0
+while true:
+    n++
+    break
+print(n)"),
         vec!["1".to_string()]
     );
+
+    // Tests that a bracket at the start of a line does not continue the line before it
+    assert_eq!(
+        run("let a = [1, 2]
+[3, 4]
+print(a)"),
+        vec!["[1, 2]".to_string()]
+    );
+
+    // Tests that statements on the same line still need a semicolon between them
+    assert_eq!(
+        run("print(1) print(2)"),
+        vec!["error".to_string()]
+    );
+
+    assert_eq!(
+        run("let a = 1 let b = 2"),
+        vec!["error".to_string()]
+    );
 }
 
 #[test]
-fn test_membership() {
-    // Test for membership in lists
+fn test_sets() {
+    // Tests for creating sets, which discard duplicate items
     assert_eq!(
         run(r#"
-let a = [1, 2, 3];
-print(1 in a);
-print(4 in a);
-print(1 not in a);
-print(4 not in a);
-
+let s = {1, 2, 2, "a"};
+print(s);
+print(set([3, 3, 4]));
+print(set((5,)));
+print(s.len());
+print(2 in s);
+print(3 not in s);
 "#
         ),
         vec![
+            "{1, 2, \"a\"}".to_string(),
+            "{3, 4}".to_string(),
+            "{5}".to_string(),
+            "3".to_string(),
+            "true".to_string(),
             "true".to_string(),
-            "false".to_string(),
-            "false".to_string(),
-            "true".to_string()
         ]
     );
 
-    // Test for membership in condition
+    // Tests for set methods
     assert_eq!(
         run(r#"
-let a = [1, 2, 3];
-if 1 in a:
-    print("1");
-
-if 4 in a:
-    print("2");
-
-if 1 not in a:
-    print("3");
-
-if 4 not in a:
-    print("4");
-
+let s = {1, 2};
+s.add(3);
+s.add(1);
+s.remove(2);
+print(s);
+print(s.contains(3));
+let t = {3, 4};
+print(s.union(t));
+print(s.intersection(t));
+print(s.intersection({5}));
+print({1, 2} == {2, 1});
 "#
         ),
         vec![
-            "1".to_string(),
-            "4".to_string(),
+            "{1, 3}".to_string(),
+            "true".to_string(),
+            "{1, 3, 4}".to_string(),
+            "{3}".to_string(),
+            "set()".to_string(),
+            "true".to_string(),
         ]
     );
-}
-
-#[test]
-fn test_negation() {
-    // Tests for negating booleans
-    assert_eq!(
-        run("print(!true);"),
-        vec!["false".to_string()]
-    );
 
+    // Tests for invalid set items and arguments
     assert_eq!(
-        run("print(!false);"),
-        vec!["true".to_string()]
+        run("let s = {[1]};"),
+        vec!["error".to_string()]
     );
 
-    // Tests for double negation
     assert_eq!(
-        run("print(!!true);"),
-        vec!["true".to_string()]
+        run("let s = {1};\ns.remove(2);"),
+        vec!["error".to_string()]
     );
 
     assert_eq!(
-        run("print(!!false);"),
-        vec!["false".to_string()]
+        run("let s = {1};\nprint(s.union([2]));"),
+        vec!["error".to_string()]
     );
 
-    // Tests for negating different values
     assert_eq!(
-        run("print(!123);"),
-        vec!["false".to_string()]
+        run("print(set(1));"),
+        vec!["error".to_string()]
     );
+}
 
+#[test]
+fn test_size_limits() {
+    // Tests that repeatedly doubling a string stops at the size limit
     assert_eq!(
-        run("print(!0);"),
-        vec!["false".to_string()]
+        run(r#"
+let s = "a";
+while true:
+    s = s + s;
+"#
+        ),
+        vec!["error".to_string()]
     );
 
     assert_eq!(
-        run("print(!null);"),
-        vec!["true".to_string()]
+        run(r#"
+let s = "a";
+while true:
+    s = s.replace("a", "aa");
+"#
+        ),
+        vec!["error".to_string()]
     );
 
+    // Tests that the error reports the line the value was created on
     assert_eq!(
-        run("print(!\"\");"),
-        vec!["false".to_string()]
+        crate::run::run("let s = \"a\";\nwhile true:\n  s = s + s;", false),
+        vec!["The value created on line 3 is too large".to_string()]
     );
 
-    // Test for negating a function
+    // Tests that values within the limit can still be built
     assert_eq!(
         run(r#"
-def foo():
-    return true;
-print(!foo());
-
+let s = "a";
+for i in 0..10:
+    s = s + s;
+print(s.len());
 "#
         ),
-        vec!["false".to_string()]
+        vec!["1024".to_string()]
     );
 }
 
 #[test]
-fn test_not_equals() {
-    // Test null
+fn test_strings() {
+    // Test for string concatenation
     assert_eq!(
-        run("print(null != null);"),
-        vec!["false".to_string()]
+        run("print(\"(\" + \"\" + \")\");"),
+        vec!["()".to_string()]
     );
 
-    // Test boolean
+    // Test for simple strings
     assert_eq!(
-        run("print(true != true);"),
-        vec!["false".to_string()]
+        run("print(\"some string\");"),
+        vec!["some string".to_string()]
     );
 
+    // Tests for string methods
     assert_eq!(
-        run("print(true != false);"),
-        vec!["true".to_string()]
+        run(r#"
+let s = "  Hello, World  ";
+let t = s.trim();
+print(t);
+print(t.upper());
+print(t.lower());
+print(t.len());
+print(t.split(", "));
+print(t.replace("World", "there"));
+print(t.startsWith("Hello"));
+print(t.endsWith("World"));
+print(t.contains("lo, W"));
+print(s);
+"#
+        ),
+        vec![
+            "Hello, World".to_string(),
+            "HELLO, WORLD".to_string(),
+            "hello, world".to_string(),
+            "12".to_string(),
+            "[\"Hello\", \"World\"]".to_string(),
+            "Hello, there".to_string(),
+            "true".to_string(),
+            "true".to_string(),
+            "true".to_string(),
+            "  Hello, World  ".to_string(),
+        ]
     );
 
-    // Test numerical
+    // Tests for converting between strings and lists
     assert_eq!(
-        run("print(1 != 1);"),
-        vec!["false".to_string()]
+        run(r#"
+let s = "abc";
+let letters = s.chars();
+print(letters);
+print(letters.join("-"));
+let empty = [];
+print(empty.join(", "));
+"#
+        ),
+        vec![
+            "[\"a\", \"b\", \"c\"]".to_string(),
+            "a-b-c".to_string(),
+            "".to_string(),
+        ]
     );
 
     assert_eq!(
-        run("print(1 != 2);"),
-        vec!["true".to_string()]
+        run("let a = [\"a\", 1];\nprint(a.join(\"\"));"),
+        vec!["error".to_string()]
     );
 
-    // Test strings
+    // Tests for escape sequences
     assert_eq!(
-        run("print(\"str\" != \"str\");"),
-        vec!["false".to_string()]
+        run(r#"print("line one\nline \"two\"\t\\");"#),
+        vec!["line one\nline \"two\"\t\\".to_string()]
     );
 
+    // Tests for multi-line strings inside an indented body
     assert_eq!(
-        run("print(\"str\" != \"ing\");"),
-        vec!["true".to_string()]
+        run("def f():\n    return \"\"\"first\nsecond \"quoted\"\n  third\"\"\";\nprint(f());\nprint(1);"),
+        vec!["first\nsecond \"quoted\"\n  third".to_string(), "1".to_string()]
     );
 
-    // Test mixed types
+    // Tests for invalid string method calls
     assert_eq!(
-        run("print(false != null);"),
-        vec!["true".to_string()]
+        run("let s = \"a\";\nprint(s.reverse());"),
+        vec!["error".to_string()]
     );
 
     assert_eq!(
-        run("print(false != 0);"),
-        vec!["true".to_string()]
+        run("let s = \"a\";\nprint(s.split(1));"),
+        vec!["error".to_string()]
     );
 
     assert_eq!(
-        run("print(0 != \"0\");"),
-        vec!["true".to_string()]
+        run("let s = \"a\";\nprint(s.upper(1));"),
+        vec!["error".to_string()]
     );
 }
 
 #[test]
-fn test_nums()  {
-    // Tests for decimal points in numbers
+fn test_trailing_commas() {
+    // Tests for trailing commas in every comma-separated list
     assert_eq!(
-        run("print(123.);"),
-        vec!["error".to_string()]
+        run(r#"
+print([1, 2, 3,]);
+print([]);
+def add(a, b,):
+    return a + b;
+print(add(1, 2,));
+print({"a": 1,});
+print({1, 2,});
+print((1, 2,));
+"#
+        ),
+        vec![
+            "[1, 2, 3]".to_string(),
+            "[]".to_string(),
+            "3".to_string(),
+            "{\"a\": 1}".to_string(),
+            "{1, 2}".to_string(),
+            "(1, 2)".to_string(),
+        ]
     );
 
+    // Tests that a comma must follow an item
     assert_eq!(
-        run("print(.123);"),
+        run("print([,]);"),
         vec!["error".to_string()]
     );
 
     assert_eq!(
-        run("print(123.456);"),
-        vec!["123.456".to_string()]
+        run("def f(a):\n    return a;\nprint(f(,));"),
+        vec!["error".to_string()]
     );
 
-    // Test for zero
     assert_eq!(
-        run("print(0);"),
-        vec!["0".to_string()]
+        run("print([1,,]);"),
+        vec!["error".to_string()]
     );
+}
 
+#[test]
+fn test_try() {
+    // Tests for catching runtime errors and binding their message
     assert_eq!(
-        run("print(-0);"),
-        vec!["-0".to_string()]
+        run(r#"
+let items = [1, 2];
+try:
+    print(items[5]);
+    print("unreachable");
+except e:
+    print(e);
+print("recovered");
+"#
+        ),
+        vec![
+            "The list index was out of range".to_string(),
+            "recovered".to_string(),
+        ]
     );
 
-    // Test for mix of decimals and negatives
+    // Tests that finally always runs, whether or not an error was raised
     assert_eq!(
-        run("print(-123.456);"),
-        vec!["-123.456".to_string()]
+        run(r#"
+try:
+    print("body");
+finally:
+    print("finally");
+try:
+    print(1 + true);
+except:
+    print("caught");
+finally:
+    print("finally");
+"#
+        ),
+        vec![
+            "body".to_string(),
+            "finally".to_string(),
+            "caught".to_string(),
+            "finally".to_string(),
+        ]
     );
 
+    // Tests for errors raised inside functions, and returning from a try body
     assert_eq!(
-        run("print(-0.001);"),
-        vec!["-0.001".to_string()]
+        run(r#"
+def divide(a, b):
+    if b == 0:
+        return a / null;
+    return a / b;
+def safe_divide(a, b):
+    try:
+        return divide(a, b);
+    except:
+        return 0;
+    finally:
+        print("divided");
+print(safe_divide(6, 3));
+print(safe_divide(6, 0));
+"#
+        ),
+        vec![
+            "divided".to_string(),
+            "2".to_string(),
+            "divided".to_string(),
+            "0".to_string(),
+        ]
     );
 
-    // NaN tests
+    // Tests that break and continue pass through a try statement
     assert_eq!(
-        run("print(0 / 0);"),
-        vec!["NaN".to_string()]
+        run(r#"
+for i in 0..5:
+    try:
+        if i == 1:
+            continue;
+        if i == 3:
+            break;
+        print(i);
+    except:
+        print("caught");
+"#
+        ),
+        vec!["0".to_string(), "2".to_string()]
     );
 
+    // Tests that an error is not caught without an except branch, and a try needs a branch
     assert_eq!(
         run(r#"
-let nan = 0 / 0;
-print(nan == 0);
-print(nan != 1);
-print(nan == nan);
-print(nan != nan);
+try:
+    print(1 + true);
+finally:
+    print("finally");
+"#
+        ),
+        vec!["error".to_string()]
+    );
 
+    assert_eq!(
+        run(r#"
+try:
+    print(1);
+print(2);
 "#
         ),
-        vec!["false".to_string(), "true".to_string(), "false".to_string(), "true".to_string()]
-    )
+        vec!["error".to_string()]
+    );
 }
 
 #[test]
-fn test_precedence() {
-    // Tests for BODMAS precedence
+fn test_tuples() {
+    // Tests for creating, printing and indexing tuples
     assert_eq!(
-        run("print(2 + 3 * 4);"),
-        vec!["14".to_string()]
+        run(r#"
+let pair = (1, "a");
+print(pair);
+print(pair[1]);
+print(pair[0:]);
+print((5,));
+print((5));
+print("a" in pair);
+print(pair == (1, "a"));
+"#
+        ),
+        vec![
+            "(1, \"a\")".to_string(),
+            "a".to_string(),
+            "(1, \"a\")".to_string(),
+            "(5,)".to_string(),
+            "5".to_string(),
+            "true".to_string(),
+            "true".to_string(),
+        ]
     );
 
+    // Tests for destructuring tuples and lists
     assert_eq!(
-        run("print(20 - 3 * 4);"),
-        vec!["8".to_string()]
+        run(r#"
+let pair = (1, "a");
+let a, b = pair;
+print(a);
+print(b);
+let x, y, z = [4, 5, 6];
+print(x + y + z);
+"#
+        ),
+        vec!["1".to_string(), "a".to_string(), "15".to_string()]
     );
 
+    // Tests that tuples are immutable and destructuring checks its length
     assert_eq!(
-        run("print(2 + 6 / 3);"),
-        vec!["4".to_string()]
+        run("let t = (1, 2);\nt[0] = 3;"),
+        vec!["error".to_string()]
     );
 
     assert_eq!(
-        run("print(2 - 6 / 3);"),
-        vec!["0".to_string()]
+        run("let a, b = (1, 2, 3);"),
+        vec!["error".to_string()]
     );
 
-    // Test for spacing with - symbol
     assert_eq!(
-        run("print(1- 1);"),
-        vec!["0".to_string()]
+        run("let a, b = 1;"),
+        vec!["error".to_string()]
     );
 
-    // Test for left associativity
     assert_eq!(
-        run("print(1 - 1 - 1);"),
-        vec!["-1".to_string()]
+        run("let a, a = (1, 2);"),
+        vec!["error".to_string()]
     );
 
-    // Test for right associativity
     assert_eq!(
-        run("print(1 - (1 - 1));"),
-        vec!["1".to_string()]
+        run("let a, b;"),
+        vec!["error".to_string()]
     );
 
-    // Test for precedence with grouping
+    // Tests for multiple assignment
     assert_eq!(
-        run("print(2 * (6 - (2 + 2)));"),
-        vec!["4".to_string()]
+        run(r#"
+let a, b = 1, 2;
+a, b = b, a;
+print(a);
+print(b);
+let c, d = [3, 4];
+a, c = c, a + d;
+print((a, b, c, d));
+"#),
+        vec!["2".to_string(), "1".to_string(), "(3, 1, 6, 4)".to_string()]
     );
-}
 
-#[test]
-fn test_print() {
-    // Test printing a simple string
+    // Tests for assigning to several items of lists and fields of instances at once
     assert_eq!(
-        run("print(\"Hello, World!\");"),
-        vec!["Hello, World!".to_string()]
+        run(r#"
+let xs = [5, 1, 4, 2, 3];
+for i in 0..xs.len():
+    for j in 0..xs.len() - i - 1:
+        if xs[j] > xs[j + 1]:
+            xs[j], xs[j + 1] = xs[j + 1], xs[j];
+print(xs);
+class Point:
+    def __init__(self, x, y):
+        self.x = x;
+        self.y = y;
+let p = Point(1, 2);
+let d = {"a": 3};
+p.x, p.y, d["a"] = p.y, d["a"], p.x;
+print((p.x, p.y, d["a"]));
+"#),
+        vec!["[1, 2, 3, 4, 5]".to_string(), "(2, 3, 1)".to_string()]
     );
 
-    // Test an empty print statement
     assert_eq!(
-        run("print();"),
+        run("let a = 1;\na, b = 1, 2;"),
         vec!["error".to_string()]
     );
 
-    // Test printing an assignment statement
     assert_eq!(
-        run(r#"
-let a = 2;
-print(a = a + 1);
-print(a);
+        run("let a, b = 1, 2;\na, b + 1 = 1, 2;"),
+        vec!["error".to_string()]
+    );
 
-"#
-        ),
-        vec!["3".to_string(), "3".to_string()]
+    assert_eq!(
+        run("let a, b = 1, 2;\na, b = 1, 2, 3;"),
+        vec!["error".to_string()]
     );
 }
 
 #[test]
-fn test_returns() {
-    // Test for returning in an else branch
+fn test_type_annotations() {
+    // Tests that annotated code runs as it would without its annotations
     assert_eq!(
         run(r#"
-def f():
-    if false:
-        "no";
-    else:
-        return "ok";
-print(f());
-
+let greeting: str = "hello";
+def shout(text: str, times: num) -> str:
+    let result: str = "";
+    for i in 0..times:
+        result = result + text;
+    return result;
+class Counter:
+    def __init__(self, start: num):
+        self.count = start;
+    def next(self, amount: num) -> num:
+        self.count = self.count + amount;
+        return self.count;
+let counter = Counter(1);
+print(shout(greeting, 2));
+print(counter.next(2));
 "#
         ),
-        vec!["ok".to_string()]
+        vec!["hellohello".to_string(), "3".to_string()]
     );
 
-    // Test for returning in an if statement
+    // Tests for mismatched and unknown types
     assert_eq!(
-        run(r#"
-def f():
-    if true:
-        return "ok";
-print(f());
-
-"#
-        ),
-        vec!["ok".to_string()]
+        run("let x: num = \"five\";"),
+        vec!["error".to_string()]
     );
 
-    // Test for returning in a while loop
     assert_eq!(
-        run(r#"
-def f():
-    while true:
-        return "ok";
-print(f());
-
-"#
-        ),
-        vec!["ok".to_string()]
+        run("def f(a: str):\n    pass;\nf(1);"),
+        vec!["error".to_string()]
     );
 
-    // Test for returning at the top level
     assert_eq!(
-        run("return \"at top level\";"),
+        run("let x: number = 5;"),
         vec!["error".to_string()]
     );
 
-    // Test for unreachable print after return
     assert_eq!(
-        run(r#"
-def f():
-    return;
-    print("unreachable");
-print(f());
-
-"#
-        ),
-        vec!["null".to_string()]
+        run("let x: = 5;"),
+        vec!["error".to_string()]
     );
 }
 
 #[test]
-fn test_strings() {
-    // Test for string concatenation
-    assert_eq!(
-        run("print(\"(\" + \"\" + \")\");"),
-        vec!["()".to_string()]
-    );
-
-    // Test for simple strings
-    assert_eq!(
-        run("print(\"some string\");"),
-        vec!["some string".to_string()]
-    );
+fn test_uuid() {
+    // Tests that uuid returns version 4 UUIDs that differ each time
+    let output = run("print(uuid());\nprint(uuid());\nprint(uuid() == uuid());");
+    assert_eq!(output[2], "false");
+    for uuid in &output[..2] {
+        let groups: Vec<&str> = uuid.split('-').collect();
+        let lengths: Vec<usize> = groups.iter().map(|group| group.len()).collect();
+        assert_eq!(lengths, vec![8, 4, 4, 4, 12]);
+        assert!(uuid.chars().all(|c| c == '-' || c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+        assert!(groups[2].starts_with('4'));
+        assert!(matches!(groups[3].chars().next(), Some('8' | '9' | 'a' | 'b')));
+    }
+    assert_ne!(output[0], output[1]);
 }
 
 #[test]
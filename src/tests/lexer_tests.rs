@@ -9,8 +9,8 @@ macro_rules! token {
     ($token:ident ; $lexeme:literal ; $lit:literal ; $line:literal ; $start:literal ; $end:literal) => {
         Token {
             token_type: TokenType::$token,
-            lexeme: $lexeme.to_string(),
-            literal: $lit.to_string(),
+            lexeme: $lexeme.into(),
+            literal: $lit.into(),
             line: $line,
             start: $start,
             end: $end
@@ -39,12 +39,24 @@ fn test_comments() {
         vec![
         ]
     );
+
+    // `//` after an operand is floor division rather than a comment
+    assert_eq!(
+        lex("a // 2; // Comment body\n"),
+        vec![
+            token!(Identifier ; "a" ; "" ; 1 ; 0 ; 1),
+            token!(FSlashFSlash ; "//" ; "" ; 1 ; 2 ; 4),
+            token!(Num ; "2" ; "2" ; 1 ; 5 ; 6),
+            token!(Semicolon ; ";" ; "" ; 1 ; 6 ; 7),
+            token!(Eof ; "" ; "" ; 2 ; 24 ; 24)
+        ]
+    );
 }
 
 #[test]
 fn test_double_symbols() {
     assert_eq!(
-        lex("-- ++ != == <= >= .."),
+        lex("-- ++ != == <= >= .. ->"),
         vec![
             token!(Decr ; "--" ; "" ; 1 ; 0 ; 2),
             token!(Incr ; "++" ; "" ; 1 ; 3 ; 5),
@@ -53,7 +65,8 @@ fn test_double_symbols() {
             token!(LessEqual ; "<=" ; "" ; 1 ; 12 ; 14),
             token!(GreaterEqual ; ">=" ; "" ; 1 ; 15 ; 17),
             token!(DotDot ; ".." ; "" ; 1 ; 18 ; 20),
-            token!(Eof ; "" ; "" ; 1 ; 20 ; 20)
+            token!(Arrow ; "->" ; "" ; 1 ; 21 ; 23),
+            token!(Eof ; "" ; "" ; 1 ; 23 ; 23)
         ]
     );
 }
@@ -104,25 +117,68 @@ fn test_identifiers() {
 fn test_keywords() {
     assert_eq!(
         lex(
-            "and def else false for if let not null or print return step true while"
+            "and class def else false for if let not null or print return step true while"
         ),
         vec![
             token!(And ; "and" ; "" ; 1 ; 0 ; 3),
-            token!(Def ; "def" ; "" ; 1 ; 4 ; 7),
-            token!(Else ; "else" ; "" ; 1 ; 8 ; 12),
-            token!(False ; "false" ; "" ; 1 ; 13 ; 18),
-            token!(For ; "for" ; "" ; 1 ; 19 ; 22),
-            token!(If ; "if" ; "" ; 1 ; 23 ; 25),
-            token!(Let ; "let" ; "" ; 1 ; 26 ; 29),
-            token!(Not ; "not" ; "" ; 1 ; 30 ; 33),
-            token!(Null ; "null" ; "" ; 1 ; 34 ; 38),
-            token!(Or ; "or" ; "" ; 1 ; 39 ; 41),
-            token!(Print ; "print" ; "" ; 1 ; 42 ; 47),
-            token!(Return ; "return" ; "" ; 1 ; 48 ; 54),
-            token!(Step ; "step" ; "" ; 1 ; 55 ; 59),
-            token!(True ; "true" ; "" ; 1 ; 60 ; 64),
-            token!(While ; "while" ; "" ; 1 ; 65 ; 70),
-            token!(Eof ; "" ; "" ; 1 ; 70 ; 70)
+            token!(Class ; "class" ; "" ; 1 ; 4 ; 9),
+            token!(Def ; "def" ; "" ; 1 ; 10 ; 13),
+            token!(Else ; "else" ; "" ; 1 ; 14 ; 18),
+            token!(False ; "false" ; "" ; 1 ; 19 ; 24),
+            token!(For ; "for" ; "" ; 1 ; 25 ; 28),
+            token!(If ; "if" ; "" ; 1 ; 29 ; 31),
+            token!(Let ; "let" ; "" ; 1 ; 32 ; 35),
+            token!(Not ; "not" ; "" ; 1 ; 36 ; 39),
+            token!(Null ; "null" ; "" ; 1 ; 40 ; 44),
+            token!(Or ; "or" ; "" ; 1 ; 45 ; 47),
+            token!(Print ; "print" ; "" ; 1 ; 48 ; 53),
+            token!(Return ; "return" ; "" ; 1 ; 54 ; 60),
+            token!(Step ; "step" ; "" ; 1 ; 61 ; 65),
+            token!(True ; "true" ; "" ; 1 ; 66 ; 70),
+            token!(While ; "while" ; "" ; 1 ; 71 ; 76),
+            token!(Eof ; "" ; "" ; 1 ; 76 ; 76)
+        ]
+    );
+
+    assert_eq!(
+        lex("break continue elif del pass"),
+        vec![
+            token!(Break ; "break" ; "" ; 1 ; 0 ; 5),
+            token!(Continue ; "continue" ; "" ; 1 ; 6 ; 14),
+            token!(Elif ; "elif" ; "" ; 1 ; 15 ; 19),
+            token!(Del ; "del" ; "" ; 1 ; 20 ; 23),
+            token!(Pass ; "pass" ; "" ; 1 ; 24 ; 28),
+            token!(Eof ; "" ; "" ; 1 ; 28 ; 28)
+        ]
+    );
+
+    assert_eq!(
+        lex("try except finally raise"),
+        vec![
+            token!(Try ; "try" ; "" ; 1 ; 0 ; 3),
+            token!(Except ; "except" ; "" ; 1 ; 4 ; 10),
+            token!(Finally ; "finally" ; "" ; 1 ; 11 ; 18),
+            token!(Raise ; "raise" ; "" ; 1 ; 19 ; 24),
+            token!(Eof ; "" ; "" ; 1 ; 24 ; 24)
+        ]
+    );
+
+    assert_eq!(
+        lex("match case loop"),
+        vec![
+            token!(Match ; "match" ; "" ; 1 ; 0 ; 5),
+            token!(Case ; "case" ; "" ; 1 ; 6 ; 10),
+            token!(Loop ; "loop" ; "" ; 1 ; 11 ; 15),
+            token!(Eof ; "" ; "" ; 1 ; 15 ; 15)
+        ]
+    );
+
+    assert_eq!(
+        lex("import enum"),
+        vec![
+            token!(Import ; "import" ; "" ; 1 ; 0 ; 6),
+            token!(Enum ; "enum" ; "" ; 1 ; 7 ; 11),
+            token!(Eof ; "" ; "" ; 1 ; 11 ; 11)
         ]
     );
 }
@@ -232,6 +288,23 @@ fn test_nums() {
             token!(Eof ; "" ; "" ; 1 ; 14 ; 14),
         ]
     );
+
+    // Tests for underscores separating the digits of a number
+    assert_eq!(
+        lex("1_000_000 + 0.000_5;"),
+        vec![
+            token!(Num ; "1_000_000" ; "1000000" ; 1 ; 0 ; 9),
+            token!(Plus ; "+" ; "" ; 1 ; 10 ; 11),
+            token!(Num ; "0.000_5" ; "0.0005" ; 1 ; 12 ; 19),
+            token!(Semicolon ; ";" ; "" ; 1 ; 19 ; 20),
+            token!(Eof ; "" ; "" ; 1 ; 20 ; 20),
+        ]
+    );
+
+    assert_eq!(lex("1__000;"), Vec::new());
+    assert_eq!(lex("1000_;"), Vec::new());
+    assert_eq!(lex("1_.5;"), Vec::new());
+    assert_eq!(lex("1.5_;"), Vec::new());
 }
 
 #[test]
@@ -271,6 +344,72 @@ fn test_strings() {
         ]
     );
 
+    // Tests for strings too long to be stored inline in the token
+    assert_eq!(
+        lex("\"a string that is longer than twenty two bytes\";"),
+        vec![
+            token!(String ; "\"a string that is longer than twenty two bytes\"" ; "a string that is longer than twenty two bytes" ; 1 ; 0 ; 47),
+            token!(Semicolon ; ";" ; "" ; 1 ; 47 ; 48),
+            token!(Eof ; "" ; "" ; 1 ; 48 ; 48)
+        ]
+    );
+
+    // Tests for escape sequences, which are replaced in the literal but not the lexeme
+    assert_eq!(
+        lex(r#""a\tb\n\"c\"\\";"#),
+        vec![
+            token!(String ; r#""a\tb\n\"c\"\\""# ; "a\tb\n\"c\"\\" ; 1 ; 0 ; 15),
+            token!(Semicolon ; ";" ; "" ; 1 ; 15 ; 16),
+            token!(Eof ; "" ; "" ; 1 ; 16 ; 16)
+        ]
+    );
+
+    // Tests for characters of more than one byte, whose tokens are measured in bytes
+    assert_eq!(
+        lex("\"héllo ✓\"; é;"),
+        vec![
+            token!(String ; "\"héllo ✓\"" ; "héllo ✓" ; 1 ; 0 ; 12),
+            token!(Semicolon ; ";" ; "" ; 1 ; 12 ; 13),
+            token!(Identifier ; "é" ; "" ; 1 ; 14 ; 16),
+            token!(Semicolon ; ";" ; "" ; 1 ; 16 ; 17),
+            token!(Eof ; "" ; "" ; 1 ; 17 ; 17)
+        ]
+    );
+
+    assert_eq!(
+        lex(r#""invalid \q escape";"#),
+        vec![]
+    );
+
+    assert_eq!(
+        lex(r#""ends with a backslash \"#),
+        vec![]
+    );
+
+    // Tests for multi-line strings, whose newlines do not produce indentation tokens
+    assert_eq!(
+        lex("\"\"\"one\n    two\"\"\";"),
+        vec![
+            token!(String ; "\"\"\"one\n    two\"\"\"" ; "one\n    two" ; 2 ; 0 ; 17),
+            token!(Semicolon ; ";" ; "" ; 2 ; 17 ; 18),
+            token!(Eof ; "" ; "" ; 2 ; 18 ; 18)
+        ]
+    );
+
+    assert_eq!(
+        lex("\"\"\"unterminated\n\"\";"),
+        vec![]
+    );
+
+    assert_eq!(
+        lex("\"\";"),
+        vec![
+            token!(String ; "\"\"" ; "" ; 1 ; 0 ; 2),
+            token!(Semicolon ; ";" ; "" ; 1 ; 2 ; 3),
+            token!(Eof ; "" ; "" ; 1 ; 3 ; 3)
+        ]
+    );
+
     assert_eq!(
         lex("\"Unterminated"),
         vec![]
@@ -1,2 +1,9 @@
 pub mod lexer_tests;
-pub mod interpreter_tests;
\ No newline at end of file
+pub mod interpreter_tests;
+pub mod resolver_tests;
+pub mod run_tests;
+pub mod semanticanalyser_tests;
+pub mod example_tests;
+pub mod engine_tests;
+pub mod sandbox_tests;
+pub mod stdlib_tests;
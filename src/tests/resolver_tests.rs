@@ -0,0 +1,134 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    environment::Environment,
+    expr::Expr,
+    lexer::Lexer,
+    parser::Parser,
+    resolver::{Resolver, Slot},
+    stmt::Stmt,
+};
+
+fn resolve(source: &str) -> Vec<Stmt> {
+    let tokens = Lexer::new(source.to_string(), 4).run().unwrap();
+    let ast = Parser::new(tokens).parse().unwrap();
+    Resolver::new(Rc::new(RefCell::new(Environment::new(None)))).resolve(&ast);
+    return ast;
+}
+
+/// Returns the slot of the variable an expression statement or `return` statement refers to.
+fn slot(stmt: &Stmt) -> Option<Slot> {
+    return match stmt {
+        Stmt::Expression { expression: Expr::Var { slot, .. } }
+        | Stmt::Expression { expression: Expr::Assign { slot, .. } }
+        | Stmt::Return { value: Some(Expr::Var { slot, .. }), .. } => slot.get(),
+        _ => panic!("{stmt} does not refer to a variable"),
+    };
+}
+
+/// Returns the body of a function, loop or class method.
+fn body(stmt: &Stmt) -> &[Stmt] {
+    return match stmt {
+        Stmt::Function { body, .. } => body,
+        Stmt::For { body, .. } | Stmt::ForEach { body, .. } => body,
+        Stmt::Class { methods, .. } => body(&methods[0]),
+        _ => panic!("{stmt} has no body"),
+    };
+}
+
+#[test]
+fn test_locals() {
+    // Tests that parameters come before the locals of a function, in the order each is declared
+    let ast = resolve(r#"
+def f(a, b):
+    let c = a;
+    c;
+    b = 1;
+    return a;
+"#);
+    let function = body(&ast[0]);
+    assert_eq!(slot(&function[1]), Some(Slot::Local { depth: 0, index: 2 }));
+    assert_eq!(slot(&function[2]), Some(Slot::Local { depth: 0, index: 1 }));
+    assert_eq!(slot(&function[3]), Some(Slot::Local { depth: 0, index: 0 }));
+
+    // Tests that a name declared anywhere in a function has its index in the order of the
+    // declarations, including inside an `if` branch, but that a reference before the
+    // declaration refers to the variable in the enclosing scope
+    let ast = resolve(r#"
+def f():
+    later;
+    if true:
+        let first = 1;
+    let later = 2;
+    later;
+"#);
+    let function = body(&ast[0]);
+    assert_eq!(slot(&function[0]), Some(Slot::Global { index: 0 }));
+    assert_eq!(slot(&function[3]), Some(Slot::Local { depth: 0, index: 1 }));
+
+    // Tests that a function refers to a local declared after it, as it can only be called once
+    // both have been declared
+    let ast = resolve(r#"
+def f():
+    def g():
+        return h;
+    def h():
+        return g;
+"#);
+    let function = body(&ast[0]);
+    assert_eq!(slot(&body(&function[0])[0]), Some(Slot::Local { depth: 1, index: 1 }));
+    assert_eq!(slot(&body(&function[1])[0]), Some(Slot::Local { depth: 1, index: 0 }));
+}
+
+#[test]
+fn test_globals() {
+    // Tests that names declared at the top level, and names that are not declared at all, are
+    // globals, both at the top level and inside functions, each at an index of its own
+    let ast = resolve(r#"
+let a = 1;
+a;
+def f():
+    a;
+    return abs;
+"#);
+    assert_eq!(slot(&ast[1]), Some(Slot::Global { index: 0 }));
+    assert_eq!(slot(&body(&ast[2])[0]), Some(Slot::Global { index: 0 }));
+    assert_eq!(slot(&body(&ast[2])[1]), Some(Slot::Global { index: 1 }));
+}
+
+#[test]
+fn test_nested_scopes() {
+    // Tests the depth of variables declared in enclosing functions and loops
+    let ast = resolve(r#"
+def outer(a):
+    for item in [1, 2]:
+        def inner():
+            return item;
+        a;
+"#);
+    let foreach = &body(&ast[0])[0];
+    assert_eq!(slot(&body(&body(foreach)[0])[0]), Some(Slot::Local { depth: 1, index: 0 }));
+    assert_eq!(slot(&body(foreach)[1]), Some(Slot::Local { depth: 1, index: 0 }));
+
+    // Tests that the body of a `for` loop reads the copy of the loop variable in the scope of
+    // each iteration, while the variable itself is declared outside of the loop
+    let ast = resolve(r#"
+def f():
+    for i in 0..3:
+        i;
+    i;
+"#);
+    assert_eq!(slot(&body(&body(&ast[0])[0])[0]), Some(Slot::Local { depth: 0, index: 0 }));
+    assert_eq!(slot(&body(&ast[0])[1]), Some(Slot::Local { depth: 0, index: 0 }));
+
+    // Tests that `self` is in a scope of its own enclosing the other parameters of a method
+    let ast = resolve(r#"
+class Counter:
+    def add(self, n):
+        n;
+        return self;
+"#);
+    let method = body(&ast[0]);
+    assert_eq!(slot(&method[0]), Some(Slot::Local { depth: 0, index: 0 }));
+    assert_eq!(slot(&method[1]), Some(Slot::Local { depth: 1, index: 0 }));
+}
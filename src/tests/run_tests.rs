@@ -0,0 +1,518 @@
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    evaluator::{
+        max_depth_for, CancelToken, GlobalDefinition, InputSource, Limit, NativePolicy,
+        OutputSink, DEFAULT_STACK_SIZE, MAX_OUTPUT_LINES,
+    },
+    lexer::MAX_SOURCE_LENGTH,
+    metrics::Metrics,
+    parser::MAX_NESTING,
+    run::{
+        ast, output, run, run_events, run_events_on, run_events_with, run_with, tokens, Backend,
+        Phase, RunEvent,
+    },
+};
+
+#[test]
+fn test_run_events() {
+    // Tests that output is collected in order and the run always finishes
+    let events: Vec<RunEvent> = run_events("print(1);\nprint(\"two\");", false).collect();
+    assert_eq!(events.len(), 4);
+    assert_eq!(events[0], RunEvent::Output("1".to_string()));
+    assert_eq!(events[1], RunEvent::Output("two".to_string()));
+    assert_eq!(events[2], RunEvent::Globals(Vec::new()));
+    match &events[3] {
+        RunEvent::Finished { stats } => {
+            assert_eq!(stats.statements, 2);
+            assert_eq!(stats.output_lines, 2);
+        },
+        _ => panic!("Expected the run to finish"),
+    }
+
+    // Tests that the globals defined by the program are reported, without the native functions
+    let events: Vec<RunEvent> = run_events(
+        "let b = \"text\";\nlet a = [1];\ndef f():\n  return;\n",
+        false
+    ).collect();
+    assert_eq!(
+        events[0],
+        RunEvent::Globals(vec![
+            GlobalDefinition { name: "a".to_string(), type_name: "list".to_string(), value: None },
+            GlobalDefinition {
+                name: "b".to_string(),
+                type_name: "string".to_string(),
+                value: Some("text".to_string())
+            },
+            GlobalDefinition { name: "f".to_string(), type_name: "function".to_string(), value: None },
+        ])
+    );
+    if let RunEvent::Globals(globals) = &events[0] {
+        assert_eq!(format!("{}", globals[1]), "b: string = text");
+    }
+
+    // Tests that a diagnostic reports the phase it came from
+    let events: Vec<RunEvent> = run_events("print(unknown);", false).collect();
+    assert!(matches!(
+        &events[0],
+        RunEvent::Diagnostic { phase: Phase::SemanticAnalyser, .. }
+    ));
+    assert!(matches!(events.last(), Some(RunEvent::Finished { .. })));
+
+    let events: Vec<RunEvent> = run_events("let a = \"unterminated;", false).collect();
+    assert!(matches!(&events[0], RunEvent::Diagnostic { phase: Phase::Lexer, .. }));
+
+    // Tests that comparing the results of float arithmetic with == produces a notice
+    let events: Vec<RunEvent> = run_events(
+        "print(0.1 + 0.2 == 0.3);\nprint(approxEqual(0.1 + 0.2, 0.3, 0.000001));",
+        false,
+    ).collect();
+    assert!(matches!(&events[0], RunEvent::Notice(notice) if notice.contains("approxEqual")));
+    assert_eq!(events[1], RunEvent::Output("false".to_string()));
+    assert_eq!(events[2], RunEvent::Output("true".to_string()));
+
+    let events: Vec<RunEvent> = run_events("print(1 + 2 == 3);\nprint(0.5 == 0.5);", false).collect();
+    assert!(!events.iter().any(|event| matches!(event, RunEvent::Notice(_))));
+}
+
+#[test]
+fn test_run_events_streamed() {
+    // Tests that a line is received as soon as it is printed, while the program is still running
+    let source = "print(1);\nlet start = clock();\nwhile clock() - start < 1:\n  pass;\nprint(2);";
+    let started = Instant::now();
+    let mut events =
+        run_events_with(source, false, NativePolicy::All, OutputSink::Quiet, InputSource::Stdin);
+    assert_eq!(events.next(), Some(RunEvent::Output("1".to_string())));
+    assert!(started.elapsed() < Duration::from_millis(500));
+
+    // Tests that the rest of the events follow once the program has finished
+    assert_eq!(events.next(), Some(RunEvent::Output("2".to_string())));
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert!(matches!(events.next(), Some(RunEvent::Globals(_))));
+    assert!(matches!(events.next(), Some(RunEvent::Finished { .. })));
+    assert_eq!(events.next(), None);
+}
+
+#[test]
+fn test_fetch() {
+    // Tests that fetch returns the body of a successful response from a local server
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+        for status in ["200 OK", "404 Not Found"] {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            let response = format!(
+                "HTTP/1.1 {status}\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello"
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+
+    let source = format!("print(fetch(\"http://{address}/data\"));");
+    assert_eq!(run(&source, false), vec!["hello".to_string()]);
+
+    // Tests that an unsuccessful response is an error
+    let failure = run(&source, false);
+    assert!(failure[0].starts_with(&format!("Could not fetch http://{address}/data")));
+    server.join().unwrap();
+
+    assert!(run("print(fetch(\"not a url\"));", false)[0].starts_with("Could not fetch not a url"));
+
+    // Tests that a program run by the server cannot fetch anything
+    assert_eq!(
+        output(run_events_on(&Backend::InProcess, &source, NativePolicy::All, "", None, CancelToken::new())),
+        vec!["Undefined variable fetch on line 1".to_string()]
+    );
+}
+
+#[test]
+fn test_get_env() {
+    // Tests that a program run from the command line can read environment variables
+    std::env::set_var("PYRU_TEST_GET_ENV", "configured");
+    let source = "print(getEnv(\"PYRU_TEST_GET_ENV\"));\nprint(getEnv(\"PYRU_TEST_UNSET_VARIABLE\"));";
+    assert_eq!(run(source, false), vec!["configured".to_string(), "null".to_string()]);
+
+    // Tests that a program run by the server cannot, even when its policy allows it
+    let policies = [
+        NativePolicy::All,
+        NativePolicy::Allow(vec!["getEnv".to_string()]),
+        NativePolicy::Deny(vec!["clock".to_string()]),
+    ];
+    for natives in policies {
+        assert_eq!(
+            output(run_events_on(&Backend::InProcess, source, natives, "", None, CancelToken::new())),
+            vec!["Undefined variable getEnv on line 1".to_string()]
+        );
+    }
+}
+
+#[test]
+fn test_input() {
+    // Tests that a program reads the lines of its stdin in order, then null once they run out
+    let source = r#"
+let name = input("Name: ");
+print("Hello " + name);
+print(input(""));
+print(input(""));
+"#;
+    assert_eq!(
+        output(run_events_on(&Backend::InProcess, source, NativePolicy::All, "Ada\r\nLovelace\n", None, CancelToken::new())),
+        vec!["Hello Ada".to_string(), "Lovelace".to_string(), "null".to_string()]
+    );
+
+    assert_eq!(
+        output(run_events_on(&Backend::InProcess, "print(input(1));", NativePolicy::All, "", None, CancelToken::new())),
+        vec!["Expected the argument to be a string".to_string()]
+    );
+}
+
+#[test]
+fn test_checked_division() {
+    // Tests that dividing by zero is an error for programs run by the server, but not otherwise
+    assert_eq!(
+        output(run_events_on(&Backend::InProcess, "print(1 / 0);", NativePolicy::All, "", None, CancelToken::new())),
+        vec!["Cannot divide by zero in 1 / 0 on line 1".to_string()]
+    );
+    assert_eq!(output(run_events("print(1 / 0);", false)), vec!["inf".to_string()]);
+}
+
+#[test]
+fn test_limit_exceeded() {
+    // Tests that a program stopped by the output limit returns what it printed before then
+    let source = "let i = 0;\nwhile true:\n  print(i);\n  i = i + 1;\n";
+    let events: Vec<RunEvent> = run_events_with(source, false, NativePolicy::All, OutputSink::Quiet, InputSource::buffer("")).collect();
+    assert_eq!(events.len(), MAX_OUTPUT_LINES + 2);
+    assert_eq!(events[0], RunEvent::Output("0".to_string()));
+    assert!(matches!(
+        &events[MAX_OUTPUT_LINES],
+        RunEvent::LimitExceeded { limit: Limit::Output, line: Some(3), code, .. }
+            if code == "OutputLimitExceeded"
+    ));
+    match events.last() {
+        Some(RunEvent::Finished { stats }) => assert_eq!(stats.output_lines, MAX_OUTPUT_LINES),
+        _ => panic!("Expected the run to finish"),
+    }
+
+    // Tests that the memory limit stops a program and cannot be caught
+    let source = r#"
+print("before");
+let s = "ab";
+try:
+  while true:
+    s = s + s;
+except:
+  print("caught");
+"#;
+    let events: Vec<RunEvent> = run_events_with(source, false, NativePolicy::All, OutputSink::Quiet, InputSource::buffer("")).collect();
+    assert_eq!(events[0], RunEvent::Output("before".to_string()));
+    assert!(matches!(
+        &events[1],
+        RunEvent::LimitExceeded { limit: Limit::Memory, line: Some(6), .. }
+    ));
+
+    let metrics = Metrics::new().with_telemetry(true);
+    metrics.record(&events);
+    assert!(metrics.render().contains(
+        "pyru_diagnostics_total{phase=\"interpreter\",code=\"ValueTooLarge\"} 1\n"
+    ));
+
+    // Tests that a list of lists cannot be repeated into more memory than the program may use
+    let source = "print(\"before\");\nlet grid = [[0] * 1000] * 1000000;\n";
+    let events: Vec<RunEvent> = run_events_with(source, false, NativePolicy::All, OutputSink::Quiet, InputSource::buffer("")).collect();
+    assert_eq!(events[0], RunEvent::Output("before".to_string()));
+    assert!(matches!(
+        &events[1],
+        RunEvent::LimitExceeded { limit: Limit::Memory, line: Some(2), code, .. }
+            if code == "MemoryLimitExceeded"
+    ));
+
+    // Tests that a program run by the server cannot loop forever
+    let events: Vec<RunEvent> = run_events_on(&Backend::InProcess, "print(1);\nwhile true:\n  pass;\n", NativePolicy::All, "", None, CancelToken::new()).collect();
+    assert_eq!(events[0], RunEvent::Output("1".to_string()));
+    assert!(matches!(
+        &events[1],
+        RunEvent::LimitExceeded { limit: Limit::Steps, line: Some(3), code, .. }
+            if code == "StepLimitExceeded"
+    ));
+
+    // Tests that running out of steps counts as a timeout
+    let metrics = Metrics::new();
+    metrics.record(&events);
+    assert!(metrics.render().contains("pyru_timeouts_total 1\n"));
+
+    // Tests that the server can stop a program sooner with a timeout
+    let source = "print(1);\nlet i = 0;\nwhile i >= 0:\n  i = i + 1;\n";
+    let events: Vec<RunEvent> = run_events_on(&Backend::InProcess, source, NativePolicy::All, "", Some(Duration::from_millis(50)), CancelToken::new()).collect();
+    assert_eq!(events[0], RunEvent::Output("1".to_string()));
+    assert!(matches!(
+        &events[1],
+        RunEvent::LimitExceeded { limit: Limit::Time, line: Some(3), code, .. }
+            if code == "TimeLimitExceeded"
+    ));
+}
+
+#[test]
+fn test_cancel() {
+    // Tests that cancelling the token of a run stops a program that would otherwise loop until
+    // its step limit, after the output it printed before then
+    let cancel = CancelToken::new();
+    let source = "print(1);\nwhile true:\n  pass;\n";
+    let mut events = run_events_on(&Backend::InProcess, source, NativePolicy::All, "", None, cancel.clone());
+    assert_eq!(events.next(), Some(RunEvent::Output("1".to_string())));
+    cancel.cancel();
+    assert!(matches!(
+        events.next(),
+        Some(RunEvent::Diagnostic { phase: Phase::Evaluator, code, .. }) if code == "Cancelled"
+    ));
+    assert!(matches!(events.next(), Some(RunEvent::Finished { .. })));
+    assert_eq!(events.next(), None);
+
+    // Tests that a program cannot catch being cancelled
+    let source = "try:\n  while true:\n    pass;\nexcept:\n  print(\"caught\");\n";
+    assert_eq!(
+        output(run_events_on(&Backend::InProcess, source, NativePolicy::All, "", None, cancel)),
+        vec!["The program was cancelled before it finished".to_string()]
+    );
+}
+
+#[test]
+fn test_deep_recursion() {
+    // Tests that a program can recurse as deeply as fits on the stack of the thread it is run on
+    let limit = max_depth_for(DEFAULT_STACK_SIZE);
+    let count = |n: usize| format!(
+        "def count(n):\n  if n == 0:\n    return 0;\n  return count(n - 1) + 1;\nprint(count({n}));\n"
+    );
+    assert_eq!(run(&count(limit - 1), false), vec![(limit - 1).to_string()]);
+
+    // Tests that recursing past the limit stops the program rather than the process
+    let events: Vec<RunEvent> = run_events_with(&count(limit), false, NativePolicy::All, OutputSink::Quiet, InputSource::buffer("")).collect();
+    assert!(matches!(
+        &events[0],
+        RunEvent::LimitExceeded { limit: Limit::Recursion, line: Some(4), code, .. }
+            if code == "RecursionLimitExceeded"
+    ));
+
+    // Tests the same for methods that call each other
+    let source = r#"
+class Parity:
+  def even(self, n):
+    if n == 0:
+      return true;
+    return self.odd(n - 1);
+  def odd(self, n):
+    if n == 0:
+      return false;
+    return self.even(n - 1);
+let parity = Parity();
+print(parity.even(100));
+print(parity.even(5000));
+"#;
+    let events: Vec<RunEvent> = run_events_with(source, false, NativePolicy::All, OutputSink::Quiet, InputSource::buffer("")).collect();
+    assert_eq!(events[0], RunEvent::Output("true".to_string()));
+    assert!(matches!(&events[1], RunEvent::LimitExceeded { limit: Limit::Recursion, .. }));
+}
+
+#[test]
+fn test_run() {
+    assert_eq!(
+        run("let a = 5;\nprint(a * 2);", false),
+        vec!["10".to_string()]
+    );
+
+    assert_eq!(
+        run("print(;", false),
+        vec!["Expect expression after '(' on line 1 (commonly due to misspelling keywords)".to_string()]
+    );
+
+    // Tests that an uncaught raise reports the line it was raised on, after the output printed
+    // before it
+    assert_eq!(
+        run("print(1);\nraise \"bad input\";", false),
+        vec!["1".to_string(), "bad input (raised on line 2)".to_string()]
+    );
+}
+
+#[test]
+fn test_run_with() {
+    // Tests that only the natives permitted by the policy are defined
+    let source = "print(hash(\"a\") == hash(\"a\"));\nprint(clock() > 0);";
+    assert_eq!(
+        run_with(source, false, NativePolicy::All),
+        vec!["true".to_string(), "true".to_string()]
+    );
+
+    assert_eq!(
+        run_with(source, false, NativePolicy::Deny(vec!["clock".to_string()])),
+        vec!["true".to_string(), "Undefined variable clock on line 2".to_string()]
+    );
+
+    assert_eq!(
+        run_with(source, false, NativePolicy::Allow(vec!["clock".to_string()])),
+        vec!["Undefined variable hash on line 1".to_string()]
+    );
+
+    // Tests that a quiet run still collects its output
+    assert_eq!(
+        output(run_events_with(source, false, NativePolicy::All, OutputSink::Quiet, InputSource::buffer(""))),
+        vec!["true".to_string(), "true".to_string()]
+    );
+}
+
+#[test]
+fn test_metrics() {
+    let metrics = Metrics::new();
+    for source in ["print(1);", "print(;", "print(undefined);"] {
+        let events: Vec<RunEvent> = run_events(source, false).collect();
+        metrics.record(&events);
+    }
+
+    let rendered = metrics.render();
+    assert!(rendered.contains("pyru_executions_total 3\n"));
+    assert!(rendered.contains("pyru_errors_total{phase=\"lexer\"} 0\n"));
+    assert!(rendered.contains("pyru_errors_total{phase=\"parser\"} 1\n"));
+    assert!(rendered.contains("pyru_errors_total{phase=\"semantic\"} 1\n"));
+    assert!(rendered.contains("pyru_timeouts_total 0\n"));
+    assert!(rendered.contains("pyru_execution_duration_seconds_count 3\n"));
+    assert!(!rendered.contains("pyru_diagnostics_total"));
+
+    // Tests that telemetry counts the code of each diagnostic
+    let metrics = Metrics::new().with_telemetry(true);
+    for source in ["print(undefined);", "print(undefined);", "print(;"] {
+        let events: Vec<RunEvent> = run_events(source, false).collect();
+        metrics.record(&events);
+    }
+
+    let rendered = metrics.render();
+    assert!(rendered.contains(
+        "pyru_diagnostics_total{phase=\"semantic\",code=\"VariableNotFound\"} 2\n"
+    ));
+    assert!(rendered.contains("pyru_diagnostics_total{phase=\"parser\","));
+}
+
+#[test]
+fn test_tokens_and_ast() {
+    let source = "print(1);";
+    assert_eq!(tokens(source).map(|t| t.len()), Ok(6));
+    assert!(ast(source).is_ok_and(|ast| ast.starts_with("[\n    Print {")));
+
+    assert!(tokens("let a = \"unterminated;").is_err());
+    assert_eq!(
+        ast("print(;"),
+        Err("Expect expression after '(' on line 1 (commonly due to misspelling keywords)".to_string())
+    );
+}
+
+#[test]
+fn test_source_too_large() {
+    let source = format!("print(\"{}\");", "a".repeat(MAX_SOURCE_LENGTH));
+    assert_eq!(
+        run(&source, false),
+        vec![format!(
+            "The source code is {} bytes long, but the limit is {MAX_SOURCE_LENGTH} bytes",
+            source.len()
+        )]
+    );
+}
+
+#[test]
+fn test_nesting_limit() {
+    // Tests that expressions can be nested as deeply as the limit allows
+    let nested = |depth: usize, open: &str, close: &str| {
+        format!("print({}1{});", open.repeat(depth - 1), close.repeat(depth - 1))
+    };
+    assert_eq!(run(&nested(MAX_NESTING, "(", ")"), false), vec!["1".to_string()]);
+
+    // Tests that nesting any deeper is an error rather than overflowing the stack, however deep
+    // it is
+    let message = format!("Expressions and blocks cannot be nested more than {MAX_NESTING} levels deep, but they are on line 1");
+    assert_eq!(run(&nested(MAX_NESTING + 1, "[", "]"), false), vec![message.clone()]);
+    assert_eq!(ast(&nested(5_000, "(", ")")), Err(message.clone()));
+    assert_eq!(ast(&format!("print({}1);", "- ".repeat(5_000))), Err(message));
+
+    // Tests that blocks count towards the limit along with the expressions inside of them
+    let blocks = MAX_NESTING / 2;
+    let mut source = String::new();
+    for depth in 0..blocks {
+        source.push_str(&format!("{}if true:\n", "  ".repeat(depth)));
+    }
+    let inner = format!("{}{}\n", "  ".repeat(blocks), nested(MAX_NESTING - blocks, "(", ")"));
+    assert!(ast(&(source.clone() + &inner)).is_ok());
+    let inner = format!("{}{}\n", "  ".repeat(blocks), nested(MAX_NESTING - blocks + 1, "(", ")"));
+    assert!(ast(&(source + &inner)).is_err_and(|error| error.starts_with("Expressions and blocks cannot be nested")));
+}
+
+#[test]
+fn test_indentation_diagnostics() {
+    // Tests that a body which is not indented reports the levels of indentation involved
+    assert_eq!(
+        run("def f():\n  if true:\n  print(1);\n", false),
+        vec![
+            "Expected an indented if body on line 3, which is indented 1 levels but the block \
+            expects 2".to_string()
+        ]
+    );
+
+    // Tests that a line indented further than its block reports the levels of indentation
+    assert_eq!(
+        run("if true:\n      print(1);\n", false),
+        vec!["Line 2 is indented 3 levels but the block expects 1".to_string()]
+    );
+
+    // Tests that indentation which is not a multiple of the tab size reports the spaces used
+    assert_eq!(
+        run("if true:\n   print(1);\n", false),
+        vec![
+            "Incorrect indentation on line 2, which is indented 3 spaces but indentation must be \
+            a multiple of 2".to_string()
+        ]
+    );
+}
+
+#[test]
+fn test_numeric_separators() {
+    // Tests that underscores between digits are ignored in the value of a number
+    assert_eq!(
+        run("print(1_000_000 + 2_5.5);", false),
+        vec!["1000025.5".to_string()]
+    );
+
+    // Tests that an underscore which does not separate digits is reported
+    assert_eq!(
+        run("print(1__000);", false),
+        vec![
+            "Invalid underscore in the number '1__000' on line 1, underscores must separate digits"
+                .to_string()
+        ]
+    );
+}
+
+#[test]
+fn test_comparison_diagnostics() {
+    // Tests that ordering values which are not both numbers or both strings is reported
+    assert_eq!(
+        run("print(1 < \"2\");", false),
+        vec!["The two values could not be compared".to_string()]
+    );
+}
+
+#[test]
+fn test_spread_diagnostics() {
+    // Tests that the arity of a function is checked against the arguments after spreading
+    assert_eq!(
+        run("def f(a, b):\n  return a;\nf(*[1, 2, 3]);", false),
+        vec!["Expected 2 arguments but got 3".to_string()]
+    );
+
+    // Tests that spreading a value other than a list or tuple is reported
+    assert_eq!(
+        run("def f(a):\n  return a;\nf(*\"ab\");", false),
+        vec!["Only a list or tuple can be spread into arguments, but a string was given".to_string()]
+    );
+}
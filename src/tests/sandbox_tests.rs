@@ -0,0 +1,145 @@
+use std::{
+    fs,
+    os::unix::fs::PermissionsExt,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    evaluator::{CancelToken, Limit, NativePolicy},
+    run::{output, Phase, RunEvent},
+    sandbox::{serve, Sandbox, WorkerRequest},
+};
+
+fn serve_request(source: &str, natives: NativePolicy) -> Vec<RunEvent> {
+    let request = WorkerRequest {
+        source: source.to_string(),
+        natives,
+        stdin: String::new(),
+        timeout: None,
+    };
+    let mut response = Vec::new();
+    serve(serde_json::to_vec(&request).unwrap().as_slice(), &mut response).unwrap();
+    return read_events(&response);
+}
+
+fn read_events(response: &[u8]) -> Vec<RunEvent> {
+    return response
+        .split(|&byte| byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).unwrap())
+        .collect();
+}
+
+#[test]
+fn test_serve() {
+    // Tests that a worker runs the request it reads and writes back the events of the run
+    let events = serve_request("print(1 + 2);", NativePolicy::All);
+    assert_eq!(events[0], RunEvent::Output("3".to_string()));
+    assert!(matches!(events.last(), Some(RunEvent::Finished { .. })));
+
+    // Tests that the worker only defines the natives the request permits
+    let events = serve_request("print(clock());", NativePolicy::Deny(vec!["clock".to_string()]));
+    assert!(matches!(&events[0], RunEvent::Diagnostic { phase: Phase::Evaluator, .. }));
+
+    // Tests that the worker reads input from the stdin of the request
+    let request = WorkerRequest {
+        source: "print(input(\"> \"));\nprint(input(\"> \"));".to_string(),
+        natives: NativePolicy::All,
+        stdin: "first line".to_string(),
+        timeout: None,
+    };
+    let mut response = Vec::new();
+    serve(serde_json::to_vec(&request).unwrap().as_slice(), &mut response).unwrap();
+    assert_eq!(
+        output(read_events(&response)),
+        vec!["first line".to_string(), "null".to_string()]
+    );
+
+    // Tests that the worker stops the program at the timeout of the request
+    let request = WorkerRequest {
+        source: "print(1);\nwhile true:\n  pass;\n".to_string(),
+        natives: NativePolicy::All,
+        stdin: String::new(),
+        timeout: Some(Duration::from_millis(50)),
+    };
+    let mut response = Vec::new();
+    serve(serde_json::to_vec(&request).unwrap().as_slice(), &mut response).unwrap();
+    let events = read_events(&response);
+    assert_eq!(events[0], RunEvent::Output("1".to_string()));
+    assert!(matches!(&events[1], RunEvent::LimitExceeded { limit: Limit::Time, .. }));
+
+    // Tests that a request which cannot be read is an error
+    assert!(serve("not json".as_bytes(), Vec::new()).is_err());
+}
+
+#[test]
+fn test_sandbox_failures() {
+    // Tests that a worker which exits unsuccessfully is reported as having crashed
+    let events: Vec<RunEvent> =
+        Sandbox::new("false").run_events("print(1);", NativePolicy::All, "", None, CancelToken::new()).collect();
+    assert_eq!(events.len(), 2);
+    assert!(matches!(&events[0], RunEvent::Diagnostic { code, .. } if code == "WorkerCrashed"));
+    assert!(matches!(&events[1], RunEvent::Finished { .. }));
+
+    // Tests that a worker which writes back something other than events has crashed
+    let events: Vec<RunEvent> =
+        Sandbox::new("cat").run_events("print(1);", NativePolicy::All, "", None, CancelToken::new()).collect();
+    assert!(matches!(&events[0], RunEvent::Diagnostic { code, .. } if code == "WorkerCrashed"));
+
+    // Tests that a worker which is still running at its timeout is killed
+    let script = std::env::temp_dir().join(format!("pyru-sandbox-{}.sh", std::process::id()));
+    fs::write(&script, "#!/bin/sh\nexec sleep 5\n").unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let events: Vec<RunEvent> = Sandbox::new(&script)
+        .with_timeout(Duration::from_millis(50))
+        .run_events("print(1);", NativePolicy::All, "", None, CancelToken::new())
+        .collect();
+    fs::remove_file(&script).unwrap();
+
+    assert!(matches!(&events[0], RunEvent::LimitExceeded { limit: Limit::Time, line: None, .. }));
+    assert_eq!(
+        output(events),
+        vec!["The program ran for longer than its time limit of 50ms".to_string()]
+    );
+
+    // Tests that the events a worker writes are passed on as they are written, and kept when the
+    // worker is killed
+    let script = std::env::temp_dir().join(format!("pyru-stream-{}.sh", std::process::id()));
+    fs::write(&script, "#!/bin/sh\necho '{\"Output\":\"1\"}'\nexec sleep 5\n").unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let timeout = Duration::from_secs(1);
+    let started = Instant::now();
+    let mut events = Sandbox::new(&script)
+        .with_timeout(timeout)
+        .run_events("print(1);", NativePolicy::All, "", None, CancelToken::new());
+    assert_eq!(events.next(), Some(RunEvent::Output("1".to_string())));
+    assert!(started.elapsed() < timeout);
+
+    let events: Vec<RunEvent> = events.collect();
+    fs::remove_file(&script).unwrap();
+    assert!(matches!(&events[0], RunEvent::LimitExceeded { limit: Limit::Time, line: None, .. }));
+    assert!(matches!(&events[1], RunEvent::Finished { .. }));
+}
+
+#[test]
+fn test_sandbox_cancel() {
+    // Tests that a worker which is still running when its run is cancelled is killed, keeping
+    // the events it wrote before then
+    let script = std::env::temp_dir().join(format!("pyru-cancel-{}.sh", std::process::id()));
+    fs::write(&script, "#!/bin/sh\necho '{\"Output\":\"1\"}'\nexec sleep 5\n").unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let cancel = CancelToken::new();
+    let started = Instant::now();
+    let mut events = Sandbox::new(&script).run_events("print(1);", NativePolicy::All, "", None, cancel.clone());
+    assert_eq!(events.next(), Some(RunEvent::Output("1".to_string())));
+    cancel.cancel();
+
+    let events: Vec<RunEvent> = events.collect();
+    fs::remove_file(&script).unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(matches!(&events[0], RunEvent::Diagnostic { code, .. } if code == "Cancelled"));
+    assert!(matches!(&events[1], RunEvent::Finished { .. }));
+}
@@ -0,0 +1,231 @@
+use crate::{
+    complexity::FunctionMetrics,
+    error::SemanticAnalyserError,
+    lexer::Lexer,
+    natives::builtins,
+    parser::Parser,
+    semanticanalyser::{ScopingMode, SemanticAnalyser},
+    stmt::Stmt,
+};
+
+fn parse(source: &str) -> Vec<Stmt> {
+    let tokens = Lexer::new(source.to_string(), 4).run().unwrap();
+    return Parser::new(tokens).parse().unwrap();
+}
+
+#[test]
+fn test_function_metrics() {
+    // Tests the complexity, nesting and length of functions, each measured on its own
+    let mut analyser = SemanticAnalyser::new(parse(r#"
+def grade(score):
+    if score > 90 and score <= 100:
+        return "A";
+    elif score > 50:
+        return "B";
+    else:
+        for i in 0..3:
+            while i > 5 or i < 0:
+                print(i);
+    def inner():
+        try:
+            pass;
+        except:
+            pass;
+    return "C";
+def empty():
+    pass;
+"#));
+    assert!(analyser.run().is_ok());
+    assert_eq!(
+        analyser.take_function_metrics(),
+        vec![
+            FunctionMetrics {
+                name: "grade".to_string(),
+                line: 2,
+                complexity: 7,
+                nesting: 3,
+                length: 8,
+            },
+            FunctionMetrics {
+                name: "inner".to_string(),
+                line: 11,
+                complexity: 2,
+                nesting: 1,
+                length: 3,
+            },
+            FunctionMetrics {
+                name: "empty".to_string(),
+                line: 17,
+                complexity: 1,
+                nesting: 0,
+                length: 1,
+            },
+        ]
+    );
+    assert!(analyser.take_function_metrics().is_empty());
+}
+
+#[test]
+fn test_builtins() {
+    // Tests that the registry is created once, and sorted by name
+    assert!(std::ptr::eq(builtins(), builtins()));
+    let names: Vec<&str> = builtins().iter().map(|builtin| builtin.name).collect();
+    assert_eq!(
+        names,
+        vec![
+            "abs", "approxEqual", "bsearch", "ceil", "clock", "csvParse", "csvStringify", "deque",
+            "doc", "fetch", "filter", "floor", "format", "getEnv", "hash", "input", "len", "map",
+            "max", "maxOf", "min", "minOf", "num", "pow", "reduce", "reverseSorted", "round", "set",
+            "shuffle", "sqrt", "str", "sum", "uuid",
+        ]
+    );
+
+    // Tests that the analyser treats every built in native function as declared
+    for builtin in builtins() {
+        let mut analyser = SemanticAnalyser::new(parse(&format!("let f = {};", builtin.name)));
+        assert!(analyser.run().is_ok(), "{} was not declared", builtin.name);
+    }
+    assert!(SemanticAnalyser::new(parse("let f = cbrt;")).run().is_err());
+
+    // Tests that the built in constants are declared too
+    assert!(SemanticAnalyser::new(parse("let tau = 2 * PI + E;")).run().is_ok());
+}
+
+#[test]
+fn test_labels() {
+    // Tests that a label can be used by any loop nested inside the loop it labels
+    let mut analyser = SemanticAnalyser::new(parse(
+        "outer: for i in 0..3:\n    while true:\n        continue outer;\n    break outer;",
+    ));
+    assert!(analyser.run().is_ok());
+
+    // Tests that a label must belong to an enclosing loop outside of the current function
+    let sources = [
+        "for i in 0..3:\n    break outer;",
+        "outer: while true:\n    break;\nwhile true:\n    continue outer;",
+        "outer: loop:\n    def f():\n        loop:\n            break outer;\n    break;",
+    ];
+    for source in sources {
+        let mut analyser = SemanticAnalyser::new(parse(source));
+        assert!(
+            matches!(analyser.run(), Err(SemanticAnalyserError::UndefinedLabel { .. })),
+            "{source}"
+        );
+    }
+
+    // Tests that a loop cannot share a label with a loop it is nested in
+    let mut analyser = SemanticAnalyser::new(parse(
+        "outer: loop:\n    outer: loop:\n        break outer;\n    break;",
+    ));
+    assert!(matches!(analyser.run(), Err(SemanticAnalyserError::LabelAlreadyInUse { .. })));
+}
+
+#[test]
+fn test_loop_notices() {
+    // Tests that a loop without a reachable way out is reported
+    let sources = [
+        "loop:\n    print(1);",
+        "loop:\n    continue;\n    break;",
+        "loop:\n    for i in 0..3:\n        break;",
+        "loop:\n    def f():\n        return 1;",
+        "outer: loop:\n    inner: loop:\n        break inner;",
+    ];
+    for source in sources {
+        let mut analyser = SemanticAnalyser::new(parse(source));
+        assert!(analyser.run().is_ok());
+        assert_eq!(
+            analyser.take_notices(),
+            vec!["The loop on line 1 has no reachable 'break' or 'return', so it will never stop"
+                .to_string()],
+            "{source}"
+        );
+    }
+
+    // Tests that a break or return anywhere it can be reached is enough
+    let sources = [
+        "loop:\n    break;",
+        "loop:\n    if true:\n        pass;\n    else:\n        break;",
+        "def f():\n    loop:\n        while true:\n            return 1;",
+        "loop:\n    match 1:\n        case 1:\n            break;",
+        "loop:\n    try:\n        pass;\n    finally:\n        break;",
+        "outer: loop:\n    while true:\n        break outer;",
+    ];
+    for source in sources {
+        let mut analyser = SemanticAnalyser::new(parse(source));
+        assert!(analyser.run().is_ok());
+        assert!(analyser.take_notices().is_empty(), "{source}");
+    }
+}
+
+#[test]
+fn test_enums() {
+    // Tests that an enum cannot declare the same variant twice
+    let mut analyser = SemanticAnalyser::new(parse("enum Color: Red, Green, Red;"));
+    assert!(matches!(
+        analyser.run(),
+        Err(SemanticAnalyserError::DuplicateEnumVariant { line: 1, .. })
+    ));
+
+    let mut analyser = SemanticAnalyser::new(parse("enum Color: Red, Green;
+print(Color.Red);"));
+    assert!(analyser.run().is_ok());
+}
+
+#[test]
+fn test_repl_mode() {
+    // Tests that redeclarations are rejected in scripts
+    let mut analyser = SemanticAnalyser::new(parse("let a = 1;\nlet a = 2;"));
+    assert!(analyser.run().is_err());
+
+    // Tests that the top level of a REPL session allows redeclarations across inputs
+    let mut analyser = SemanticAnalyser::new(Vec::new()).with_mode(ScopingMode::Repl);
+    assert!(analyser.run_next(parse("let a = 1;\ndef f():\n    return a;")).is_ok());
+    assert!(analyser.take_notices().is_empty());
+    assert!(analyser.run_next(parse("let a = 2;\nprint(f());")).is_ok());
+    assert!(analyser.take_notices().is_empty());
+    assert!(analyser.run_next(parse("def f():\n    return 3;")).is_ok());
+    assert_eq!(analyser.take_notices(), vec!["Redefined 'f'".to_string()]);
+
+    // Tests that redeclarations inside functions are still rejected
+    assert!(analyser.run_next(parse("def g():\n    let b = 1;\n    let b = 2;")).is_err());
+}
+
+#[test]
+fn test_type_annotations() {
+    // Tests that values whose types are obvious are checked against annotations
+    let sources = [
+        "let x: num = \"five\";",
+        "let x: num = 5;\nx = \"five\";",
+        "let s: str = \"a\" + \"b\";\nlet n: num = s;",
+        "def f(a: str) -> num:\n    return a.len();\nf(1);",
+        "def f(a) -> num:\n    return \"a\";",
+        "def f() -> num:\n    return;",
+        "def f() -> str:\n    return \"a\";\nlet n: num = f();",
+        "def f(a: num):\n    let b: str = -a;",
+        "let b: bool = 1 < 2;\nlet n: num = b;",
+    ];
+    for source in sources {
+        let mut analyser = SemanticAnalyser::new(parse(source));
+        assert!(
+            matches!(analyser.run(), Err(SemanticAnalyserError::TypeMismatch { .. })),
+            "{source}"
+        );
+    }
+
+    // Tests that code whose types are not obvious is left to be checked when it runs
+    let sources = [
+        "let x: num = 5;\nx = 6;\nlet y = x;\ny = \"six\";",
+        "def f(a):\n    return a;\nlet x: str = f(1);",
+        "def f(a: num, b) -> num:\n    return a + b;\nf(1, \"b\");",
+        "let x: list = [1];\nlet y: null = null;\nlet z: num;",
+        "let x: str = \"a\";\ndef f():\n    let x = \"b\";\n    x = 1;",
+    ];
+    for source in sources {
+        let mut analyser = SemanticAnalyser::new(parse(source));
+        assert!(analyser.run().is_ok(), "{source}");
+    }
+
+    // Tests that an annotation must name a type
+    let mut analyser = SemanticAnalyser::new(parse("let x: integer = 5;"));
+    assert!(matches!(analyser.run(), Err(SemanticAnalyserError::UnknownType { .. })));
+}
@@ -0,0 +1,96 @@
+use std::{fs, path::Path};
+
+use crate::{run::run, stdlib};
+
+/// Checks that every module in the `stdlib/` directory is embedded, and that each one can be
+/// run on its own without any errors or output.
+#[test]
+fn test_embedded_modules() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("stdlib");
+    let mut names: Vec<String> = fs::read_dir(&directory)
+        .expect("Unable to read the stdlib directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pyru"))
+        .map(|path| path.file_stem().unwrap().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+
+    assert_eq!(stdlib::modules(), names);
+
+    for name in stdlib::modules() {
+        assert!(stdlib::load(name).is_some(), "{name} could not be parsed");
+        assert_eq!(run(stdlib::source(name).unwrap(), false), Vec::<String>::new(), "{name} did not run");
+    }
+}
+
+#[test]
+fn test_import() {
+    // Tests for using the functions of each module
+    assert_eq!(
+        run(r#"
+import math;
+print(clamp(12, 0, 10));
+print(sign(-3));
+print(gcd(12, -18));
+print(lcm(4, 6));
+print(factorial(5));
+"#, false),
+        vec!["10", "-1", "6", "12", "120"]
+    );
+
+    assert_eq!(
+        run(r#"
+import lists;
+print(range(1, 4));
+let items = [3, 1, 3, 2];
+print(reversed(items));
+print(unique(items));
+print(flatten([[1], [], [2, 3]]));
+"#, false),
+        vec!["[1, 2, 3]", "[2, 3, 1, 3]", "[3, 1, 2]", "[1, 2, 3]"]
+    );
+
+    assert_eq!(
+        run(r#"
+import strings;
+print(repeat("ab", 3));
+print(padLeft("7", 3, "0"));
+print(padRight("x", 3, "."));
+print(capitalize("pyru"));
+print(isBlank("  "));
+"#, false),
+        vec!["ababab", "007", "x..", "Pyru", "true"]
+    );
+
+    // Tests for importing inside a function, which only declares the names in its scope
+    assert_eq!(
+        run(r#"
+def f():
+  import math;
+  return sign(-2);
+print(f());
+"#, false),
+        vec!["-1"]
+    );
+
+    assert_eq!(
+        run("def f():\n  import math;\n  return 1;\nprint(sign(1));", false),
+        vec!["Couldn't find variable 'sign'"]
+    );
+
+    // Tests for modules that do not exist and names that clash with the module
+    assert_eq!(
+        run("import maths;", false),
+        vec!["There is no module named 'maths' to import on line 1"]
+    );
+
+    assert_eq!(
+        run("def clamp(x):\n  return x;\nimport math;", false),
+        vec!["Already a variable named 'clamp' in this scope"]
+    );
+
+    assert_eq!(
+        run("import;", false),
+        vec!["Expect a module name after 'import' on line 1"]
+    );
+}
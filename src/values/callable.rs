@@ -15,9 +15,8 @@
 //! ## Example
 //!
 //! ```rust
-//! use crate::callable::{Callable, Func, NativeFunc};
-//! use crate::value::{Value, LiteralType};
-//! use crate::evaluator::Evaluator;
+//! use pyru::callable::NativeFunc;
+//! use pyru::value::{Value, LiteralType};
 //!
 //! let native_func = NativeFunc::new(
 //!     "print".to_string(),
@@ -70,17 +69,21 @@ pub trait Callable {
 pub struct Func {
     name: String,
     pub arity: usize,
-    declaration: Stmt,
+    declaration: Box<Stmt>,
     closure: Env,
 }
 
 impl PartialEq for Func {
-    /// Implements equality for `Func` based on its name, arity, and declaration.
+    /// Two functions are equal if they are the same function, created by evaluating the same
+    /// declaration in the same environment. Binding a method to an instance creates a new
+    /// function each time.
     fn eq(&self, other: &Self) -> bool {
-        println!("Should never be called");
-        return self.name == other.name
-            && self.arity == other.arity
-            && self.declaration == other.declaration;
+        return match (self.declaration.as_ref(), other.declaration.as_ref()) {
+            (Stmt::Function { body, .. }, Stmt::Function { body: other_body, .. }) => {
+                Rc::ptr_eq(body, other_body) && Rc::ptr_eq(&self.closure, &other.closure)
+            },
+            _ => false,
+        };
     }
 }
 
@@ -104,15 +107,61 @@ impl Func {
         match &declaration {
             Stmt::Function { name, params, .. } => {
                 return Ok(Self {
-                    name: name.lexeme.clone(),
+                    name: name.lexeme.to_string(),
                     arity: params.len(),
-                    declaration,
+                    declaration: Box::new(declaration),
                     closure,
                 });
             },
             _ => return Err(EvaluatorError::ExpectedFunctionStatementForDeclaration),
         }
     }
+
+    /// Returns the docstring of the function, if it has one.
+    pub fn doc(&self) -> Option<&str> {
+        return match self.declaration.as_ref() {
+            Stmt::Function { doc, .. } => doc.as_deref(),
+            _ => None,
+        };
+    }
+
+    /// Returns the environment in which the function was declared.
+    pub fn closure(&self) -> &Env {
+        return &self.closure;
+    }
+
+    /// Binds a method to an instance. The returned function defines the method's first
+    /// parameter, `self`, as the instance in an environment enclosing the method's closure, so
+    /// it only takes the remaining parameters and can be called, or stored and called later,
+    /// like any other function.
+    pub fn bind(&self, instance: Value) -> Result<Func, EvaluatorError> {
+        match self.declaration.as_ref() {
+            Stmt::Function { name, params, param_types, return_type, body, doc, locals } => {
+                let environment = Rc::new(RefCell::new(Environment::new(Some(Rc::clone(
+                    &self.closure,
+                )))));
+
+                let Some((receiver, params)) = params.split_first() else {
+                    return Err(EvaluatorError::ExpectedSelfParameter {
+                        name: name.lexeme.to_string(),
+                    });
+                };
+                environment.borrow_mut().define(receiver.lexeme.to_string(), instance);
+
+                let declaration = Stmt::Function {
+                    name: name.clone(),
+                    params: params.to_vec(),
+                    param_types: param_types.iter().skip(1).cloned().collect(),
+                    return_type: return_type.clone(),
+                    body: Rc::clone(body),
+                    doc: doc.clone(),
+                    locals: locals.clone(),
+                };
+                return Func::new(declaration, environment);
+            },
+            _ => return Err(EvaluatorError::ExpectedDeclarationToBeAFunction),
+        }
+    }
 }
 
 impl Callable for Func {
@@ -125,22 +174,11 @@ impl Callable for Func {
     /// ## Returns
     /// The result of the function execution or an `EvaluatorError`.
     fn call(&self, evaluator: &mut Evaluator, arguments: Vec<Value>) -> Result<Value, EvaluatorError> {
-        match &self.declaration {
-            Stmt::Function { name: _, params, body } => {
-                let environment = Rc::new(RefCell::new(Environment::new(Some(Rc::clone(
-                    &self.closure,
-                )))));
-
-                for i in 0..params.len() {
-                    environment
-                        .borrow_mut()
-                        .define(params[i].lexeme.clone(), arguments[i].clone());
-                }
-
-                return match evaluator.execute_block(body.clone(), environment) {
-                    Ok(_) => Ok(Value::Literal(LiteralType::Null)),
-                    Err(r) => Ok(r?)
-                }
+        match self.declaration.as_ref() {
+            Stmt::Function { name: _, params, body, locals, .. } => {
+                let locals = locals.get().map_or(&[][..], |locals| locals);
+                let returned = evaluator.execute_call(&self.closure, params, locals, arguments, body).into_result()?;
+                return Ok(returned.unwrap_or(Value::Literal(LiteralType::Null)));
             }
             _ => return Err(EvaluatorError::ExpectedDeclarationToBeAFunction),
         }
@@ -152,14 +190,33 @@ impl Callable for Func {
 /// ## Fields
 /// - `name`: The name of the native function.
 /// - `arity`: The number of parameters the native function takes.
+/// - `optional`: How many of the last parameters can be left out of a call.
+/// - `variadic`: Whether any number of arguments can follow the parameters.
 /// - `fun`: The function pointer to the native function implementation.
-#[derive(Clone, Debug, PartialEq, PartialOrd)]
+#[derive(Clone, Debug)]
 pub struct NativeFunc {
-    name: String,
+    pub name: String,
     pub arity: usize,
+    pub optional: usize,
+    pub variadic: bool,
     fun: fn(&mut Evaluator, Vec<Value>) -> Result<Value, EvaluatorError>,
 }
 
+impl PartialEq for NativeFunc {
+    /// Two native functions are equal if they have the same name, as the addresses of function
+    /// pointers are not guaranteed to be unique.
+    fn eq(&self, other: &Self) -> bool {
+        return self.name == other.name;
+    }
+}
+
+impl PartialOrd for NativeFunc {
+    /// Implements partial ordering for `NativeFunc` based on its name.
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        return self.name.partial_cmp(&other.name);
+    }
+}
+
 impl NativeFunc {
     /// Creates a new `NativeFunc` instance.
     ///
@@ -171,7 +228,26 @@ impl NativeFunc {
     /// ## Returns
     /// A new `NativeFunc` instance.
     pub fn new(name: String, arity: usize, fun: fn(&mut Evaluator, Vec<Value>) -> Result<Value, EvaluatorError>) -> Self {
-        return Self { name, arity, fun };
+        return Self { name, arity, optional: 0, variadic: false, fun };
+    }
+
+    /// Lets the given number of the last parameters be left out of a call, in which case the
+    /// function is passed fewer arguments.
+    pub fn with_optional(mut self, optional: usize) -> Self {
+        self.optional = optional.min(self.arity);
+        return self;
+    }
+
+    /// Lets any number of arguments follow the parameters, which the function is passed after
+    /// the arguments for its parameters.
+    pub fn with_variadic(mut self) -> Self {
+        self.variadic = true;
+        return self;
+    }
+
+    /// Checks whether the function can be called with the given number of arguments.
+    pub fn accepts(&self, args: usize) -> bool {
+        return (args <= self.arity || self.variadic) && args + self.optional >= self.arity;
     }
 }
 
@@ -0,0 +1,169 @@
+//! The `class` module defines the `Class` and `Instance` structs, which represent the classes
+//! declared in the language and the instances created by calling them.
+//!
+//! ## Overview
+//!
+//! A class is declared with the `class` keyword followed by an indented list of methods. As in
+//! Python, every method takes the instance it is called on as its first parameter, and the
+//! `__init__` method is called with the arguments passed to the class when an instance is
+//! created:
+//!
+//! ```text
+//! class Point:
+//!   def __init__(self, x, y):
+//!     self.x = x;
+//!     self.y = y;
+//!   def sum(self):
+//!     return self.x + self.y;
+//! ```
+//!
+//! Inside a method, `self` is defined in an environment bound to the instance, so reading a
+//! method without calling it, as in `let f = point.sum;`, gives a function that still refers to
+//! the instance it was read from.
+//!
+//! The fields of an instance are shared by every copy of it, so a method that assigns to a field
+//! of `self` changes the instance held by the caller. Instances are only equal to themselves.
+
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    rc::Rc,
+};
+
+use crate::{callable::Func, value::Value};
+
+/// The name of the method that is called when an instance is created.
+pub const INITIALIZER: &str = "__init__";
+
+/// The `Class` struct represents a class declared in the source code.
+///
+/// ## Fields
+/// - `name`: The name of the class.
+/// - `methods`: The methods of the class, by name.
+#[derive(Clone, Debug)]
+pub struct Class {
+    pub name: String,
+    methods: Rc<HashMap<String, Func>>,
+}
+
+impl PartialEq for Class {
+    /// Two classes are equal if they come from the same declaration being evaluated.
+    fn eq(&self, other: &Self) -> bool {
+        return Rc::ptr_eq(&self.methods, &other.methods);
+    }
+}
+
+impl PartialOrd for Class {
+    /// Classes have no ordering, so they can only be compared for equality.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            return Some(Ordering::Equal);
+        }
+        return None;
+    }
+}
+
+impl Class {
+    /// Creates a new `Class` instance with the given methods.
+    pub fn new(name: String, methods: HashMap<String, Func>) -> Self {
+        return Self { name, methods: Rc::new(methods) };
+    }
+
+    /// Returns the method with the given name, if the class has one.
+    pub fn find_method(&self, name: &str) -> Option<Func> {
+        return self.methods.get(name).cloned();
+    }
+
+    /// Returns the number of arguments that must be passed to the class to create an instance,
+    /// which is the number of parameters of `__init__` other than `self`.
+    pub fn arity(&self) -> usize {
+        return match self.methods.get(INITIALIZER) {
+            Some(init) => init.arity.saturating_sub(1),
+            None => 0,
+        };
+    }
+
+    /// Returns the methods of the class, which are shared between copies of it.
+    pub(crate) fn method_table(&self) -> &Rc<HashMap<String, Func>> {
+        return &self.methods;
+    }
+}
+
+/// The `Instance` struct represents an instance of a class.
+///
+/// ## Fields
+/// - `class`: The class that the instance was created from.
+/// - `fields`: The fields of the instance, which are shared between copies of it.
+#[derive(Clone, Debug)]
+pub struct Instance {
+    pub class: Class,
+    fields: Rc<RefCell<HashMap<String, Value>>>,
+}
+
+impl PartialEq for Instance {
+    /// Two instances are equal if they are the same instance.
+    fn eq(&self, other: &Self) -> bool {
+        return Rc::ptr_eq(&self.fields, &other.fields);
+    }
+}
+
+impl PartialOrd for Instance {
+    /// Instances have no ordering, so they can only be compared for equality.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            return Some(Ordering::Equal);
+        }
+        return None;
+    }
+}
+
+impl Instance {
+    /// Creates a new `Instance` of the given class, with no fields.
+    pub fn new(class: Class) -> Self {
+        return Self { class, fields: Rc::new(RefCell::new(HashMap::new())) };
+    }
+
+    /// Returns the value of the field with the given name, if it has been assigned.
+    pub fn get(&self, name: &str) -> Option<Value> {
+        return self.fields.borrow().get(name).cloned();
+    }
+
+    /// Returns the fields of the instance, sorted by name.
+    pub fn fields(&self) -> Vec<(String, Value)> {
+        let mut fields: Vec<(String, Value)> = self.fields
+            .borrow()
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        return fields;
+    }
+
+    /// Returns the fields of the instance, which are shared between copies of it.
+    pub(crate) fn field_table(&self) -> &Rc<RefCell<HashMap<String, Value>>> {
+        return &self.fields;
+    }
+
+    /// Assigns a value to the field with the given name, creating the field if needed.
+    pub fn set(&self, name: &str, value: Value) {
+        self.fields.borrow_mut().insert(name.to_string(), value);
+    }
+}
+
+impl fmt::Display for Class {
+    /// Implements the `Display` trait for `Class` to provide a string representation
+    /// of the class.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "<class {}>", self.name);
+    }
+}
+
+impl fmt::Display for Instance {
+    /// Implements the `Display` trait for `Instance` to provide a string representation
+    /// of the instance.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "<{} instance>", self.class.name);
+    }
+}
@@ -0,0 +1,118 @@
+//! The `deque` module defines the `Deque` struct, which represents a double-ended queue of values,
+//! along with the methods that can be called on a deque in the language.
+//!
+//! ## Overview
+//!
+//! Deques are created by passing a list or tuple to the native `deque` function, and support
+//! the `push()`, `pushFront()`, `pop()`, `popFront()`, `peek()`, `peekFront()` and `len()`
+//! methods, each of which takes constant time.
+//!
+//! A list already works as a stack, as `push()` and `pop()` both work on its end, but removing
+//! the first item of a list moves every other item along. A deque is used instead wherever
+//! items are taken from the front, such as a queue, which has items pushed onto its back with
+//! `push()` and taken from its front with `popFront()`.
+
+use std::{collections::VecDeque, fmt};
+
+use crate::{error::EvaluatorError, number::NumberFormat, value::{write_item, Value}};
+
+/// The `Deque` struct represents a double-ended queue of values.
+///
+/// ## Fields
+/// - `items`: The items of the deque, from front to back.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct Deque {
+    items: VecDeque<Value>,
+}
+
+impl Deque {
+    /// Creates a new `Deque` instance with the given values, from front to back.
+    pub fn new(values: Vec<Value>) -> Self {
+        return Self { items: VecDeque::from(values) };
+    }
+
+    /// Adds a value to the back of the deque.
+    pub fn push(&mut self, args: Vec<Value>) -> Result<&mut Deque, EvaluatorError> {
+        self.items.push_back(Self::argument(args)?);
+        return Ok(self);
+    }
+
+    /// Adds a value to the front of the deque.
+    pub fn push_front(&mut self, args: Vec<Value>) -> Result<&mut Deque, EvaluatorError> {
+        self.items.push_front(Self::argument(args)?);
+        return Ok(self);
+    }
+
+    /// Removes and returns the value at the back of the deque.
+    pub fn pop(&mut self) -> (Option<Value>, &mut Deque) {
+        return (self.items.pop_back(), self);
+    }
+
+    /// Removes and returns the value at the front of the deque.
+    pub fn pop_front(&mut self) -> (Option<Value>, &mut Deque) {
+        return (self.items.pop_front(), self);
+    }
+
+    /// Returns the value at the back of the deque without removing it.
+    pub fn peek(&self) -> Option<&Value> {
+        return self.items.back();
+    }
+
+    /// Returns the value at the front of the deque without removing it.
+    pub fn peek_front(&self) -> Option<&Value> {
+        return self.items.front();
+    }
+
+    /// Returns the items of the deque, from front to back.
+    pub fn values(&self) -> Vec<Value> {
+        return self.items.iter().cloned().collect();
+    }
+
+    /// Returns an iterator over the items of the deque, from front to back.
+    pub fn iter(&self) -> impl Iterator<Item = &Value> {
+        return self.items.iter();
+    }
+
+    /// Returns whether the deque contains the given value.
+    pub fn contains(&self, value: &Value) -> bool {
+        return self.items.contains(value);
+    }
+
+    /// Returns the number of items in the deque.
+    pub fn len(&self) -> usize {
+        return self.items.len();
+    }
+
+    /// Returns whether the deque has no items.
+    pub fn is_empty(&self) -> bool {
+        return self.items.is_empty();
+    }
+
+    /// Checks that a single value was passed to a method that adds a value.
+    fn argument(args: Vec<Value>) -> Result<Value, EvaluatorError> {
+        if args.len() != 1 {
+            return Err(EvaluatorError::ArgsDifferFromArity { args: args.len(), arity: 1 });
+        }
+        return Ok(args.into_iter().next().unwrap());
+    }
+}
+
+impl Deque {
+    /// Writes the deque, writing any numbers in it with the given format.
+    pub fn write(&self, f: &mut fmt::Formatter<'_>, format: &NumberFormat) -> fmt::Result {
+        write!(f, "deque([")?;
+        for (i, value) in self.items.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write_item(f, value, format)?;
+        }
+        write!(f, "])")
+    }
+}
+
+impl fmt::Display for Deque {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return self.write(f, &NumberFormat::default());
+    }
+}
@@ -0,0 +1,192 @@
+//! The `dict` module defines the `Dict` struct, which represents a dictionary mapping literal
+//! keys to values, along with the methods that can be called on a dictionary in the language.
+//!
+//! ## Overview
+//!
+//! Dictionaries are created with the literal syntax `{"key": value}`, read with indexing
+//! (`d["key"]`), updated with index assignment (`d["key"] = value`) and support the `keys()`,
+//! `values()`, `has()`, `remove()` and `len()` methods.
+//!
+//! Only literal values (strings, numbers, booleans and null) can be used as keys, as they are the
+//! only values that can be hashed. The entries are kept in the order their keys were first added,
+//! so printing a dictionary or iterating over its keys is deterministic.
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use crate::{error::EvaluatorError, number::NumberFormat, value::{write_item, LiteralType, OrderedMap, Value}};
+
+/// A literal value used as the key of a dictionary or as an item of a set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DictKey(LiteralType);
+
+impl Eq for DictKey {}
+
+impl Hash for DictKey {
+    /// Hashes the key by its type and contents. Numbers are hashed by their bits, with `-0`
+    /// normalised to `0` so that keys which compare as equal also hash equally.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match &self.0 {
+            LiteralType::Str(s) => {
+                0.hash(state);
+                s.hash(state);
+            },
+            LiteralType::Num(n) => {
+                1.hash(state);
+                let n = if *n == 0.0 { 0.0 } else { *n };
+                n.to_bits().hash(state);
+            },
+            LiteralType::True => 2.hash(state),
+            LiteralType::False => 3.hash(state),
+            LiteralType::Null => 4.hash(state),
+        }
+    }
+}
+
+impl DictKey {
+    /// Creates a key from a value, returning an error if the value is not a literal.
+    pub fn new(value: &Value) -> Result<Self, EvaluatorError> {
+        return match value {
+            Value::Literal(literal) => Ok(Self(literal.clone())),
+            _ => Err(EvaluatorError::InvalidDictKey),
+        };
+    }
+
+    /// Returns the key as a value.
+    pub fn to_value(&self) -> Value {
+        return Value::Literal(self.0.clone());
+    }
+
+    /// Returns roughly how many bytes the key takes up, including its string if it is one.
+    pub fn approximate_size(&self) -> usize {
+        let held = match &self.0 {
+            LiteralType::Str(s) => s.len(),
+            _ => 0,
+        };
+        return std::mem::size_of::<DictKey>() + held;
+    }
+}
+
+/// The `Dict` struct represents a dictionary and provides methods for manipulating it.
+///
+/// ## Fields
+/// - `entries`: An `OrderedMap` that stores the keys and their corresponding values, in the
+///   order the keys were first added.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dict {
+    pub entries: OrderedMap<DictKey, Value>,
+}
+
+impl PartialOrd for Dict {
+    /// Dictionaries have no ordering, so they can only be compared for equality.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            return Some(Ordering::Equal);
+        }
+        return None;
+    }
+}
+
+impl Dict {
+    /// Creates a new `Dict` instance from the given key-value pairs.
+    pub fn new(items: Vec<(Value, Value)>) -> Result<Self, EvaluatorError> {
+        let mut entries = OrderedMap::new();
+        for (key, value) in items {
+            entries.insert(DictKey::new(&key)?, value);
+        }
+        return Ok(Self { entries });
+    }
+
+    /// Returns the value stored under the given key.
+    pub fn get(&self, key: &Value) -> Result<Value, EvaluatorError> {
+        return match self.entries.get(&DictKey::new(key)?) {
+            Some(value) => Ok(value.clone()),
+            None => Err(EvaluatorError::KeyNotFound { key: format!("{key}") }),
+        };
+    }
+
+    /// Stores a value under the given key, replacing any existing value.
+    pub fn insert(&mut self, key: &Value, value: Value) -> Result<&mut Dict, EvaluatorError> {
+        self.entries.insert(DictKey::new(key)?, value);
+        return Ok(self);
+    }
+
+    /// Returns whether the dictionary contains the given key.
+    pub fn has(&self, args: Vec<Value>) -> Result<bool, EvaluatorError> {
+        if args.len() != 1 {
+            return Err(EvaluatorError::ArgsDifferFromArity { args: args.len(), arity: 1 });
+        }
+
+        return Ok(self.contains(&args[0]));
+    }
+
+    /// Returns whether the dictionary contains the given key, treating non-literal values as
+    /// keys that are never present.
+    pub fn contains(&self, key: &Value) -> bool {
+        return match DictKey::new(key) {
+            Ok(key) => self.entries.contains_key(&key),
+            Err(_) => false,
+        };
+    }
+
+    /// Removes the given key and returns the value that was stored under it.
+    pub fn remove(&mut self, args: Vec<Value>) -> Result<(Value, &mut Dict), EvaluatorError> {
+        if args.len() != 1 {
+            return Err(EvaluatorError::ArgsDifferFromArity { args: args.len(), arity: 1 });
+        }
+
+        return match self.entries.remove(&DictKey::new(&args[0])?) {
+            Some(value) => Ok((value, self)),
+            None => Err(EvaluatorError::KeyNotFound { key: format!("{}", args[0]) }),
+        };
+    }
+
+    /// Returns the keys of the dictionary.
+    pub fn keys(&self) -> Vec<Value> {
+        return self.entries.keys().map(DictKey::to_value).collect();
+    }
+
+    /// Returns the values of the dictionary.
+    pub fn values(&self) -> Vec<Value> {
+        return self.entries.values().cloned().collect();
+    }
+
+    /// Returns the number of entries in the dictionary.
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    /// Returns whether the dictionary has no entries.
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+}
+
+impl Dict {
+    /// Writes the dictionary, writing any numbers in it with the given format.
+    pub fn write(&self, f: &mut fmt::Formatter<'_>, format: &NumberFormat) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (key, value)) in self.entries.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            if let LiteralType::Str(s) = &key.0 {
+                write!(f, "\"{s}\": ")?;
+            } else {
+                key.0.write(f, format)?;
+                write!(f, ": ")?;
+            }
+            write_item(f, value, format)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Display for Dict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return self.write(f, &NumberFormat::default());
+    }
+}
@@ -0,0 +1,104 @@
+//! The `enumeration` module defines the `Enum` and `Variant` structs, which represent the enums
+//! declared in the language and the constant values they name.
+//!
+//! ## Overview
+//!
+//! An enum is declared with the `enum` keyword followed by the names of its variants:
+//!
+//! ```text
+//! enum Color: Red, Green, Blue;
+//! let c = Color.Green;
+//! print(c == Color.Green);
+//! ```
+//!
+//! Each variant is read as a property of the enum. A variant is only equal to the same variant of
+//! the same declaration, so variants of two enums never compare equal even when their names
+//! match, and variants of the same enum are ordered by the position they were declared in.
+
+use std::{cmp::Ordering, fmt, rc::Rc};
+
+/// The `Enum` struct represents an enum declared in the source code.
+///
+/// ## Fields
+/// - `name`: The name of the enum.
+/// - `variants`: The names of the variants of the enum, in the order they were declared.
+#[derive(Clone, Debug)]
+pub struct Enum {
+    pub name: String,
+    variants: Rc<Vec<String>>,
+}
+
+impl PartialEq for Enum {
+    /// Two enums are equal if they come from the same declaration being evaluated.
+    fn eq(&self, other: &Self) -> bool {
+        return Rc::ptr_eq(&self.variants, &other.variants);
+    }
+}
+
+impl PartialOrd for Enum {
+    /// Enums have no ordering, so they can only be compared for equality.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            return Some(Ordering::Equal);
+        }
+        return None;
+    }
+}
+
+impl Enum {
+    /// Creates a new `Enum` with the given variants.
+    pub fn new(name: String, variants: Vec<String>) -> Self {
+        return Self { name, variants: Rc::new(variants) };
+    }
+
+    /// Returns the variant with the given name, if the enum has one.
+    pub fn variant(&self, name: &str) -> Option<Variant> {
+        let index = self.variants.iter().position(|variant| variant == name)?;
+        return Some(Variant { enumeration: self.clone(), index });
+    }
+}
+
+/// The `Variant` struct represents one of the named constant values of an enum.
+///
+/// ## Fields
+/// - `enumeration`: The enum that the variant belongs to.
+/// - `index`: The position of the variant in the declaration of the enum.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variant {
+    pub enumeration: Enum,
+    index: usize,
+}
+
+impl PartialOrd for Variant {
+    /// Variants of the same enum are ordered by the position they were declared in, while
+    /// variants of different enums have no ordering.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.enumeration != other.enumeration {
+            return None;
+        }
+        return self.index.partial_cmp(&other.index);
+    }
+}
+
+impl Variant {
+    /// Returns the name of the variant.
+    pub fn name(&self) -> &str {
+        return &self.enumeration.variants[self.index];
+    }
+}
+
+impl fmt::Display for Enum {
+    /// Implements the `Display` trait for `Enum` to provide a string representation
+    /// of the enum.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "<enum {}>", self.name);
+    }
+}
+
+impl fmt::Display for Variant {
+    /// Implements the `Display` trait for `Variant` to provide a string representation
+    /// of the variant, which is how it is written in the source code.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}.{}", self.enumeration.name, self.name());
+    }
+}
@@ -17,16 +17,16 @@
 //! ## Example
 //!
 //! ```rust
-//! use crate::expr::Expr;
-//! use crate::token::{Token, TokenType};
-//! use crate::value::LiteralType;
+//! use pyru::expr::Expr;
+//! use pyru::token::{Token, TokenType};
+//! use pyru::value::LiteralType;
 //!
 //! let expr = Expr::Binary {
 //!     left: Box::new(Expr::Literal { value: LiteralType::Num(5.0) }),
 //!     operator: Token {
 //!         token_type: TokenType::Plus,
-//!         lexeme: "+".to_string(),
-//!         literal: "".to_string(),
+//!         lexeme: "+".into(),
+//!         literal: "".into(),
 //!         line: 1,
 //!         start: 0,
 //!         end: 1,
@@ -44,10 +44,11 @@
 //! all possible expression types in the language.
 
 use paste::paste;
-use std::fmt;
+use std::{cell::Cell, fmt};
 
 use crate::{
     expr_visitor,
+    resolver::Slot,
     token::{Token, TokenType},
     value::LiteralType,
 };
@@ -59,24 +60,38 @@ use crate::{
 /// - `Assign`: Represents an assignment of a value to a variable.
 /// - `Binary`: Represents a binary operation (e.g., addition, subtraction).
 /// - `Call`: Represents a function or method call.
+/// - `Comparison`: Represents a chain of comparisons (e.g., `0 <= x < 10`).
+/// - `Dict`: Represents a dictionary literal.
 /// - `Grouping`: Represents a grouped expression (e.g., expressions in parentheses).
+/// - `IndexAssign`: Represents an assignment of a value to an item of a list or dictionary.
 /// - `List`: Represents a list literal.
-/// - `ListMethodCall`: Represents a method call on a list.
+/// - `ListMethodCall`: Represents a method call on a list, dictionary, set or string.
 /// - `Literal`: Represents a literal value (e.g., string, number, boolean).
 /// - `Logical`: Represents a logical operation (e.g., `and`, `or`).
 /// - `Membership`: Represents a membership test (e.g., `in`, `not in`).
-/// - `Splice`: Represents a list slicing operation.
+/// - `Property`: Represents reading a field of an instance (e.g., `point.x`).
+/// - `PropertyAssign`: Represents an assignment of a value to a field of an instance.
+/// - `Set`: Represents a set literal.
+/// - `SliceAssign`: Represents an assignment of the items of a list to a slice of a list.
+/// - `Splice`: Represents indexing or slicing a value (e.g., `a[i]`, `a[i:j]` or `a[i][j]`).
+/// - `Spread`: Represents a list or tuple expanded into the arguments of a call (e.g., `f(*xs)`).
+/// - `Tuple`: Represents a tuple literal.
 /// - `Unary`: Represents a unary operation (e.g., negation).
 /// - `Var`: Represents a variable reference.
+///
+/// The `slot` of an expression that names a variable is filled in by the resolver once the
+/// program has been parsed, and is `None` until then.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Expr {
     Alteration {
         name: Token, // Variable name
         alteration_type: TokenType, // Incr or Decr tokens
+        slot: Cell<Option<Slot>>, // Where the variable is stored
     },
     Assign {
         name: Token, // Variable name
         value: Box<Expr>, // The expression to be assigned
+        slot: Cell<Option<Slot>>, // Where the variable is stored
     },
     Binary {
         left: Box<Expr>,
@@ -87,15 +102,28 @@ pub enum Expr {
         callee: Box<Expr>, // The name of the call, e.g., the function name
         arguments: Vec<Expr>, // The arguments passed in the parentheses
     },
+    Comparison {
+        left: Box<Expr>, // The first operand of the chain
+        comparisons: Vec<(Token, Expr)>, // Each operator and the operand to its right
+    },
+    Dict {
+        items: Vec<(Expr, Expr)>, // The key-value pairs to be in the created dictionary
+    },
     Grouping {
         expression: Box<Expr>, // The expression in parentheses, usually binary
     },
+    IndexAssign {
+        object: Box<Expr>, // The variable or item being indexed
+        index: Box<Expr>, // The key being assigned to
+        value: Box<Expr>, // The expression to be assigned
+    },
     List {
         items: Vec<Expr>, // The items to be in the created list
     },
     ListMethodCall {
         object: Token, // The name of the instance that the method is being called on
         call: Box<Expr>, // A call expression for the method call
+        slot: Cell<Option<Slot>>, // Where the variable holding the instance is stored
     },
     Literal {
         value: LiteralType,
@@ -110,37 +138,110 @@ pub enum Expr {
         not: bool, // Whether the membership test is negated
         right: Box<Expr>, // The list
     },
+    Property {
+        object: Token, // The name of the variable holding the instance
+        name: Token, // The name of the field
+        slot: Cell<Option<Slot>>, // Where the variable holding the instance is stored
+    },
+    PropertyAssign {
+        object: Token, // The name of the variable holding the instance
+        name: Token, // The name of the field being assigned to
+        value: Box<Expr>, // The expression to be assigned
+        slot: Cell<Option<Slot>>, // Where the variable holding the instance is stored
+    },
+    Set {
+        items: Vec<Expr>, // The items to be in the created set
+    },
+    SliceAssign {
+        object: Box<Expr>, // The variable or item holding the list being sliced
+        start: Option<Box<Expr>>, // The index of the first item replaced, if not the first
+        end: Option<Box<Expr>>, // The index of the last item replaced, if not the last
+        value: Box<Expr>, // The expression whose items replace the slice
+    },
     Splice {
-        list: Token, // The name of the variable for the list
+        list: Box<Expr>, // The expression whose value is indexed or sliced
         is_splice: bool, // Whether it is a splice (returns a list or value)
         start: Option<Box<Expr>>, // The start index (inclusive)
         end: Option<Box<Expr>>, // The end index (inclusive)
     },
+    Spread {
+        operator: Token,
+        value: Box<Expr>, // The expression whose items are passed as separate arguments
+    },
+    Tuple {
+        items: Vec<Expr>, // The items to be in the created tuple
+    },
     Unary {
         operator: Token,
         right: Box<Expr>,
     },
     Var {
         name: Token, // The name of the variable whose value is retrieved
+        slot: Cell<Option<Slot>>, // Where the variable is stored
     },
 }
 
+impl Expr {
+    /// Returns the line that the expression starts on, if it contains a token to take it from.
+    /// Only literals have no token.
+    pub fn line(&self) -> Option<usize> {
+        return match self {
+            Expr::Alteration { name, .. } | Expr::Assign { name, .. } | Expr::Var { name, .. } => {
+                Some(name.line)
+            },
+            Expr::ListMethodCall { object, .. }
+            | Expr::Property { object, .. }
+            | Expr::PropertyAssign { object, .. } => Some(object.line),
+            Expr::IndexAssign { object, .. } | Expr::SliceAssign { object, .. } => object.line(),
+            Expr::Splice { list, .. } => list.line(),
+            Expr::Binary { left, operator, .. } | Expr::Logical { left, operator, .. } => {
+                left.line().or(Some(operator.line))
+            },
+            Expr::Spread { operator, .. } | Expr::Unary { operator, .. } => Some(operator.line),
+            Expr::Call { callee, arguments } => {
+                callee.line().or_else(|| arguments.iter().find_map(Expr::line))
+            },
+            Expr::Comparison { left, comparisons } => {
+                left.line().or_else(|| comparisons.first().map(|(operator, _)| operator.line))
+            },
+            Expr::Dict { items } => items.iter().find_map(|(key, value)| key.line().or(value.line())),
+            Expr::Grouping { expression } => expression.line(),
+            Expr::List { items } | Expr::Set { items } | Expr::Tuple { items } => {
+                items.iter().find_map(Expr::line)
+            },
+            Expr::Membership { left, right, .. } => left.line().or_else(|| right.line()),
+            Expr::Literal { .. } => None,
+        };
+    }
+}
+
 impl fmt::Display for Expr {
     /// Implements the `Display` trait for `Expr` to provide a string representation
     /// of each expression variant.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         return match self {
-            Expr::Alteration { name, alteration_type } => {
+            Expr::Alteration { name, alteration_type, .. } => {
                 write!(f, "Alteration({name} {alteration_type})")
             },
-            Expr::Assign { name, value } => write!(f, "Assign({name} = {value}"),
+            Expr::Assign { name, value, .. } => write!(f, "Assign({name} = {value}"),
             Expr::Binary { left, operator, right } => {
                 write!(f, "Binary({left} {operator} {right})")
             },
             Expr::Call { callee, arguments } => write!(f, "Call({callee} {arguments:?})"),
+            Expr::Comparison { left, comparisons } => {
+                write!(f, "Comparison({left}")?;
+                for (operator, right) in comparisons {
+                    write!(f, " {operator} {right}")?;
+                }
+                write!(f, ")")
+            },
+            Expr::Dict { items } => write!(f, "{{{items:?}}}"),
             Expr::Grouping { expression } => write!(f, "Grouping({expression})"),
+            Expr::IndexAssign { object, index, value } => {
+                write!(f, "IndexAssign({object}[{index}] = {value})")
+            },
             Expr::List { items } => write!(f, "[{items:?}]"),
-            Expr::ListMethodCall { object, call } => write!(f, "{object}.{call}"),
+            Expr::ListMethodCall { object, call, .. } => write!(f, "{object}.{call}"),
             Expr::Literal { value } => write!(f, "{value}"),
             Expr::Logical { left, operator, right } => {
                 write!(f, "Logical({left} {operator} {right})")
@@ -151,11 +252,21 @@ impl fmt::Display for Expr {
                 };
                 write!(f, "{left} in {right}")
             },
+            Expr::Property { object, name, .. } => write!(f, "Property({object}.{name})"),
+            Expr::PropertyAssign { object, name, value, .. } => {
+                write!(f, "PropertyAssign({object}.{name} = {value})")
+            },
+            Expr::Set { items } => write!(f, "Set({items:?})"),
+            Expr::SliceAssign { object, start, end, value } => {
+                write!(f, "SliceAssign({object}[{start:?}:{end:?}] = {value})")
+            },
             Expr::Splice { list, is_splice: _, start, end } => {
                 write!(f, "{list}[{start:?}:{end:?}]")
             },
+            Expr::Spread { value, .. } => write!(f, "Spread({value})"),
+            Expr::Tuple { items } => write!(f, "Tuple({items:?})"),
             Expr::Unary { operator, right } => write!(f, "Unary({operator} {right})"),
-            Expr::Var { name } => write!(f, "Var({name})"),
+            Expr::Var { name, .. } => write!(f, "Var({name})"),
         }
     }
 }
@@ -165,4 +276,4 @@ impl fmt::Display for Expr {
 // This macro defines an `ExprVisitor` trait with methods for visiting each expression type.
 // It also implements the `accept_expr` method for the `Expr` enum, which dispatches the
 // appropriate visitor method based on the expression type.
-expr_visitor!(Alteration, Assign, Binary, Call, Grouping, List, ListMethodCall, Literal, Logical, Membership, Splice, Unary, Var);
\ No newline at end of file
+expr_visitor!(Alteration, Assign, Binary, Call, Comparison, Dict, Grouping, IndexAssign, List, ListMethodCall, Literal, Logical, Membership, Property, PropertyAssign, Set, SliceAssign, Splice, Spread, Tuple, Unary, Var);
\ No newline at end of file
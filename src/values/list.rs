@@ -1,6 +1,6 @@
-use std::{cmp::min, fmt};
+use std::{cell::{Ref, RefCell}, cmp::{min, Ordering}, fmt, rc::Rc};
 
-use crate::{error::EvaluatorError, value::{LiteralType, Value}};
+use crate::{error::EvaluatorError, number::NumberFormat, value::{write_item, LiteralType, Value}};
 
 // Sets the threshold used in the Tim sort algorithm
 const THRESHOLD: f32 = 32.0;
@@ -8,89 +8,231 @@ const THRESHOLD: f32 = 32.0;
 /// The `List` struct represents a list of values and provides methods for manipulating the list.
 ///
 /// ## Fields
-/// - `values`: A vector that stores the values in the list.
+/// - `values`: A vector that stores the values in the list, which is shared between copies of
+///   the list, so that changing a list in place changes it everywhere it is held.
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct List {
-    pub values: Vec<Value>
+    values: Rc<RefCell<Vec<Value>>>,
 }
 
 impl List {
     /// Creates a new `List` instance with the given values.
     pub fn new(values: Vec<Value>) -> Self {
-        return Self { values };
+        return Self { values: Rc::new(RefCell::new(values)) };
+    }
+
+    /// Returns the values in the list.
+    pub fn values(&self) -> Ref<'_, Vec<Value>> {
+        return self.values.borrow();
+    }
+
+    /// Returns the values in the list, taking them if nothing else holds the list and copying
+    /// them otherwise.
+    pub fn into_values(self) -> Vec<Value> {
+        return match Rc::try_unwrap(self.values) {
+            Ok(values) => values.into_inner(),
+            Err(values) => values.borrow().clone(),
+        };
+    }
+
+    /// Returns the values of the list, which are shared between copies of it.
+    pub(crate) fn storage(&self) -> &Rc<RefCell<Vec<Value>>> {
+        return &self.values;
+    }
+
+    /// Returns whether a value is this list or holds it, in which case adding the value to the
+    /// list would make the list hold itself.
+    fn is_held_by(&self, value: &Value) -> bool {
+        return match value {
+            Value::List(list) => {
+                Rc::ptr_eq(&self.values, &list.values)
+                    || list.values().iter().any(|item| self.is_held_by(item))
+            },
+            Value::Tuple(items) => items.iter().any(|item| self.is_held_by(item)),
+            Value::Deque(deque) => deque.iter().any(|item| self.is_held_by(item)),
+            Value::Dict(dict) => dict.entries.values().any(|item| self.is_held_by(item)),
+            _ => false,
+        };
+    }
+
+    /// Returns an error if a value cannot be added to the list because it holds the list.
+    fn check_holdable(&self, value: &Value) -> Result<(), EvaluatorError> {
+        if self.is_held_by(value) {
+            return Err(EvaluatorError::ListCannotHoldItself);
+        }
+        return Ok(());
     }
 
     /// Adds a value to the end of the list.
-    pub fn push(&mut self, args: Vec<Value>) -> Result<&mut List, EvaluatorError>  {
+    pub fn push(&self, args: Vec<Value>) -> Result<(), EvaluatorError>  {
         if args.len() != 1 {
             return Err(EvaluatorError::ArgsDifferFromArity { args: args.len(), arity: 1 });
         }
-        self.values.push(args[0].clone());
-        return Ok(self);
+        self.check_holdable(&args[0])?;
+        self.values.borrow_mut().push(args[0].clone());
+        return Ok(());
     }
 
     /// Removes and returns the last value from the list.
-    pub fn pop(&mut self) -> (Option<Value>, &mut List) {
-        return (self.values.pop(), self);
+    pub fn pop(&self) -> Option<Value> {
+        return self.values.borrow_mut().pop();
     }
 
     /// Removes and returns the value at the specified index.
-    pub fn remove(&mut self, args: Vec<Value>) -> Result<(Value, &mut List), EvaluatorError> {
+    pub fn remove(&self, args: Vec<Value>) -> Result<Value, EvaluatorError> {
         if args.len() != 1 {
             return Err(EvaluatorError::ArgsDifferFromArity { args: args.len(), arity: 1 });
         }
 
         if let Value::Literal(LiteralType::Num(num)) = args[0] {
-            return Ok((self.values.remove(num as usize), self));
+            return Ok(self.values.borrow_mut().remove(num as usize));
         }
 
         return Err(EvaluatorError::ExpectedIndexToBeANum);
     }
 
     /// Inserts a value at the specified index.
-    pub fn insert_at(&mut self, args: Vec<Value>) -> Result<&mut List, EvaluatorError> {
+    pub fn insert_at(&self, args: Vec<Value>) -> Result<(), EvaluatorError> {
         if args.len() != 2 {
             return Err(EvaluatorError::ArgsDifferFromArity { args: args.len(), arity: 2 });
         }
 
         if let Value::Literal(LiteralType::Num(num)) = args[0] {
-            self.values.insert(num as usize, args[1].clone());
-            return Ok(self);
+            self.check_holdable(&args[1])?;
+            self.values.borrow_mut().insert(num as usize, args[1].clone());
+            return Ok(());
         }
         
         return Err(EvaluatorError::ExpectedIndexToBeANum);
     }
 
+    /// Returns the value at the position that a value used as an index refers to.
+    pub fn get(&self, index: &Value) -> Result<Value, EvaluatorError> {
+        let i = self.checked_index(index)?;
+        return Ok(self.values.borrow()[i].clone());
+    }
+
+    /// Replaces the value at the position that a value used as an index refers to, returning
+    /// the value it replaced.
+    pub fn set(&self, index: &Value, value: Value) -> Result<Value, EvaluatorError> {
+        let i = self.checked_index(index)?;
+        self.check_holdable(&value)?;
+        return Ok(std::mem::replace(&mut self.values.borrow_mut()[i], value));
+    }
+
+    /// Removes and returns the value at the position that a value used as an index refers to.
+    pub fn delete(&self, index: &Value) -> Result<Value, EvaluatorError> {
+        let i = self.checked_index(index)?;
+        return Ok(self.values.borrow_mut().remove(i));
+    }
+
+    /// Replaces the values from the start up to, but not including, the end of the list with
+    /// the given values, returning the values they replaced.
+    pub fn splice(&self, start: usize, end: usize, values: Vec<Value>) -> Result<Vec<Value>, EvaluatorError> {
+        for value in &values {
+            self.check_holdable(value)?;
+        }
+        return Ok(self.values.borrow_mut().splice(start..end, values).collect());
+    }
+
     /// Returns the index of the specified value in the list.
     pub fn index(&self, args: Vec<Value>) -> Result<usize, EvaluatorError> {
         if args.len() != 1 {
             return Err(EvaluatorError::ArgsDifferFromArity { args: args.len(), arity: 1 });
         }
 
-        return match self.values.iter().position(|x| x == &args[0]) {
+        return match self.values.borrow().iter().position(|x| x == &args[0]) {
             Some(index) => Ok(index),
             None => Err(EvaluatorError::ItemNotFound),
         }
     }
 
+    /// Joins the strings in the list into a single string, with the separator between each one.
+    pub fn join(&self, args: Vec<Value>) -> Result<String, EvaluatorError> {
+        if args.len() != 1 {
+            return Err(EvaluatorError::ArgsDifferFromArity { args: args.len(), arity: 1 });
+        }
+
+        let separator = match &args[0] {
+            Value::Literal(LiteralType::Str(s)) => s,
+            _ => return Err(EvaluatorError::ExpectedStringArgument),
+        };
+
+        let values = self.values.borrow();
+        let mut parts = Vec::with_capacity(values.len());
+        for value in values.iter() {
+            match value {
+                Value::Literal(LiteralType::Str(s)) => parts.push(s.as_str()),
+                _ => return Err(EvaluatorError::ExpectedStringArgument),
+            }
+        }
+        return Ok(parts.join(separator));
+    }
+
+    /// Returns a new list holding the values of this list followed by those of the other.
+    pub fn concat(&self, other: &List) -> List {
+        let (first, second) = (self.values.borrow(), other.values.borrow());
+        let mut values = Vec::with_capacity(first.len() + second.len());
+        values.extend_from_slice(&first);
+        values.extend_from_slice(&second);
+        return List::new(values);
+    }
+
+    /// Returns a new list holding the values of this list repeated the given number of times.
+    pub fn repeat(&self, times: usize) -> List {
+        let items = self.values.borrow();
+        let mut values = Vec::with_capacity(items.len() * times);
+        for _ in 0..times {
+            values.extend_from_slice(&items);
+        }
+        return List::new(values);
+    }
+
+    /// Returns the position in the list that a value used as an index refers to, checking that
+    /// it is a whole number within the bounds of the list.
+    pub fn checked_index(&self, index: &Value) -> Result<usize, EvaluatorError> {
+        let i = position(index)?;
+        if i >= self.len() {
+            return Err(EvaluatorError::IndexOutOfRange);
+        }
+        return Ok(i);
+    }
+
+    /// Returns the position in the list that a value used as the start of a slice refers to,
+    /// checking it in the same way as `checked_index`, except that it can be the length of the
+    /// list, where an empty slice at the end starts.
+    pub fn checked_start(&self, start: &Value) -> Result<usize, EvaluatorError> {
+        let i = position(start)?;
+        if i > self.len() {
+            return Err(EvaluatorError::IndexOutOfRange);
+        }
+        return Ok(i);
+    }
+
     /// Returns the length of the list.
     pub fn len(&self) -> usize {
-        return self.values.len();
+        return self.values.borrow().len();
     }
 
-    /// Sorts the list using the TimSort algorithm.
-    pub fn tim_sort(&mut self) -> Result<&mut List, EvaluatorError> {
-        let n = self.values.len();
+    /// Returns whether the list has no values.
+    pub fn is_empty(&self) -> bool {
+        return self.values.borrow().is_empty();
+    }
 
-        let mut run_length = self.calc_min_run(n as f32);
+    /// Sorts the list in place using the TimSort algorithm.
+    pub fn tim_sort(&self) -> Result<(), EvaluatorError> {
+        let mut values = self.values.borrow_mut();
+        let n = values.len();
+
+        let mut run_length = calc_min_run(n as f32);
 
         for start in (0..n).step_by(run_length) {
             let end = min(start + run_length - 1, n - 1);
-            self.insertion_sort(start, end);
+            insertion_sort(&mut values, start, end);
         }
 
         if n <= 32 {
-            return Ok(self);
+            return Ok(());
         }
 
         while run_length < n {
@@ -99,110 +241,189 @@ impl List {
                 let right = min(n - 1, left + 2 * run_length - 1);
 
                 if mid < right {
-                    self.merge_sort(left, mid, right)?;
+                    merge_sort(&mut values, left, mid, right)?;
                 }
             }
             run_length *= 2;
         }
-        return Ok(self);
+        return Ok(());
     }
 
-    fn calc_min_run(&self, len: f32) -> usize {
-        let mut run_len = len;
-        let mut remainder: f32 = 0.0;
-        while run_len > THRESHOLD {
-            if run_len % 2.0 == 1.0 {
-                remainder = 1.0;
+    /// Searches the list, which must already be sorted from smallest to largest, for a value,
+    /// returning the index of the value if the list contains it. Each step halves the part of
+    /// the list left to search, so a list of a million items takes at most twenty steps.
+    pub fn binary_search(&self, target: &Value) -> Result<Option<usize>, EvaluatorError> {
+        let values = self.values.borrow();
+        let mut low = 0;
+        let mut high = values.len();
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match compare(&values[mid], target)? {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Ok(Some(mid)),
             }
-            run_len = run_len.floor() / 2.0;
         }
-        
-        return (run_len + remainder) as usize;
+        return Ok(None);
     }
 
-    fn insertion_sort(&mut self, left: usize, right: usize) {
-        let mut j;
-        for i in left + 1..right + 1 {
-            j = i;
-            while j > left && self.values[j] < self.values[j - 1] {
-                self.values.swap(j, j - 1);
-                j -= 1;
-            }
+    /// Returns a copy of the list sorted from largest to smallest, leaving the list unchanged.
+    pub fn reverse_sorted(&self) -> Result<List, EvaluatorError> {
+        let sorted = List::new(self.values.borrow().clone());
+        sorted.tim_sort()?;
+        sorted.values.borrow_mut().reverse();
+        return Ok(sorted);
+    }
+
+    /// Returns a copy of the list with its items in a random order decided by the seed, so that
+    /// the same seed always gives the same order.
+    pub fn shuffled(&self, seed: f64) -> List {
+        let mut shuffled = self.values.borrow().clone();
+        let mut state = seed.to_bits();
+
+        // A Fisher-Yates shuffle, using SplitMix64 to generate the random numbers
+        for i in (1..shuffled.len()).rev() {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+
+            let j = (z % (i as u64 + 1)) as usize;
+            shuffled.swap(i, j);
         }
+        return List::new(shuffled);
     }
+}
 
-    fn merge_sort(&mut self, l: usize, m: usize, r: usize) -> Result<(), EvaluatorError> {
-        let left_len = m - l + 1;
-        let right_len = r - m;
-
-        let left = self.values[l..=m].to_vec();
-        let right = self.values[m+1..=r].to_vec();
-
-
-        let mut i = 0;
-        let mut j = 0;
-        let mut k = l;
-
-        while i < left_len && j < right_len {
-            match (&left[i], &right[j]) {
-                (Value::Literal(a), Value::Literal(b)) => {
-                    match (a, b) {
-                        (LiteralType::Num(n1), LiteralType::Num(n2)) => {
-                            if n1 <= n2 {
-                                self.values[k] = left[i].clone();
-                                i += 1;
-                            } else {
-                                self.values[k] = right[j].clone();
-                                j += 1;
-                            }
-                            k += 1;
-                        },
-                        (LiteralType::Str(s1), LiteralType::Str(s2)) => {
-                            if s1 <= s2 {
-                                self.values[k] = left[i].clone();
-                                i += 1;
-                            } else {
-                                self.values[k] = right[j].clone();
-                                j += 1;
-                            }
-                            k += 1;
-                        },
-                        _ => return Err(EvaluatorError::CannotCompareValues),
-                    }
-                },
-                _ => return Err(EvaluatorError::CannotCompareValues),
-            }
+fn calc_min_run(len: f32) -> usize {
+    let mut run_len = len;
+    let mut remainder: f32 = 0.0;
+    while run_len > THRESHOLD {
+        if run_len % 2.0 == 1.0 {
+            remainder = 1.0;
         }
+        run_len = run_len.floor() / 2.0;
+    }
+    
+    return (run_len + remainder) as usize;
+}
 
-        while i < left_len {
-            self.values[k] = left[i].clone();
-            i += 1;
-            k += 1;
+fn insertion_sort(values: &mut [Value], left: usize, right: usize) {
+    let mut j;
+    for i in left + 1..right + 1 {
+        j = i;
+        while j > left && values[j] < values[j - 1] {
+            values.swap(j, j - 1);
+            j -= 1;
         }
+    }
+}
+
+fn merge_sort(values: &mut [Value], l: usize, m: usize, r: usize) -> Result<(), EvaluatorError> {
+    let left_len = m - l + 1;
+    let right_len = r - m;
 
-        while j < right_len {
-            self.values[k] = right[j].clone();
-            j += 1;
-            k += 1;
+    let left = values[l..=m].to_vec();
+    let right = values[m+1..=r].to_vec();
+
+
+    let mut i = 0;
+    let mut j = 0;
+    let mut k = l;
+
+    while i < left_len && j < right_len {
+        match (&left[i], &right[j]) {
+            (Value::Literal(a), Value::Literal(b)) => {
+                match (a, b) {
+                    (LiteralType::Num(n1), LiteralType::Num(n2)) => {
+                        if n1 <= n2 {
+                            values[k] = left[i].clone();
+                            i += 1;
+                        } else {
+                            values[k] = right[j].clone();
+                            j += 1;
+                        }
+                        k += 1;
+                    },
+                    (LiteralType::Str(s1), LiteralType::Str(s2)) => {
+                        if s1 <= s2 {
+                            values[k] = left[i].clone();
+                            i += 1;
+                        } else {
+                            values[k] = right[j].clone();
+                            j += 1;
+                        }
+                        k += 1;
+                    },
+                    _ => return Err(EvaluatorError::CannotCompareValues),
+                }
+            },
+            _ => return Err(EvaluatorError::CannotCompareValues),
         }
+    }
 
-        return Ok(());
+    while i < left_len {
+        values[k] = left[i].clone();
+        i += 1;
+        k += 1;
+    }
+
+    while j < right_len {
+        values[k] = right[j].clone();
+        j += 1;
+        k += 1;
     }
+
+    return Ok(());
 }
 
-impl fmt::Display for List {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+/// Compares two values in the order they are sorted in, which is only defined between two
+/// numbers or two strings.
+pub fn compare(a: &Value, b: &Value) -> Result<Ordering, EvaluatorError> {
+    return match (a, b) {
+        (Value::Literal(LiteralType::Num(n1)), Value::Literal(LiteralType::Num(n2))) => {
+            n1.partial_cmp(n2).ok_or(EvaluatorError::CannotCompareValues)
+        },
+        (Value::Literal(LiteralType::Str(s1)), Value::Literal(LiteralType::Str(s2))) => {
+            Ok(s1.cmp(s2))
+        },
+        _ => Err(EvaluatorError::CannotCompareValues),
+    };
+}
+
+/// Returns the position that a value used as an index refers to, which must be a whole number
+/// that is not negative.
+fn position(index: &Value) -> Result<usize, EvaluatorError> {
+    let Value::Literal(LiteralType::Num(num)) = index else {
+        return Err(EvaluatorError::ExpectedIndexToBeANum);
+    };
+    if num.fract() != 0.0 {
+        return Err(EvaluatorError::ExpectedIndexToBeANum);
+    }
+    if *num < 0.0 {
+        return Err(EvaluatorError::IndexOutOfRange);
+    }
+    return Ok(*num as usize);
+}
+
+impl List {
+    /// Writes the list, writing any numbers in it with the given format.
+    pub fn write(&self, f: &mut fmt::Formatter<'_>, format: &NumberFormat) -> fmt::Result {
         write!(f, "[")?;
-        for (i, value) in self.values.iter().enumerate() {
+        for (i, value) in self.values.borrow().iter().enumerate() {
             if i > 0 {
             write!(f, ", ")?;
             }
-            if let Value::Literal(LiteralType::Str(_)) = value {
-                write!(f, "\"{}\"", value)?;
-                continue;
-            }
-            write!(f, "{}", value)?;
+            write_item(f, value, format)?;
         }
         write!(f, "]")
     }
+}
+
+impl fmt::Display for List {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return self.write(f, &NumberFormat::default());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,206 @@
+//! The `marshal` module converts between the values of the language and Rust values, so that a
+//! program embedding the interpreter can pass its own data into a script and read back the
+//! results.
+//!
+//! ## Overview
+//!
+//! `Value` implements Serde's `Serialize` and `Deserialize` traits, and the `to_value` and
+//! `from_value` functions use them to convert any Serde compatible Rust type to and from a
+//! `Value`. The values are mapped as follows:
+//!
+//! - Numbers, strings, booleans and `null` map to their Rust and JSON equivalents. A number with
+//!   no fractional part is serialized as an integer, so it can be read back into an integer type.
+//! - Lists, tuples and sets are serialized as sequences, and sequences are deserialized as lists.
+//! - Dictionaries are serialized as maps whose keys are written as strings, and maps are
+//!   deserialized as dictionaries.
+//! - Instances are serialized as maps of their fields, so a struct can be read back from one.
+//! - Functions and classes cannot be converted.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use serde::{Deserialize, Serialize};
+//!
+//! use pyru::marshal::{from_value, to_value};
+//!
+//! #[derive(Debug, Deserialize, PartialEq, Serialize)]
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! let value = to_value(&Point { x: 1, y: 2 }).unwrap();
+//! assert_eq!(from_value::<Point>(&value).unwrap(), Point { x: 1, y: 2 });
+//! ```
+
+use std::fmt;
+
+use serde::{
+    de::{self, DeserializeOwned, MapAccess, SeqAccess, Visitor},
+    ser::{self, SerializeMap, SerializeSeq},
+    Deserialize,
+    Deserializer,
+    Serialize,
+    Serializer,
+};
+
+use crate::{
+    dict::Dict,
+    error::MarshalError,
+    list::List,
+    value::{LiteralType, Value},
+};
+
+/// The largest integer that a number can hold exactly, above which numbers are serialized as
+/// floating point numbers.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+/// Converts a Rust value into a value of the language.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value, MarshalError> {
+    let json = serde_json::to_value(value)
+        .map_err(|e| MarshalError::IntoValue { message: e.to_string() })?;
+    return Value::deserialize(json).map_err(|e| MarshalError::IntoValue { message: e.to_string() });
+}
+
+/// Converts a value of the language into a Rust value.
+pub fn from_value<T: DeserializeOwned>(value: &Value) -> Result<T, MarshalError> {
+    let json = serde_json::to_value(value)
+        .map_err(|e| MarshalError::FromValue { message: e.to_string() })?;
+    return serde_json::from_value(json).map_err(|e| MarshalError::FromValue { message: e.to_string() });
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return match self {
+            Value::Literal(literal) => literal.serialize(serializer),
+            Value::List(list) => serialize_seq(serializer, &list.values()),
+            Value::Tuple(items) => serialize_seq(serializer, items),
+            Value::Set(set) => serialize_seq(serializer, &set.values()),
+            Value::Deque(deque) => serialize_seq(serializer, &deque.values()),
+            Value::Dict(dict) => {
+                let mut map = serializer.serialize_map(Some(dict.len()))?;
+                for (key, value) in dict.entries.iter() {
+                    // Keys are written as strings, as they are in JSON
+                    match key.to_value() {
+                        Value::Literal(LiteralType::Str(s)) => map.serialize_entry(&s, value)?,
+                        key => map.serialize_entry(&key.to_string(), value)?,
+                    }
+                }
+                map.end()
+            },
+            Value::Instance(instance) => {
+                let fields = instance.fields();
+                let mut map = serializer.serialize_map(Some(fields.len()))?;
+                for (name, value) in &fields {
+                    map.serialize_entry(name, value)?;
+                }
+                map.end()
+            },
+            // A variant is written as its name, which is how it would be stored as text
+            Value::Variant(variant) => serializer.serialize_str(variant.name()),
+            Value::Class(_) | Value::Enum(_) | Value::Function(_) | Value::NativeFunction(_) => Err(
+                ser::Error::custom(format!("a {} cannot be converted", self.type_name()))
+            ),
+        };
+    }
+}
+
+impl Serialize for LiteralType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return match self {
+            LiteralType::Str(s) => serializer.serialize_str(s),
+            LiteralType::Num(n) => {
+                if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER {
+                    serializer.serialize_i64(*n as i64)
+                } else {
+                    serializer.serialize_f64(*n)
+                }
+            },
+            LiteralType::True => serializer.serialize_bool(true),
+            LiteralType::False => serializer.serialize_bool(false),
+            LiteralType::Null => serializer.serialize_unit(),
+        };
+    }
+}
+
+/// Serializes the items of a list, tuple, set or deque as a sequence.
+fn serialize_seq<S: Serializer>(serializer: S, items: &[Value]) -> Result<S::Ok, S::Error> {
+    let mut seq = serializer.serialize_seq(Some(items.len()))?;
+    for item in items {
+        seq.serialize_element(item)?;
+    }
+    return seq.end();
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        return deserializer.deserialize_any(ValueVisitor);
+    }
+}
+
+/// Builds a `Value` from whichever kind of data the deserializer holds.
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "a number, string, boolean, null, sequence or map");
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        if v {
+            return Ok(Value::Literal(LiteralType::True));
+        }
+        return Ok(Value::Literal(LiteralType::False));
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        return Ok(Value::Literal(LiteralType::Num(v as f64)));
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        return Ok(Value::Literal(LiteralType::Num(v as f64)));
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        return Ok(Value::Literal(LiteralType::Num(v)));
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        return Ok(Value::Literal(LiteralType::Str(v.to_string())));
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        return Ok(Value::Literal(LiteralType::Str(v)));
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        return Ok(Value::Literal(LiteralType::Null));
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        return Ok(Value::Literal(LiteralType::Null));
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        return Value::deserialize(deserializer);
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        return Ok(Value::List(List::new(values)));
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some((key, value)) = map.next_entry::<Value, Value>()? {
+            items.push((key, value));
+        }
+        let dict = Dict::new(items).map_err(de::Error::custom)?;
+        return Ok(Value::Dict(dict));
+    }
+}
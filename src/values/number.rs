@@ -0,0 +1,117 @@
+//! The `number` module defines the `NumberFormat` struct, which decides how numbers are written
+//! when they are printed or converted to strings.
+//!
+//! ## Overview
+//!
+//! A number is written with as many significant digits as it takes to read it back exactly,
+//! unless the format limits them, and without a fractional part if it is whole. Numbers whose
+//! magnitude is very large or very small are written in scientific notation, such as `1e+21`
+//! or `1.5e-7`, as writing out every zero would hide the digits that matter.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use pyru::number::NumberFormat;
+//!
+//! let format = NumberFormat::default().with_significant_digits(Some(3));
+//! assert_eq!(format.format(3.14159), "3.14");
+//! assert_eq!(format.format(1e25), "1e+25");
+//! ```
+
+/// How numbers are written as text.
+///
+/// ## Fields
+/// - `significant_digits`: The most significant digits to write, rounding the rest away, or
+///   `None` to write as many as it takes to read the number back exactly.
+/// - `lowest_exponent`: Numbers whose decimal exponent is below this are written in scientific
+///   notation.
+/// - `highest_exponent`: Numbers whose decimal exponent is at least this are written in
+///   scientific notation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NumberFormat {
+    significant_digits: Option<usize>,
+    lowest_exponent: i32,
+    highest_exponent: i32,
+}
+
+impl Default for NumberFormat {
+    /// Writes numbers exactly, switching to scientific notation below `1e-6` and from `1e21`
+    /// upwards.
+    fn default() -> Self {
+        return Self {
+            significant_digits: None,
+            lowest_exponent: -6,
+            highest_exponent: 21,
+        };
+    }
+}
+
+impl NumberFormat {
+    /// Sets the most significant digits to write, which is at least one.
+    pub fn with_significant_digits(mut self, significant_digits: Option<usize>) -> Self {
+        self.significant_digits = significant_digits.map(|digits| digits.max(1));
+        return self;
+    }
+
+    /// Sets the decimal exponents outside of which numbers are written in scientific notation,
+    /// from `lowest` up to but not including `highest`.
+    pub fn with_exponent_thresholds(mut self, lowest: i32, highest: i32) -> Self {
+        self.lowest_exponent = lowest;
+        self.highest_exponent = highest;
+        return self;
+    }
+
+    /// Writes a number as text.
+    pub fn format(&self, n: f64) -> String {
+        if n.is_nan() {
+            return "NaN".to_string();
+        }
+        if n.is_infinite() {
+            return if n < 0.0 { "-inf" } else { "inf" }.to_string();
+        }
+        if n == 0.0 {
+            return if n.is_sign_negative() { "-0" } else { "0" }.to_string();
+        }
+
+        // Scientific notation gives the digits and the exponent without any padding zeros
+        let scientific = match self.significant_digits {
+            Some(digits) => format!("{:.*e}", digits - 1, n.abs()),
+            None => format!("{:e}", n.abs()),
+        };
+        let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+        let exponent: i32 = exponent.parse().unwrap_or(0);
+        let digits = mantissa.replace('.', "");
+        let digits = digits.trim_end_matches('0');
+
+        let mut text = String::new();
+        if n < 0.0 {
+            text.push('-');
+        }
+
+        if exponent < self.lowest_exponent || exponent >= self.highest_exponent {
+            text.push_str(&digits[..1]);
+            if digits.len() > 1 {
+                text.push('.');
+                text.push_str(&digits[1..]);
+            }
+            text.push('e');
+            text.push(if exponent < 0 { '-' } else { '+' });
+            text.push_str(&exponent.abs().to_string());
+        } else if exponent < 0 {
+            text.push_str("0.");
+            text.push_str(&"0".repeat((-exponent - 1) as usize));
+            text.push_str(digits);
+        } else {
+            let whole = exponent as usize + 1;
+            if digits.len() > whole {
+                text.push_str(&digits[..whole]);
+                text.push('.');
+                text.push_str(&digits[whole..]);
+            } else {
+                text.push_str(digits);
+                text.push_str(&"0".repeat(whole - digits.len()));
+            }
+        }
+        return text;
+    }
+}
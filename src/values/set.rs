@@ -0,0 +1,180 @@
+//! The `set` module defines the `Set` struct, which represents an unordered collection of unique
+//! values, along with the methods that can be called on a set in the language.
+//!
+//! ## Overview
+//!
+//! Sets are created with the literal syntax `{1, 2, 3}` or by passing a list or tuple to the
+//! native `set` function, and support the `add()`, `remove()`, `contains()`, `union()`,
+//! `intersection()` and `len()` methods.
+//!
+//! As with the keys of a dictionary, only literal values can be stored in a set. The items are
+//! kept in the order they were first added so that printing a set is deterministic.
+
+use std::{cmp::Ordering, collections::HashSet, fmt};
+
+use crate::{dict::DictKey, error::EvaluatorError, number::NumberFormat, value::{write_item, Value}};
+
+/// The `Set` struct represents a set of unique values and provides methods for manipulating it.
+///
+/// ## Fields
+/// - `items`: The items of the set, in the order they were added.
+/// - `index`: A `HashSet` of the items, used to check membership.
+#[derive(Clone, Debug)]
+pub struct Set {
+    items: Vec<DictKey>,
+    index: HashSet<DictKey>,
+}
+
+impl PartialEq for Set {
+    /// Two sets are equal if they contain the same items, regardless of their order.
+    fn eq(&self, other: &Self) -> bool {
+        return self.items.len() == other.items.len()
+            && self.items.iter().all(|item| other.index.contains(item));
+    }
+}
+
+impl PartialOrd for Set {
+    /// Sets have no ordering, so they can only be compared for equality.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self == other {
+            return Some(Ordering::Equal);
+        }
+        return None;
+    }
+}
+
+impl Set {
+    /// Creates a new `Set` instance from the given values, discarding any duplicates.
+    pub fn new(values: Vec<Value>) -> Result<Self, EvaluatorError> {
+        let mut set = Self { items: Vec::new(), index: HashSet::new() };
+        for value in values {
+            set.insert(&value)?;
+        }
+        return Ok(set);
+    }
+
+    /// Inserts a value into the set if it is not already present.
+    fn insert(&mut self, value: &Value) -> Result<(), EvaluatorError> {
+        let key = DictKey::new(value).map_err(|_| EvaluatorError::InvalidSetItem)?;
+        if self.index.insert(key.clone()) {
+            self.items.push(key);
+        }
+        return Ok(());
+    }
+
+    /// Adds a value to the set.
+    pub fn add(&mut self, args: Vec<Value>) -> Result<&mut Set, EvaluatorError> {
+        if args.len() != 1 {
+            return Err(EvaluatorError::ArgsDifferFromArity { args: args.len(), arity: 1 });
+        }
+
+        self.insert(&args[0])?;
+        return Ok(self);
+    }
+
+    /// Removes a value from the set.
+    pub fn remove(&mut self, args: Vec<Value>) -> Result<&mut Set, EvaluatorError> {
+        if args.len() != 1 {
+            return Err(EvaluatorError::ArgsDifferFromArity { args: args.len(), arity: 1 });
+        }
+
+        let key = DictKey::new(&args[0]).map_err(|_| EvaluatorError::ItemNotFound)?;
+        if !self.index.remove(&key) {
+            return Err(EvaluatorError::ItemNotFound);
+        }
+        self.items.retain(|item| item != &key);
+        return Ok(self);
+    }
+
+    /// Returns whether the set contains the given value.
+    pub fn contains(&self, value: &Value) -> bool {
+        return match DictKey::new(value) {
+            Ok(key) => self.index.contains(&key),
+            Err(_) => false,
+        };
+    }
+
+    /// Returns a new set containing the items of both sets.
+    pub fn union(&self, args: Vec<Value>) -> Result<Set, EvaluatorError> {
+        let other = Self::argument(args)?;
+
+        let mut result = self.clone();
+        for item in other.items {
+            if result.index.insert(item.clone()) {
+                result.items.push(item);
+            }
+        }
+        return Ok(result);
+    }
+
+    /// Returns a new set containing the items that are in both sets.
+    pub fn intersection(&self, args: Vec<Value>) -> Result<Set, EvaluatorError> {
+        let other = Self::argument(args)?;
+
+        let items: Vec<DictKey> = self.items
+            .iter()
+            .filter(|item| other.index.contains(item))
+            .cloned()
+            .collect();
+        let index = items.iter().cloned().collect();
+        return Ok(Self { items, index });
+    }
+
+    /// Returns the items of the set as values.
+    pub fn values(&self) -> Vec<Value> {
+        return self.items.iter().map(DictKey::to_value).collect();
+    }
+
+    /// Returns the number of items in the set.
+    pub fn len(&self) -> usize {
+        return self.items.len();
+    }
+
+    /// Returns whether the set has no items.
+    pub fn is_empty(&self) -> bool {
+        return self.items.is_empty();
+    }
+
+    /// Returns roughly how many bytes the items of the set take up. Each item is stored twice,
+    /// in the order it was added and in the index.
+    pub fn approximate_size(&self) -> usize {
+        return self.items.iter().map(|item| 2 * item.approximate_size()).sum();
+    }
+
+    /// Checks that a single set was passed to a method that combines two sets.
+    fn argument(args: Vec<Value>) -> Result<Set, EvaluatorError> {
+        if args.len() != 1 {
+            return Err(EvaluatorError::ArgsDifferFromArity { args: args.len(), arity: 1 });
+        }
+
+        return match args.into_iter().next() {
+            Some(Value::Set(set)) => Ok(set),
+            _ => Err(EvaluatorError::ExpectedSet),
+        };
+    }
+}
+
+impl Set {
+    /// Writes the set, writing any numbers in it with the given format.
+    pub fn write(&self, f: &mut fmt::Formatter<'_>, format: &NumberFormat) -> fmt::Result {
+        // An empty set is written as a call, as `{}` is an empty dictionary
+        if self.items.is_empty() {
+            return write!(f, "set()");
+        }
+
+        write!(f, "{{")?;
+        for (i, value) in self.values().iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write_item(f, value, format)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Display for Set {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return self.write(f, &NumberFormat::default());
+    }
+}
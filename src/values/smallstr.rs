@@ -0,0 +1,134 @@
+//! The `smallstr` module defines the `SmallStr` type, an immutable string used to store the
+//! lexemes and literals of tokens.
+//!
+//! ## Overview
+//!
+//! Every token stores its lexeme and literal, so a program produces a large number of short
+//! strings that are cloned repeatedly by the parser as they are moved into the AST. `SmallStr`
+//! stores strings of up to 22 bytes inline, without allocating, and shares longer strings behind
+//! an `Rc`, which makes cloning a token cheap regardless of its length. A `SmallStr` is the same
+//! size as a `String`.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use pyru::smallstr::SmallStr;
+//!
+//! let lexeme = SmallStr::from("identifier");
+//! assert_eq!(lexeme, "identifier");
+//! assert_eq!(lexeme.len(), 10);
+//! ```
+
+use std::{
+    borrow::Borrow,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    rc::Rc,
+};
+
+// The maximum number of bytes that can be stored without allocating
+const INLINE_CAPACITY: usize = 22;
+
+/// An immutable string which avoids allocating for short strings.
+///
+/// ## Variants
+/// - `Inline`: A string of at most `INLINE_CAPACITY` bytes stored in place.
+/// - `Heap`: A longer string stored in a shared allocation.
+#[derive(Clone)]
+pub enum SmallStr {
+    Inline { len: u8, buf: [u8; INLINE_CAPACITY] },
+    Heap(Rc<str>),
+}
+
+impl SmallStr {
+    /// Creates a new `SmallStr` from the given string slice.
+    pub fn new(text: &str) -> Self {
+        if text.len() <= INLINE_CAPACITY {
+            let mut buf = [0; INLINE_CAPACITY];
+            buf[..text.len()].copy_from_slice(text.as_bytes());
+            return SmallStr::Inline { len: text.len() as u8, buf };
+        }
+
+        return SmallStr::Heap(Rc::from(text));
+    }
+
+    /// Returns the contents of the string as a string slice.
+    pub fn as_str(&self) -> &str {
+        return match self {
+            // The buffer is only ever filled from a valid `&str`, so it is always valid UTF-8
+            SmallStr::Inline { len, buf } => std::str::from_utf8(&buf[..*len as usize]).unwrap(),
+            SmallStr::Heap(text) => text,
+        };
+    }
+}
+
+impl Default for SmallStr {
+    fn default() -> Self {
+        return SmallStr::new("");
+    }
+}
+
+impl Deref for SmallStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        return self.as_str();
+    }
+}
+
+impl Borrow<str> for SmallStr {
+    fn borrow(&self) -> &str {
+        return self.as_str();
+    }
+}
+
+impl From<&str> for SmallStr {
+    fn from(text: &str) -> Self {
+        return SmallStr::new(text);
+    }
+}
+
+impl From<String> for SmallStr {
+    fn from(text: String) -> Self {
+        return SmallStr::new(&text);
+    }
+}
+
+impl PartialEq for SmallStr {
+    fn eq(&self, other: &Self) -> bool {
+        return self.as_str() == other.as_str();
+    }
+}
+
+impl Eq for SmallStr {}
+
+impl PartialEq<str> for SmallStr {
+    fn eq(&self, other: &str) -> bool {
+        return self.as_str() == other;
+    }
+}
+
+impl PartialEq<&str> for SmallStr {
+    fn eq(&self, other: &&str) -> bool {
+        return self.as_str() == *other;
+    }
+}
+
+impl Hash for SmallStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl fmt::Debug for SmallStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{:?}", self.as_str());
+    }
+}
+
+impl fmt::Display for SmallStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.as_str());
+    }
+}
@@ -17,12 +17,12 @@
 //! ## Example
 //!
 //! ```rust
-//! use crate::stmt::Stmt;
-//! use crate::expr::Expr;
-//! use crate::token::Token;
+//! use pyru::stmt::Stmt;
+//! use pyru::expr::Expr;
+//! use pyru::value::LiteralType;
 //!
 //! let stmt = Stmt::Print {
-//!     expression: Expr::Literal("Hello, world!".into()),
+//!     expressions: vec![Expr::Literal { value: LiteralType::Str("Hello, world!".to_string()) }],
 //! };
 //!
 //! println!("{}", stmt);
@@ -35,96 +35,279 @@
 //! types in the language.
 
 use paste::paste;
-use std::fmt;
+use std::{cell::OnceCell, fmt, rc::Rc};
 
 use crate::{
     expr::Expr,
     stmt_visitor,
-    token::Token
+    token::Token,
+    value::{LiteralType, Value},
 };
 
 /// Represents the different types of statements that can be encountered in the source code.
 ///
 /// ## Variants
+/// - `Break`: Represents a `break` statement, which exits the enclosing loop, or the enclosing
+///   loop with the given label.
+/// - `Class`: Represents a class declaration, whose methods are `Function` statements.
+/// - `Continue`: Represents a `continue` statement, which skips to the next iteration of the
+///   enclosing loop, or the enclosing loop with the given label.
+/// - `Del`: Represents a `del` statement, which removes a variable from the current scope, or an
+///   item from a list or dictionary held in a place, e.g. `del grid[i][j];`.
+/// - `Destructure`: Represents a declaration of, or an assignment to, several variables from the
+///   items of a tuple or list, e.g. `let a, b = pair;` or `a, b = b, a;`. An assignment can also
+///   be to items of lists and dictionaries and to fields of instances, e.g.
+///   `xs[i], xs[j] = xs[j], xs[i];`.
+/// - `Enum`: Represents an enum declaration, which names the variants of the enum, e.g.
+///   `enum Color: Red, Green, Blue;`.
 /// - `Expression`: Represents an expression statement.
 /// - `For`: Represents a `for` loop.
-/// - `Function`: Represents a function declaration.
-/// - `If`: Represents an `if` statement with optional `else` branch.
-/// - `Print`: Represents a `print` statement.
+/// - `ForEach`: Represents a `for` loop over the elements of a list or deque, or the characters
+///   of a string, e.g. `for item in items:`.
+/// - `Function`: Represents a function declaration, whose parameters and return value can be
+///   annotated with types, e.g. `def f(a: str) -> num:`. A string written as the first statement
+///   of the body is kept as the docstring of the function.
+/// - `If`: Represents an `if` statement with an optional `else` branch. An `elif` is stored as
+///   an `If` statement that is the only statement of the `else` branch.
+/// - `Import`: Represents an `import` statement, which runs a module of the standard library in
+///   the current scope, e.g. `import math;`.
+/// - `Loop`: Represents a `loop` statement, which runs its body until it is broken out of.
+/// - `Match`: Represents a `match` statement, which runs the body of the first arm whose pattern
+///   matches the value. Nothing is run if none of the patterns match.
+/// - `Pass`: Represents a `pass` statement, which does nothing and allows a body to be empty.
+/// - `Print`: Represents a `print` statement, which prints the values of its expressions on one
+///   line, separated by spaces.
+/// - `Raise`: Represents a `raise` statement, which raises a value as an error.
 /// - `Return`: Represents a `return` statement.
-/// - `Var`: Represents a variable declaration.
+/// - `Try`: Represents a `try` statement, with an `except` branch that runs if the body raises a
+///   runtime error and a `finally` branch that always runs afterwards. At least one of the two
+///   branches is present.
+/// - `Var`: Represents a variable declaration, which can be annotated with a type, e.g.
+///   `let x: num = 5;`.
 /// - `While`: Represents a `while` loop.
+///
+/// Each of the loops can have a label, e.g. `outer: for i in 0..3:`, which lets a `break` or
+/// `continue` inside a nested loop refer to it.
+///
+/// The `locals` of a function or `for` loop are the names declared in the scope its body runs in,
+/// in the order of their slots. They are filled in by the resolver once the program has been
+/// parsed, and are empty until then. The resolver also fills in the `module` of an `import`,
+/// resolved in the scope it is imported into, which is empty if there is no such module.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Stmt {
+    Break {
+        keyword: Token,
+        label: Option<Token>,
+    },
+    Class {
+        name: Token,
+        methods: Vec<Stmt>,
+    },
+    Continue {
+        keyword: Token,
+        label: Option<Token>,
+    },
+    Del {
+        target: Expr, // A variable, or an index of a place
+    },
+    Destructure {
+        targets: Vec<Expr>, // Variables when declaring, or any assignment targets when assigning
+        initializer: Expr,
+        declare: bool, // Whether the names are declared with `let` rather than assigned to
+    },
+    Enum {
+        name: Token,
+        variants: Vec<Token>,
+    },
     Expression {
         expression: Expr,
     },
     For {
+        label: Option<Token>,
         initializer: Box<Stmt>,
         condition: Expr,
         step: Expr,
         body: Vec<Stmt>,
+        locals: OnceCell<Rc<[String]>>, // The names declared in the scope of each iteration
+    },
+    ForEach {
+        label: Option<Token>,
+        name: Token, // The name each element is bound to
+        iterable: Expr, // The list or string being looped over
+        body: Vec<Stmt>,
+        locals: OnceCell<Rc<[String]>>, // The names declared in the scope of each iteration
     },
     Function {
         name: Token,
         params: Vec<Token>,
-        body: Vec<Stmt>,
+        param_types: Vec<Option<Token>>, // The annotated type of each parameter, if it has one
+        return_type: Option<Token>,
+        body: Rc<[Stmt]>, // Shared by the function values created from the declaration
+        doc: Option<String>, // The docstring written as the first statement of the body
+        locals: OnceCell<Rc<[String]>>, // The names declared in the scope of each call
     },
     If {
         condition: Expr,
         then_branch: Vec<Stmt>,
-        else_branch: Option<Box<Stmt>>,
+        else_branch: Option<Vec<Stmt>>,
+    },
+    Import {
+        name: Token,
+        module: OnceCell<Rc<[Stmt]>>, // The statements of the module being imported
+    },
+    Loop {
+        keyword: Token,
+        label: Option<Token>,
+        body: Vec<Stmt>,
+    },
+    Match {
+        keyword: Token,
+        subject: Expr, // The value being matched
+        arms: Vec<(Pattern, Vec<Stmt>)>, // Each pattern and the body run if it matches
+    },
+    Pass {
+        keyword: Token,
     },
     Print {
-        expression: Expr,
+        expressions: Vec<Expr>,
+    },
+    Raise {
+        keyword: Token,
+        value: Expr,
     },
     Return {
         keyword: Token,
         value: Option<Expr>,
     },
+    Try {
+        body: Vec<Stmt>,
+        name: Option<Token>, // The name the error is bound to in the except branch
+        handler: Option<Vec<Stmt>>,
+        finally: Option<Vec<Stmt>>,
+    },
     Var {
         name: Token,
+        annotation: Option<Token>, // The annotated type of the variable
         initializer: Option<Expr>,
     },
     While {
+        label: Option<Token>,
         condition: Expr,
         body: Vec<Stmt>,
     },
 }
 
+/// A pattern that a value is compared against by an arm of a `match` statement.
+///
+/// ## Variants
+/// - `Literal`: Matches a value equal to the literal, e.g. `case "yes":`.
+/// - `Range`: Matches a number from the start up to, but not including, the end, as in a `for`
+///   loop, e.g. `case 0..10:`.
+/// - `Wildcard`: Matches any value, written as `case _:`. It can only be the last arm.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    Literal(LiteralType),
+    Range { start: f64, end: f64 },
+    Wildcard,
+}
+
+impl Pattern {
+    /// Checks whether a value matches the pattern.
+    pub fn matches(&self, value: &Value) -> bool {
+        return match (self, value) {
+            (Pattern::Literal(literal), Value::Literal(value)) => literal == value,
+            (Pattern::Range { start, end }, Value::Literal(LiteralType::Num(n))) => {
+                start <= n && n < end
+            },
+            (Pattern::Wildcard, _) => true,
+            _ => false,
+        };
+    }
+}
+
+impl Stmt {
+    /// Returns the line that the statement starts on, if it contains a token to take it from.
+    /// A `try` statement does not keep its keyword, so it has no line of its own.
+    pub fn line(&self) -> Option<usize> {
+        return match self {
+            Stmt::Break { keyword, .. }
+            | Stmt::Continue { keyword, .. }
+            | Stmt::Loop { keyword, .. }
+            | Stmt::Match { keyword, .. }
+            | Stmt::Pass { keyword }
+            | Stmt::Raise { keyword, .. }
+            | Stmt::Return { keyword, .. } => Some(keyword.line),
+            Stmt::Class { name, .. }
+            | Stmt::Enum { name, .. }
+            | Stmt::ForEach { name, .. }
+            | Stmt::Function { name, .. }
+            | Stmt::Import { name, .. }
+            | Stmt::Var { name, .. } => Some(name.line),
+            Stmt::Destructure { targets, initializer, .. } => {
+                targets.iter().find_map(Expr::line).or_else(|| initializer.line())
+            },
+            Stmt::Del { target: expression }
+            | Stmt::Expression { expression } => expression.line(),
+            Stmt::Print { expressions } => expressions.iter().find_map(Expr::line),
+            Stmt::For { initializer, .. } => initializer.line(),
+            Stmt::If { condition, .. } | Stmt::While { condition, .. } => condition.line(),
+            Stmt::Try { .. } => None,
+        };
+    }
+}
+
 impl fmt::Display for Stmt {
     /// Implements the `Display` trait for `Stmt` to provide a string representation
     /// of each statement variant.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Stmt::Break { .. } => write!(f, "Break"),
+            Stmt::Class { name, methods } => write!(f, "Class({name} {methods:?})"),
+            Stmt::Continue { .. } => write!(f, "Continue"),
+            Stmt::Del { target } => write!(f, "Del({target})"),
+            Stmt::Destructure { targets, initializer, declare } => {
+                return write!(f, "Destructure({declare} {targets:?} {initializer})");
+            },
+            Stmt::Enum { name, variants } => write!(f, "Enum({name} {variants:?})"),
             Stmt::Expression { expression } => write!(f, "Expression({expression})"),
-            Stmt::For { initializer, condition, step, body } => {
+            Stmt::For { initializer, condition, step, body, .. } => {
                 return write!(f, "For({initializer:?} {condition} {step:?} {body:?})");
             },
-            Stmt::Function { name, params, body } => {
+            Stmt::ForEach { name, iterable, body, .. } => {
+                write!(f, "ForEach({name} {iterable} {body:?})")
+            },
+            Stmt::Function { name, params, body, .. } => {
                 return write!(f, "Function({name} {params:?} {body:?})")
             },
             Stmt::If { condition, then_branch, else_branch } => {
                 if else_branch.is_some() {
                     return write!(
                         f,
-                        "If({condition} {then_branch:?} {})",
+                        "If({condition} {then_branch:?} {:?})",
                         else_branch.as_ref().unwrap()
                     );
                 } else {
                     return write!(f, "If({condition} {then_branch:?})");
                 }
             },
-            Stmt::Print { expression } => write!(f, "Print({expression})"),
+            Stmt::Import { name, .. } => write!(f, "Import({name})"),
+            Stmt::Loop { body, .. } => write!(f, "Loop({body:?})"),
+            Stmt::Match { subject, arms, .. } => write!(f, "Match({subject} {arms:?})"),
+            Stmt::Pass { .. } => write!(f, "Pass"),
+            Stmt::Print { expressions } => write!(f, "Print({expressions:?})"),
+            Stmt::Raise { keyword: _, value } => return write!(f, "Raise({value})"),
             Stmt::Return { keyword: _, value } => return write!(f, "Return({value:?})"),
-            Stmt::Var { name, initializer } => {
+            Stmt::Try { body, name, handler, finally } => {
+                return write!(f, "Try({body:?} {name:?} {handler:?} {finally:?})");
+            },
+            Stmt::Var { name, initializer, .. } => {
                 if initializer.is_some() {
                     return write!(f, "Var({name} {}", initializer.as_ref().unwrap());
                 } else {
                     return write!(f, "Var({name})");
                 }
             }
-            Stmt::While { condition, body } => return write!(f, "While({condition} {body:?})"),
+            Stmt::While { condition, body, .. } => return write!(f, "While({condition} {body:?})"),
         }
     }
 }
@@ -134,4 +317,4 @@ impl fmt::Display for Stmt {
 // This macro defines a `StmtVisitor` trait with methods for visiting each statement type.
 // It also implements the `accept_stmt` method for the `Stmt` enum, which dispatches the
 // appropriate visitor method based on the statement type.
-stmt_visitor!(Expression, For, Function, If, Print, Return, Var, While);
\ No newline at end of file
+stmt_visitor!(Break, Class, Continue, Del, Destructure, Enum, Expression, For, ForEach, Function, If, Import, Loop, Match, Pass, Print, Raise, Return, Try, Var, While);
\ No newline at end of file
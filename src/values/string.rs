@@ -0,0 +1,96 @@
+//! The `string` module defines the methods that can be called on a string in the language, such
+//! as `upper()`, `split(",")`, `chars()` and `replace(a, b)`.
+//!
+//! ## Overview
+//!
+//! Strings are stored as `LiteralType::Str` values and are immutable, so unlike the methods of a
+//! list, every method returns a new value and the string it was called on is left unchanged.
+
+use crate::{
+    error::EvaluatorError,
+    list::List,
+    value::{LiteralType, Value},
+};
+
+/// Calls the method with the given name on a string.
+pub fn call_method(text: &str, method: &str, args: Vec<Value>) -> Result<Value, EvaluatorError> {
+    return match method {
+        "upper" => {
+            check_arity(&args, 0)?;
+            Ok(string(text.to_uppercase()))
+        },
+        "lower" => {
+            check_arity(&args, 0)?;
+            Ok(string(text.to_lowercase()))
+        },
+        "trim" => {
+            check_arity(&args, 0)?;
+            Ok(string(text.trim().to_string()))
+        },
+        "len" => {
+            check_arity(&args, 0)?;
+            Ok(Value::Literal(LiteralType::Num(text.chars().count() as f64)))
+        },
+        "split" => {
+            check_arity(&args, 1)?;
+            let separator = string_arg(&args[0])?;
+            let parts: Vec<Value> = if separator.is_empty() {
+                text.chars().map(|c| string(c.to_string())).collect()
+            } else {
+                text.split(separator).map(|part| string(part.to_string())).collect()
+            };
+            Ok(Value::List(List::new(parts)))
+        },
+        "chars" => {
+            check_arity(&args, 0)?;
+            let chars = text.chars().map(|c| string(c.to_string())).collect();
+            Ok(Value::List(List::new(chars)))
+        },
+        "replace" => {
+            check_arity(&args, 2)?;
+            Ok(string(text.replace(string_arg(&args[0])?, string_arg(&args[1])?)))
+        },
+        "startsWith" => {
+            check_arity(&args, 1)?;
+            Ok(boolean(text.starts_with(string_arg(&args[0])?)))
+        },
+        "endsWith" => {
+            check_arity(&args, 1)?;
+            Ok(boolean(text.ends_with(string_arg(&args[0])?)))
+        },
+        "contains" => {
+            check_arity(&args, 1)?;
+            Ok(boolean(text.contains(string_arg(&args[0])?)))
+        },
+        _ => Err(EvaluatorError::InvalidStringMethod),
+    };
+}
+
+/// Checks that a method was called with the expected number of arguments.
+fn check_arity(args: &[Value], arity: usize) -> Result<(), EvaluatorError> {
+    if args.len() != arity {
+        return Err(EvaluatorError::ArgsDifferFromArity { args: args.len(), arity });
+    }
+    return Ok(());
+}
+
+/// Returns the contents of an argument that is expected to be a string.
+fn string_arg(arg: &Value) -> Result<&str, EvaluatorError> {
+    return match arg {
+        Value::Literal(LiteralType::Str(s)) => Ok(s),
+        _ => Err(EvaluatorError::ExpectedStringArgument),
+    };
+}
+
+/// Wraps a string in a value.
+fn string(text: String) -> Value {
+    return Value::Literal(LiteralType::Str(text));
+}
+
+/// Wraps a boolean in a value.
+fn boolean(value: bool) -> Value {
+    if value {
+        return Value::Literal(LiteralType::True);
+    }
+    return Value::Literal(LiteralType::False);
+}
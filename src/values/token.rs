@@ -14,7 +14,7 @@
 //! ## Example
 //!
 //! ```rust
-//! use crate::token::{Token, TokenType};
+//! use pyru::token::{Token, TokenType};
 //!
 //! let token = Token::new(
 //!     TokenType::Identifier,
@@ -36,6 +36,8 @@
 
 use std::fmt;
 
+use crate::smallstr::SmallStr;
+
 /// Represents the different types of tokens that can be encountered in the source code.
 /// 
 /// ## Variants
@@ -44,26 +46,29 @@ use std::fmt;
 /// - `LBrack`, `RBrack`: Represents `[` and `]` brackets.
 /// - `Comma`, `Dot`, `DotDot`: Represents `,`, `.`, and `..`.
 /// - `Minus`, `Plus`, `Semicolon`, `Colon`, `FSlash`, `Asterisk`: Represents `-`, `+`, `;`, `:`, `/`, and `*`.
+/// - `FSlashFSlash`: Represents `//` when it is used for floor division.
 /// - `Incr`, `Decr`: Represents `++` and `--`.
+/// - `Arrow`: Represents `->`, which comes before the return type of a function.
 /// - `Bang`, `BangEqual`: Represents `!` and `!=`.
 /// - `Equal`, `EqualEqual`: Represents `=` and `==`.
 /// - `Greater`, `GreaterEqual`, `Less`, `LessEqual`: Represents comparison operators.
 /// - `Identifier`, `String`, `Num`: Represents identifiers, string literals, and numeric literals.
-/// - Keywords: `And`, `Def`, `Else`, `False`, `For`, `If`, `In`, `Let`, `Not`, `Null`, `Or`, `Print`, `Return`, `Step`, `True`, `While`.
+/// - Keywords: `And`, `Break`, `Case`, `Class`, `Continue`, `Def`, `Del`, `Elif`, `Else`, `Except`, `False`, `Finally`, `For`, `If`, `In`, `Let`, `Loop`, `Match`, `Not`, `Null`, `Or`, `Pass`, `Print`, `Raise`, `Return`, `Step`, `True`, `Try`, `While`.
 /// - `Eof`: Represents the end of the file.
 /// - `Indent`, `Dedent`: Represents changes in indentation.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum TokenType {
     LParen, RParen, LBrace, RBrace, LBrack, RBrack, Comma, Dot, DotDot,
-    Minus, Plus, Semicolon, Colon, FSlash, Asterisk, Incr, Decr,
+    Minus, Plus, Semicolon, Colon, FSlash, FSlashFSlash, Asterisk, Incr, Decr, Arrow,
 
     Bang, BangEqual, Equal, EqualEqual,
     Greater, GreaterEqual, Less, LessEqual,
 
     Identifier, String, Num,
 
-    And, Def, Else, False, For, If, In, Let, Not,
-    Null, Or, Print, Return, Step, True, While,
+    And, Break, Case, Class, Continue, Def, Del, Elif, Else, Enum, Except, False, Finally, For,
+    If, Import, In, Let, Loop, Match, Not, Null, Or, Pass, Print, Raise, Return, Step, True, Try,
+    While,
 
     Eof, Indent, Dedent
 }
@@ -74,14 +79,17 @@ pub enum TokenType {
 /// - `token_type`: The type of the token (e.g., `Identifier`, `String`, `Num`).
 /// - `lexeme`: The lexeme (text) of the token.
 /// - `literal`: The literal value of the token, if any (e.g., the value of a string or number).
+///
+/// The lexeme and literal are stored as `SmallStr`s, so short tokens don't allocate and cloning
+/// a token into the AST is cheap.
 /// - `line`: The line number where the token is located.
 /// - `start`: The starting index of the token in the source code.
 /// - `end`: The ending index of the token in the source code.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Token {
     pub token_type: TokenType,
-    pub lexeme: String,
-    pub literal: String,
+    pub lexeme: SmallStr,
+    pub literal: SmallStr,
     pub line: usize,
     pub start: usize,
     pub end: usize
@@ -89,11 +97,18 @@ pub struct Token {
 
 impl Token {
     /// Creates a new `Token` instance.
-    pub fn new(token_type: TokenType, lexeme: String, literal: String, line: usize, start: usize, end: usize) -> Self {
+    pub fn new(
+        token_type: TokenType,
+        lexeme: impl Into<SmallStr>,
+        literal: impl Into<SmallStr>,
+        line: usize,
+        start: usize,
+        end: usize
+    ) -> Self {
         Self {
             token_type,
-            lexeme,
-            literal,
+            lexeme: lexeme.into(),
+            literal: literal.into(),
             line,
             start,
             end
@@ -120,9 +135,11 @@ impl fmt::Display for TokenType {
             TokenType::Semicolon => write!(f, "Semicolon"),
             TokenType::Colon => write!(f, "Colon"),
             TokenType::FSlash => write!(f, "FSlash"),
+            TokenType::FSlashFSlash => write!(f, "FSlashFSlash"),
             TokenType::Asterisk => write!(f, "Asterisk"),
             TokenType::Incr => write!(f, "Incr"),
             TokenType::Decr => write!(f, "Decr"),
+            TokenType::Arrow => write!(f, "Arrow"),
             TokenType::Bang => write!(f, "Bang"),
             TokenType::BangEqual => write!(f, "BangEqual"),
             TokenType::Equal => write!(f, "Equal"),
@@ -135,20 +152,35 @@ impl fmt::Display for TokenType {
             TokenType::String => write!(f, "String"),
             TokenType::Num => write!(f, "Num"),
             TokenType::And => write!(f, "And"),
+            TokenType::Break => write!(f, "Break"),
+            TokenType::Case => write!(f, "Case"),
+            TokenType::Class => write!(f, "Class"),
+            TokenType::Continue => write!(f, "Continue"),
+            TokenType::Elif => write!(f, "Elif"),
             TokenType::Else => write!(f, "Else"),
+            TokenType::Enum => write!(f, "Enum"),
+            TokenType::Except => write!(f, "Except"),
             TokenType::False => write!(f, "False"),
+            TokenType::Finally => write!(f, "Finally"),
             TokenType::For => write!(f, "For"),
             TokenType::Def => write!(f, "Def"),
+            TokenType::Del => write!(f, "Del"),
             TokenType::If => write!(f, "If"),
+            TokenType::Import => write!(f, "Import"),
             TokenType::In => write!(f, "In"),
             TokenType::Let => write!(f, "Let"),
+            TokenType::Loop => write!(f, "Loop"),
+            TokenType::Match => write!(f, "Match"),
             TokenType::Not => write!(f, "Not"),
             TokenType::Null => write!(f, "Null"),
             TokenType::Or => write!(f, "Or"),
+            TokenType::Pass => write!(f, "Pass"),
             TokenType::Print => write!(f, "Print"),
+            TokenType::Raise => write!(f, "Raise"),
             TokenType::Return => write!(f, "Return"),
             TokenType::Step => write!(f, "Step"),
             TokenType::True => write!(f, "True"),
+            TokenType::Try => write!(f, "Try"),
             TokenType::While => write!(f, "While"),
             TokenType::Eof => write!(f, "Eof"),
             TokenType::Indent => write!(f, "Indent"),
@@ -1,25 +1,50 @@
 //! This module defines the `Value` and `LiteralType` enums, which represent the different types of
 //! values that can be used in the interpreter. These include functions, lists, literals, and native
 //! functions. The module also implements the `Display` trait for these types to provide string
-//! representations of their values.
+//! representations of their values, and defines the `OrderedMap` used to store dictionaries.
 
-use std::fmt;
+use std::{collections::HashMap, fmt, hash::Hash, mem};
 
-use crate::{callable::{Func, NativeFunc}, list::List};
+use crate::{
+    callable::{Func, NativeFunc},
+    class::{Class, Instance},
+    deque::Deque,
+    dict::Dict,
+    enumeration::{Enum, Variant},
+    list::List,
+    number::NumberFormat,
+    set::Set,
+};
 
 /// Represents the different types of values that can be used in the interpreter.
 /// 
 /// ## Variants
+/// - `Class(Class)`: Represents a class declared in the source code.
+/// - `Deque(Deque)`: Represents a double-ended queue of values.
+/// - `Dict(Dict)`: Represents a dictionary mapping literal keys to values.
+/// - `Enum(Enum)`: Represents an enum declared in the source code.
 /// - `Function(Func)`: Represents a user-defined function.
+/// - `Instance(Instance)`: Represents an instance of a class.
 /// - `List(List)`: Represents a list of values.
 /// - `Literal(LiteralType)`: Represents a literal value (e.g., string, number, boolean, null).
 /// - `NativeFunction(NativeFunc)`: Represents a native function implemented in Rust.
+/// - `Set(Set)`: Represents a set of unique literal values.
+/// - `Tuple(Vec<Value>)`: Represents an immutable, fixed-length sequence of values.
+/// - `Variant(Variant)`: Represents a variant of an enum.
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum Value {
+    Class(Class),
+    Deque(Deque),
+    Dict(Dict),
+    Enum(Enum),
     Function(Func),
+    Instance(Instance),
     List(List),
     Literal(LiteralType),
     NativeFunction(NativeFunc),
+    Set(Set),
+    Tuple(Vec<Value>),
+    Variant(Variant),
 }
 
 /// Represents the different types of literal values that can be used in the interpreter.
@@ -39,29 +64,251 @@ pub enum LiteralType {
     Null,
 }
 
-/// Implements the `Display` trait for the `Value` enum to provide a string representation
-/// of each variant.
-impl fmt::Display for Value {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+/// A map that remembers the order its keys were first inserted in, so that iterating over it,
+/// and so printing it, always gives the same result for the same program.
+///
+/// ## Fields
+/// - `entries`: The keys and their values, in the order the keys were first inserted.
+/// - `index`: The position of each key in `entries`, used to look keys up.
+#[derive(Clone, Debug)]
+pub struct OrderedMap<K, V> {
+    entries: Vec<(K, V)>,
+    index: HashMap<K, usize>,
+}
+
+impl<K: Clone + Eq + Hash, V: PartialEq> PartialEq for OrderedMap<K, V> {
+    /// Two maps are equal if they contain the same entries, regardless of their order.
+    fn eq(&self, other: &Self) -> bool {
+        return self.len() == other.len()
+            && self.entries.iter().all(|(key, value)| other.get(key) == Some(value));
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> Default for OrderedMap<K, V> {
+    fn default() -> Self {
+        return Self { entries: Vec::new(), index: HashMap::new() };
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> OrderedMap<K, V> {
+    /// Creates a new, empty `OrderedMap`.
+    pub fn new() -> Self {
+        return Self::default();
+    }
+
+    /// Returns the value stored under the given key.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        return self.index.get(key).map(|&i| &self.entries[i].1);
+    }
+
+    /// Stores a value under the given key. A key that is already present keeps its position.
+    pub fn insert(&mut self, key: K, value: V) {
+        match self.index.get(&key) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            },
+        }
+    }
+
+    /// Removes the given key and returns the value that was stored under it.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let i = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        // The entries after the removed one have each moved back by one
+        for (key, _) in &self.entries[i..] {
+            if let Some(position) = self.index.get_mut(key) {
+                *position -= 1;
+            }
+        }
+        return Some(value);
+    }
+
+    /// Returns whether the map contains the given key.
+    pub fn contains_key(&self, key: &K) -> bool {
+        return self.index.contains_key(key);
+    }
+
+    /// Returns an iterator over the entries, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        return self.entries.iter().map(|(key, value)| (key, value));
+    }
+
+    /// Returns an iterator over the keys, in insertion order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        return self.entries.iter().map(|(key, _)| key);
+    }
+
+    /// Returns an iterator over the values, in insertion order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        return self.entries.iter().map(|(_, value)| value);
+    }
+
+    /// Returns the number of entries in the map.
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    /// Returns whether the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+}
+
+impl Value {
+    /// Returns the name of the value's type as it is described to users.
+    pub fn type_name(&self) -> &'static str {
+        return match self {
+            Value::Class(_) => "class",
+            Value::Deque(_) => "deque",
+            Value::Dict(_) => "dict",
+            Value::Enum(_) => "enum",
+            Value::Function(_) => "function",
+            Value::Instance(_) => "instance",
+            Value::List(_) => "list",
+            Value::Literal(LiteralType::Str(_)) => "string",
+            Value::Literal(LiteralType::Num(_)) => "number",
+            Value::Literal(LiteralType::True | LiteralType::False) => "bool",
+            Value::Literal(LiteralType::Null) => "null",
+            Value::NativeFunction(_) => "native function",
+            Value::Set(_) => "set",
+            Value::Tuple(_) => "tuple",
+            Value::Variant(_) => "enum variant",
+        };
+    }
+
+    /// Returns whether the value counts as true in a condition. As in Python, `false`, `null`,
+    /// `0`, the empty string and empty collections are false, and every other value, including
+    /// every function, class, instance and enum, is true.
+    pub fn is_truthy(&self) -> bool {
+        return match self {
+            Value::Literal(LiteralType::False | LiteralType::Null) => false,
+            Value::Literal(LiteralType::Num(n)) => *n != 0.0,
+            Value::Literal(LiteralType::Str(s)) => !s.is_empty(),
+            Value::Literal(LiteralType::True) => true,
+            Value::Deque(deque) => !deque.is_empty(),
+            Value::Dict(dict) => !dict.is_empty(),
+            Value::List(list) => !list.is_empty(),
+            Value::Set(set) => !set.is_empty(),
+            Value::Tuple(items) => !items.is_empty(),
+            Value::Class(_)
+            | Value::Enum(_)
+            | Value::Function(_)
+            | Value::Instance(_)
+            | Value::NativeFunction(_)
+            | Value::Variant(_) => true,
+        };
+    }
+
+    /// Returns roughly how many bytes the value takes up, including the strings and items it
+    /// holds. The fields of an instance are shared by every copy of it, so they are not counted.
+    pub fn approximate_size(&self) -> usize {
+        let held = match self {
+            Value::Literal(LiteralType::Str(s)) => s.len(),
+            Value::List(list) => list.values().iter().map(Value::approximate_size).sum(),
+            Value::Tuple(items) => items.iter().map(Value::approximate_size).sum(),
+            Value::Deque(deque) => deque.iter().map(Value::approximate_size).sum(),
+            // Each key is stored twice, in the entries and in the index
+            Value::Dict(dict) => dict.entries
+                .iter()
+                .map(|(key, value)| 2 * key.approximate_size() + value.approximate_size())
+                .sum(),
+            Value::Set(set) => set.approximate_size(),
+            _ => 0,
+        };
+        return mem::size_of::<Value>() + held;
+    }
+}
+
+impl Value {
+    /// Writes the value, writing any numbers in it, including the items of collections, with
+    /// the given format.
+    pub fn write(&self, f: &mut fmt::Formatter<'_>, format: &NumberFormat) -> fmt::Result {
         return match self {
+            Value::Class(class) => write!(f, "{class}"),
+            Value::Deque(deque) => deque.write(f, format),
+            Value::Dict(dict) => dict.write(f, format),
+            Value::Enum(enumeration) => write!(f, "{enumeration}"),
             Value::Function(fun) => write!(f, "Function({fun})"),
-            Value::List(list) => write!(f, "{list}"),
-            Value::Literal(literal) => write!(f, "{literal}"),
+            Value::Instance(instance) => write!(f, "{instance}"),
+            Value::List(list) => list.write(f, format),
+            Value::Literal(literal) => literal.write(f, format),
             Value::NativeFunction(nf) => write!(f, "NativeFunction({nf})"),
+            Value::Set(set) => set.write(f, format),
+            Value::Tuple(items) => {
+                write!(f, "(")?;
+                for (i, value) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_item(f, value, format)?;
+                }
+                // A tuple of one item is written with a trailing comma, as it is in the source
+                if items.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            },
+            Value::Variant(variant) => write!(f, "{variant}"),
         };
     }
+
+    /// Returns the value in a form that displays its numbers with the given format.
+    pub fn display<'a>(&'a self, format: &'a NumberFormat) -> Formatted<'a> {
+        return Formatted { value: self, format };
+    }
 }
 
-/// Implements the `Display` trait for the `LiteralType` enum to provide a string representation
+/// A value displayed with a number format other than the default one.
+///
+/// ## Fields
+/// - `value`: The value to display.
+/// - `format`: How the numbers in the value are written.
+pub struct Formatted<'a> {
+    value: &'a Value,
+    format: &'a NumberFormat,
+}
+
+impl fmt::Display for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return self.value.write(f, self.format);
+    }
+}
+
+/// Writes a value held by a collection, quoting it if it is a string.
+pub(crate) fn write_item(f: &mut fmt::Formatter<'_>, value: &Value, format: &NumberFormat) -> fmt::Result {
+    if let Value::Literal(LiteralType::Str(s)) = value {
+        return write!(f, "\"{s}\"");
+    }
+    return value.write(f, format);
+}
+
+/// Implements the `Display` trait for the `Value` enum to provide a string representation
 /// of each variant.
-impl fmt::Display for LiteralType {
+impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return self.write(f, &NumberFormat::default());
+    }
+}
+
+impl LiteralType {
+    /// Writes the literal, writing a number with the given format.
+    pub fn write(&self, f: &mut fmt::Formatter<'_>, format: &NumberFormat) -> fmt::Result {
         return match self {
             LiteralType::Str(s) => write!(f, "{s}"),
-            LiteralType::Num(n) => write!(f, "{n}"),
+            LiteralType::Num(n) => write!(f, "{}", format.format(*n)),
             LiteralType::True => write!(f, "true"),
             LiteralType::False => write!(f, "false"),
             LiteralType::Null => write!(f, "null"),
         };
     }
+}
+
+/// Implements the `Display` trait for the `LiteralType` enum to provide a string representation
+/// of each variant.
+impl fmt::Display for LiteralType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return self.write(f, &NumberFormat::default());
+    }
 }
\ No newline at end of file